@@ -87,6 +87,12 @@ pub(crate) fn render_diagnostics(error: OperationError) {
             ],
         ),
         OperationError::FlashingFailed { source, target, target_spec, .. } => generate_flash_error_hints(source, target, target_spec),
+        OperationError::VerifyMismatch { .. } => (
+            error.to_string(),
+            vec![
+                "The target is not running this binary. Flash it by running the same command without `--verify-only`.".into(),
+            ],
+        ),
         OperationError::FailedChipDescriptionParsing { .. } => (
             error.to_string(),
             vec![],
@@ -138,7 +138,13 @@ fn main_try(metadata: Arc<Mutex<Metadata>>) -> Result<(), OperationError> {
 
     logging::println(format!(
         "    {} {}",
-        "Flashing".green().bold(),
+        if opt.verify_only {
+            "Verifying"
+        } else {
+            "Flashing"
+        }
+        .green()
+        .bold(),
         path.display()
     ));
 
@@ -170,8 +176,14 @@ fn main_try(metadata: Arc<Mutex<Metadata>>) -> Result<(), OperationError> {
     // Create a new session
     let mut session = opt.probe_options.attach_session(probe, target_selector)?;
 
-    // Flash the binary
     let flashloader = opt.probe_options.build_flashloader(&mut session, &path)?;
+
+    // Only compare the contents of the target with the binary, and leave the target untouched.
+    if opt.verify_only {
+        return flash::run_flash_verify(&mut session, &path, &opt, flashloader);
+    }
+
+    // Flash the binary
     flash::run_flash_download(&mut session, &path, &opt, flashloader, false)?;
 
     // Reset target according to CLI options
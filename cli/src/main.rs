@@ -15,7 +15,7 @@ use probe_rs::{
     architecture::arm::{component::TraceSink, swo::SwoConfig},
     debug::debug_info::DebugInfo,
     flashing::{erase_all, BinOptions, FileDownloadError, Format},
-    MemoryInterface, Probe,
+    Core, CoreDump, MemoryInterface, Probe,
 };
 
 use probe_rs_cli_util::{
@@ -106,6 +106,16 @@ enum Subcommand {
         /// Binary to debug
         exe: Option<PathBuf>,
     },
+    /// Inspect a core dump with the command line debugger, without an attached target
+    Replay {
+        /// The core dump, as saved by the `saveCoreDump` request of the debugger
+        #[clap(value_parser)]
+        dump: PathBuf,
+
+        #[clap(long, value_parser)]
+        /// Binary that was running when the core dump was captured
+        exe: Option<PathBuf>,
+    },
     /// Dump memory from attached target
     Dump {
         #[clap(flatten)]
@@ -173,6 +183,25 @@ enum Subcommand {
         /// Disable double-buffering when downloading flash.  If downloading times out, try this option.
         #[clap(long = "disable-double-buffering")]
         disable_double_buffering: bool,
+
+        /// Record the raw RTT data to this file, so that it can be decoded again later with `rtt-replay`.
+        #[clap(long, value_parser)]
+        rtt_record: Option<PathBuf>,
+    },
+    /// Decode an RTT recording made with `run --rtt-record`, without a target
+    #[clap(name = "rtt-replay")]
+    RttReplay {
+        /// The RTT recording to decode
+        #[clap(value_parser)]
+        recording: PathBuf,
+
+        /// The ELF file that was running when the recording was made. This is required for defmt channels.
+        #[clap(long, value_parser)]
+        elf: Option<PathBuf>,
+
+        /// Prefix every read with the time it was recorded, and the channel name
+        #[clap(long)]
+        show_timestamps: bool,
     },
     /// Trace a memory location on the target
     #[clap(name = "trace")]
@@ -333,6 +362,7 @@ fn main() -> Result<()> {
             common,
             exe,
         } => debug(&shared, &common, exe),
+        Subcommand::Replay { dump, exe } => replay(&dump, exe),
         Subcommand::Dump {
             shared,
             common,
@@ -361,13 +391,20 @@ fn main() -> Result<()> {
             path,
             chip_erase,
             disable_double_buffering,
+            rtt_record,
         } => run::run(
             common,
             &path,
             chip_erase,
             disable_double_buffering,
+            rtt_record,
             utc_offset,
         ),
+        Subcommand::RttReplay {
+            recording,
+            elf,
+            show_timestamps,
+        } => run::replay_rtt(&recording, elf.as_deref(), show_timestamps, utc_offset),
         Subcommand::Erase { common } => erase(&common),
         Subcommand::Trace {
             shared,
@@ -486,6 +523,7 @@ fn download_program_fast(
             reset_halt: false,
             log: None,
             restore_unwritten: false,
+            verify_only: false,
             flash_layout_output_path: None,
             elf: None,
             work_dir: None,
@@ -574,10 +612,25 @@ fn debug(shared_options: &CoreOptions, common: &ProbeOptions, exe: Option<PathBu
         .as_ref()
         .and_then(|path| DebugInfo::from_file(path).ok());
 
-    let cli = debugger::DebugCli::new();
-
     let core = session.core(shared_options.core)?;
 
+    run_debug_cli(core, di)
+}
+
+fn replay(dump: &Path, exe: Option<PathBuf>) -> Result<()> {
+    let core_dump = CoreDump::load(dump)?;
+    let mut core_state = core_dump.create_state();
+
+    let di = exe
+        .as_ref()
+        .and_then(|path| DebugInfo::from_file(path).ok());
+
+    run_debug_cli(core_dump.core(&mut core_state), di)
+}
+
+fn run_debug_cli(core: Core, di: Option<DebugInfo>) -> Result<()> {
+    let cli = debugger::DebugCli::new();
+
     let mut cli_data = debugger::CliData::new(core, di)?;
 
     let mut rl = Editor::<()>::new()?;
@@ -5,7 +5,7 @@ use probe_rs_cli_util::flash::run_flash_download;
 use probe_rs_cli_util::rtt;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use time::UtcOffset;
 
@@ -14,6 +14,7 @@ pub fn run(
     path: &str,
     chip_erase: bool,
     disable_double_buffering: bool,
+    rtt_record: Option<PathBuf>,
     timestamp_offset: UtcOffset,
 ) -> Result<()> {
     let mut session = common.simple_attach()?;
@@ -37,6 +38,7 @@ pub fn run(
             reset_halt: false,
             log: None,
             restore_unwritten: false,
+            verify_only: false,
             flash_layout_output_path: None,
             elf: None,
             work_dir: None,
@@ -47,7 +49,10 @@ pub fn run(
         chip_erase,
     )?;
 
-    let rtt_config = rtt::RttConfig::default();
+    let rtt_config = rtt::RttConfig {
+        recording_path: rtt_record,
+        ..Default::default()
+    };
 
     let memory_map = session.target().memory_map.clone();
 
@@ -85,3 +90,27 @@ pub fn run(
 
     Ok(())
 }
+
+/// Decode an RTT recording made with `run --rtt-record`, and print it like live RTT data.
+pub fn replay_rtt(
+    recording: &Path,
+    elf: Option<&Path>,
+    show_timestamps: bool,
+    timestamp_offset: UtcOffset,
+) -> Result<()> {
+    // The channels are decoded with the data format and options that were recorded with them.
+    let rtt_config = rtt::RttConfig::default();
+    let mut stdout = std::io::stdout();
+    for entry in rtt::replay_rtt_recording(recording, elf, &rtt_config, timestamp_offset)? {
+        if show_timestamps {
+            write!(
+                stdout,
+                "[{} {}] ",
+                entry.timestamp.to_offset(timestamp_offset),
+                entry.channel_name
+            )?;
+        }
+        stdout.write_all(entry.data.as_bytes())?;
+    }
+    Ok(())
+}
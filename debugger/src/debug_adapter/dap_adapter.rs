@@ -1,19 +1,20 @@
 use crate::{
     debug_adapter::{dap_types, protocol::ProtocolAdapter},
     debugger::{
-        configuration::ConsoleLog,
+        breakpoint_condition,
+        configuration::{ConsoleLog, CoverageConfig, SessionConfig, VariablesFilter},
         core_data::CoreHandle,
+        data_watchpoints::{self, WatchKind},
         debug_entry::TargetSessionType,
-        session_data::{ActiveBreakpoint, BreakpointType},
+        device_identity, elf_symbols, expression, program_modules, register_groups, riscv_csrs,
+        rtos,
+        session_data::{BreakpointType, SessionData},
+        set_variable,
     },
     DebuggerError,
 };
 use anyhow::{anyhow, Result};
 use base64::{engine::general_purpose as base64_engine, Engine as _};
-use capstone::{
-    arch::arm::ArchMode as armArchMode, arch::arm64::ArchMode as aarch64ArchMode,
-    arch::riscv::ArchMode as riscvArchMode, prelude::*, Capstone, Endian,
-};
 use dap_types::*;
 use num_traits::Zero;
 use parse_int::parse;
@@ -21,18 +22,36 @@ use probe_rs::{
     architecture::{arm::ArmError, riscv::communication_interface::RiscvError},
     debug::{
         ColumnType, DebugRegisters, SourceLocation, SteppingMode, VariableName, VariableNodeType,
-        VerifiedBreakpoint,
+        VariableValue, VerifiedBreakpoint,
     },
     Architecture::Riscv,
-    CoreStatus, CoreType, Error, HaltReason, InstructionSet, MemoryInterface, RegisterValue,
+    CoreStatus, Error, HaltReason, MemoryInterface, RegisterValue,
 };
 use probe_rs_cli_util::rtt;
 use serde::{de::DeserializeOwned, Serialize};
-use std::{convert::TryInto, path::Path, str, string::ToString, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::TryInto,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    str,
+    string::ToString,
+    time::{Duration, Instant},
+};
 
 /// Progress ID used for progress reporting when the debug adapter protocol is used.
 type ProgressId = i64;
 
+/// The number of bytes read from the target, and written to file, between progress updates of a `dumpMemory` request.
+const MEMORY_DUMP_CHUNK_SIZE: usize = 4096;
+
+/// The maximum number of raw bytes that are shown for a variable when `showRawBytes` is enabled, to limit the reads for large arrays and structs.
+const MAX_RAW_BYTES: u64 = 64;
+
+/// A source breakpoint that is moved by more than this number of lines, to the nearest valid location, is reported with a message that explains why.
+const MAX_SILENT_BREAKPOINT_LINE_MOVE: u64 = 2;
+
 pub struct DebugAdapter<P: ProtocolAdapter> {
     pub(crate) halt_after_reset: bool,
     /// NOTE: VSCode sends a 'threads' request when it receives the response from the `ConfigurationDone` request, irrespective of target state.
@@ -48,10 +67,18 @@ pub struct DebugAdapter<P: ProtocolAdapter> {
     /// Flag to indicate if all cores of the target are halted. This is used to accurately report the `all_threads_stopped` field in the DAP `StoppedEvent`, as well as to prevent unnecessary polling of core status.
     /// The default is `true`, and will be set to `false` if any of the cores report a status other than `CoreStatus::Halted(_)`.
     pub(crate) all_cores_halted: bool,
+    /// Flag to indicate if all cores of the target are running (or sleeping). This is used to accurately report the `all_threads_continued` field in the DAP `ContinuedEvent`.
+    pub(crate) all_cores_running: bool,
+    /// The status of each core, by core index, when the cores were last polled. This is used to report the `all_threads_continued` field in the DAP `ContinueResponse`, when a single core is continued.
+    pub(crate) core_statuses: HashMap<usize, CoreStatus>,
     /// Progress ID used for progress reporting when the debug adapter protocol is used.
     progress_id: ProgressId,
     /// Flag to indicate if the connected client supports progress reporting.
     pub(crate) supports_progress_reporting: bool,
+    /// Flag to indicate if the connected client supports the `invalidated` event.
+    pub(crate) supports_invalidated_event: bool,
+    /// Entries that are hidden from the `variables` response. See [`VariablesFilter`].
+    pub(crate) variables_filter: VariablesFilter,
     /// Flags to improve breakpoint accuracy.
     /// DWARF spec at Sect 2.14 uses 1 based numbering, with a 0 indicating not-specified. We will follow that standard, and translate incoming requests depending on the DAP Client treatment of 0 or 1 based numbering.
     pub(crate) lines_start_at_1: bool,
@@ -59,6 +86,9 @@ pub struct DebugAdapter<P: ProtocolAdapter> {
     pub(crate) columns_start_at_1: bool,
     /// The behaviour of the debug adapter sometimes depend on the TargetSessionType
     pub(crate) target_session_type: Option<TargetSessionType>,
+    /// Requests that were received while a long running request (e.g. `dumpMemory`) was checking for cancellation.
+    /// These will be processed, in order, before any new requests are read from the client.
+    deferred_requests: VecDeque<Request>,
     adapter: P,
 }
 
@@ -68,11 +98,16 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             halt_after_reset: false,
             configuration_done: false,
             all_cores_halted: true,
+            all_cores_running: false,
+            core_statuses: HashMap::new(),
             progress_id: 0,
             supports_progress_reporting: false,
+            supports_invalidated_event: false,
+            variables_filter: VariablesFilter::default(),
             lines_start_at_1: true,
             columns_start_at_1: true,
             target_session_type: None,
+            deferred_requests: VecDeque::new(),
             adapter,
         }
     }
@@ -81,6 +116,94 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.configuration_done
     }
 
+    /// `true` if all the cores, other than the core at `core_index`, were running (or sleeping) when they were last polled.
+    pub(crate) fn other_cores_running(&self, core_index: usize) -> bool {
+        self.core_statuses
+            .iter()
+            .filter(|(other_core_index, _)| **other_core_index != core_index)
+            .all(|(_, core_status)| {
+                matches!(core_status, CoreStatus::Running | CoreStatus::Sleeping)
+            })
+    }
+
+    /// The name of the thread of `target_core`. The core index is added to the name of the target when more than one core is debugged.
+    fn core_thread_name(&self, target_core: &CoreHandle) -> String {
+        if self.core_statuses.len() > 1 {
+            format!(
+                "{} core {}",
+                target_core.core_data.target_name,
+                target_core.core.id()
+            )
+        } else {
+            target_core.core_data.target_name.clone()
+        }
+    }
+
+    /// The threads of the debugged cores, other than `target_core`, in the order of their core index, which is also their thread id.
+    fn other_core_threads(&self, target_core: &CoreHandle) -> Vec<Thread> {
+        let mut core_indexes = self
+            .core_statuses
+            .keys()
+            .copied()
+            .filter(|core_index| *core_index != target_core.core.id())
+            .collect::<Vec<usize>>();
+        core_indexes.sort_unstable();
+        core_indexes
+            .into_iter()
+            .map(|core_index| Thread {
+                id: core_index as i64,
+                name: format!("{} core {core_index}", target_core.core_data.target_name),
+            })
+            .collect()
+    }
+
+    /// Custom `pauseAll` request, to halt all the cores of a multi-core target at once. A single `stopped` event, with `all_threads_stopped`, is sent for the first core.
+    pub(crate) fn pause_all(
+        &mut self,
+        session_data: &mut SessionData,
+        core_indexes: &[usize],
+        request: Request,
+    ) -> Result<()> {
+        let mut first_halted_core = None;
+        for core_index in core_indexes {
+            let mut target_core = match session_data.attach_core(*core_index) {
+                Ok(target_core) => target_core,
+                Err(error) => return self.send_response::<()>(request, Err(error)),
+            };
+            let program_counter = match target_core.core.halt(Duration::from_millis(500)) {
+                Ok(cpu_info) => cpu_info.pc,
+                Err(error) => {
+                    return self.send_response::<()>(request, Err(DebuggerError::ProbeRs(error)))
+                }
+            };
+            // We override the halt reason to prevent duplicate stopped events.
+            target_core.core_data.last_known_status = CoreStatus::Halted(HaltReason::Request);
+            self.core_statuses
+                .insert(*core_index, CoreStatus::Halted(HaltReason::Request));
+            first_halted_core.get_or_insert((*core_index, program_counter));
+        }
+        self.all_cores_halted = true;
+        self.all_cores_running = false;
+        self.send_response::<()>(request, Ok(None))?;
+        if let Some((core_index, program_counter)) = first_halted_core {
+            let event_body = Some(StoppedEventBody {
+                reason: "pause".to_owned(),
+                description: Some(
+                    CoreStatus::Halted(HaltReason::Request)
+                        .short_long_status(Some(program_counter))
+                        .1,
+                ),
+                thread_id: Some(core_index as i64),
+                preserve_focus_hint: Some(false),
+                text: None,
+                all_threads_stopped: Some(true),
+                hit_breakpoint_ids: None,
+            });
+            self.send_event("stopped", event_body)?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn pause(&mut self, target_core: &mut CoreHandle, request: Request) -> Result<()> {
         match target_core.core.halt(Duration::from_millis(500)) {
             Ok(cpu_info) => {
@@ -137,82 +260,820 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             let _ = target_core.core.halt(Duration::from_millis(100));
         }
 
+        // The target must not run into a `BKPT` instruction once the debugger has detached.
+        if let Err(error) = target_core.restore_software_breakpoints() {
+            tracing::warn!(
+                "Failed to restore the instructions of the software breakpoints: {error}"
+            );
+        }
+        // Likewise, the DWT comparators must not halt the target on a data access once the debugger has detached.
+        if let Err(error) = target_core.clear_data_watchpoints() {
+            tracing::warn!("Failed to clear the data watchpoints: {error}");
+        }
+
         self.send_response::<DisconnectResponse>(request, Ok(None))
     }
 
-    pub(crate) fn read_memory(
+    /// Notify the debug client that the pending breakpoint with `id` was set at the `verified_breakpoint` location.
+    pub(crate) fn send_breakpoint_verified_event(
+        &mut self,
+        id: i64,
+        verified_breakpoint: &VerifiedBreakpoint,
+    ) -> Result<()> {
+        self.send_breakpoint_event(id, verified_breakpoint, None, "changed")
+    }
+
+    /// Notify the debug client that the debugger set a breakpoint with `id` in `source`, that the client did not request in this session,
+    /// e.g. a breakpoint that was restored from the breakpoints file.
+    pub(crate) fn send_breakpoint_new_event(
+        &mut self,
+        id: i64,
+        verified_breakpoint: &VerifiedBreakpoint,
+        source: Source,
+    ) -> Result<()> {
+        self.send_breakpoint_event(id, verified_breakpoint, Some(source), "new")
+    }
+
+    /// Send a `breakpoint` event with `reason` for the breakpoint with `id` at the `verified_breakpoint` location.
+    fn send_breakpoint_event(
+        &mut self,
+        id: i64,
+        verified_breakpoint: &VerifiedBreakpoint,
+        source: Option<Source>,
+        reason: &str,
+    ) -> Result<()> {
+        let line_offset = u64::from(!self.lines_start_at_1);
+        let column_offset = u64::from(!self.columns_start_at_1);
+        let event_body = Some(BreakpointEventBody {
+            breakpoint: Breakpoint {
+                column: verified_breakpoint
+                    .source_location
+                    .column
+                    .map(|col| match col {
+                        ColumnType::LeftEdge => 1,
+                        ColumnType::Column(c) => c,
+                    })
+                    .map(|column| column.saturating_sub(column_offset) as i64),
+                end_column: None,
+                end_line: None,
+                id: Some(id),
+                line: verified_breakpoint
+                    .source_location
+                    .line
+                    .map(|line| line.saturating_sub(line_offset) as i64),
+                message: Some(format!(
+                    "Source breakpoint at memory address: {:#010X}",
+                    verified_breakpoint.address
+                )),
+                source,
+                instruction_reference: Some(format!("{:#010X}", verified_breakpoint.address)),
+                offset: None,
+                verified: true,
+            },
+            reason: reason.to_string(),
+        });
+        self.send_event("breakpoint", event_body)
+    }
+
+    /// Log a summary of the line coverage to the console, and if a `report_file` is configured, write the JSON report to it.
+    pub(crate) fn report_coverage(
+        &mut self,
+        target_core: &CoreHandle,
+        coverage_config: &CoverageConfig,
+    ) {
+        let coverage_report = target_core.coverage_report();
+        let lines_executed = coverage_report
+            .iter()
+            .filter(|report_entry| report_entry.hit_count > 0)
+            .count();
+        self.log_to_console(format!(
+            "COVERAGE: {lines_executed} of {} tracked source lines were executed",
+            coverage_report.len()
+        ));
+        if let Some(report_file) = &coverage_config.report_file {
+            match File::create(report_file)
+                .map_err(|error| anyhow!(error))
+                .and_then(|file| {
+                    serde_json::to_writer_pretty(file, &coverage_report)
+                        .map_err(|error| anyhow!(error))
+                }) {
+                Ok(()) => {
+                    self.log_to_console(format!("COVERAGE: Report written to {report_file:?}"));
+                }
+                Err(error) => {
+                    self.show_message(
+                        MessageSeverity::Warning,
+                        format!("Failed to write coverage report to {report_file:?}: {error}"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Custom `healthCheck` request, to check the connection to the target with a single read, without changing the state of the core.
+    /// A failed check is reported in the response body, rather than as an error response, so that clients can poll the link health.
+    pub(crate) fn health_check(
+        &mut self,
+        session_data: &mut SessionData,
+        core_index: usize,
+        request: Request,
+    ) -> Result<()> {
+        let start = Instant::now();
+        let health_check_result = session_data.health_check(core_index);
+        let latency_microseconds = start.elapsed().as_micros() as u64;
+        let response_body = match health_check_result {
+            Ok(message) => HealthCheckResponseBody {
+                alive: true,
+                latency_microseconds,
+                message,
+            },
+            Err(error) => HealthCheckResponseBody {
+                alive: false,
+                latency_microseconds,
+                message: format!("{error:?}"),
+            },
+        };
+        self.send_response(request, Ok(Some(response_body)))
+    }
+
+    /// Custom `resetCause` request, to read, decode, and optionally clear, the reset cause register of the target.
+    /// The register is located using the SVD file. See [`crate::peripherals::reset_cause`] for the supported targets.
+    pub(crate) fn reset_cause(
         &mut self,
         target_core: &mut CoreHandle,
         request: Request,
     ) -> Result<()> {
-        let arguments: ReadMemoryArguments = match get_arguments(&request) {
+        let arguments: ResetCauseArguments = match get_arguments(&request) {
             Ok(arguments) => arguments,
             Err(error) => return self.send_response::<()>(request, Err(error)),
         };
-        let memory_offset = arguments.offset.unwrap_or(0);
-        let mut address: u64 =
-            if let Ok(address) = parse::<u64>(arguments.memory_reference.as_ref()) {
-                address + memory_offset as u64
+
+        let reset_cause =
+            if let Some(core_peripherals) = &mut target_core.core_data.core_peripherals {
+                match core_peripherals.reset_cause(&mut target_core.core, arguments.clear) {
+                    Ok(reset_cause) => reset_cause,
+                    Err(error) => return self.send_response::<()>(request, Err(error)),
+                }
             } else {
+                None
+            };
+
+        let response_body = match reset_cause {
+            Some(reset_cause) => ResetCauseResponseBody {
+                message: format!(
+                    "Reset cause ({} @ {:#010x} = {:#010x}): {}{}",
+                    reset_cause.register_name,
+                    reset_cause.address,
+                    reset_cause.value,
+                    if reset_cause.flags.is_empty() {
+                        "No flags are set".to_string()
+                    } else {
+                        reset_cause.flags.join(", ")
+                    },
+                    if reset_cause.cleared {
+                        " (cleared)"
+                    } else {
+                        ""
+                    }
+                ),
+                register: Some(reset_cause.register_name),
+                value: Some(format!("{:#010x}", reset_cause.value)),
+                flags: reset_cause.flags,
+                cleared: reset_cause.cleared,
+            },
+            None => ResetCauseResponseBody {
+                message: if target_core.core_data.core_peripherals.is_some() {
+                    "The reset cause is unknown for this target.".to_string()
+                } else {
+                    "The reset cause is unknown for this target. Please configure an `svdFile` for the core."
+                        .to_string()
+                },
+                ..Default::default()
+            },
+        };
+        self.send_response(request, Ok(Some(response_body)))
+    }
+
+    /// Custom `stackUsage` request, to find the high-water mark of a stack that the firmware painted with a known value.
+    /// The stack region is scanned upwards from its lowest address, until the first word that does not contain the paint value.
+    pub(crate) fn stack_usage(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: StackUsageArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        let (stack_start, paint_value) = match parse::<u64>(arguments.memory_reference.as_ref())
+            .map_err(|error| {
+                anyhow!(
+                    "Invalid memory reference {:?}: {error}",
+                    arguments.memory_reference
+                )
+            })
+            .and_then(|stack_start| {
+                let paint_value = arguments.paint_value.as_deref().unwrap_or("0xAAAAAAAA");
+                parse::<u32>(paint_value)
+                    .map(|paint_value| (stack_start, paint_value))
+                    .map_err(|error| anyhow!("Invalid paint value {paint_value:?}: {error}"))
+            }) {
+            Ok(parsed_arguments) => parsed_arguments,
+            Err(error) => {
+                return self.send_response::<()>(request, Err(DebuggerError::Other(error)))
+            }
+        };
+        if stack_start % 4 != 0 || arguments.count % 4 != 0 {
+            return self.send_response::<()>(
+                request,
+                Err(DebuggerError::Other(anyhow!(
+                    "The stack region has to be 4 byte aligned, and a multiple of 4 bytes in size."
+                ))),
+            );
+        }
+        if let Err(error) = target_core.check_memory_range(stack_start, arguments.count) {
+            return self.send_response::<()>(request, Err(error));
+        }
+
+        let mut buffer = vec![0_u32; MEMORY_DUMP_CHUNK_SIZE / 4];
+        let mut free_bytes = 0_u64;
+        while free_bytes < arguments.count {
+            let chunk_words =
+                ((arguments.count - free_bytes) / 4).min(buffer.len() as u64) as usize;
+            let chunk = &mut buffer[..chunk_words];
+            if let Err(error) = target_core.core.read_32(stack_start + free_bytes, chunk) {
+                return self.send_response::<()>(request, Err(DebuggerError::ProbeRs(error)));
+            }
+            match chunk.iter().position(|word| *word != paint_value) {
+                Some(unpainted_word) => {
+                    free_bytes += unpainted_word as u64 * 4;
+                    break;
+                }
+                None => free_bytes += chunk_words as u64 * 4,
+            }
+        }
+        let used_bytes = arguments.count - free_bytes;
+        let high_water_mark = stack_start + free_bytes;
+        let response_body = StackUsageResponseBody {
+            high_water_mark: format!("{high_water_mark:#010x}"),
+            used_bytes,
+            free_bytes,
+            message: format!(
+                "Maximum stack usage is {used_bytes} of {} bytes ({:.1}%), with {free_bytes} bytes of headroom. High-water mark at {high_water_mark:#010x}.",
+                arguments.count,
+                if arguments.count > 0 {
+                    used_bytes as f64 * 100.0 / arguments.count as f64
+                } else {
+                    0.0
+                }
+            ),
+        };
+        self.send_response(request, Ok(Some(response_body)))
+    }
+
+    /// Custom `deviceInfo` request, to read and decode the device identification registers, and the unique device id, of the target.
+    /// See [`crate::debugger::device_identity`] for the supported targets.
+    pub(crate) fn device_info(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        // The `target_name` of the core is prefixed with the core index, e.g. `0-STM32F407VGTx`.
+        let target_name = target_core
+            .core_data
+            .target_name
+            .split_once('-')
+            .map_or(
+                target_core.core_data.target_name.as_str(),
+                |(_, target_name)| target_name,
+            )
+            .to_string();
+        let device_identity =
+            match device_identity::read_device_identity(&mut target_core.core, &target_name) {
+                Ok(device_identity) => device_identity,
+                Err(error) => return self.send_response::<()>(request, Err(error)),
+            };
+        let response_body = DeviceInfoResponseBody {
+            label: device_identity.label,
+            target_name,
+            cpuid: device_identity.cpuid.map(|cpuid| format!("{cpuid:#010x}")),
+            core: device_identity.core,
+            device_id: device_identity
+                .device_id
+                .map(|device_id| format!("{device_id:#x}")),
+            revision: device_identity.revision,
+            unique_id: device_identity.unique_id,
+        };
+        self.send_response(request, Ok(Some(response_body)))
+    }
+
+    /// Custom `listBreakpoints` request, to show the breakpoints that are currently armed by the debugger, including the
+    /// breakpoints that are not visible in the client (e.g. coverage and function trace breakpoints), and the number of
+    /// hardware breakpoint units that are in use.
+    pub(crate) fn list_breakpoints(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let breakpoints = target_core
+            .core_data
+            .breakpoints
+            .iter()
+            .map(|breakpoint| {
+                let mut breakpoint_info = ActiveBreakpointInfo {
+                    id: breakpoint.id,
+                    address: format!("{:#010x}", breakpoint.address),
+                    condition: breakpoint.condition.clone(),
+                    hit_count: breakpoint.hit_count,
+                    placement: if breakpoint.breakpoint_type.original_instruction().is_some() {
+                        "software".to_string()
+                    } else {
+                        "hardware".to_string()
+                    },
+                    enabled: true,
+                    ..Default::default()
+                };
+                match breakpoint.breakpoint_type.requested_type() {
+                    BreakpointType::InstructionBreakpoint => {
+                        breakpoint_info.breakpoint_type = "instruction".to_string();
+                    }
+                    BreakpointType::SourceBreakpoint(source, source_location, log_message) => {
+                        breakpoint_info.breakpoint_type = if log_message.is_some() {
+                            "logpoint".to_string()
+                        } else {
+                            "source".to_string()
+                        };
+                        breakpoint_info.path = source.path.clone();
+                        breakpoint_info.line = source_location.line;
+                    }
+                    BreakpointType::CoverageBreakpoint(source_location) => {
+                        breakpoint_info.breakpoint_type = "coverage".to_string();
+                        breakpoint_info.path = source_location
+                            .combined_path()
+                            .ok()
+                            .map(|path| path.to_string_lossy().to_string());
+                        breakpoint_info.line = source_location.line;
+                    }
+                    BreakpointType::FunctionTraceBreakpoint(function_name) => {
+                        breakpoint_info.breakpoint_type = "functionTrace".to_string();
+                        breakpoint_info.function = Some(function_name.clone());
+                    }
+                    BreakpointType::Catchpoint(function_name) => {
+                        breakpoint_info.breakpoint_type = "catchpoint".to_string();
+                        breakpoint_info.function = Some(function_name.clone());
+                    }
+                    BreakpointType::TemporaryBreakpoint => {
+                        breakpoint_info.breakpoint_type = "temporary".to_string();
+                    }
+                    // The requested type is never a software breakpoint.
+                    BreakpointType::SoftwareBreakpoint(..) => {}
+                }
+                breakpoint_info
+            })
+            .chain(
+                target_core
+                    .core_data
+                    .data_watchpoints
+                    .iter()
+                    .map(|watchpoint| ActiveBreakpointInfo {
+                        id: watchpoint.id,
+                        breakpoint_type: "data".to_string(),
+                        address: format!("{:#010x}", watchpoint.address),
+                        placement: "hardware".to_string(),
+                        enabled: true,
+                        ..Default::default()
+                    }),
+            )
+            .collect::<Vec<ActiveBreakpointInfo>>();
+
+        let hardware_breakpoints_used = target_core.hardware_breakpoints_used() as u64;
+        let hardware_breakpoints_available = target_core
+            .available_breakpoint_units()
+            .map_err(|error| {
+                tracing::warn!("Failed to read the number of breakpoint units: {error}")
+            })
+            .ok()
+            .map(u64::from);
+
+        let response_body = ListBreakpointsResponseBody {
+            message: format!(
+                "{} breakpoints, using {hardware_breakpoints_used} of {} hardware breakpoint units.",
+                breakpoints.len(),
+                hardware_breakpoints_available
+                    .map_or_else(|| "an unknown number of".to_string(), |available| available.to_string())
+            ),
+            breakpoints,
+            hardware_breakpoints_used,
+            hardware_breakpoints_available,
+        };
+        self.send_response(request, Ok(Some(response_body)))
+    }
+
+    /// Custom `riscvCsr` request, to read (and optionally write) the control and status registers of a RISC-V core by name.
+    /// Without a CSR name, all the machine mode CSRs that are described by the architecture are read. CSRs that cannot be read
+    /// (e.g. because they are not implemented by the core) are reported with the error, rather than failing the request.
+    pub(crate) fn riscv_csr(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: RiscvCsrArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        if target_core.core.architecture() != Riscv {
+            return self.send_response::<()>(
+                request,
+                Err(DebuggerError::Other(anyhow!(
+                    "CSRs are only available on RISC-V cores"
+                ))),
+            );
+        }
+
+        let csrs = match &arguments.name {
+            Some(name) => match riscv_csrs::find_csr(name) {
+                Ok(csr) => vec![csr],
+                Err(error) => return self.send_response::<()>(request, Err(error)),
+            },
+            None => probe_rs::architecture::riscv::MACHINE_CSRS.to_vec(),
+        };
+        if let Some(value) = &arguments.value {
+            let write_result = match (parse::<u32>(value), csrs.first()) {
+                (Ok(value), Some(csr)) if arguments.name.is_some() => {
+                    riscv_csrs::write_csr(&mut target_core.core, csr, value)
+                }
+                (Ok(_), _) => Err(DebuggerError::Other(anyhow!(
+                    "The name of the CSR is required to write a value"
+                ))),
+                (Err(error), _) => Err(DebuggerError::Other(anyhow!(
+                    "Invalid CSR value {value:?}: {error}"
+                ))),
+            };
+            if let Err(error) = write_result {
+                return self.send_response::<()>(request, Err(error));
+            }
+        }
+
+        let csrs = csrs
+            .iter()
+            .map(
+                |csr| match riscv_csrs::read_csr(&mut target_core.core, csr) {
+                    Ok(value) => RiscvCsrInfo {
+                        name: csr.name.to_string(),
+                        address: format!("{:#05x}", csr.address),
+                        value: format!("{value:#010x}"),
+                        decoded: riscv_csrs::decode_csr(csr, value),
+                    },
+                    Err(error) => RiscvCsrInfo {
+                        name: csr.name.to_string(),
+                        address: format!("{:#05x}", csr.address),
+                        value: error.to_string(),
+                        decoded: None,
+                    },
+                },
+            )
+            .collect();
+        self.send_response(request, Ok(Some(RiscvCsrResponseBody { csrs })))
+    }
+
+    /// Custom `rttWrite` request, to send data to the target on an RTT down channel.
+    /// The data is encoded with the down channel format, which can differ from the format of the paired up channel.
+    pub(crate) fn rtt_write(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: RttWriteArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        match encode_rtt_input(target_core, arguments.channel_number, &arguments.data)
+            .and_then(|encoded_data| target_core.write_rtt(arguments.channel_number, &encoded_data))
+        {
+            Ok(bytes_written) => self.send_response(
+                request,
+                Ok(Some(RttWriteResponseBody {
+                    bytes_written: bytes_written as u64,
+                })),
+            ),
+            Err(error) => self.send_response::<()>(request, Err(error)),
+        }
+    }
+
+    /// Custom `rttDefmtFilter` request, to change the filter for the defmt frames of an RTT channel, without restarting the session.
+    pub(crate) fn rtt_defmt_filter(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: RttDefmtFilterArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        let defmt_filter = match arguments.filter.as_deref().map(str::parse).transpose() {
+            Ok(defmt_filter) => defmt_filter,
+            Err(error) => {
+                return self.send_response::<()>(request, Err(DebuggerError::Other(error)))
+            }
+        };
+        let result = match target_core.core_data.rtt_connection.as_mut() {
+            Some(rtt_connection) => {
+                rtt_connection.set_defmt_filter(arguments.channel_number, defmt_filter)
+            }
+            None => Err(DebuggerError::Other(anyhow!(
+                "RTT is not attached to the target"
+            ))),
+        };
+        self.send_response::<()>(request, result.map(|()| None))
+    }
+
+    /// Custom `rttChannels` request, to list the active RTT channels, with their data formats, and whether the client has opened a window for them.
+    pub(crate) fn rtt_channels(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let rtt_connection = match target_core.core_data.rtt_connection.as_ref() {
+            Some(rtt_connection) => rtt_connection,
+            None => {
                 return self.send_response::<()>(
                     request,
                     Err(DebuggerError::Other(anyhow!(
-                        "Could not read any data at address {:?}",
-                        arguments.memory_reference
+                        "RTT is not attached to the target"
                     ))),
-                );
+                )
+            }
+        };
+        let channels = rtt_connection
+            .target_rtt
+            .active_channels
+            .iter()
+            .map(|active_channel| RttChannelInfo {
+                channel_number: active_channel.number(),
+                down_channel_number: active_channel.down_number(),
+                channel_name: active_channel.channel_name.clone(),
+                data_format: active_channel.data_format,
+                down_data_format: active_channel
+                    .down_number()
+                    .map(|_| active_channel.down_data_format),
+                has_client_window: active_channel.number().map_or(false, |channel_number| {
+                    rtt_connection
+                        .debugger_rtt_channels
+                        .iter()
+                        .any(|debugger_rtt_channel| {
+                            debugger_rtt_channel.channel_number == channel_number
+                                && debugger_rtt_channel.has_client_window
+                        })
+                }),
+            })
+            .collect();
+        self.send_response(request, Ok(Some(RttChannelsResponseBody { channels })))
+    }
+
+    /// Send a line that was typed in the debug console to the target, on the RTT down channel with the lowest number.
+    /// For the `String` format, a newline is appended, which is what a command line interface on the target expects.
+    fn rtt_console_input(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+        input: &str,
+    ) -> Result<()> {
+        let channel_number =
+            match target_core
+                .core_data
+                .rtt_connection
+                .as_ref()
+                .and_then(|rtt_connection| {
+                    rtt_connection
+                        .debugger_rtt_down_channels
+                        .iter()
+                        .map(|down_channel| down_channel.channel_number)
+                        .min()
+                }) {
+                Some(channel_number) => channel_number,
+                None => {
+                    return self.send_response::<()>(
+                        request,
+                        Err(DebuggerError::Other(anyhow!(
+                            "There is no RTT down channel to send the input to"
+                        ))),
+                    )
+                }
             };
-        let mut num_bytes_unread = arguments.count as usize;
-        // The probe-rs API does not return partially read data.
-        // It either succeeds for the whole buffer or not. However, doing single byte reads is slow, so we will
-        // do reads in larger chunks, until we get an error, and then do single byte reads for the last few bytes, to make
-        // sure we get all the data we can.
-        let mut result_buffer = vec![];
-        let large_read_byte_count = 8usize;
-        let mut fast_buff = vec![0u8; large_read_byte_count];
-        // Read as many large chunks as possible.
-        while num_bytes_unread > 0 {
-            if let Ok(()) = target_core.core.read(address, &mut fast_buff) {
-                result_buffer.extend_from_slice(&fast_buff);
-                address += large_read_byte_count as u64;
-                num_bytes_unread -= large_read_byte_count;
-            } else {
-                break;
+        let is_string_format = target_core
+            .core_data
+            .rtt_connection
+            .as_ref()
+            .and_then(|rtt_connection| {
+                rtt_connection
+                    .target_rtt
+                    .active_channels
+                    .iter()
+                    .find(|active_channel| active_channel.down_number() == Some(channel_number))
+            })
+            .map_or(false, |active_channel| {
+                active_channel.down_data_format == rtt::DataFormat::String
+            });
+        let input = if is_string_format {
+            format!("{input}\n")
+        } else {
+            input.to_string()
+        };
+        match encode_rtt_input(target_core, channel_number, &input).and_then(|encoded_data| {
+            target_core
+                .write_rtt(channel_number, &encoded_data)
+                .map(|_| encoded_data.len())
+        }) {
+            Ok(bytes_sent) => self.send_response(
+                request,
+                Ok(Some(EvaluateResponseBody {
+                    indexed_variables: None,
+                    memory_reference: None,
+                    named_variables: None,
+                    presentation_hint: None,
+                    result: format!("Sent {bytes_sent} bytes to RTT channel {channel_number}"),
+                    type_: None,
+                    variables_reference: 0,
+                })),
+            ),
+            Err(error) => self.send_response::<()>(request, Err(error)),
+        }
+    }
+
+    /// Custom `dumpMemory` request, to write a memory range directly to a binary file, rather than sending it to the client.
+    /// The response includes the CRC-32 of the data, so that the integrity of the dump can be confirmed.
+    pub(crate) fn dump_memory(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: DumpMemoryArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        let start_address = match parse::<u64>(arguments.memory_reference.as_ref()) {
+            Ok(address) => address,
+            Err(error) => {
+                return self.send_response::<()>(
+                    request,
+                    Err(DebuggerError::Other(anyhow!(
+                        "Invalid memory reference {:?}: {error}",
+                        arguments.memory_reference
+                    ))),
+                )
             }
+        };
+        if let Err(error) = target_core.check_memory_range(start_address, arguments.count) {
+            return self.send_response::<()>(request, Err(error));
         }
-        // Read the remaining bytes one by one.
-        while num_bytes_unread > 0 {
-            if let Ok(good_byte) = target_core.core.read_word_8(address) {
-                result_buffer.push(good_byte);
-                address += 1;
-                num_bytes_unread -= 1;
-            } else {
-                break;
+        let mut dump_file = match File::create(&arguments.path) {
+            Ok(dump_file) => BufWriter::new(dump_file),
+            Err(error) => {
+                return self.send_response::<()>(
+                    request,
+                    Err(DebuggerError::Other(anyhow!(
+                        "Failed to create {:?}: {error}",
+                        arguments.path
+                    ))),
+                )
+            }
+        };
+
+        let progress_id = self
+            .start_cancellable_progress(
+                &format!("Dumping memory to {:?}", arguments.path),
+                Some(request.seq),
+            )
+            .ok();
+        let mut crc = Crc32::new();
+        let mut buffer = vec![0_u8; MEMORY_DUMP_CHUNK_SIZE];
+        let mut bytes_written = 0_u64;
+        let dump_result = loop {
+            if bytes_written >= arguments.count {
+                break Ok(false);
             }
+            if self.cancel_requested(request.seq, progress_id) {
+                break Ok(true);
+            }
+            let chunk_size = (arguments.count - bytes_written).min(buffer.len() as u64) as usize;
+            let chunk = &mut buffer[..chunk_size];
+            if let Err(error) = target_core
+                .core
+                .read(start_address + bytes_written, chunk)
+                .map_err(DebuggerError::ProbeRs)
+                .and_then(|_| dump_file.write_all(chunk).map_err(DebuggerError::StdIO))
+            {
+                break Err(error);
+            }
+            crc.update(chunk);
+            bytes_written += chunk_size as u64;
+            if let Some(progress_id) = progress_id {
+                self.update_progress(
+                    Some(bytes_written as f64 / arguments.count as f64),
+                    Some(format!("{bytes_written} of {} bytes", arguments.count)),
+                    progress_id,
+                )
+                .ok();
+            }
+        }
+        .and_then(|cancelled| {
+            dump_file
+                .flush()
+                .map(|_| cancelled)
+                .map_err(DebuggerError::StdIO)
+        });
+        if let Some(progress_id) = progress_id {
+            self.end_progress(progress_id).ok();
         }
-        if !result_buffer.is_empty() || arguments.count.is_zero() {
-            // Currently, VSCode sends a request with count=0 after the last successful one ... so let's ignore it.
-            let response = base64_engine::STANDARD.encode(&result_buffer);
-            self.send_response(
+
+        match dump_result {
+            Ok(false) => self.send_response(
                 request,
-                Ok(Some(ReadMemoryResponseBody {
-                    address: format!("{address:#010x}"),
-                    data: Some(response),
-                    unreadable_bytes: if num_bytes_unread.is_zero() {
-                        None
-                    } else {
-                        Some(num_bytes_unread as i64)
-                    },
+                Ok(Some(DumpMemoryResponseBody {
+                    path: arguments.path,
+                    bytes_written,
+                    crc32: format!("{:#010x}", crc.finalize()),
                 })),
-            )
+            ),
+            Ok(true) => self.send_response::<()>(
+                request,
+                Err(DebuggerError::Other(anyhow!(
+                    "Memory dump cancelled after {bytes_written} bytes were written to {:?}",
+                    arguments.path
+                ))),
+            ),
+            Err(error) => self.send_response::<()>(request, Err(error)),
+        }
+    }
+
+    pub(crate) fn save_core_dump(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: SaveCoreDumpArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        match target_core.save_core_dump(&arguments.path) {
+            Ok(ram_bytes) => {
+                let stack_frames = target_core
+                    .core_data
+                    .stack_frames
+                    .iter()
+                    .map(|stack_frame| stack_frame.function_name.clone())
+                    .collect();
+                self.send_response(
+                    request,
+                    Ok(Some(SaveCoreDumpResponseBody {
+                        path: arguments.path,
+                        ram_bytes,
+                        stack_frames,
+                    })),
+                )
+            }
+            Err(error) => self.send_response::<()>(request, Err(error)),
+        }
+    }
+
+    pub(crate) fn read_memory(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: ReadMemoryArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        let memory_offset = arguments.offset.unwrap_or(0);
+        let address: u64 = if let Ok(address) = parse::<u64>(arguments.memory_reference.as_ref()) {
+            address + memory_offset as u64
         } else {
-            self.send_response::<()>(
+            return self.send_response::<()>(
                 request,
                 Err(DebuggerError::Other(anyhow!(
-                    "Could not read any data at address {:#010x}",
-                    address
+                    "Could not read any data at address {:?}",
+                    arguments.memory_reference
                 ))),
-            )
+            );
+        };
+        match target_core.read_memory(address, arguments.count as usize) {
+            Ok(result_buffer) => {
+                let num_bytes_unread = arguments.count as usize - result_buffer.len();
+                self.send_response(
+                    request,
+                    Ok(Some(ReadMemoryResponseBody {
+                        address: format!("{address:#010x}"),
+                        data: Some(base64_engine::STANDARD.encode(&result_buffer)),
+                        unreadable_bytes: if num_bytes_unread.is_zero() {
+                            None
+                        } else {
+                            Some(num_bytes_unread as i64)
+                        },
+                    })),
+                )
+            }
+            Err(error) => self.send_response::<()>(request, Err(error)),
         }
     }
 
@@ -259,11 +1120,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 );
             }
         };
-        match target_core
-            .core
-            .write_8(address, &data_bytes)
-            .map_err(DebuggerError::ProbeRs)
-        {
+        match target_core.write_memory(address, &data_bytes) {
             Ok(_) => {
                 self.send_response(
                     request,
@@ -300,6 +1157,13 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             Err(error) => return self.send_response::<()>(request, Err(error)),
         };
 
+        // In the debug console, a line that starts with `>` is sent to the target over RTT, e.g. to use a shell on the target.
+        if arguments.context.as_deref() == Some("repl") {
+            if let Some(input) = arguments.expression.strip_prefix('>') {
+                return self.rtt_console_input(target_core, request, input);
+            }
+        }
+
         // Various fields in the response_body will be updated before we return.
         let mut response_body = EvaluateResponseBody {
             indexed_variables: None,
@@ -314,6 +1178,15 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         // The Variables request sometimes returns the variable name, and other times the variable id, so this expression will be tested to determine if it is an id or not.
         let expression = arguments.expression.clone();
 
+        // The registers of SVD peripherals are only loaded when they are needed, so load the peripheral that the expression refers to.
+        if let Some(core_peripherals) = &mut target_core.core_data.core_peripherals {
+            if let Err(error) =
+                core_peripherals.expand_peripheral_for_name(&mut target_core.core, &expression)
+            {
+                tracing::warn!("{error}");
+            }
+        }
+
         // Make sure we have a valid StackFrame
         if let Some(stack_frame) = match arguments.frame_id {
             Some(frame_id) => target_core
@@ -367,8 +1240,29 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         }
                     }
                 }
+                // If the expression is not the name of a variable, then resolve any member access, array indexing, and pointer dereference in it.
+                let found_variable = match (variable, variable_cache) {
+                    (Some(variable), Some(variable_cache)) => Some((variable, variable_cache)),
+                    _ => match expression::parse_expression(&expression) {
+                        Ok(parsed_expression) if parsed_expression.has_accessors() => {
+                            match expression::evaluate_expression(
+                                &parsed_expression,
+                                stack_frame,
+                                &target_core.core_data.debug_info,
+                                &mut target_core.core,
+                            ) {
+                                Ok(found_variable) => Some(found_variable),
+                                // In the watch pane, the expression is reported as out of scope, below.
+                                Err(_) if arguments.context.as_deref() == Some("watch") => None,
+                                Err(error) => return self.send_response::<()>(request, Err(error)),
+                            }
+                        }
+                        // A name that is not a variable may still be an ELF symbol, below.
+                        _ => None,
+                    },
+                };
                 // Check if we found a variable.
-                if let (Some(variable), Some(variable_cache)) = (variable, variable_cache) {
+                if let Some((variable, variable_cache)) = found_variable {
                     let (
                         variables_reference,
                         named_child_variables_cnt,
@@ -386,6 +1280,67 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             }
         }
 
+        // Symbols that are not described by DWARF (e.g. assembly labels, or linker defined symbols like `_stack_start`) are resolved from the ELF symbol table.
+        // `&symbol` resolves to the address of the symbol, and `symbol` resolves to its value, for data symbols of up to 8 bytes.
+        if response_body.type_.is_none() {
+            let (symbol_name, address_only) = match expression.strip_prefix('&') {
+                Some(symbol_name) => (symbol_name.trim(), true),
+                None => (expression.as_str(), false),
+            };
+            if let Some(symbol) =
+                elf_symbols::find_elf_symbol(&target_core.core_data.elf_symbols, symbol_name)
+            {
+                response_body.memory_reference = Some(format!("{:#010x}", symbol.address));
+                response_body.type_ = Some(format!("ELF symbol ({} bytes)", symbol.size));
+                response_body.result = format!("{:#010x}", symbol.address);
+                if !address_only && symbol.is_data && (1..=8).contains(&symbol.size) {
+                    let mut value_bytes = vec![0_u8; symbol.size as usize];
+                    response_body.result =
+                        match target_core.core.read_8(symbol.address, &mut value_bytes) {
+                            Ok(()) => {
+                                // The value is assumed to be little-endian, like the supported targets.
+                                let value = value_bytes
+                                    .iter()
+                                    .rev()
+                                    .fold(0_u64, |value, byte| (value << 8) | *byte as u64);
+                                format!("{value} ({value:#x})")
+                            }
+                            Err(error) => {
+                                format!(
+                                    "<failed to read {symbol_name} at {:#010x}: {error}>",
+                                    symbol.address
+                                )
+                            }
+                        };
+                }
+            }
+        }
+
+        if arguments.context.as_deref() == Some("watch") {
+            // An expression that cannot be evaluated, e.g. a variable that is not in scope of the current frame, does not fail the refresh of the watch pane.
+            if response_body.type_.is_none() {
+                response_body.result = expression::OUT_OF_SCOPE.to_string();
+            }
+            let watch_value = target_core
+                .core_data
+                .watch_values
+                .entry(expression)
+                .or_insert_with(|| expression::WatchValue {
+                    previous_value: None,
+                    value: response_body.result.clone(),
+                    requested: false,
+                });
+            watch_value.requested = true;
+            let changed = watch_value.changed(&response_body.result);
+            return self.send_response(
+                request,
+                Ok(Some(WatchEvaluateResponseBody {
+                    evaluate_response: response_body,
+                    changed,
+                })),
+            );
+        }
+
         self.send_response(request, Ok(Some(response_body)))
     }
 
@@ -415,7 +1370,35 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         let parent_key = arguments.variables_reference;
         let new_value = arguments.value.clone();
 
-        //TODO: Check for, and prevent SVD Peripheral/Register/Field values from being updated, until such time as we can do it safely.
+        // The parent_key refers to a peripheral or register in the SVD peripheral tree.
+        if let Some(core_peripherals) = &mut target_core.core_data.core_peripherals {
+            if core_peripherals
+                .svd_variable_cache
+                .get_variable_by_key(parent_key)
+                .is_some()
+            {
+                return match core_peripherals.write_child_by_name(
+                    &mut target_core.core,
+                    parent_key,
+                    &arguments.name,
+                    &new_value,
+                ) {
+                    Ok(updated_value) => {
+                        response_body.value = updated_value;
+                        self.send_response(request, Ok(Some(response_body)))
+                    }
+                    Err(error) => self.send_response::<SetVariableResponseBody>(
+                        request,
+                        Err(DebuggerError::Other(anyhow!(
+                            "Failed to update {}, with new value {:?} : {}",
+                            arguments.name,
+                            new_value,
+                            error
+                        ))),
+                    ),
+                };
+            }
+        }
 
         match target_core
             .core_data
@@ -470,10 +1453,11 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     (cache_variable, variable_cache)
                 {
                     // We have found the variable that needs to be updated.
-                    match cache_variable.update_value(
+                    match set_variable::write_variable(
                         &mut target_core.core,
+                        &cache_variable,
                         variable_cache,
-                        new_value.clone(),
+                        &new_value,
                     ) {
                         Ok(updated_value) => {
                             let (
@@ -491,7 +1475,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             return self.send_response::<SetVariableResponseBody>(
                                 request,
                                 Err(DebuggerError::Other(anyhow!(
-                                    "Failed to update variable: {}, with new value {:?} : {:?}",
+                                    "Failed to update variable: {}, with new value {:?} : {}",
                                     cache_variable.name,
                                     new_value,
                                     error
@@ -538,32 +1522,41 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         }
 
         target_core.reset_core_status(self);
+        // The ignored exceptions are counted per run of the program.
+        target_core.core_data.ignored_exception_counts.clear();
         // Different code paths if we invoke this from a request, versus an internal function.
         if let Some(request) = request {
+            // The startup code may copy code to RAM, so restore the original instructions before the reset, and write the `BKPT` instructions again after it.
+            if let Err(error) = target_core.restore_software_breakpoints() {
+                tracing::warn!(
+                    "Failed to restore the instructions of the software breakpoints: {error}"
+                );
+            }
             // Use reset_and_halt(), and then resume again afterwards, depending on the reset_after_halt flag.
             match target_core.core.reset_and_halt(Duration::from_millis(500)) {
                 Ok(_) => {
+                    if let Err(error) = target_core.rewrite_software_breakpoints() {
+                        tracing::warn!("Failed to set the software breakpoints again: {error}");
+                    }
                     // Ensure ebreak enters debug mode, this is necessary for soft breakpoints to work on architectures like RISC-V.
                     target_core.core.debug_on_sw_breakpoint(true)?;
 
                     // For RISC-V, we need to re-enable any breakpoints that were previously set, because the core reset 'forgets' them.
                     if target_core.core.architecture() == Riscv {
-                        let saved_breakpoints = target_core
+                        let breakpoint_addresses = target_core
                             .core_data
                             .breakpoints
-                            .drain(..)
-                            .collect::<Vec<ActiveBreakpoint>>();
-                        for breakpoint in saved_breakpoints {
-                            match target_core.set_breakpoint(
-                                breakpoint.address,
-                                breakpoint.breakpoint_type.clone(),
-                            ) {
+                            .iter()
+                            .map(|breakpoint| breakpoint.address)
+                            .collect::<Vec<u64>>();
+                        for breakpoint_address in breakpoint_addresses {
+                            match target_core.core.set_hw_breakpoint(breakpoint_address) {
                                 Ok(_) => {}
                                 Err(error) => {
                                     //This will cause the debugger to show the user an error, but not stop the debugger.
                                     tracing::error!(
-                                        "Failed to re-enable breakpoint {:?} after reset. {}",
-                                        breakpoint,
+                                        "Failed to re-enable breakpoint at {:#010x} after reset. {}",
+                                        breakpoint_address,
                                         error
                                     );
                                 }
@@ -577,7 +1570,9 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             Ok(_) => {
                                 self.send_response::<()>(request, Ok(None))?;
                                 let event_body = Some(ContinuedEventBody {
-                                    all_threads_continued: Some(false), // TODO: Implement multi-core logic here
+                                    all_threads_continued: Some(
+                                        self.other_cores_running(target_core.core.id()),
+                                    ),
                                     thread_id: target_core.core.id() as i64,
                                 });
                                 self.send_event("continued", event_body)?;
@@ -659,6 +1654,210 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response::<()>(request, Ok(None))
     }
 
+    /// Return the lines and columns, between `line` and `endLine` of the requested source, where a breakpoint can be placed.
+    /// Lines without a statement boundary in the line table (e.g. comments, or code that was optimized away) are not included.
+    pub(crate) fn breakpoint_locations(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: BreakpointLocationsArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        let source_path = match arguments.source.path.as_ref() {
+            Some(source_path) => Path::new(source_path),
+            None => {
+                return self.send_response::<()>(
+                    request,
+                    Err(DebuggerError::Other(anyhow!(
+                        "Breakpoint locations can only be requested for a source with a path."
+                    ))),
+                )
+            }
+        };
+        // The debug information uses 1 based line numbers, so translate from, and back to, the numbering of the client.
+        let line_offset = u64::from(!self.lines_start_at_1);
+        let column_offset = u64::from(!self.columns_start_at_1);
+        let start_line = arguments.line as u64 + line_offset;
+        let end_line = arguments
+            .end_line
+            .map_or(start_line, |end_line| end_line as u64 + line_offset);
+
+        let breakpoints = match target_core.core_data.debug_info.get_breakpoint_locations(
+            source_path,
+            start_line,
+            end_line,
+        ) {
+            Ok(locations) => locations
+                .into_iter()
+                .map(|(line, column)| BreakpointLocation {
+                    line: line.saturating_sub(line_offset) as i64,
+                    // A location without a column is at the left edge of the line.
+                    column: Some(column.unwrap_or(1).saturating_sub(column_offset) as i64),
+                    end_line: None,
+                    end_column: None,
+                })
+                .collect(),
+            Err(error) => {
+                return self.send_response::<()>(
+                    request,
+                    Err(DebuggerError::Other(anyhow!(
+                        "Failed to find the breakpoint locations for {:?}: {error}",
+                        source_path
+                    ))),
+                )
+            }
+        };
+
+        self.send_response(
+            request,
+            Ok(Some(BreakpointLocationsResponseBody { breakpoints })),
+        )
+    }
+
+    /// Return the `dataId` of a variable that can be watched with a data breakpoint, e.g. with VSCode's "Break on Value Change".
+    /// Only variables of 1, 2 or 4 bytes, that are stored in memory at an aligned address, can be watched.
+    pub(crate) fn data_breakpoint_info(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: DataBreakpointInfoArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+
+        let variable = target_core
+            .core_data
+            .stack_frames
+            .iter()
+            .flat_map(|stack_frame| {
+                [
+                    stack_frame.local_variables.as_ref(),
+                    stack_frame.static_variables.as_ref(),
+                ]
+            })
+            .flatten()
+            .find_map(|variable_cache| match arguments.variables_reference {
+                Some(parent_key) => variable_cache
+                    .get_children(Some(parent_key))
+                    .ok()?
+                    .into_iter()
+                    .find(|variable| variable.name.to_string() == arguments.name),
+                None => variable_cache
+                    .get_variable_by_name(&VariableName::Named(arguments.name.clone())),
+            });
+        let location = variable.and_then(|variable| {
+            Some((
+                variable.memory_location.memory_address().ok()?,
+                variable.byte_size?,
+            ))
+        });
+
+        let response_body = match location {
+            Some((address, byte_size))
+                if matches!(byte_size, 1 | 2 | 4) && address % byte_size == 0 =>
+            {
+                DataBreakpointInfoResponseBody {
+                    data_id: Some(format!("{address:#010x}/{byte_size}")),
+                    description: format!(
+                        "{} ({byte_size} bytes at {address:#010x})",
+                        arguments.name
+                    ),
+                    access_types: Some(vec![
+                        DataBreakpointAccessType::Read,
+                        DataBreakpointAccessType::Write,
+                        DataBreakpointAccessType::ReadWrite,
+                    ]),
+                    can_persist: Some(false),
+                }
+            }
+            Some((address, byte_size)) => DataBreakpointInfoResponseBody {
+                data_id: None,
+                description: format!(
+                    "Cannot watch {} ({byte_size} bytes at {address:#010x}). Data breakpoints require a size of 1, 2 or 4 bytes, at an aligned address.",
+                    arguments.name
+                ),
+                access_types: None,
+                can_persist: None,
+            },
+            None => DataBreakpointInfoResponseBody {
+                data_id: None,
+                description: format!("Cannot watch {}, because it is not stored in memory.", arguments.name),
+                access_types: None,
+                can_persist: None,
+            },
+        };
+        self.send_response(request, Ok(Some(response_body)))
+    }
+
+    /// Replace all the data breakpoints with the requested ones, using the DWT comparators of the core.
+    /// Data breakpoints that cannot be set, e.g. because no free comparators remain, are reported as not verified.
+    pub(crate) fn set_data_breakpoints(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: SetDataBreakpointsArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        if let Err(error) = target_core.clear_data_watchpoints() {
+            return self.send_response::<()>(request, Err(error));
+        }
+
+        let breakpoints = arguments
+            .breakpoints
+            .iter()
+            .map(|data_breakpoint| {
+                let access = data_breakpoint
+                    .access_type
+                    .clone()
+                    .map_or(WatchKind::Write, WatchKind::from);
+                match data_watchpoints::parse_data_id(&data_breakpoint.data_id).and_then(
+                    |(address, size)| {
+                        target_core
+                            .set_data_watchpoint(address, size, access)
+                            .map(|id| (id, address))
+                    },
+                ) {
+                    Ok((id, address)) => Breakpoint {
+                        column: None,
+                        end_column: None,
+                        end_line: None,
+                        id: Some(id),
+                        line: None,
+                        message: Some(format!(
+                            "Data breakpoint at memory address: {address:#010X}"
+                        )),
+                        source: None,
+                        instruction_reference: None,
+                        offset: None,
+                        verified: true,
+                    },
+                    Err(error) => Breakpoint {
+                        column: None,
+                        end_column: None,
+                        end_line: None,
+                        id: None,
+                        line: None,
+                        message: Some(error.to_string()),
+                        source: None,
+                        instruction_reference: None,
+                        offset: None,
+                        verified: false,
+                    },
+                }
+            })
+            .collect();
+        self.send_response(
+            request,
+            Ok(Some(SetDataBreakpointsResponseBody { breakpoints })),
+        )
+    }
+
     pub(crate) fn set_breakpoints(
         &mut self,
         target_core: &mut CoreHandle,
@@ -682,7 +1881,8 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         if let Some(source_path) = args.source.path.as_ref().map(Path::new) {
             // Always clear existing breakpoints for the specified `[crate::debug_adapter::dap_types::Source]` before setting new ones.
             // The DAP Specification doesn't make allowances for deleting and setting individual breakpoints for a specific `Source`.
-            match target_core.clear_breakpoints(None) {
+            // Breakpoints in other sources, and instruction breakpoints from the disassembly view, are managed separately.
+            match target_core.clear_source_breakpoints(source_path) {
                 Ok(_) => {}
                 Err(error) => {
                     return self.send_response::<()>(
@@ -718,27 +1918,82 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         requested_breakpoint_line,
                         requested_breakpoint_column,
                         &args.source,
+                        bp.condition.clone(),
+                        bp.hit_condition.clone(),
+                        bp.log_message.clone(),
                     ) {
-                        Ok(VerifiedBreakpoint {
-                            address,
-                            source_location,
-                        }) => created_breakpoints.push(Breakpoint {
-                            column: source_location.column.map(|col| match col {
-                                ColumnType::LeftEdge => 0_i64,
-                                ColumnType::Column(c) => c as i64,
-                            }),
-                            end_column: None,
-                            end_line: None,
-                            id: None,
-                            line: source_location.line.map(|line| line as i64),
-                            message: Some(format!(
-                                "Source breakpoint at memory address: {address:#010X}"
-                            )),
-                            source: None,
-                            instruction_reference: Some(format!("{address:#010X}")),
-                            offset: None,
-                            verified: true,
-                        }),
+                        Ok((
+                            VerifiedBreakpoint {
+                                address,
+                                source_location,
+                            },
+                            id,
+                        )) => {
+                            // Report where the breakpoint was actually set, in the numbering of the client, so that it shows the breakpoint there.
+                            let actual_line = source_location.line;
+                            let actual_column = source_location.column.map(|col| match col {
+                                ColumnType::LeftEdge => 1,
+                                ColumnType::Column(c) => c,
+                            });
+                            let line_offset = u64::from(!self.lines_start_at_1);
+                            let column_offset = u64::from(!self.columns_start_at_1);
+                            let message = match actual_line {
+                                Some(actual_line)
+                                    if actual_line.abs_diff(requested_breakpoint_line)
+                                        > MAX_SILENT_BREAKPOINT_LINE_MOVE =>
+                                {
+                                    format!("The breakpoint was moved from line {requested_breakpoint_line} to line {actual_line}, the nearest line with code, at memory address: {address:#010X}")
+                                }
+                                _ => {
+                                    format!("Source breakpoint at memory address: {address:#010X}")
+                                }
+                            };
+                            created_breakpoints.push(Breakpoint {
+                                column: actual_column
+                                    .map(|column| column.saturating_sub(column_offset) as i64),
+                                end_column: None,
+                                end_line: None,
+                                id: Some(id),
+                                line: actual_line
+                                    .map(|line| line.saturating_sub(line_offset) as i64),
+                                message: Some(message),
+                                source: None,
+                                instruction_reference: Some(format!("{address:#010X}")),
+                                offset: None,
+                                verified: true,
+                            })
+                        }
+                        Err(error @ DebuggerError::NoBreakpointLocation(_)) => {
+                            // Keep the breakpoint as unverified, so that it can be set once a binary with its source location is flashed.
+                            // If it cannot be kept either (e.g. because of an invalid hit condition), report that error instead.
+                            let (pending_id, message) = match target_core.set_pending_breakpoint(
+                                source_path,
+                                requested_breakpoint_line,
+                                requested_breakpoint_column,
+                                &args.source,
+                                bp.condition.clone(),
+                                bp.hit_condition.clone(),
+                                bp.log_message.clone(),
+                            ) {
+                                Ok(pending_id) => (
+                                    Some(pending_id),
+                                    format!("{error}. The breakpoint is pending, and will be set when a binary with this source location is flashed."),
+                                ),
+                                Err(pending_error) => (None, pending_error.to_string()),
+                            };
+                            created_breakpoints.push(Breakpoint {
+                                column: None,
+                                end_column: None,
+                                end_line: None,
+                                id: pending_id,
+                                line: Some(bp.line),
+                                message: Some(message),
+                                source: None,
+                                instruction_reference: None,
+                                offset: None,
+                                verified: false,
+                            })
+                        }
                         Err(error) => created_breakpoints.push(Breakpoint {
                             column: None,
                             end_column: None,
@@ -749,12 +2004,16 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             source: None,
                             instruction_reference: None,
                             offset: None,
-                            verified: true,
+                            verified: false,
                         }),
                     };
                 }
             }
 
+            if let Err(error) = target_core.save_breakpoints() {
+                tracing::warn!("Failed to save the breakpoints: {error}");
+            }
+
             let breakpoint_body = SetBreakpointsResponseBody {
                 breakpoints: created_breakpoints,
             };
@@ -791,7 +2050,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         let mut created_breakpoints: Vec<Breakpoint> = Vec::new();
 
         // Always clear existing breakpoints before setting new ones.
-        match target_core.clear_breakpoints(Some(BreakpointType::InstructionBreakpoint)) {
+        match target_core.clear_breakpoints(BreakpointType::InstructionBreakpoint) {
             Ok(_) => {}
             Err(error) => tracing::warn!("Failed to clear instruction breakpoints. {}", error),
         }
@@ -811,19 +2070,20 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 verified: false,
             };
 
-            if let Ok(memory_reference) =
-                if requested_breakpoint.instruction_reference.starts_with("0x")
-                    || requested_breakpoint.instruction_reference.starts_with("0X")
-                {
-                    u64::from_str_radix(&requested_breakpoint.instruction_reference[2..], 16)
-                } else {
-                    requested_breakpoint.instruction_reference.parse()
-                }
+            // The optional `offset` (in bytes) is relative to the instruction reference.
+            if let Ok(memory_reference) = parse::<u64>(&requested_breakpoint.instruction_reference)
+                .map(|address| {
+                    (address as i64).wrapping_add(requested_breakpoint.offset.unwrap_or(0)) as u64
+                })
             {
-                match target_core
-                    .set_breakpoint(memory_reference, BreakpointType::InstructionBreakpoint)
-                {
-                    Ok(_) => {
+                match target_core.set_breakpoint(
+                    memory_reference,
+                    BreakpointType::InstructionBreakpoint,
+                    requested_breakpoint.condition.clone(),
+                    requested_breakpoint.hit_condition.clone(),
+                ) {
+                    Ok(id) => {
+                        breakpoint_response.id = Some(id);
                         breakpoint_response.verified = true;
                         breakpoint_response.instruction_reference =
                             Some(format!("{memory_reference:#010x}"));
@@ -878,45 +2138,137 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, Ok(Some(instruction_breakpoint_body)))
     }
 
+    /// List the program binary of each core as a module. Cores that share a program binary share the module,
+    /// and cores with different program binaries (e.g. a bootloader and an application) each have their own module.
+    /// A bootloader and an application that run on the same core cannot both be listed, see [`program_modules::program_module`].
+    pub(crate) fn modules(
+        &mut self,
+        session_config: &SessionConfig,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: ModulesArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        let mut program_binaries: Vec<&std::path::Path> = vec![];
+        for program_binary in session_config
+            .core_configs
+            .iter()
+            .filter_map(|core_config| core_config.program_binary.as_deref())
+        {
+            if !program_binaries.contains(&program_binary) {
+                program_binaries.push(program_binary);
+            }
+        }
+        let modules = program_binaries
+            .iter()
+            .enumerate()
+            .filter_map(|(module_id, program_binary)| {
+                program_modules::program_module(module_id, program_binary)
+                    .map_err(|error| {
+                        tracing::warn!("Failed to read the module {:?}: {error}", program_binary);
+                    })
+                    .ok()
+            })
+            .collect::<Vec<Module>>();
+        let total_modules = modules.len() as i64;
+        let modules = modules
+            .into_iter()
+            .skip(arguments.start_module.unwrap_or(0).max(0) as usize)
+            .take(match arguments.module_count {
+                Some(module_count) if module_count > 0 => module_count as usize,
+                _ => usize::MAX,
+            })
+            .collect();
+        self.send_response(
+            request,
+            Ok(Some(ModulesResponseBody {
+                modules,
+                total_modules: Some(total_modules),
+            })),
+        )
+    }
+
     pub(crate) fn threads(&mut self, target_core: &mut CoreHandle, request: Request) -> Result<()> {
-        // TODO: Implement actual thread resolution. For now, we just use the core id as the thread id.
+        // The core id is used as the thread id. With RTOS awareness, the running task is shown as the thread of the core, and the other tasks as additional threads.
         let current_core_status = target_core.core.status()?;
         let mut threads: Vec<Thread> = vec![];
         if self.configuration_is_done() {
             // We can handle this request normally.
             if current_core_status.is_halted() {
+                let rtos_tasks = match target_core.core_data.rtos {
+                    Some(rtos_kernel) => rtos::read_rtos_tasks(
+                        rtos_kernel,
+                        &mut target_core.core,
+                        &target_core.core_data.debug_info,
+                        &target_core.core_data.elf_symbols,
+                        &mut target_core.core_data.rtos_layout,
+                    )
+                    .unwrap_or_else(|error| {
+                        tracing::warn!("Failed to read the RTOS tasks, showing a single thread for the core: {error}");
+                        vec![]
+                    }),
+                    None => vec![],
+                };
+                let core_thread_name = self.core_thread_name(target_core);
                 let single_thread = Thread {
                     id: target_core.core.id() as i64,
-                    name: target_core.core_data.target_name.clone(),
+                    name: match rtos_tasks
+                        .iter()
+                        .find(|rtos_task| rtos_task.state == rtos::TaskState::Running)
+                    {
+                        Some(running_task) => {
+                            format!("{core_thread_name}: {}", running_task.thread_name())
+                        }
+                        None => core_thread_name,
+                    },
                 };
                 threads.push(single_thread);
+                threads.extend(self.other_core_threads(target_core));
+                threads.extend(
+                    rtos_tasks
+                        .iter()
+                        .filter(|rtos_task| rtos_task.state != rtos::TaskState::Running)
+                        .map(|rtos_task| Thread {
+                            id: rtos_task.thread_id(),
+                            name: rtos_task.thread_name(),
+                        }),
+                );
                 // We do the actual stack trace here, because VSCode sometimes sends multiple StackTrace requests, which lead to unnecessary unwind processing.
                 // By doing it here, we do it once, and serve up the results when we get the StackTrace requests.
-                let regs = target_core.core.registers();
-                let pc = match target_core.core.read_core_reg(regs.program_counter()) {
-                    Ok(pc) => pc,
-                    Err(error) => {
-                        return self
-                            .send_response::<()>(request, Err(DebuggerError::ProbeRs(error)))
-                    }
-                };
-                tracing::debug!(
-                    "Updating the stack frame data for core #{}",
-                    target_core.core.id()
-                );
-
-                target_core.core_data.stack_frames = target_core
-                    .core_data
-                    .debug_info
-                    .unwind(&mut target_core.core, pc)?;
+                // The stack of the other cores is unwound by their first `stackTrace` request.
+                if let Err(error) = target_core.refresh_stack_frames() {
+                    return self.send_response::<()>(request, Err(error));
+                }
+                target_core.refresh_global_variables();
+                target_core.core_data.register_groups =
+                    match register_groups::read_register_groups(&mut target_core.core) {
+                        Ok(register_groups) => register_groups,
+                        Err(error) => {
+                            tracing::warn!("Failed to read the register groups: {error}");
+                            Vec::new()
+                        }
+                    };
+                return self.send_response(request, Ok(Some(ThreadsResponseBody { threads })));
+            } else if self.core_statuses.len() > 1 {
+                // Another core halted, and the client needs the threads of all the cores, including this running core.
+                threads.push(Thread {
+                    id: target_core.core.id() as i64,
+                    name: self.core_thread_name(target_core),
+                });
+                threads.extend(self.other_core_threads(target_core));
                 return self.send_response(request, Ok(Some(ThreadsResponseBody { threads })));
             }
         } else {
             // This is the initial call to `threads` that happens after the `configuration_done` request, and requires special handling. (see [`DebugAdapter.configuration_done`])
             self.configuration_done = true;
             // At startup, we have to make sure the DAP Client and the DAP Server are in sync with the status of the core.
+            // An `attach` session must not disturb the target, so the core is left in the status that it was in when the debugger attached.
+            let is_attach_session =
+                self.target_session_type == Some(TargetSessionType::AttachRequest);
             if current_core_status.is_halted() {
                 if self.halt_after_reset
+                    || is_attach_session
                     || matches!(
                         current_core_status,
                         CoreStatus::Halted(HaltReason::Breakpoint(_))
@@ -926,6 +2278,8 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         .core
                         .read_core_reg(target_core.core.registers().program_counter())
                         .ok();
+                    // The client is notified here, so `poll_core()` must not report the same halt again.
+                    target_core.core_data.last_known_status = current_core_status;
                     let event_body = Some(StoppedEventBody {
                         reason: current_core_status
                             .short_long_status(program_counter)
@@ -942,12 +2296,22 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 } else {
                     let single_thread = Thread {
                         id: target_core.core.id() as i64,
-                        name: target_core.core_data.target_name.clone(),
+                        name: self.core_thread_name(target_core),
                     };
                     threads.push(single_thread);
+                    threads.extend(self.other_core_threads(target_core));
                     self.send_response(request.clone(), Ok(Some(ThreadsResponseBody { threads })))?;
                     return self.r#continue(target_core, request);
                 }
+            } else if is_attach_session {
+                // The core keeps running until the user pauses it, and `poll_core()` must not report it as a change of status.
+                target_core.core_data.last_known_status = current_core_status;
+                threads.push(Thread {
+                    id: target_core.core.id() as i64,
+                    name: self.core_thread_name(target_core),
+                });
+                threads.extend(self.other_core_threads(target_core));
+                return self.send_response(request, Ok(Some(ThreadsResponseBody { threads })));
             }
         }
         self.send_response::<()>(
@@ -993,6 +2357,24 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             }
         };
 
+        if arguments.thread_id >= rtos::FIRST_TASK_THREAD_ID {
+            // The context of an RTOS task that is not running is saved on its own stack, which is not unwound (yet).
+            return self.send_response(
+                request,
+                Ok(Some(StackTraceResponseBody {
+                    stack_frames: vec![],
+                    total_frames: Some(0),
+                })),
+            );
+        }
+
+        // The stack of a core other than the first one is unwound by its first `stackTrace` request after it halts.
+        if target_core.core_data.stack_frames.is_empty() {
+            if let Err(error) = target_core.refresh_stack_frames() {
+                return self.send_response::<()>(request, Err(error));
+            }
+        }
+
         if let Some(levels) = arguments.levels {
             if let Some(start_frame) = arguments.start_frame {
                 // Determine the correct 'slice' of available [StackFrame]s to serve up ...
@@ -1075,8 +2457,9 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             .and_then(|sl| sl.line)
                             .unwrap_or(0) as i64;
 
+                        // Inlined functions do not have a frame of their own on the target, so their address is not shown.
                         let function_display_name = if frame.is_inlined {
-                            format!("{} #[inline]", frame.function_name)
+                            format!("{} [inlined]", frame.function_name)
                         } else {
                             format!("{} @{}", frame.function_name, frame.pc)
                         };
@@ -1164,6 +2547,30 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             }
         };
 
+        if let Some(global_root_variable) = target_core
+            .core_data
+            .global_variables
+            .as_ref()
+            .and_then(|global_variables| {
+                global_variables
+                    .get_variable_by_name_and_parent(&VariableName::GlobalScopeRoot, None)
+            })
+        {
+            dap_scopes.push(Scope {
+                line: None,
+                column: None,
+                end_column: None,
+                end_line: None,
+                expensive: true, // VSCode won't open this tree by default.
+                indexed_variables: None,
+                name: "Globals".to_string(),
+                presentation_hint: Some("globals".to_string()),
+                named_variables: None,
+                source: None,
+                variables_reference: global_root_variable.variable_key,
+            });
+        };
+
         tracing::trace!("Getting scopes for frame {}", arguments.frame_id,);
 
         if let Some(stack_frame) = target_core.get_stackframe(arguments.frame_id) {
@@ -1182,6 +2589,30 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 variables_reference: stack_frame.id,
             });
 
+            // The register groups hold the values of the halted core, so they only apply to the top stack frame.
+            if target_core
+                .core_data
+                .stack_frames
+                .first()
+                .map_or(false, |top_frame| top_frame.id == stack_frame.id)
+            {
+                for register_group in &target_core.core_data.register_groups {
+                    dap_scopes.push(Scope {
+                        line: None,
+                        column: None,
+                        end_column: None,
+                        end_line: None,
+                        expensive: true, // VSCode won't open this tree by default.
+                        indexed_variables: None,
+                        name: register_group.name.to_string(),
+                        presentation_hint: Some("registers".to_string()),
+                        named_variables: None,
+                        source: None,
+                        variables_reference: register_group.variables_reference,
+                    });
+                }
+            }
+
             if let Some(static_root_variable) =
                 stack_frame
                     .static_variables
@@ -1234,259 +2665,11 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     presentation_hint: Some("locals".to_string()),
                     named_variables: None,
                     source: None,
-                    variables_reference: locals_root_variable.variable_key,
-                });
-            };
-        }
-        self.send_response(request, Ok(Some(ScopesResponseBody { scopes: dap_scopes })))
-    }
-
-    /// Attempt to extract disassembled source code to supply the instruction_count required.
-    pub(crate) fn get_disassembled_source(
-        &mut self,
-        target_core: &mut CoreHandle,
-        // The program_counter where our desired instruction range is based.
-        memory_reference: i64,
-        // The number of bytes offset from the memory reference. Can be zero.
-        byte_offset: i64,
-        // The number of instruction offset from the memory reference. Can be zero.
-        instruction_offset: i64,
-        // The EXACT number of instructions to return in the result.
-        instruction_count: i64,
-    ) -> Result<Vec<dap_types::DisassembledInstruction>, DebuggerError> {
-        let target_instruction_set = target_core.core.instruction_set()?;
-        let mut cs = match target_instruction_set {
-            InstructionSet::Thumb2 => {
-                let mut capstone_builder = Capstone::new()
-                    .arm()
-                    .mode(armArchMode::Thumb)
-                    .endian(Endian::Little);
-                if matches!(target_core.core.core_type(), CoreType::Armv8m) {
-                    capstone_builder = capstone_builder
-                        .extra_mode(std::iter::once(capstone::arch::arm::ArchExtraMode::V8));
-                }
-                capstone_builder.build()
-            }
-            InstructionSet::A32 => Capstone::new()
-                .arm()
-                .mode(armArchMode::Arm)
-                .endian(Endian::Little)
-                .build(),
-            InstructionSet::A64 => Capstone::new()
-                .arm64()
-                .mode(aarch64ArchMode::Arm)
-                .endian(Endian::Little)
-                .build(),
-            InstructionSet::RV32 => Capstone::new()
-                .riscv()
-                .mode(riscvArchMode::RiscV32)
-                .endian(Endian::Little)
-                .build(),
-            InstructionSet::RV32C => Capstone::new()
-                .riscv()
-                .mode(riscvArchMode::RiscV32)
-                .endian(Endian::Little)
-                .extra_mode(std::iter::once(
-                    capstone::arch::riscv::ArchExtraMode::RiscVC,
-                ))
-                .build(),
-        }
-        .map_err(|err| anyhow!("Error creating capstone: {:?}", err))?;
-        let _ = cs.set_skipdata(true);
-
-        // Adjust instruction offset as required for variable length instruction sets.
-        let instruction_offset_as_bytes = match target_instruction_set {
-            InstructionSet::Thumb2 | InstructionSet::RV32C => {
-                // Since we cannot guarantee the size of individual instructions, let's assume we will read the 120% of the requested number of 16-bit instructions.
-                (instruction_offset
-                    * target_core
-                        .core
-                        .instruction_set()?
-                        .get_minimum_instruction_size() as i64)
-                    / 4
-                    * 5
-            }
-            InstructionSet::A32 | InstructionSet::A64 | InstructionSet::RV32 => {
-                instruction_offset
-                    * target_core
-                        .core
-                        .instruction_set()?
-                        .get_minimum_instruction_size() as i64
-            }
-        };
-
-        // The vector we will use to return results.
-        let mut assembly_lines: Vec<DisassembledInstruction> = vec![];
-
-        // The buffer to hold data we read from our target.
-        let mut code_buffer: Vec<u8> = vec![];
-
-        // Control whether we need to read target memory in order to disassemble the next instruction.
-        let mut read_more_bytes = true;
-
-        // The memory address for the next read from target memory. We have to manually adjust it to be word aligned, and make sure it doesn't underflow/overflow.
-        let mut read_pointer = if byte_offset.is_negative() {
-            Some(memory_reference.saturating_sub(byte_offset.abs()) as u64)
-        } else {
-            Some(memory_reference.saturating_add(byte_offset) as u64)
-        };
-        // We can't rely on the MSDAP arguments to result in a memory aligned address for us to read from, so we force the read_pointer to be a 32-bit memory_aligned address.
-        read_pointer = if instruction_offset_as_bytes.is_negative() {
-            read_pointer
-                .and_then(|rp| {
-                    rp.saturating_sub(instruction_offset_as_bytes.unsigned_abs())
-                        .checked_div(4)
-                })
-                .map(|rp_memory_aligned| rp_memory_aligned * 4)
-        } else {
-            read_pointer
-                .and_then(|rp| {
-                    rp.saturating_add(instruction_offset_as_bytes as u64)
-                        .checked_div(4)
-                })
-                .map(|rp_memory_aligned| rp_memory_aligned * 4)
-        };
-
-        // The memory address for the next instruction to be disassembled
-        let mut instruction_pointer = if let Some(read_pointer) = read_pointer {
-            read_pointer
-        } else {
-            let error_message = format!("Unable to calculate starting address for disassembly request with memory reference:{memory_reference:#010X}, byte offset:{byte_offset:#010X}, and instruction offset:{instruction_offset:#010X}.");
-            return Err(DebuggerError::Other(anyhow!(error_message)));
-        };
-
-        // We will only include source location data in a resulting instruction, if it is different from the previous one.
-        let mut stored_source_location = None;
-
-        // The MS DAP spec requires that we always have to return a fixed number of instructions.
-        while assembly_lines.len() < instruction_count as usize {
-            if read_more_bytes {
-                if let Some(current_read_pointer) = read_pointer {
-                    // All supported architectures use maximum 32-bit instructions, and require 32-bit memory aligned reads.
-                    match target_core.core.read_word_32(current_read_pointer) {
-                        Ok(new_word) => {
-                            // Advance the read pointer for next time we need it.
-                            read_pointer = if let Some(valid_read_pointer) =
-                                current_read_pointer.checked_add(4)
-                            {
-                                Some(valid_read_pointer)
-                            } else {
-                                // If this happens, the next loop will generate "invalid instruction" records.
-                                read_pointer = None;
-                                continue;
-                            };
-                            // Update the code buffer.
-                            for new_byte in new_word.to_le_bytes() {
-                                code_buffer.push(new_byte);
-                            }
-                        }
-                        Err(memory_read_error) => {
-                            // If we can't read data at a given address, then create a "invalid instruction" record, and keep trying.
-                            assembly_lines.push(dap_types::DisassembledInstruction {
-                                address: format!("{current_read_pointer:#010X}"),
-                                column: None,
-                                end_column: None,
-                                end_line: None,
-                                instruction: format!(
-                                    "<instruction address not readable : {memory_read_error:?}>"
-                                ),
-                                instruction_bytes: None,
-                                line: None,
-                                location: None,
-                                symbol: None,
-                            });
-                            read_pointer = Some(current_read_pointer.saturating_add(4));
-                            continue;
-                        }
-                    }
-                }
-            }
-
-            match cs.disasm_all(&code_buffer, instruction_pointer) {
-                Ok(instructions) => {
-                    if num_traits::Zero::is_zero(&instructions.len()) {
-                        // The capstone library sometimes returns an empty result set, instead of an Err. Catch it here or else we risk an infinte loop looking for a valid instruction.
-                        return Err(DebuggerError::Other(anyhow::anyhow!(
-                            "Disassembly encountered unsupported instructions at memory reference {:#010x?}",
-                            instruction_pointer
-                        )));
-                    }
-
-                    let mut result_instruction = instructions
-                        .iter()
-                        .map(|instruction| {
-                            // Before processing, update the code buffer appropriately
-                            code_buffer = code_buffer.split_at(instruction.len()).1.to_vec();
-
-                            // Variable width instruction sets my not use the full `code_buffer`, so we need to read ahead, to ensure we have enough code in the buffer to disassemble the 'widest' of instructions in the instruction set.
-                            read_more_bytes = code_buffer.len() < target_instruction_set.get_maximum_instruction_size() as usize;
-
-                            // Move the instruction_pointer for the next read.
-                            instruction_pointer += instruction.len() as u64;
-
-                            // Try to resolve the source location for this instruction.
-                            // If we find one, we use it ONLY if it is different from the previous one (stored_source_location).
-                            // - This helps to reduce visual noise in the VSCode UX, by not displaying the same line of source code multiple times over.
-                            // If we do not find a source location, then just return the raw assembly without file/line/column information.
-                            let mut location = None;
-                            let mut line = None;
-                            let mut column = None;
-                            if let Some(current_source_location) = target_core
-                                .core_data
-                                .debug_info
-                                .get_source_location(instruction.address()) {
-                                if let Some(previous_source_location) = stored_source_location.clone() {
-                                    if current_source_location != previous_source_location {
-                                        location = get_dap_source(&current_source_location);
-                                        line = current_source_location.line.map(|line| line as i64);
-                                        column = current_source_location.column.map(|col| match col {
-                                            ColumnType::LeftEdge => 0_i64,
-                                            ColumnType::Column(c) => c as i64,
-                                        });
-                                        stored_source_location = Some(current_source_location);
-                                    }
-                                } else {
-                                        stored_source_location = Some(current_source_location);
-                                }
-                            } else {
-                                // It won't affect the outcome, but log it for completeness.
-                                tracing::debug!("The request `Disassemble` could not resolve a source location for memory reference: {:#010}", instruction.address());
-                            }
-
-                            // Create the instruction data.
-                            dap_types::DisassembledInstruction {
-                                address: format!("{:#010X}", instruction.address()),
-                                column,
-                                end_column: None,
-                                end_line: None,
-                                instruction: format!(
-                                    "{}  {}",
-                                    instruction.mnemonic().unwrap_or("<unknown>"),
-                                    instruction.op_str().unwrap_or("")
-                                ),
-                                instruction_bytes: None,
-                                line,
-                                location,
-                                symbol: None,
-                            }
-                        })
-                        .collect::<Vec<DisassembledInstruction>>();
-                    assembly_lines.append(&mut result_instruction);
-                }
-                Err(error) => {
-                    return Err(DebuggerError::Other(anyhow!(error)));
-                }
+                    variables_reference: locals_root_variable.variable_key,
+                });
             };
         }
-
-        if assembly_lines.is_empty() {
-            Err(DebuggerError::Other(anyhow::anyhow!(
-                "No valid instructions found at memory reference {:#010x?}",
-                memory_reference
-            )))
-        } else {
-            Ok(assembly_lines)
-        }
+        self.send_response(request, Ok(Some(ScopesResponseBody { scopes: dap_scopes })))
     }
 
     /// Implementing the MS DAP for `request Disassemble` has a number of problems:
@@ -1520,8 +2703,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         } else {
             arguments.memory_reference.parse()
         } {
-            match self.get_disassembled_source(
-                target_core,
+            match target_core.get_disassembled_source(
                 memory_reference as i64,
                 arguments.offset.unwrap_or(0_i64),
                 arguments.instruction_offset.unwrap_or(0_i64),
@@ -1548,6 +2730,29 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         }
     }
 
+    /// Custom `setVariablesFilter` request, to change the [`VariablesFilter`] during the debug session.
+    /// The arguments replace the current filter, so that the filter can be toggled without restarting the session.
+    pub(crate) fn set_variables_filter(&mut self, request: Request) -> Result<()> {
+        let arguments: VariablesFilter = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        self.variables_filter = arguments;
+        self.send_response::<()>(request, Ok(None))?;
+        if self.supports_invalidated_event {
+            // Ask the client to request the variables again, so that the new filter is applied.
+            self.send_event(
+                "invalidated",
+                Some(InvalidatedEventBody {
+                    areas: Some(vec!["variables".to_string()]),
+                    stack_frame_id: None,
+                    thread_id: None,
+                }),
+            )?;
+        }
+        Ok(())
+    }
+
     /// The MS DAP Specification only gives us the unique reference of the variable, and does not tell us which StackFrame it belongs to, nor does it specify if this variable is in the local, register or static scope. Unfortunately this means we have to search through all the available [`probe_rs::debug::variable_cache::VariableCache`]'s until we find it. To minimize the impact of this, we will search in the most 'likely' places first (first stack frame's locals, then statics, then registers, then move to next stack frame, and so on ...)
     pub(crate) fn variables(
         &mut self,
@@ -1559,12 +2764,20 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             Err(error) => return self.send_response::<()>(request, Err(error)),
         };
 
+        let variables_filter = self.variables_filter.clone();
+
         if let Some(core_peripherals) = &mut target_core.core_data.core_peripherals {
             // First we check the SVD VariableCache, we do this first because it is the lowest computational overhead.
             if let Some(search_variable) = core_peripherals
                 .svd_variable_cache
                 .get_variable_by_key(arguments.variables_reference)
             {
+                // The registers of a peripheral are loaded the first time it is expanded.
+                if let Err(error) = core_peripherals
+                    .expand_peripheral(&mut target_core.core, search_variable.variable_key)
+                {
+                    return self.send_response::<()>(request, Err(error));
+                }
                 let dap_variables: Vec<Variable> = core_peripherals
                     .svd_variable_cache
                     .get_children(Some(search_variable.variable_key))?
@@ -1575,10 +2788,18 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             variables_reference,
                             named_child_variables_cnt,
                             indexed_child_variables_cnt,
-                        ) = self.get_variable_reference(
-                            variable,
-                            &mut core_peripherals.svd_variable_cache,
-                        );
+                        ) = if core_peripherals
+                            .unexpanded_peripherals
+                            .contains_key(&variable.variable_key)
+                        {
+                            // Provide a reference, so that the DAP client will ask for the registers when the peripheral is expanded.
+                            (variable.variable_key, 0, 0)
+                        } else {
+                            self.get_variable_reference(
+                                variable,
+                                &mut core_peripherals.svd_variable_cache,
+                            )
+                        };
                         Variable {
                             name: if let VariableName::Named(variable_name) = &variable.name {
                                 if let Some(last_part) = variable_name.split_terminator('.').last()
@@ -1606,11 +2827,31 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                                     &mut target_core.core,
                                     &core_peripherals.svd_variable_cache,
                                 );
-                                variable.get_value(&core_peripherals.svd_variable_cache)
+                                // Fields with `enumeratedValues` show the name of the value, e.g. `Enabled (0x1)`.
+                                match variable.svd_field_value().and_then(|field_value| {
+                                    core_peripherals
+                                        .field_enumerated_values
+                                        .get(&variable.variable_key)?
+                                        .name(field_value as u64)
+                                        .map(|name| format!("{name} ({field_value:#x})"))
+                                }) {
+                                    Some(enumerated_value) => enumerated_value,
+                                    None => {
+                                        variable.get_value(&core_peripherals.svd_variable_cache)
+                                    }
+                                }
                             },
                             variables_reference,
                         }
                     })
+                    .filter(|variable| {
+                        !variables_filter.hides(
+                            &variable.name,
+                            &variable.value,
+                            variable.variables_reference > 0,
+                            true,
+                        )
+                    })
                     .collect();
                 return self.send_response(
                     request,
@@ -1621,11 +2862,60 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
             }
         }
 
+        // Registers are shown as hexadecimal, and integer variables as decimal, unless the client requests otherwise.
+        let requested_hex = arguments.format.as_ref().and_then(|format| format.hex);
+        let registers_as_hex = requested_hex.unwrap_or(true);
+        let variables_as_hex = requested_hex.unwrap_or(false);
+
+        if let Some(register_group) =
+            target_core
+                .core_data
+                .register_groups
+                .iter()
+                .find(|register_group| {
+                    register_group.variables_reference == arguments.variables_reference
+                })
+        {
+            let dap_variables: Vec<Variable> = register_group
+                .registers
+                .iter()
+                .map(|register| Variable {
+                    name: register.name.clone(),
+                    evaluate_name: None,
+                    memory_reference: None,
+                    indexed_variables: None,
+                    named_variables: None,
+                    presentation_hint: None,
+                    type_: Some(register_group.name.to_string()),
+                    value: register.formatted_value(registers_as_hex),
+                    variables_reference: 0,
+                })
+                .collect();
+            return self.send_response(
+                request,
+                Ok(Some(VariablesResponseBody {
+                    variables: dap_variables,
+                })),
+            );
+        }
+
         let response = {
+            let global_variables_registers;
             let mut parent_variable: Option<probe_rs::debug::Variable> = None;
             let mut variable_cache: Option<&mut probe_rs::debug::VariableCache> = None;
             let mut stack_frame_registers: Option<&DebugRegisters> = None;
             let mut frame_base: Option<u64> = None;
+            // The global variables do not belong to a stack frame, so they are resolved with the registers of the halted core.
+            if let Some(search_cache) = &mut target_core.core_data.global_variables {
+                if let Some(search_variable) =
+                    search_cache.get_variable_by_key(arguments.variables_reference)
+                {
+                    global_variables_registers = DebugRegisters::from_core(&mut target_core.core);
+                    parent_variable = Some(search_variable);
+                    variable_cache = Some(search_cache);
+                    stack_frame_registers = Some(&global_variables_registers);
+                }
+            }
             for stack_frame in target_core.core_data.stack_frames.iter_mut() {
                 if let Some(search_cache) = &mut stack_frame.local_variables {
                     if let Some(search_variable) =
@@ -1663,9 +2953,12 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             memory_reference: None,
                             indexed_variables: None,
                             named_variables: None,
-                            presentation_hint: None, // TODO: Implement hint as Hex for registers
+                            presentation_hint: None,
                             type_: Some(format!("{}", VariableName::RegistersRoot)),
-                            value: register.value.unwrap_or_default().to_string(),
+                            value: format_register_value(
+                                register.value.unwrap_or_default(),
+                                registers_as_hex,
+                            ),
                             variables_reference: 0,
                         })
                         .collect();
@@ -1686,19 +2979,43 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         && !variable_cache.has_children(parent_variable)?
                     {
                         if let Some(stack_frame_registers) = stack_frame_registers {
-                            target_core.core_data.debug_info.cache_deferred_variables(
-                                variable_cache,
-                                &mut target_core.core,
-                                parent_variable,
-                                stack_frame_registers,
-                                frame_base,
-                            )?;
+                            // Children that were optimized out are cached with an `<optimized out>` value, and do not fail here.
+                            // Any other failure is shown as an error child, so that the user sees why the variable has no children,
+                            // and the other variables of the scope remain usable.
+                            if let Err(error) =
+                                target_core.core_data.debug_info.cache_deferred_variables(
+                                    variable_cache,
+                                    &mut target_core.core,
+                                    parent_variable,
+                                    stack_frame_registers,
+                                    frame_base,
+                                )
+                            {
+                                tracing::warn!(
+                                    "Could not cache deferred child variables for variable: {}. {error}",
+                                    parent_variable.name
+                                );
+                                let mut error_variable = probe_rs::debug::Variable::new(
+                                    parent_variable.unit_header_offset,
+                                    None,
+                                );
+                                error_variable.name = VariableName::Named("<error>".to_string());
+                                error_variable.set_value(VariableValue::Error(format!(
+                                    "Could not resolve the children of this variable: {error}"
+                                )));
+                                variable_cache.cache_variable(
+                                    Some(parent_variable.variable_key),
+                                    error_variable,
+                                    &mut target_core.core,
+                                )?;
+                            }
                         } else {
                             tracing::error!("Could not cache deferred child variables for variable: {}. No register data available.", parent_variable.name);
                         }
                     }
                 }
 
+                let show_raw_bytes = target_core.core_data.show_raw_bytes;
                 let dap_variables: Vec<Variable> = variable_cache
                     .get_children(Some(arguments.variables_reference))?
                     .iter()
@@ -1722,6 +3039,13 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             named_child_variables_cnt,
                             indexed_child_variables_cnt,
                         ) = self.get_variable_reference(variable, variable_cache);
+                        let mut type_name = format!("{:?}", variable.type_name);
+                        if show_raw_bytes {
+                            if let Some(raw_bytes) = read_raw_bytes(&mut target_core.core, variable)
+                            {
+                                type_name.push_str(&format!(" [raw bytes: {raw_bytes}]"));
+                            }
+                        }
                         Variable {
                             name: variable.name.to_string(),
                             // evaluate_name: Some(variable.name.to_string()),
@@ -1732,11 +3056,24 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                             indexed_variables: Some(indexed_child_variables_cnt),
                             named_variables: Some(named_child_variables_cnt),
                             presentation_hint: None,
-                            type_: Some(format!("{:?}", variable.type_name)),
-                            value: variable.get_value(variable_cache),
+                            type_: Some(type_name),
+                            value: format_variable_value(
+                                variable.get_value(variable_cache),
+                                variable.byte_size,
+                                variable.is_integer(),
+                                variables_as_hex,
+                            ),
                             variables_reference,
                         }
                     })
+                    .filter(|variable| {
+                        !variables_filter.hides(
+                            &variable.name,
+                            &variable.value,
+                            variable.variables_reference > 0,
+                            false,
+                        )
+                    })
                     .collect();
                 Ok(Some(VariablesResponseBody {
                     variables: dap_variables,
@@ -1752,12 +3089,118 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, response)
     }
 
+    /// Resolve the source location of a `gotoTargets` request to the address of the closest valid breakpoint location.
+    /// The id of the goto target is the address, which is used by the `goto` request.
+    pub(crate) fn goto_targets(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: GotoTargetsArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        let source_path = match arguments.source.path.as_ref() {
+            Some(source_path) => Path::new(source_path),
+            None => {
+                return self.send_response::<()>(
+                    request,
+                    Err(DebuggerError::Other(anyhow!(
+                        "Goto targets can only be requested for a source with a path."
+                    ))),
+                )
+            }
+        };
+        // The debug information uses 1 based line and column numbers, so translate from, and back to, the numbering of the client.
+        let line_offset = u64::from(!self.lines_start_at_1);
+        let column_offset = u64::from(!self.columns_start_at_1);
+        let requested_line = arguments.line as u64 + line_offset;
+        match target_core.core_data.debug_info.get_breakpoint_location(
+            source_path,
+            requested_line,
+            arguments.column.map(|column| column as u64 + column_offset),
+        ) {
+            Ok(VerifiedBreakpoint {
+                address,
+                source_location,
+            }) => {
+                let line = source_location.line.unwrap_or(requested_line);
+                self.send_response(
+                    request,
+                    Ok(Some(GotoTargetsResponseBody {
+                        targets: vec![GotoTarget {
+                            id: address as i64,
+                            label: format!("Run to line {line} ({address:#010x})"),
+                            line: line.saturating_sub(line_offset) as i64,
+                            column: source_location
+                                .column
+                                .map(|col| match col {
+                                    ColumnType::LeftEdge => 1,
+                                    ColumnType::Column(c) => c,
+                                })
+                                .map(|column| column.saturating_sub(column_offset) as i64),
+                            end_line: None,
+                            end_column: None,
+                            instruction_pointer_reference: Some(format!("{address:#010x}")),
+                        }],
+                    })),
+                )
+            }
+            Err(error) => self.send_response::<()>(
+                request,
+                Err(DebuggerError::Other(anyhow!(
+                    "Cannot run to this location. Try reducing compile time-, and link time-, optimization in your build configuration, or choose a different source location: {error}"
+                ))),
+            ),
+        }
+    }
+
+    /// Run to the address of a goto target, by setting a [`BreakpointType::TemporaryBreakpoint`] there, and resuming the core.
+    /// The temporary breakpoint is cleared when the core halts, even if it halts at a different location.
+    pub(crate) fn goto(&mut self, target_core: &mut CoreHandle, request: Request) -> Result<()> {
+        let arguments: GotoArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        if let Err(error) = target_core.set_temporary_breakpoint(arguments.target_id as u64, "goto")
+        {
+            return self.send_response::<()>(request, Err(error));
+        }
+        self.run_to_temporary_breakpoint(target_core, request)
+    }
+
+    /// Resume the core, after a [`BreakpointType::TemporaryBreakpoint`] was set for a `goto` or `stepOut` request, and respond to the request.
+    /// Unlike [`Self::r#continue`], exactly one response is sent, whether the core could be resumed or not.
+    fn run_to_temporary_breakpoint(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        if let Err(error) = target_core
+            .step_over_software_breakpoint()
+            .and_then(|_| target_core.core.run())
+        {
+            if let Err(clear_error) =
+                target_core.clear_breakpoints(BreakpointType::TemporaryBreakpoint)
+            {
+                tracing::warn!("Failed to clear the temporary breakpoint: {clear_error}");
+            }
+            return self.send_response::<()>(request, Err(DebuggerError::from(error)));
+        }
+        target_core.reset_core_status(self);
+        target_core.start_step_timeout();
+        self.send_response::<()>(request, Ok(None))
+    }
+
     pub(crate) fn r#continue(
         &mut self,
         target_core: &mut CoreHandle,
         request: Request,
     ) -> Result<()> {
-        match target_core.core.run() {
+        match target_core
+            .step_over_software_breakpoint()
+            .and_then(|_| target_core.core.run())
+        {
             Ok(_) => {
                 target_core.reset_core_status(self);
                 if request.command.as_str() == "continue" {
@@ -1765,7 +3208,10 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                     self.send_response(
                         request,
                         Ok(Some(ContinueResponseBody {
-                            all_threads_continued: Some(false), // TODO: Implement multi-core logic here
+                            // Only the requested core is continued, so this is only `true` if the other cores are already running.
+                            all_threads_continued: Some(
+                                self.other_cores_running(target_core.core.id()),
+                            ),
                         })),
                     )?;
                 }
@@ -1808,48 +3254,77 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
     }
 
     /// Steps through the code at the requested granularity.
-    /// - [SteppingMode::StepInstruction]: If MS DAP [SteppingGranularity::Instruction] (usually sent from the disassembly view)
+    /// - A single instruction, with [`CoreHandle::step_instruction`]: If MS DAP [SteppingGranularity::Instruction] (usually sent from the disassembly view)
     /// - [SteppingMode::OverStatement]: In all other cases.
     pub(crate) fn next(&mut self, target_core: &mut CoreHandle, request: Request) -> Result<()> {
         let arguments: NextArguments = get_arguments(&request)?;
 
-        let stepping_granularity = match arguments.granularity {
-            Some(SteppingGranularity::Instruction) => SteppingMode::StepInstruction,
-            _ => SteppingMode::OverStatement,
-        };
-
-        self.debug_step(stepping_granularity, target_core, request)
+        match arguments.granularity {
+            Some(SteppingGranularity::Instruction) => self.instruction_step(target_core, request),
+            _ => self.debug_step(SteppingMode::OverStatement, target_core, request),
+        }
     }
 
     /// Steps through the code at the requested granularity.
-    /// - [SteppingMode::StepInstruction]: If MS DAP [SteppingGranularity::Instruction] (usually sent from the disassembly view)
+    /// - A single instruction, with [`CoreHandle::step_instruction`]: If MS DAP [SteppingGranularity::Instruction] (usually sent from the disassembly view)
     /// - [SteppingMode::IntoStatement]: In all other cases.
     pub(crate) fn step_in(&mut self, target_core: &mut CoreHandle, request: Request) -> Result<()> {
         let arguments: StepInArguments = get_arguments(&request)?;
 
-        let stepping_granularity = match arguments.granularity {
-            Some(SteppingGranularity::Instruction) => SteppingMode::StepInstruction,
-            _ => SteppingMode::IntoStatement,
-        };
-        self.debug_step(stepping_granularity, target_core, request)
+        match arguments.granularity {
+            Some(SteppingGranularity::Instruction) => self.instruction_step(target_core, request),
+            _ => self.debug_step(SteppingMode::IntoStatement, target_core, request),
+        }
     }
 
-    /// Steps through the code at the requested granularity.
-    /// - [SteppingMode::StepInstruction]: If MS DAP [SteppingGranularity::Instruction] (usually sent from the disassembly view)
-    /// - [SteppingMode::OutOfStatement]: In all other cases.
+    /// Steps out of the current function, by unwinding the stack to the calling frame, and running to a [`BreakpointType::TemporaryBreakpoint`]
+    /// at its return address. The halt is reported by [`CoreHandle::poll_core`], once the stack pointer shows that the function has returned,
+    /// so that a recursive call of the function that reaches the same return address does not halt the core.
+    /// If the function does not return before the [`CoreConfig::step_timeout_ms`](crate::debugger::configuration::CoreConfig::step_timeout_ms) expires,
+    /// the core is halted, and the halt is reported with a `step timeout` reason.
+    /// - Inlined functions, and functions without a calling frame, use [SteppingMode::OutOfStatement] instead.
+    /// - The MS DAP [SteppingGranularity::Instruction] (usually sent from the disassembly view) is ignored, because stepping out of a function
+    ///   has to continue until the function returns, and a single instruction step would leave the core in the same function.
     pub(crate) fn step_out(
         &mut self,
         target_core: &mut CoreHandle,
         request: Request,
     ) -> Result<()> {
-        let arguments: StepOutArguments = get_arguments(&request)?;
+        let _arguments: StepOutArguments = get_arguments(&request)?;
+        let program_counter: u64 = target_core
+            .core
+            .read_core_reg(target_core.core.registers().program_counter())?;
+        match target_core.set_step_out_breakpoint(program_counter) {
+            Ok(Some(return_address)) => {
+                tracing::debug!(
+                    "Step Out target: running to return address: {return_address:#010x}"
+                );
+                self.run_to_temporary_breakpoint(target_core, request)
+            }
+            Ok(None) => self.debug_step(SteppingMode::OutOfStatement, target_core, request),
+            Err(error) => {
+                tracing::debug!("Failed to find the return address of the current function, so stepping out by statement: {error}");
+                self.debug_step(SteppingMode::OutOfStatement, target_core, request)
+            }
+        }
+    }
 
-        let stepping_granularity = match arguments.granularity {
-            Some(SteppingGranularity::Instruction) => SteppingMode::StepInstruction,
-            _ => SteppingMode::OutOfStatement,
+    /// Common code for the `next` and `step_in` methods with [SteppingGranularity::Instruction], which step a single instruction with [`CoreHandle::step_instruction`].
+    fn instruction_step(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<(), anyhow::Error> {
+        let (new_status, program_counter) = match target_core.step_instruction(self) {
+            Ok(step_result) => step_result,
+            Err(error) => {
+                target_core.core.halt(Duration::from_millis(100)).ok();
+                return Err(anyhow!("Unexpected error during stepping :{}", error));
+            }
         };
-
-        self.debug_step(stepping_granularity, target_core, request)
+        tracing::debug!("Stepped one instruction, to {program_counter:#010x}");
+        self.send_response::<()>(request, Ok(None))?;
+        self.send_step_stopped_event(target_core, new_status, program_counter)
     }
 
     /// Common code for the `next`, `step_in`, and `step_out` methods.
@@ -1860,9 +3335,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         request: Request,
     ) -> Result<(), anyhow::Error> {
         target_core.reset_core_status(self);
-        let (new_status, program_counter) = match stepping_granularity
-            .step(&mut target_core.core, &target_core.core_data.debug_info)
-        {
+        let (new_status, program_counter) = match target_core.step(&stepping_granularity) {
             Ok((new_status, program_counter)) => (new_status, program_counter),
             Err(error) => match &error {
                 probe_rs::debug::DebugError::NoValidHaltLocation {
@@ -1883,10 +3356,130 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         };
 
         self.send_response::<()>(request, Ok(None))?;
+        self.send_step_stopped_event(target_core, new_status, program_counter)
+    }
+
+    /// Custom `stepUntil` request, to step repeatedly until a condition is `true`, or until the step limit or timeout is reached.
+    /// The condition is evaluated after every step, using [`breakpoint_condition::evaluate_condition`].
+    pub(crate) fn step_until(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: Request,
+    ) -> Result<()> {
+        let arguments: StepUntilArguments = match get_arguments(&request) {
+            Ok(arguments) => arguments,
+            Err(error) => return self.send_response::<()>(request, Err(error)),
+        };
+        let stepping_granularity = match arguments.granularity {
+            Some(SteppingGranularity::Instruction) => SteppingMode::StepInstruction,
+            _ => SteppingMode::OverStatement,
+        };
+        let max_steps = arguments.max_steps.unwrap_or(1000);
+        let timeout = Duration::from_millis(arguments.timeout_ms.unwrap_or(10_000));
+
+        target_core.reset_core_status(self);
+        let start = Instant::now();
+        let mut steps = 0_u64;
+        let mut new_status = target_core.core.status()?;
+        let mut program_counter: u64 = target_core
+            .core
+            .read_core_reg(target_core.core.registers().program_counter())?;
+        let step_result = loop {
+            if steps >= max_steps {
+                break Ok(StepUntilStopReason::StepLimit);
+            }
+            if start.elapsed() >= timeout {
+                break Ok(StepUntilStopReason::Timeout);
+            }
+            if self.cancel_requested(request.seq, None) {
+                break Ok(StepUntilStopReason::Cancelled);
+            }
+            match target_core.step(&stepping_granularity) {
+                Ok((status, pc)) => {
+                    new_status = status;
+                    program_counter = pc;
+                }
+                Err(error) => {
+                    target_core.core.halt(Duration::from_millis(100)).ok();
+                    break Err(DebuggerError::Other(anyhow!(
+                        "Stepping failed after {steps} steps: {error}"
+                    )));
+                }
+            }
+            steps += 1;
+            if !matches!(new_status, CoreStatus::Halted(_)) {
+                break Err(DebuggerError::Other(anyhow!(
+                    "The core did not halt after {steps} steps"
+                )));
+            }
+            match target_core
+                .core_data
+                .debug_info
+                .unwind(&mut target_core.core, program_counter)
+                .map_err(DebuggerError::ProbeRs)
+                .and_then(|stack_frames| {
+                    stack_frames.first().map_or_else(
+                        || {
+                            Err(DebuggerError::Other(anyhow!(
+                                "No stack frame available at {program_counter:#010x}"
+                            )))
+                        },
+                        |stack_frame| {
+                            breakpoint_condition::evaluate_condition(
+                                &arguments.condition,
+                                stack_frame,
+                            )
+                        },
+                    )
+                }) {
+                Ok(true) => break Ok(StepUntilStopReason::Condition),
+                Ok(false) => {}
+                Err(error) => break Err(error),
+            }
+        };
+
+        match step_result {
+            Ok(stop_reason) => {
+                let message = match stop_reason {
+                    StepUntilStopReason::Condition => format!(
+                        "Stopped after {steps} steps, because `{}` is true.",
+                        arguments.condition
+                    ),
+                    StepUntilStopReason::StepLimit => format!(
+                        "Stopped after reaching the limit of {steps} steps, before `{}` became true.",
+                        arguments.condition
+                    ),
+                    StepUntilStopReason::Timeout => format!(
+                        "Stopped after {steps} steps, because the timeout of {} ms was reached.",
+                        timeout.as_millis()
+                    ),
+                    StepUntilStopReason::Cancelled => format!("Cancelled after {steps} steps."),
+                };
+                self.send_response(
+                    request,
+                    Ok(Some(StepUntilResponseBody {
+                        stop_reason,
+                        steps,
+                        message,
+                    })),
+                )?;
+            }
+            Err(error) => self.send_response::<()>(request, Err(error))?,
+        }
+        self.send_step_stopped_event(target_core, new_status, program_counter)
+    }
 
+    /// Notify the client that the core halted after a step.
+    fn send_step_stopped_event(
+        &mut self,
+        target_core: &mut CoreHandle,
+        new_status: CoreStatus,
+        program_counter: u64,
+    ) -> Result<(), anyhow::Error> {
         // We override the halt reason because our implementation of stepping uses breakpoints and results in a "BreakPoint" halt reason, which is not appropriate here.
         target_core.core_data.last_known_status = CoreStatus::Halted(HaltReason::Step);
         if matches!(new_status, CoreStatus::Halted(_)) {
+            target_core.refresh_watch_values(program_counter);
             let event_body = Some(StoppedEventBody {
                 reason: target_core
                     .core_data
@@ -1954,9 +3547,42 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
     /// Returns one of the standard DAP Requests if all goes well, or a "error" request, which should indicate that the calling function should return.
     /// When preparing to return an "error" request, we will send a Response containing the DebuggerError encountered.
     pub fn listen_for_request(&mut self) -> anyhow::Result<Option<Request>> {
+        if let Some(deferred_request) = self.deferred_requests.pop_front() {
+            return Ok(Some(deferred_request));
+        }
         self.adapter.listen_for_request()
     }
 
+    /// Check, without blocking, if the client sent a `cancel` request for the given `request_seq` or `progress_id`.
+    /// Any other requests that arrive in the meantime are deferred, and will be returned by [`DebugAdapter::listen_for_request`].
+    pub(crate) fn cancel_requested(
+        &mut self,
+        request_seq: i64,
+        progress_id: Option<ProgressId>,
+    ) -> bool {
+        let incoming_request = match self.adapter.listen_for_request() {
+            Ok(Some(incoming_request)) => incoming_request,
+            Ok(None) => return false,
+            Err(error) => {
+                tracing::warn!("Failed to check for `cancel` requests: {}", error);
+                return false;
+            }
+        };
+        if incoming_request.command == "cancel" {
+            if let Ok(arguments) = get_arguments::<CancelArguments>(&incoming_request) {
+                if arguments.request_id == Some(request_seq)
+                    || (progress_id.is_some()
+                        && arguments.progress_id == progress_id.map(|id| id.to_string()))
+                {
+                    self.send_response::<()>(incoming_request, Ok(None)).ok();
+                    return true;
+                }
+            }
+        }
+        self.deferred_requests.push_back(incoming_request);
+        false
+    }
+
     /// Sends either the success response or an error response if passed a
     /// DebuggerError. For the DAP Client, it forwards the response, while for
     /// the CLI, it will print the body for success, or the message for
@@ -2063,6 +3689,24 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
     }
 
     pub fn start_progress(&mut self, title: &str, request_id: Option<i64>) -> Result<ProgressId> {
+        self.start_progress_with_cancel(title, request_id, false)
+    }
+
+    /// Start a progress report that the user can cancel. See [`DebugAdapter::cancel_requested`].
+    pub fn start_cancellable_progress(
+        &mut self,
+        title: &str,
+        request_id: Option<i64>,
+    ) -> Result<ProgressId> {
+        self.start_progress_with_cancel(title, request_id, true)
+    }
+
+    fn start_progress_with_cancel(
+        &mut self,
+        title: &str,
+        request_id: Option<i64>,
+        cancellable: bool,
+    ) -> Result<ProgressId> {
         anyhow::ensure!(
             self.supports_progress_reporting,
             "Progress reporting is not supported by client."
@@ -2073,7 +3717,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_event(
             "progressStart",
             Some(ProgressStartEventBody {
-                cancellable: Some(false),
+                cancellable: Some(cancellable),
                 message: None,
                 percentage: None,
                 progress_id: progress_id.to_string(),
@@ -2131,7 +3775,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
 }
 
 /// A helper function to greate a [`dap_types::Source`] struct from a [`SourceLocation`]
-fn get_dap_source(source_location: &SourceLocation) -> Option<Source> {
+pub(crate) fn get_dap_source(source_location: &SourceLocation) -> Option<Source> {
     // Attempt to construct the path for the source code
     source_location.directory.as_ref().map(|path| {
         let mut path = if path.is_relative() {
@@ -2177,6 +3821,83 @@ fn get_dap_source(source_location: &SourceLocation) -> Option<Source> {
     })
 }
 
+/// Format the value of a stack frame register as hexadecimal when `hex` is `true`, and as decimal otherwise.
+fn format_register_value(value: RegisterValue, hex: bool) -> String {
+    if hex {
+        value.to_string()
+    } else {
+        TryInto::<u128>::try_into(value)
+            .map(|value| value.to_string())
+            .unwrap_or_else(|_| value.to_string())
+    }
+}
+
+/// Format the value of an integer variable as hexadecimal when `hex` is `true`. Negative values are shown as their two's complement in `byte_size` bytes.
+/// Values of variables that do not have an integer type, e.g. pointers, floating point numbers or characters, are returned unchanged.
+fn format_variable_value(
+    value: String,
+    byte_size: Option<u64>,
+    is_integer: bool,
+    hex: bool,
+) -> String {
+    if !hex || !is_integer {
+        return value;
+    }
+    let integer = match value
+        .parse::<u128>()
+        .ok()
+        .or_else(|| value.parse::<i128>().ok().map(|integer| integer as u128))
+    {
+        Some(integer) => integer,
+        None => return value,
+    };
+    let size_in_bits = byte_size.map_or(0, |byte_size| byte_size as usize * 8);
+    let integer = if size_in_bits == 0 || size_in_bits >= 128 {
+        integer
+    } else {
+        integer & ((1_u128 << size_in_bits) - 1)
+    };
+    register_groups::format_hex(integer, size_in_bits)
+}
+
+/// Read the memory of a variable, and format it as hexadecimal bytes in address order, e.g. `2A 00 00 00`.
+/// Returns `None` if the variable is not stored in memory, has an unknown size, or cannot be read.
+/// Only the first [`MAX_RAW_BYTES`] bytes are read, and a truncated result ends with `...`.
+fn read_raw_bytes(
+    core: &mut probe_rs::Core,
+    variable: &probe_rs::debug::Variable,
+) -> Option<String> {
+    let address = variable.memory_location.memory_address().ok()?;
+    let byte_size = variable.byte_size.filter(|byte_size| *byte_size > 0)?;
+    let mut raw_bytes = vec![0_u8; byte_size.min(MAX_RAW_BYTES) as usize];
+    core.read(address, &mut raw_bytes).ok()?;
+    let mut formatted_bytes = raw_bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<String>>()
+        .join(" ");
+    if byte_size > MAX_RAW_BYTES {
+        formatted_bytes.push_str(" ...");
+    }
+    Some(formatted_bytes)
+}
+
+/// Encode `data` with the format of the RTT down channel `channel_number`.
+fn encode_rtt_input(
+    target_core: &CoreHandle,
+    channel_number: usize,
+    data: &str,
+) -> Result<Vec<u8>, DebuggerError> {
+    target_core
+        .core_data
+        .rtt_connection
+        .as_ref()
+        .ok_or_else(|| DebuggerError::Other(anyhow!("RTT is not attached to the target")))?
+        .target_rtt
+        .encode_rtt(channel_number, data)
+        .map_err(DebuggerError::Other)
+}
+
 /// Provides halt functionality that is re-used elsewhere, in context of multiple DAP Requests
 pub(crate) fn halt_core(
     target_core: &mut probe_rs::Core,
@@ -2196,6 +3917,34 @@ pub fn get_arguments<T: DeserializeOwned>(req: &Request) -> Result<T, crate::Deb
     serde_json::from_value(value.to_owned()).map_err(|e| e.into())
 }
 
+/// Incremental CRC-32 (IEEE 802.3), as used by zlib and most image tools, to confirm the integrity of memory dumps.
+pub(crate) struct Crc32 {
+    value: u32,
+}
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Crc32 { value: u32::MAX }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.value ^= *byte as u32;
+            for _ in 0..8 {
+                self.value = if self.value & 1 == 1 {
+                    (self.value >> 1) ^ 0xEDB8_8320
+                } else {
+                    self.value >> 1
+                };
+            }
+        }
+    }
+
+    pub(crate) fn finalize(&self) -> u32 {
+        !self.value
+    }
+}
+
 pub(crate) trait DapStatus {
     fn short_long_status(&self, program_counter: Option<u64>) -> (&'static str, String);
 }
@@ -2258,3 +4007,67 @@ impl DapStatus for CoreStatus {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{format_variable_value, Crc32};
+
+    #[test]
+    fn integer_is_formatted_as_hex() {
+        assert_eq!(
+            format_variable_value("42".to_string(), Some(4), true, true),
+            "0x0000002a"
+        );
+    }
+
+    #[test]
+    fn negative_integer_is_formatted_as_twos_complement() {
+        assert_eq!(
+            format_variable_value("-1".to_string(), Some(2), true, true),
+            "0xffff"
+        );
+    }
+
+    #[test]
+    fn integer_is_unchanged_without_hex() {
+        assert_eq!(
+            format_variable_value("42".to_string(), Some(4), true, false),
+            "42"
+        );
+    }
+
+    #[test]
+    fn float_with_an_integer_value_is_unchanged() {
+        assert_eq!(
+            format_variable_value("1".to_string(), Some(4), false, true),
+            "1"
+        );
+    }
+
+    #[test]
+    fn value_that_is_not_a_number_is_unchanged() {
+        assert_eq!(
+            format_variable_value("Some(3)".to_string(), Some(8), true, true),
+            "Some(3)"
+        );
+    }
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        // The standard check value of CRC-32 (IEEE 802.3), for the ASCII digits `123456789`.
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finalize(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn crc32_is_the_same_when_updated_in_chunks() {
+        let data = b"The quick brown fox jumps over the lazy dog";
+        let mut crc = Crc32::new();
+        for chunk in data.chunks(5) {
+            crc.update(chunk);
+        }
+        assert_eq!(crc.finalize(), 0x414F_A339);
+        assert_eq!(Crc32::new().finalize(), 0);
+    }
+}
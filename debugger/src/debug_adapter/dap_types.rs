@@ -8,7 +8,7 @@ use parse_int::parse;
 use probe_rs_cli_util::rtt;
 use schemafy::schemafy;
 use serde::{Deserialize, Serialize};
-use std::convert::TryFrom;
+use std::{convert::TryFrom, path::PathBuf};
 
 // Convert the MSDAP `debugAdaptor.json` file into Rust types.
 schemafy!(root: debugserver_types "src/debug_adapter/debugProtocol.json");
@@ -84,6 +84,325 @@ pub struct ShowMessageEventBody {
     pub message: String,
 }
 
+/// Arguments for the custom `resetCause` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetCauseArguments {
+    /// Clear the reset cause flags after they have been read.
+    #[serde(default)]
+    pub clear: bool,
+}
+
+/// Response to the custom `resetCause` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetCauseResponseBody {
+    /// The register that was decoded, as `PERIPHERAL.REGISTER`, or `None` if the reset cause is unknown for this target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub register: Option<String>,
+    /// The raw register value, formatted as a hex string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// The names of the reset cause flags that are set.
+    pub flags: Vec<String>,
+    /// `true` if the flags were cleared after they were read.
+    pub cleared: bool,
+    /// A human readable summary of the reset cause.
+    pub message: String,
+}
+
+/// The probe-rs specific arguments of the `restart` request, which are sent alongside the standard `arguments` of [`RestartArguments`].
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestartModeArguments {
+    /// Only reset the core, and run the binary that is already on the target, instead of restarting the debug session and flashing the binary again.
+    #[serde(default)]
+    pub reset_only: bool,
+}
+
+/// Arguments for the custom `dumpMemory` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpMemoryArguments {
+    /// The start address of the memory range, as a decimal or `0x` prefixed hex string.
+    pub memory_reference: String,
+    /// The number of bytes to read.
+    pub count: u64,
+    /// The binary file that the memory contents will be written to.
+    pub path: PathBuf,
+}
+
+/// Response to the custom `dumpMemory` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpMemoryResponseBody {
+    pub path: PathBuf,
+    pub bytes_written: u64,
+    /// The CRC-32 (IEEE) of the data written to `path`, formatted as a hex string.
+    pub crc32: String,
+}
+
+/// Arguments for the custom `saveCoreDump` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveCoreDumpArguments {
+    /// The file that the core dump will be written to. It can be inspected later with `probe-rs-cli replay`.
+    pub path: PathBuf,
+}
+
+/// Response to the custom `saveCoreDump` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveCoreDumpResponseBody {
+    pub path: PathBuf,
+    /// The number of bytes of RAM in the core dump.
+    pub ram_bytes: u64,
+    /// The names of the functions on the stack when the core dump was captured, innermost first.
+    pub stack_frames: Vec<String>,
+}
+
+/// Arguments for the custom `stackUsage` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackUsageArguments {
+    /// The lowest address of the stack region, as a decimal or `0x` prefixed hex string. The stack is assumed to grow downwards, towards this address.
+    pub memory_reference: String,
+    /// The size of the stack region, in bytes.
+    pub count: u64,
+    /// The 32-bit value that the firmware paints the stack with, as a decimal or `0x` prefixed hex string. Defaults to `0xAAAAAAAA`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paint_value: Option<String>,
+}
+
+/// Response to the custom `stackUsage` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackUsageResponseBody {
+    /// The lowest stack address that was used (the first word that does not contain the paint value), formatted as a hex string.
+    pub high_water_mark: String,
+    /// The maximum number of bytes that were used, since the stack was painted.
+    pub used_bytes: u64,
+    /// The number of bytes that have never been used.
+    pub free_bytes: u64,
+    /// A human readable summary of the stack usage.
+    pub message: String,
+}
+
+/// Arguments for the custom `stepUntil` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepUntilArguments {
+    /// The thread (core index) to step. Defaults to the first core.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<i64>,
+    /// The condition to stop at, using the same syntax as conditional breakpoints, e.g. `counter >= 10`.
+    pub condition: String,
+    /// Step over statements (the default), or single instructions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub granularity: Option<SteppingGranularity>,
+    /// The maximum number of steps. Defaults to 1000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_steps: Option<u64>,
+    /// The maximum duration, in milliseconds. Defaults to 10 seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Why the custom `stepUntil` request stopped stepping.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StepUntilStopReason {
+    /// The condition is `true`.
+    Condition,
+    /// The maximum number of steps was taken.
+    StepLimit,
+    /// The timeout was reached.
+    Timeout,
+    /// The client cancelled the request.
+    Cancelled,
+}
+
+/// Response to the custom `stepUntil` request.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepUntilResponseBody {
+    /// Why stepping stopped.
+    pub stop_reason: StepUntilStopReason,
+    /// The number of steps that were taken.
+    pub steps: u64,
+    /// A human readable summary of the result.
+    pub message: String,
+}
+
+/// A single breakpoint in the response to the custom `listBreakpoints` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveBreakpointInfo {
+    /// The id that was reported to the client when the breakpoint was set.
+    pub id: i64,
+    /// The kind of breakpoint: `instruction`, `source`, `logpoint`, `data`, `coverage`, `functionTrace`, `catchpoint`, or `temporary`.
+    pub breakpoint_type: String,
+    /// The resolved address of the breakpoint, formatted as a hex string.
+    pub address: String,
+    /// The source file of `source` and `coverage` breakpoints.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The source line of `source` and `coverage` breakpoints.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    /// The function of `functionTrace` and `catchpoint` breakpoints.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    /// The number of times the core halted at this breakpoint.
+    pub hit_count: u64,
+    /// How the breakpoint is armed on the target, either `hardware` (a breakpoint unit), or `software` (a `BKPT` instruction in RAM).
+    pub placement: String,
+    /// `true` if the breakpoint is armed on the target.
+    pub enabled: bool,
+}
+
+/// Response to the custom `listBreakpoints` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListBreakpointsResponseBody {
+    pub breakpoints: Vec<ActiveBreakpointInfo>,
+    /// The number of hardware breakpoint units in use. Breakpoints at the same address share a unit.
+    pub hardware_breakpoints_used: u64,
+    /// The number of hardware breakpoint units of the core, or `None` if it could not be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hardware_breakpoints_available: Option<u64>,
+    /// A human readable summary of the breakpoints.
+    pub message: String,
+}
+
+/// Arguments for the custom `rttWrite` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RttWriteArguments {
+    /// The number of the RTT down channel.
+    pub channel_number: usize,
+    /// The data to send, formatted according to the `downDataFormat` of the channel. For `BinaryLE`, this is a list of hexadecimal bytes, e.g. `0x01 0xA0`.
+    pub data: String,
+}
+
+/// Response to the custom `rttWrite` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RttWriteResponseBody {
+    /// The number of bytes that were written to the target immediately. If the target buffer is full, the remaining data is written as soon as the target has read from the buffer.
+    pub bytes_written: u64,
+}
+
+/// Arguments for the custom `rttDefmtFilter` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RttDefmtFilterArguments {
+    /// The number of the RTT up channel that receives the defmt frames.
+    pub channel_number: usize,
+    /// The new filter, in the format of `RUST_LOG`, e.g. `warn,my_app::radio=trace`. If this is `None`, all frames are shown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+/// A single RTT channel in the response to the custom `rttChannels` request.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RttChannelInfo {
+    /// The number of the up channel, that receives data from the target, if the channel has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_number: Option<usize>,
+    /// The number of the down channel, that sends data to the target, if the channel has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub down_channel_number: Option<usize>,
+    pub channel_name: String,
+    /// The format of the data that is received from the target.
+    pub data_format: rtt::DataFormat,
+    /// The format of the data that is sent to the target, if the channel has a down channel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub down_data_format: Option<rtt::DataFormat>,
+    /// `true` once the client confirmed, with the `rttWindowOpened` request, that the window for the up channel is open.
+    pub has_client_window: bool,
+}
+
+/// Response to the custom `rttChannels` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RttChannelsResponseBody {
+    pub channels: Vec<RttChannelInfo>,
+}
+
+/// Arguments for the custom `riscvCsr` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiscvCsrArguments {
+    /// The name (e.g. `mcause`) or address (e.g. `0x342`) of the CSR. If this is `None`, all the known machine mode CSRs are read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// If this is set, the value is written to the CSR before it is read back.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+/// The value of a single CSR in the response to the custom `riscvCsr` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiscvCsrInfo {
+    pub name: String,
+    /// The CSR address, as a hexadecimal string.
+    pub address: String,
+    /// The value, as a hexadecimal string, or the error if the CSR could not be read.
+    pub value: String,
+    /// The decoded fields of the value, e.g. the exception name of `mcause`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<String>,
+}
+
+/// Response to the custom `riscvCsr` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiscvCsrResponseBody {
+    pub csrs: Vec<RiscvCsrInfo>,
+}
+
+/// Response to the custom `healthCheck` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckResponseBody {
+    /// `true` if the probe could communicate with the target.
+    pub alive: bool,
+    /// The round-trip time of the health check, in microseconds.
+    pub latency_microseconds: u64,
+    /// The value that was read from the target, or the reason why the health check failed.
+    pub message: String,
+}
+
+/// Response to the custom `deviceInfo` request.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceInfoResponseBody {
+    /// A human readable summary of the target, device revision, and core.
+    pub label: String,
+    /// The name of the target, as it is used in the target description.
+    pub target_name: String,
+    /// The raw `CPUID` register value, formatted as a hex string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpuid: Option<String>,
+    /// The decoded core name and revision, e.g. `Cortex-M4F r0p1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub core: Option<String>,
+    /// The vendor specific device id, formatted as a hex string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    /// The vendor specific silicon revision.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    /// The factory programmed unique device id, as a hex string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique_id: Option<String>,
+}
+
 impl TryFrom<&serde_json::Value> for ReadMemoryArguments {
     fn try_from(arguments: &serde_json::Value) -> Result<Self, Self::Error> {
         let count = get_int_argument(Some(arguments), "count", 1)?;
@@ -184,3 +503,13 @@ fn get_string_argument(
         }),
     }
 }
+
+/// Response to an `evaluate` request in the `watch` context, which also tells the client if the value changed since the previous halt.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchEvaluateResponseBody {
+    #[serde(flatten)]
+    pub evaluate_response: EvaluateResponseBody,
+    /// `true` if the value is different from the value at the previous halt.
+    pub changed: bool,
+}
@@ -0,0 +1,381 @@
+use crate::DebuggerError;
+use anyhow::anyhow;
+use probe_rs::debug::{stack_frame::StackFrame, VariableName};
+use std::cmp::Ordering;
+
+/// The comparison operators that can be used in a breakpoint condition.
+/// The two character operators have to be listed first, so that e.g. `<=` is not matched as `<`.
+const OPERATORS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+/// The value of one side of a breakpoint condition.
+#[derive(Debug, PartialEq)]
+enum Operand {
+    Integer(i128),
+    Text(String),
+}
+
+impl std::fmt::Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Integer(integer) => write!(f, "{integer}"),
+            Operand::Text(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+/// Evaluate the breakpoint `condition` in the context of `stack_frame`.
+///
+/// A condition is either a single operand, which is `true` if it is non-zero, or two operands separated by one of
+/// `==`, `!=`, `<`, `<=`, `>` or `>=`. An operand is an integer literal (e.g. `42`, `-1`, `0x2000_0000`),
+/// a quoted string, or the name of a register or of a local or static variable in the stack frame.
+/// Operands that are not numeric can only be compared with `==` and `!=`, using the value as it is shown in the variables view.
+pub(crate) fn evaluate_condition(
+    condition: &str,
+    stack_frame: &StackFrame,
+) -> Result<bool, DebuggerError> {
+    let condition = condition.trim();
+    let operator = condition.char_indices().find_map(|(position, _)| {
+        OPERATORS
+            .iter()
+            .find(|operator| condition[position..].starts_with(**operator))
+            .map(|operator| (position, *operator))
+    });
+    let (position, operator) = match operator {
+        Some(operator) => operator,
+        None => {
+            return match resolve_operand(condition, stack_frame)? {
+                Operand::Integer(integer) => Ok(integer != 0),
+                Operand::Text(text) if text == "true" => Ok(true),
+                Operand::Text(text) if text == "false" => Ok(false),
+                Operand::Text(text) => Err(DebuggerError::Other(anyhow!(
+                    "The value `{text}` of `{condition}` is not a number or a boolean"
+                ))),
+            }
+        }
+    };
+    let left = resolve_operand(&condition[..position], stack_frame)?;
+    let right = resolve_operand(&condition[position + operator.len()..], stack_frame)?;
+    let ordering = match (&left, &right) {
+        (Operand::Integer(left), Operand::Integer(right)) => left.cmp(right),
+        _ if operator == "==" || operator == "!=" => {
+            if left.to_string() == right.to_string() {
+                Ordering::Equal
+            } else {
+                Ordering::Less
+            }
+        }
+        _ => {
+            return Err(DebuggerError::Other(anyhow!(
+                "Cannot use `{operator}` to compare the non-numeric values `{left}` and `{right}`"
+            )))
+        }
+    };
+    Ok(match operator {
+        "==" => ordering == Ordering::Equal,
+        "!=" => ordering != Ordering::Equal,
+        "<=" => ordering != Ordering::Greater,
+        ">=" => ordering != Ordering::Less,
+        "<" => ordering == Ordering::Less,
+        _ => ordering == Ordering::Greater,
+    })
+}
+
+/// Evaluate the breakpoint `hit_condition` for the `hit_count`, which includes the current hit.
+///
+/// A hit condition is a number `N`, to halt on the N-th hit, or one of `==`, `!=`, `<`, `<=`, `>` or `>=` followed by a number,
+/// e.g. `>= 50` to halt on the 50th and every following hit, or `% N` to halt on every N-th hit.
+pub(crate) fn evaluate_hit_condition(
+    hit_condition: &str,
+    hit_count: u64,
+) -> Result<bool, DebuggerError> {
+    let hit_condition = hit_condition.trim();
+    let (operator, operand) = std::iter::once(&"%")
+        .chain(OPERATORS.iter())
+        .find_map(|operator| {
+            hit_condition
+                .strip_prefix(*operator)
+                .map(|operand| (*operator, operand))
+        })
+        .unwrap_or(("==", hit_condition));
+    let operand = parse_integer(operand.trim())
+        .and_then(|operand| u64::try_from(operand).ok())
+        .ok_or_else(|| {
+            DebuggerError::Other(anyhow!(
+                "The hit condition `{hit_condition}` is not a number, optionally preceded by `==`, `!=`, `<`, `<=`, `>`, `>=` or `%`"
+            ))
+        })?;
+    Ok(match operator {
+        "%" if operand == 0 => {
+            return Err(DebuggerError::Other(anyhow!(
+                "The hit condition `{hit_condition}` cannot use `% 0`"
+            )))
+        }
+        "%" => hit_count % operand == 0,
+        "==" => hit_count == operand,
+        "!=" => hit_count != operand,
+        "<=" => hit_count <= operand,
+        ">=" => hit_count >= operand,
+        "<" => hit_count < operand,
+        _ => hit_count > operand,
+    })
+}
+
+/// Format the `log_message` of a logpoint, replacing every `{expression}` with the value of the register or variable
+/// in `stack_frame`, or with `<unavailable>` if it cannot be read. Literal braces are written as `{{` and `}}`.
+pub(crate) fn format_log_message(log_message: &str, stack_frame: Option<&StackFrame>) -> String {
+    let mut message = String::new();
+    let mut characters = log_message.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '{' | '}' if characters.peek() == Some(&character) => {
+                characters.next();
+                message.push(character);
+            }
+            '{' => {
+                let expression = characters
+                    .by_ref()
+                    .take_while(|character| *character != '}')
+                    .collect::<String>();
+                message.push_str(
+                    &stack_frame
+                        .and_then(|stack_frame| evaluate_value(&expression, stack_frame).ok())
+                        .unwrap_or_else(|| "<unavailable>".to_string()),
+                );
+            }
+            other => message.push(other),
+        }
+    }
+    message
+}
+
+/// Evaluate a single operand, e.g. the name of a register or a variable, in the context of `stack_frame`,
+/// and return its value as it is shown in the variables view.
+pub(crate) fn evaluate_value(
+    expression: &str,
+    stack_frame: &StackFrame,
+) -> Result<String, DebuggerError> {
+    resolve_operand(expression, stack_frame).map(|operand| operand.to_string())
+}
+
+/// Resolve a single operand of a breakpoint condition to its current value.
+fn resolve_operand(operand: &str, stack_frame: &StackFrame) -> Result<Operand, DebuggerError> {
+    let operand = operand.trim();
+    if operand.is_empty() {
+        return Err(DebuggerError::Other(anyhow!(
+            "The breakpoint condition is missing an operand"
+        )));
+    }
+    if let Some(integer) = parse_integer(operand) {
+        return Ok(Operand::Integer(integer));
+    }
+    if let Some(text) = operand
+        .strip_prefix('"')
+        .and_then(|operand| operand.strip_suffix('"'))
+    {
+        return Ok(Operand::Text(text.to_string()));
+    }
+    if let Some(register_value) = stack_frame
+        .registers
+        .get_register_by_name(operand)
+        .and_then(|register| register.value)
+    {
+        let register_value: u128 = register_value.try_into()?;
+        return Ok(Operand::Integer(register_value as i128));
+    }
+    for variable_cache in [
+        stack_frame.local_variables.as_ref(),
+        stack_frame.static_variables.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if let Some(variable) =
+            variable_cache.get_variable_by_name(&VariableName::Named(operand.to_string()))
+        {
+            let value = variable.get_value(variable_cache);
+            return Ok(parse_integer(&value).map_or(Operand::Text(value), Operand::Integer));
+        }
+    }
+    Err(DebuggerError::Other(anyhow!(
+        "`{operand}` is not an integer, a register, or a variable in scope"
+    )))
+}
+
+/// Parse a decimal, hexadecimal (`0x`) or binary (`0b`) integer, with an optional leading `-`, and optional `_` separators.
+fn parse_integer(value: &str) -> Option<i128> {
+    let (is_negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let value = value.replace('_', "");
+    let magnitude = if let Some(hex_digits) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        i128::from_str_radix(hex_digits, 16).ok()?
+    } else if let Some(binary_digits) = value.strip_prefix("0b") {
+        i128::from_str_radix(binary_digits, 2).ok()?
+    } else if value.starts_with(|character: char| character.is_ascii_digit()) {
+        value.parse::<i128>().ok()?
+    } else {
+        return None;
+    };
+    Some(if is_negative { -magnitude } else { magnitude })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate_condition, evaluate_hit_condition, format_log_message, parse_integer};
+    use probe_rs::{
+        debug::{registers::DebugRegisters, stack_frame::StackFrame},
+        RegisterValue,
+    };
+
+    /// A stack frame without registers or variables, so that only literal operands can be resolved.
+    fn empty_stack_frame() -> StackFrame {
+        StackFrame {
+            id: 1,
+            function_name: "main".to_string(),
+            source_location: None,
+            registers: DebugRegisters(vec![]),
+            pc: RegisterValue::U32(0x1000),
+            frame_base: None,
+            is_inlined: false,
+            static_variables: None,
+            local_variables: None,
+        }
+    }
+
+    #[test]
+    fn integers_are_parsed_in_decimal_hex_and_binary() {
+        assert_eq!(parse_integer("42"), Some(42));
+        assert_eq!(parse_integer("-1"), Some(-1));
+        assert_eq!(parse_integer("0x2000_0000"), Some(0x2000_0000));
+        assert_eq!(parse_integer("0XFF"), Some(0xFF));
+        assert_eq!(parse_integer("0b101"), Some(5));
+        assert_eq!(parse_integer("counter"), None);
+        assert_eq!(parse_integer("0xZZ"), None);
+        assert_eq!(parse_integer(""), None);
+    }
+
+    #[test]
+    fn comparisons_of_integers() {
+        let stack_frame = empty_stack_frame();
+        for (condition, expected) in [
+            ("1 == 1", true),
+            ("1 != 1", false),
+            ("0x10 == 16", true),
+            ("-1 < 0", true),
+            ("2 <= 2", true),
+            ("3 <= 2", false),
+            ("2 >= 3", false),
+            ("3 > 2", true),
+            ("3>2", true),
+        ] {
+            assert_eq!(
+                evaluate_condition(condition, &stack_frame).unwrap(),
+                expected,
+                "{condition}"
+            );
+        }
+    }
+
+    #[test]
+    fn single_operand_is_true_if_it_is_not_zero() {
+        let stack_frame = empty_stack_frame();
+        assert!(evaluate_condition("1", &stack_frame).unwrap());
+        assert!(!evaluate_condition(" 0 ", &stack_frame).unwrap());
+        assert!(evaluate_condition("\"true\"", &stack_frame).unwrap());
+        assert!(!evaluate_condition("\"false\"", &stack_frame).unwrap());
+        assert!(evaluate_condition("\"yes\"", &stack_frame).is_err());
+    }
+
+    #[test]
+    fn text_can_only_be_compared_for_equality() {
+        let stack_frame = empty_stack_frame();
+        assert!(evaluate_condition("\"Idle\" == \"Idle\"", &stack_frame).unwrap());
+        assert!(evaluate_condition("\"Idle\" != \"Busy\"", &stack_frame).unwrap());
+        assert!(evaluate_condition("\"Idle\" < \"Busy\"", &stack_frame).is_err());
+    }
+
+    #[test]
+    fn malformed_conditions_are_errors() {
+        let stack_frame = empty_stack_frame();
+        assert!(evaluate_condition("", &stack_frame).is_err());
+        assert!(evaluate_condition("== 1", &stack_frame).is_err());
+        assert!(evaluate_condition("1 ==", &stack_frame).is_err());
+        // Names that are not a register or a variable in scope.
+        assert!(evaluate_condition("counter > 1", &stack_frame).is_err());
+    }
+
+    #[test]
+    fn hit_condition_with_a_number_halts_on_that_hit() {
+        assert!(!evaluate_hit_condition("50", 49).unwrap());
+        assert!(evaluate_hit_condition("50", 50).unwrap());
+        assert!(!evaluate_hit_condition("50", 51).unwrap());
+        assert!(evaluate_hit_condition("== 3", 3).unwrap());
+    }
+
+    #[test]
+    fn hit_condition_operators() {
+        for (hit_condition, hit_count, expected) in [
+            (">= 50", 49, false),
+            (">= 50", 50, true),
+            (">=50", 51, true),
+            ("> 2", 2, false),
+            ("< 2", 1, true),
+            ("<= 2", 3, false),
+            ("!= 1", 1, false),
+            ("% 3", 3, true),
+            ("% 3", 4, false),
+            ("%3", 6, true),
+            ("0x10", 16, true),
+        ] {
+            assert_eq!(
+                evaluate_hit_condition(hit_condition, hit_count).unwrap(),
+                expected,
+                "{hit_condition} at hit {hit_count}"
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_hit_conditions_are_errors() {
+        assert!(evaluate_hit_condition("% 0", 1).is_err());
+        assert!(evaluate_hit_condition(">= -1", 1).is_err());
+        assert!(evaluate_hit_condition("often", 1).is_err());
+        assert!(evaluate_hit_condition("", 1).is_err());
+    }
+
+    #[test]
+    fn log_message_substitutes_expressions() {
+        let stack_frame = empty_stack_frame();
+        assert_eq!(
+            format_log_message("limit={0x10} text={\"ok\"}", Some(&stack_frame)),
+            "limit=16 text=ok"
+        );
+    }
+
+    #[test]
+    fn log_message_shows_unavailable_values() {
+        let stack_frame = empty_stack_frame();
+        assert_eq!(
+            format_log_message("counter={counter}", Some(&stack_frame)),
+            "counter=<unavailable>"
+        );
+        // Without a stack frame, no expression can be evaluated.
+        assert_eq!(format_log_message("value={1}", None), "value=<unavailable>");
+    }
+
+    #[test]
+    fn log_message_keeps_escaped_braces() {
+        assert_eq!(
+            format_log_message("{{literal}} and }}", None),
+            "{literal} and }"
+        );
+        assert_eq!(
+            format_log_message("no placeholders", None),
+            "no placeholders"
+        );
+    }
+}
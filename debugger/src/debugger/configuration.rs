@@ -1,9 +1,9 @@
 use crate::DebuggerError;
 use anyhow::{anyhow, Result};
-use probe_rs::{DebugProbeSelector, WireProtocol};
+use probe_rs::{DebugProbeInfo, DebugProbeSelector, WireProtocol};
 use probe_rs_cli_util::rtt;
-use serde::Deserialize;
-use std::{env::current_dir, path::PathBuf};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, env::current_dir, path::PathBuf};
 
 /// Shared options for all session level configuration.
 #[derive(Clone, Deserialize, Debug, Default)]
@@ -19,9 +19,9 @@ pub struct SessionConfig {
     /// Path to the requested working directory for the debugger
     pub(crate) cwd: Option<PathBuf>,
 
-    /// The number associated with the debug probe to use. Use 'list' command to see available probes
+    /// The debug probe to use, as `VID:PID`, `VID:PID:Serial`, or only the serial number. Use 'list' command to see available probes
     #[serde(alias = "probe")]
-    pub(crate) probe_selector: Option<DebugProbeSelector>,
+    pub(crate) probe_selector: Option<ProbeSelector>,
 
     /// The target to be selected.
     pub(crate) chip: Option<String>,
@@ -30,7 +30,7 @@ pub struct SessionConfig {
     #[serde(default)]
     pub(crate) connect_under_reset: bool,
 
-    /// Protocol speed in kHz
+    /// Protocol speed in kHz. Probes that do not support this speed use the nearest speed that they support, which is reported at the start of the session.
     pub(crate) speed: Option<u32>,
 
     /// Protocol to use for target connection
@@ -40,22 +40,81 @@ pub struct SessionConfig {
     #[serde(default)]
     pub(crate) allow_erase_all: bool,
 
+    /// Keep the debug power domain of ARM targets powered for the duration of the session, by re-asserting the
+    /// debug and system power-up request bits whenever the target powers the domain down (e.g. in a deep sleep mode).
+    #[serde(default)]
+    pub(crate) keep_debug_power_on: bool,
+
+    /// When the connection to the target recovers after a transient error (see [`ErrorClass::CoreStatusRead`]),
+    /// set the hardware breakpoints again, and re-attach to RTT, because the target may have lost that state.
+    #[serde(default)]
+    pub(crate) rearm_breakpoints_on_reconnect: bool,
+
+    /// Reset the target at the start of a `launch` session, and select if the core halts at the reset vector, or runs.
+    /// This also applies when flashing is disabled, and it replaces `resetAfterFlashing` and `haltAfterReset` when it is set.
+    pub(crate) reset_mode: Option<ResetMode>,
+
+    /// Before the debug session starts, compare the flash contents with the `program_binary`, without flashing anything,
+    /// and fail the session if they do not match. Use this with `attach`, to avoid debugging stale firmware. It is ignored when flashing is enabled.
+    #[serde(default)]
+    pub(crate) verify: bool,
+
     /// Flashing configuration
     #[serde(default)]
     pub(crate) flashing_config: FlashingConfig,
 
     /// Every core on the target has certain configuration.
     ///
-    /// NOTE: Although we allow specifying multiple core configurations, this is a work in progress, and probe-rs-debugger currently only supports debugging a single core.
+    /// Every configured core is debugged, and each can have its own program binary. Requests refer to a core with its `threadId`, which is the core index.
     pub(crate) core_configs: Vec<CoreConfig>,
+
+    /// Downgrade specific classes of errors, that would otherwise end the debug session, to warnings.
+    /// By default, all errors are fatal. See [`ErrorClass`] for the errors that can be downgraded.
+    #[serde(default)]
+    pub(crate) error_policy: HashMap<ErrorClass, ErrorAction>,
+
+    /// Per-device settings, keyed by the unique device id of the target, so that a single launch configuration can be used for multiple boards.
+    /// The unique device id is logged to the debug console when the debugger connects, and is also reported by the custom `deviceInfo` request.
+    #[serde(default)]
+    pub(crate) device_configs: HashMap<String, DeviceConfig>,
+
+    /// Hide uninteresting entries (e.g. zero values, or reserved fields) from the variables view.
+    /// The filter can be changed during the debug session with the custom `setVariablesFilter` request.
+    #[serde(default)]
+    pub(crate) variables_filter: VariablesFilter,
+
+    /// Replay a core dump that was saved with the custom `saveCoreDump` request, instead of attaching to a target.
+    /// The registers and memory of the core dump are read-only, so the core can not run, step, or be flashed.
+    #[serde(default)]
+    pub(crate) core_dump: Option<PathBuf>,
 }
 
 impl SessionConfig {
+    /// Check if the `error_policy` downgrades the given [`ErrorClass`] to a warning.
+    pub(crate) fn is_warning(&self, error_class: ErrorClass) -> bool {
+        self.error_policy.get(&error_class) == Some(&ErrorAction::Warn)
+    }
+
     /// Ensure all file names are correctly specified and that the files they point to are accessible.
     pub(crate) fn validate_config_files(&mut self) -> Result<(), DebuggerError> {
         // Update the `cwd`.
         self.cwd = self.resolve_cwd()?;
 
+        // Update the `core_dump` and validate that the file exists.
+        if let Some(core_dump) = &self.core_dump {
+            let core_dump =
+                get_absolute_path(self.cwd.clone(), Some(core_dump)).map_err(|error| {
+                    DebuggerError::Other(anyhow!("Invalid core dump file: {error}"))
+                })?;
+            if !core_dump.is_file() {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Core dump file {:?} not found.",
+                    core_dump
+                )));
+            }
+            self.core_dump = Some(core_dump);
+        }
+
         for target_core_config in &mut self.core_configs {
             // Update the `program_binary` and validate that the file exists.
             target_core_config.program_binary = match get_absolute_path(
@@ -96,6 +155,30 @@ impl SessionConfig {
                         None
                     }
                 };
+            // Update the `coverage` source and report paths, so that they are resolved relative to `cwd`.
+            if let Some(coverage) = &mut target_core_config.coverage {
+                for coverage_line in &mut coverage.lines {
+                    coverage_line.path =
+                        get_absolute_path(self.cwd.clone(), Some(&coverage_line.path))?;
+                }
+                coverage.report_file = coverage
+                    .report_file
+                    .as_ref()
+                    .map(|report_file| get_absolute_path(self.cwd.clone(), Some(report_file)))
+                    .transpose()?;
+            }
+            // Update the local paths of the `source_path_remaps`, so that they are resolved relative to `cwd`.
+            for source_path_remap in &mut target_core_config.rtt_config.source_path_remaps {
+                source_path_remap.to =
+                    get_absolute_path(self.cwd.clone(), Some(&source_path_remap.to))?;
+            }
+        }
+
+        // Update the `memory_writes` files of every device, so that they are resolved relative to `cwd`.
+        for device_config in self.device_configs.values_mut() {
+            for memory_write in &mut device_config.memory_writes {
+                memory_write.file = get_absolute_path(self.cwd.clone(), Some(&memory_write.file))?;
+            }
         }
 
         Ok(())
@@ -151,6 +234,92 @@ fn get_absolute_path(
     }
 }
 
+/// Errors that can be downgraded to warnings with [`SessionConfig::error_policy`], so that long running sessions (e.g. hardware-in-the-loop tests)
+/// survive transient failures.
+///
+/// Errors that are not listed here always end the debug session, because the debugger cannot reliably continue after them.
+/// These include a core in LOCKUP status, flashing failures, and failures to attach to the probe or core.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorClass {
+    /// The probe reported an `Unknown` core status. It is safe to downgrade, because the status will be polled again.
+    UnknownCoreStatus,
+    /// Reading the core status failed, e.g. because of a transient communication error with the probe.
+    /// Only downgrade this if the probe connection is otherwise reliable, because a disconnected probe
+    /// will then result in repeated warnings, rather than the end of the debug session.
+    CoreStatusRead,
+    /// Reading, or decoding, RTT data failed. It is safe to downgrade, but the data that caused the failure will be lost.
+    Rtt,
+}
+
+/// The action to take when an error of a specific [`ErrorClass`] occurs.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorAction {
+    /// Report the error, and end the debug session.
+    #[default]
+    Error,
+    /// Report the error as a warning, and continue the debug session.
+    Warn,
+}
+
+/// Criteria to hide entries from the variables view. All criteria are disabled by default.
+/// This only changes which variables are sent to the client. The variables are still available to, for example, the `evaluate` request.
+#[derive(Clone, Deserialize, Serialize, Debug, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct VariablesFilter {
+    /// Hide variables without children, where the value is zero, e.g. `0`, `0x00`, or an SVD field with no bits set.
+    pub(crate) hide_zero_values: bool,
+    /// Hide variables that are named like padding or reserved fields, e.g. `_reserved`, `__reserved1` or `_padding`.
+    pub(crate) hide_reserved_names: bool,
+    /// Hide SVD registers and fields that are named like reserved fields, e.g. `RESERVED` or `RESERVED2`.
+    pub(crate) hide_svd_reserved: bool,
+}
+
+impl VariablesFilter {
+    /// Check if a variable with the given `name` and formatted `value` should be hidden.
+    /// Variables with children are never hidden because of their value, because the value does not describe the children.
+    pub(crate) fn hides(&self, name: &str, value: &str, has_children: bool, is_svd: bool) -> bool {
+        // SVD variables use fully qualified names, e.g. `PERIPHERAL.REGISTER.FIELD`.
+        let name = name.rsplit('.').next().unwrap_or(name);
+        if self.hide_zero_values && !has_children && is_zero_value(value) {
+            return true;
+        }
+        if is_svd {
+            self.hide_svd_reserved && is_reserved_name(name)
+        } else {
+            self.hide_reserved_names && name.starts_with('_') && is_reserved_name(name)
+        }
+    }
+}
+
+/// Check if the first word of a formatted value is a zero, in decimal, hexadecimal or binary notation.
+/// SVD values are formatted as binary digits, followed by the address, e.g. `0000 @ 0x40021000:4..8`.
+fn is_zero_value(value: &str) -> bool {
+    let first_word = value.split_whitespace().next().unwrap_or_default();
+    let digits = first_word
+        .strip_prefix("0x")
+        .or_else(|| first_word.strip_prefix("0b"))
+        .unwrap_or(first_word);
+    !digits.is_empty()
+        && digits
+            .chars()
+            .all(|character| character == '0' || character == '_')
+}
+
+/// Check if `name` (ignoring case, and leading underscores) is `reserved`, `padding` or `pad`, optionally followed by a number.
+fn is_reserved_name(name: &str) -> bool {
+    let name = name.trim_start_matches('_').to_ascii_lowercase();
+    ["reserved", "padding", "pad"].iter().any(|prefix| {
+        name.strip_prefix(prefix).map_or(false, |suffix| {
+            suffix
+                .trim_start_matches('_')
+                .chars()
+                .all(|character| character.is_ascii_digit())
+        })
+    })
+}
+
 /// Configuration options to control flashing.
 #[derive(Clone, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
@@ -174,6 +343,83 @@ pub struct FlashingConfig {
     /// Restore erased bytes that will not be rewritten from ELF
     #[serde(default)]
     pub(crate) restore_unwritten_bytes: bool,
+
+    /// Load the flash algorithm into the RAM region that contains this address, instead of the first available RAM region.
+    /// Use this to avoid overwriting RAM contents (e.g. a persistent log) during flashing.
+    pub(crate) flash_algorithm_ram_address: Option<u64>,
+
+    /// Wait this many milliseconds after flashing, before the target is reset.
+    /// Use this for external flash or EEPROM that needs time to settle after programming.
+    #[serde(default)]
+    pub(crate) post_flash_delay_ms: u64,
+
+    /// After flashing, read back the flashed data to verify that it was written correctly.
+    #[serde(default)]
+    pub(crate) verify_after_flashing: bool,
+}
+
+/// Selects the debug probe to use. See [`SessionConfig::probe_selector`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) enum ProbeSelector {
+    /// The USB vendor and product id, and optionally the serial number, e.g. `0483:374b:0671FF`.
+    Usb(DebugProbeSelector),
+    /// Only the serial number, e.g. `0671FF`, which is useful when several probes of the same type are connected.
+    SerialNumber(String),
+}
+
+impl TryFrom<String> for ProbeSelector {
+    type Error = DebuggerError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.contains(':') {
+            DebugProbeSelector::try_from(value.as_str())
+                .map(ProbeSelector::Usb)
+                .map_err(|error| {
+                    DebuggerError::Other(anyhow!(
+                        "Invalid probe selector `{value}`, expected `VID:PID`, `VID:PID:Serial`, or a serial number: {error}"
+                    ))
+                })
+        } else {
+            Ok(ProbeSelector::SerialNumber(value))
+        }
+    }
+}
+
+impl std::fmt::Display for ProbeSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProbeSelector::Usb(selector) => {
+                write!(f, "{:04x}:{:04x}", selector.vendor_id, selector.product_id)?;
+                if let Some(serial_number) = &selector.serial_number {
+                    write!(f, ":{serial_number}")?;
+                }
+                Ok(())
+            }
+            ProbeSelector::SerialNumber(serial_number) => write!(f, "{serial_number}"),
+        }
+    }
+}
+
+impl ProbeSelector {
+    /// Returns `true` if the probe described by `probe_info` is selected.
+    pub(crate) fn matches(&self, probe_info: &DebugProbeInfo) -> bool {
+        match self {
+            ProbeSelector::Usb(selector) => {
+                selector.vendor_id == probe_info.vendor_id
+                    && selector.product_id == probe_info.product_id
+                    && selector
+                        .serial_number
+                        .as_ref()
+                        .map_or(true, |serial_number| {
+                            probe_info.serial_number.as_ref() == Some(serial_number)
+                        })
+            }
+            ProbeSelector::SerialNumber(serial_number) => {
+                probe_info.serial_number.as_ref() == Some(serial_number)
+            }
+        }
+    }
 }
 
 /// Configuration options for all core level configuration.
@@ -190,8 +436,203 @@ pub struct CoreConfig {
     /// CMSIS-SVD file for the target. Relative to `cwd`, or fully qualified.
     pub(crate) svd_file: Option<PathBuf>,
 
+    /// Count how often selected source lines are executed, and report the results when the session ends.
+    pub(crate) coverage: Option<CoverageConfig>,
+
+    /// Log every call to selected functions, without halting the debug session.
+    pub(crate) function_trace: Option<FunctionTraceConfig>,
+
     #[serde(flatten)]
     pub(crate) rtt_config: rtt::RttConfig,
+
+    /// Controls when the client window for an RTT up channel is created.
+    #[serde(default)]
+    pub(crate) rtt_window_mode: RttWindowMode,
+
+    /// The maximum number of bytes of RTT output that are kept for each up channel, until the client has opened its window. Defaults to 64 KiB.
+    /// Once this limit is reached, the data is left in the target buffer.
+    pub(crate) rtt_window_buffer_size: Option<usize>,
+
+    /// Stop the watchdog, and other peripherals like timers, while the core is halted, using the debug freeze registers described in the `svdFile`.
+    #[serde(default)]
+    pub(crate) freeze_peripherals_when_halted: bool,
+
+    /// What to do when the core halts at a breakpoint while it is handling an exception or interrupt (Cortex-M only).
+    #[serde(default)]
+    pub(crate) isr_breakpoint_action: IsrBreakpointAction,
+
+    /// Read variables and evaluate pass or fail conditions each time the core halts, e.g. to drive a hardware-in-the-loop test from the debugger.
+    pub(crate) on_stop: Option<OnStopConfig>,
+
+    /// Show the raw memory bytes of each variable, in hexadecimal, in addition to the decoded value. This requires an additional memory read for every variable that is displayed.
+    #[serde(default)]
+    pub(crate) show_raw_bytes: bool,
+
+    /// When all the hardware breakpoint units are in use, set further breakpoints in RAM by replacing the instruction with a `BKPT` instruction.
+    /// This is only supported on Cortex-M cores, and only for code that executes from a writable (RAM) memory region.
+    #[serde(default)]
+    pub(crate) software_breakpoint_fallback: bool,
+
+    /// Names of functions (e.g. `rust_begin_unwind`, or `HardFault_Handler`) where the core always halts, and the halt is reported as a caught panic.
+    /// These breakpoints are set for every run, and are not affected by the breakpoints that the client sets or clears.
+    #[serde(default)]
+    pub(crate) catchpoints: Vec<String>,
+
+    /// Save the source breakpoints to a file next to the `program_binary` (e.g. `firmware.elf.breakpoints.json`) whenever they change,
+    /// and set them again at the start of the next debug session, also if the client does not send them.
+    #[serde(default)]
+    pub(crate) persist_breakpoints: bool,
+
+    /// The numbers of expected exceptions (e.g. `15` for SysTick, or `14` for PendSV), that resume the core when it halts at a breakpoint or exception
+    /// while it is handling one of them (Cortex-M only).
+    /// They are counted, and the counts are shown when the core halts for another reason, until the next restart.
+    #[serde(default)]
+    pub(crate) ignored_exceptions: Vec<u32>,
+
+    /// The maximum number of elements of a `Vec`, `VecDeque` or `HashMap` that are read from the target, and shown when the collection is expanded. Defaults to 100.
+    pub(crate) collection_element_limit: Option<usize>,
+
+    /// Show the tasks of the RTOS that runs on the core as threads, with their names and states, instead of a single thread for the core.
+    pub(crate) rtos: Option<Rtos>,
+
+    /// The time, in milliseconds, to wait for a `stepOut` or `goto` request to halt the core, before the debugger halts it and reports that the step timed out.
+    /// Defaults to 30000 ms. Use `0` to wait indefinitely.
+    pub(crate) step_timeout_ms: Option<u64>,
+
+    /// Halt the core as soon as the firmware writes its first RTT data, e.g. to inspect the state right after the initialization logging.
+    /// This only halts the core once per debug session, and requires `rttEnabled`.
+    #[serde(default)]
+    pub(crate) halt_on_rtt_output: bool,
+}
+
+/// A declarative action that runs each time the core halts at a breakpoint, watchpoint or exception (but not after a step or a pause request).
+///
+/// The variables are logged to the debug console, then `failCondition` and `passCondition` are evaluated, in that order,
+/// with the same syntax as breakpoint conditions. If either is `true`, the result is logged, and the debug session ends with exit code `1` or `0`.
+/// Otherwise, the core is resumed if `continue` is `true`, or the halt is reported to the client as usual.
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OnStopConfig {
+    /// The names of the registers, and local or static variables, to read and log.
+    #[serde(default)]
+    pub(crate) read_variables: Vec<String>,
+    /// The test fails if this condition is `true`, or if it cannot be evaluated.
+    pub(crate) fail_condition: Option<String>,
+    /// The test passes if this condition is `true`.
+    pub(crate) pass_condition: Option<String>,
+    /// Resume the core if the test neither passed nor failed.
+    #[serde(default, rename = "continue")]
+    pub(crate) continue_after_stop: bool,
+}
+
+/// What to do when the core halts at a breakpoint inside an exception or interrupt handler, where peripherals like
+/// watchdogs, that keep running while the core is halted, can cause cascading failures.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IsrBreakpointAction {
+    /// Halt normally.
+    #[default]
+    Proceed,
+    /// Stop the peripherals while the core is halted, using the debug freeze registers described in the `svdFile`.
+    /// See [`CoreConfig::freeze_peripherals_when_halted`].
+    FreezePeripherals,
+    /// Warn the user that the core halted inside an exception handler.
+    Warn,
+}
+
+/// The RTOS kernels for which the tasks can be shown as threads. See [`CoreConfig::rtos`].
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Rtos {
+    /// The tasks are read from the task lists of the FreeRTOS kernel, e.g. `pxReadyTasksLists`. Only 32-bit targets are supported.
+    FreeRtos,
+}
+
+/// Controls when the client window for an RTT up channel is created.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RttWindowMode {
+    /// Create a window for every up channel as soon as RTT is attached.
+    #[default]
+    Eager,
+    /// Only create a window for an up channel once it has produced output, so that unused channels do not create empty windows.
+    Lazy,
+}
+
+/// How the core is reset at the start of a `launch` session. See [`SessionConfig::reset_mode`].
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ResetMode {
+    /// Reset the core, and halt it at the reset vector. The client receives a `stopped` event once its configuration is done.
+    ResetAndHalt,
+    /// Reset the core, and let it run. The client is only notified of status changes that are found by polling the core.
+    ResetAndRun,
+}
+
+/// Settings that only apply to the device with a specific unique device id. See [`SessionConfig::device_configs`].
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceConfig {
+    /// The address of the RTT control block, to use instead of the address of the `_SEGGER_RTT` symbol in the program binary.
+    pub(crate) rtt_control_block_address: Option<u64>,
+    /// Data to write to the target (e.g. device specific calibration data), every time the debugger connects, or restarts the session.
+    /// The writes happen after the core is halted, but before it is reset (if `resetAfterFlashing` is set).
+    #[serde(default)]
+    pub(crate) memory_writes: Vec<DeviceMemoryWrite>,
+}
+
+/// The contents of `file` will be written to the target memory at `address`.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceMemoryWrite {
+    pub(crate) address: u64,
+    /// The file with the raw binary data. Relative to `cwd`, or fully qualified.
+    pub(crate) file: PathBuf,
+}
+
+/// Configuration for breakpoint based line coverage.
+///
+/// This is not instrumented coverage. Each line uses a breakpoint unit on the target, and every hit briefly halts the core,
+/// which is good enough to answer "did this code path execute" questions in hardware-in-the-loop tests.
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageConfig {
+    /// The source lines for which hits will be counted.
+    #[serde(default)]
+    pub(crate) lines: Vec<CoverageLine>,
+    /// The JSON coverage report will be written to this file. Relative to `cwd`, or fully qualified.
+    pub(crate) report_file: Option<PathBuf>,
+}
+
+/// Configuration for tracing function entries with breakpoints.
+///
+/// Every call to an instrumented function halts the core, logs the function name, and resumes the core.
+/// This is a substitute for trace hardware, and will significantly slow down the target application.
+#[derive(Clone, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionTraceConfig {
+    /// Names of the functions to trace, e.g. `my_app::module::function`. Use `*` as a wildcard, e.g. `my_app::driver::*`.
+    #[serde(default)]
+    pub(crate) functions: Vec<String>,
+    /// The maximum number of functions to instrument. This is also limited by the number of available breakpoint units.
+    #[serde(default = "default_max_trace_functions")]
+    pub(crate) max_functions: usize,
+}
+
+fn default_max_trace_functions() -> usize {
+    32
+}
+
+/// A single source location to be tracked by [`CoverageConfig`].
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageLine {
+    /// Source file path. Relative to `cwd`, or fully qualified.
+    pub(crate) path: PathBuf,
+    /// Line number, using 1 based numbering.
+    pub(crate) line: u64,
+    /// Optional column number, using 1 based numbering.
+    pub(crate) column: Option<u64>,
 }
 
 fn default_console_log() -> Option<ConsoleLog> {
@@ -220,3 +661,159 @@ impl std::str::FromStr for ConsoleLog {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{IsrBreakpointAction, ProbeSelector, VariablesFilter};
+    use probe_rs::{DebugProbeInfo, DebugProbeType};
+
+    fn probe_info(vendor_id: u16, product_id: u16, serial_number: Option<&str>) -> DebugProbeInfo {
+        DebugProbeInfo::new(
+            "Test probe",
+            vendor_id,
+            product_id,
+            serial_number.map(str::to_string),
+            DebugProbeType::StLink,
+            None,
+        )
+    }
+
+    #[test]
+    fn probe_selector_is_parsed_from_usb_ids_or_a_serial_number() {
+        let selector = ProbeSelector::try_from("0483:374b".to_string()).unwrap();
+        assert!(matches!(
+            &selector,
+            ProbeSelector::Usb(selector)
+                if selector.vendor_id == 0x0483 && selector.product_id == 0x374b && selector.serial_number.is_none()
+        ));
+        assert_eq!(selector.to_string(), "0483:374b");
+
+        let selector = ProbeSelector::try_from("0483:374B:0671FF".to_string()).unwrap();
+        assert!(matches!(
+            &selector,
+            ProbeSelector::Usb(selector) if selector.serial_number.as_deref() == Some("0671FF")
+        ));
+        assert_eq!(selector.to_string(), "0483:374b:0671FF");
+
+        let selector = ProbeSelector::try_from("0671FF".to_string()).unwrap();
+        assert!(
+            matches!(&selector, ProbeSelector::SerialNumber(serial_number) if serial_number == "0671FF")
+        );
+        assert_eq!(selector.to_string(), "0671FF");
+    }
+
+    #[test]
+    fn invalid_usb_probe_selector_is_rejected() {
+        assert!(ProbeSelector::try_from("0483:xyz".to_string()).is_err());
+        assert!(ProbeSelector::try_from("12345:374b".to_string()).is_err());
+        assert!(serde_json::from_str::<ProbeSelector>(r#""0483:""#).is_err());
+    }
+
+    #[test]
+    fn usb_probe_selector_matches_the_ids_and_the_serial_number() {
+        let any_serial_number = ProbeSelector::try_from("0483:374b".to_string()).unwrap();
+        assert!(any_serial_number.matches(&probe_info(0x0483, 0x374b, Some("0671FF"))));
+        assert!(any_serial_number.matches(&probe_info(0x0483, 0x374b, None)));
+        assert!(!any_serial_number.matches(&probe_info(0x0483, 0x3748, Some("0671FF"))));
+        assert!(!any_serial_number.matches(&probe_info(0x1366, 0x374b, Some("0671FF"))));
+
+        let with_serial_number = ProbeSelector::try_from("0483:374b:0671FF".to_string()).unwrap();
+        assert!(with_serial_number.matches(&probe_info(0x0483, 0x374b, Some("0671FF"))));
+        assert!(!with_serial_number.matches(&probe_info(0x0483, 0x374b, Some("066DFF"))));
+        assert!(!with_serial_number.matches(&probe_info(0x0483, 0x374b, None)));
+    }
+
+    #[test]
+    fn serial_number_probe_selector_matches_any_probe_type() {
+        let selector = ProbeSelector::try_from("0671FF".to_string()).unwrap();
+        assert!(selector.matches(&probe_info(0x0483, 0x374b, Some("0671FF"))));
+        assert!(selector.matches(&probe_info(0x1366, 0x0101, Some("0671FF"))));
+        assert!(!selector.matches(&probe_info(0x0483, 0x374b, Some("0671FE"))));
+        assert!(!selector.matches(&probe_info(0x0483, 0x374b, None)));
+    }
+
+    #[test]
+    fn default_filter_hides_nothing() {
+        let variables_filter = VariablesFilter::default();
+        assert!(!variables_filter.hides("counter", "0", false, false));
+        assert!(!variables_filter.hides("_reserved", "0x12", false, false));
+        assert!(!variables_filter.hides("UART.RESERVED", "0000 @ 0x40021000:4..8", false, true));
+    }
+
+    #[test]
+    fn zero_values_are_hidden_unless_they_have_children() {
+        let variables_filter = VariablesFilter {
+            hide_zero_values: true,
+            ..Default::default()
+        };
+        assert!(variables_filter.hides("counter", "0", false, false));
+        assert!(variables_filter.hides("flags", "0x0000", false, false));
+        assert!(variables_filter.hides("UART.CR1.UE", "0 @ 0x40021000:0..1", false, true));
+        assert!(!variables_filter.hides("counter", "10", false, false));
+        assert!(!variables_filter.hides("counter", "0.5", false, false));
+        assert!(!variables_filter.hides("config", "0", true, false));
+        assert!(!variables_filter.hides("name", "", false, false));
+    }
+
+    #[test]
+    fn reserved_names_are_hidden() {
+        let variables_filter = VariablesFilter {
+            hide_reserved_names: true,
+            ..Default::default()
+        };
+        assert!(variables_filter.hides("_reserved", "0x12", false, false));
+        assert!(variables_filter.hides("__reserved1", "0x12", false, false));
+        assert!(variables_filter.hides("_padding", "0x12", false, false));
+        assert!(variables_filter.hides("_pad_2", "0x12", false, false));
+        assert!(!variables_filter.hides("reserved", "0x12", false, false));
+        assert!(!variables_filter.hides("_reserved_size", "0x12", false, false));
+        // SVD names are only hidden with `hide_svd_reserved`.
+        assert!(!variables_filter.hides("UART.RESERVED", "0x12", false, true));
+    }
+
+    #[test]
+    fn svd_reserved_names_are_hidden() {
+        let variables_filter = VariablesFilter {
+            hide_svd_reserved: true,
+            ..Default::default()
+        };
+        assert!(variables_filter.hides("UART.RESERVED", "0x12", false, true));
+        assert!(variables_filter.hides("UART.CR1.RESERVED2", "0x12", false, true));
+        assert!(!variables_filter.hides("UART.CR1", "0x12", false, true));
+        assert!(!variables_filter.hides("_reserved", "0x12", false, false));
+    }
+
+    #[test]
+    fn filter_arguments_replace_the_whole_filter() {
+        // The `setVariablesFilter` arguments only list the enabled criteria, and the others are disabled.
+        let variables_filter: VariablesFilter =
+            serde_json::from_str(r#"{"hideZeroValues": true}"#).unwrap();
+        assert_eq!(
+            variables_filter,
+            VariablesFilter {
+                hide_zero_values: true,
+                hide_reserved_names: false,
+                hide_svd_reserved: false,
+            }
+        );
+    }
+
+    #[test]
+    fn isr_breakpoint_action_is_deserialized_from_camel_case() {
+        for (json, expected) in [
+            (r#""proceed""#, IsrBreakpointAction::Proceed),
+            (
+                r#""freezePeripherals""#,
+                IsrBreakpointAction::FreezePeripherals,
+            ),
+            (r#""warn""#, IsrBreakpointAction::Warn),
+        ] {
+            assert_eq!(
+                serde_json::from_str::<IsrBreakpointAction>(json).unwrap(),
+                expected
+            );
+        }
+        assert!(serde_json::from_str::<IsrBreakpointAction>(r#""halt""#).is_err());
+        assert_eq!(IsrBreakpointAction::default(), IsrBreakpointAction::Proceed);
+    }
+}
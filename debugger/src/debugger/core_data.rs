@@ -1,10 +1,29 @@
-use std::{fs::File, path::Path};
+use std::{
+    collections::HashMap,
+    fs::File,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
-use super::session_data::{self, BreakpointType};
+use super::{
+    breakpoint_condition,
+    configuration::{
+        CoverageConfig, DeviceConfig, FunctionTraceConfig, IsrBreakpointAction, OnStopConfig,
+        ResetMode, Rtos, RttWindowMode,
+    },
+    cortex_m_faults,
+    data_watchpoints::{self, WatchKind},
+    device_identity, elf_symbols, expression, function_trace, persisted_breakpoints,
+    register_groups, riscv_csrs, rtos, semihosting,
+    session_data::{self, BreakpointType},
+};
 use crate::{
     debug_adapter::{
-        dap_adapter::{DapStatus, DebugAdapter},
-        dap_types::{ContinuedEventBody, MessageSeverity, Source, StoppedEventBody},
+        dap_adapter::{get_dap_source, DapStatus, DebugAdapter},
+        dap_types::{
+            ContinuedEventBody, DisassembledInstruction, ExitedEventBody, MessageSeverity,
+            OutputEventBody, Source, StoppedEventBody, TerminatedEventBody,
+        },
         protocol::ProtocolAdapter,
     },
     debugger::debug_rtt,
@@ -12,13 +31,42 @@ use crate::{
     DebuggerError,
 };
 use anyhow::{anyhow, Result};
+use capstone::{
+    arch::arm::ArchMode as armArchMode, arch::arm64::ArchMode as aarch64ArchMode,
+    arch::riscv::ArchMode as riscvArchMode, prelude::*, Capstone, Endian,
+};
 use probe_rs::{
-    debug::{debug_info::DebugInfo, ColumnType, VerifiedBreakpoint},
+    config::MemoryRegion,
+    debug::{
+        debug_info::DebugInfo, ColumnType, DebugError, SourceLocation, SteppingMode, VariableCache,
+        VariableName, VerifiedBreakpoint,
+    },
     rtt::{Rtt, ScanRegion},
-    Core, CoreStatus, Error, HaltReason,
+    Architecture, Core, CoreDump, CoreStatus, CoreType, Error, HaltReason, InstructionSet,
+    MemoryInterface, RegisterId,
 };
 use probe_rs_cli_util::rtt::{self, ChannelMode, DataFormat, RttActiveTarget};
-use time::UtcOffset;
+use serde::Serialize;
+use time::{format_description::FormatItem, macros::format_description, OffsetDateTime, UtcOffset};
+
+/// The format used for timestamps in function trace messages.
+const TRACE_TIMESTAMP_FORMAT: &[FormatItem] =
+    format_description!("[hour]:[minute]:[second].[subsecond digits:6]");
+
+/// The Thumb `BKPT #0` instruction, in little endian byte order, that is used for software breakpoints.
+const THUMB_BKPT_INSTRUCTION: [u8; 2] = [0x00, 0xBE];
+
+/// The maximum number of bytes that are read from target memory at once by [`CoreHandle::read_memory`].
+const MEMORY_READ_CHUNK_SIZE: usize = 1024;
+
+/// The maximum number of interrupts that are stepped over during a single step, by [`CoreHandle::step`].
+const MAX_STEPPED_OVER_INTERRUPTS: usize = 16;
+
+/// The time to wait for an interrupt handler that was entered during a step to return.
+const INTERRUPT_RETURN_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// The default for [`CoreConfig::step_timeout_ms`].
+pub(crate) const DEFAULT_STEP_TIMEOUT_MS: u64 = 30_000;
 
 /// [CoreData] is used to cache data needed by the debugger, on a per-core basis.
 pub struct CoreData {
@@ -34,10 +82,147 @@ pub struct CoreData {
     pub(crate) last_known_status: CoreStatus,
     pub(crate) target_name: String,
     pub(crate) debug_info: DebugInfo,
+    /// The symbols from the ELF symbol table of the program binary, used when DWARF does not describe a symbol.
+    pub(crate) elf_symbols: Vec<elf_symbols::ElfSymbol>,
     pub(crate) core_peripherals: Option<SvdCache>,
     pub(crate) stack_frames: Vec<probe_rs::debug::stack_frame::StackFrame>,
+    /// The global variables of all the compile units, that are shown irrespective of the current stack frame.
+    /// The compile units that declare them are enumerated once, and their values are read again when they are expanded after a halt.
+    pub(crate) global_variables: Option<VariableCache>,
+    /// The system and FPU registers of the halted core, that are shown as scopes of the top stack frame.
+    pub(crate) register_groups: Vec<register_groups::RegisterGroup>,
     pub(crate) breakpoints: Vec<session_data::ActiveBreakpoint>,
+    /// The source breakpoints that could not be set yet, and are reported to the debug client as unverified. They share their ids with the [`CoreData::breakpoints`].
+    pub(crate) pending_breakpoints: Vec<session_data::PendingBreakpoint>,
+    /// The data watchpoints that were requested with `setDataBreakpoints`. They share their ids with the [`CoreData::breakpoints`].
+    pub(crate) data_watchpoints: Vec<data_watchpoints::ActiveDataWatchpoint>,
+    /// The number of hardware breakpoint units of the core, cached by [`CoreHandle::available_breakpoint_units`].
+    pub(crate) breakpoint_units: Option<u32>,
+    /// The id that will be assigned to the next [`session_data::ActiveBreakpoint`].
+    pub(crate) next_breakpoint_id: i64,
+    /// The stack pointer of the calling frame, while a `stepOut` request runs to the [`BreakpointType::TemporaryBreakpoint`] at the return address.
+    /// A recursive call of the same function can reach the return address with a lower stack pointer, and is not reported as a halt.
+    pub(crate) step_out_stack_pointer: Option<u64>,
+    /// The reason of the `stopped` event when the core halts at the [`BreakpointType::TemporaryBreakpoint`], e.g. `step` for a `stepOut` request.
+    pub(crate) temporary_breakpoint_stop_reason: &'static str,
     pub(crate) rtt_connection: Option<debug_rtt::RttConnection>,
+    /// `true` after [`CoreHandle::detach_rtt`], until the core runs again. Until then, the control block in RAM may be a stale copy from before a reset,
+    /// so RTT is not attached again.
+    pub(crate) rtt_reattach_after_run: bool,
+    /// The channels of the last [`debug_rtt::RttConnection`], kept by [`CoreHandle::detach_rtt`], so that the state of their client windows,
+    /// and the data that was not sent to the client yet, carry over to the channels with the same number when RTT is attached again.
+    pub(crate) detached_rtt_channels: Vec<debug_rtt::DebuggerRttChannel>,
+    /// The number of failed attempts to attach to RTT, since the last time the `retries` of the [`rtt::RttConfig`] were exhausted.
+    pub(crate) rtt_attach_attempts: u32,
+    /// The earliest time of the next attempt to attach to RTT, so that the retries are spread over the `timeout_ms` of the [`rtt::RttConfig`],
+    /// without blocking the processing of requests.
+    pub(crate) rtt_next_attach_attempt: Option<Instant>,
+    /// Overrides the RTT control block address from the program binary. See [`DeviceConfig::rtt_control_block_address`].
+    pub(crate) rtt_control_block_address: Option<u64>,
+    /// `true` while the core status cannot be read, because of an error that was downgraded by the `error_policy`.
+    pub(crate) connection_lost: bool,
+    /// The memory map of the target, used to validate memory accesses requested by the client.
+    pub(crate) memory_map: Vec<MemoryRegion>,
+    /// Offset used for timestamps in debugger generated output, e.g. function trace messages.
+    pub(crate) timestamp_offset: UtcOffset,
+    /// What to do when a breakpoint is hit inside an exception handler. See [`CoreConfig::isr_breakpoint_action`].
+    pub(crate) isr_breakpoint_action: IsrBreakpointAction,
+    /// The action that runs each time the core halts. See [`CoreConfig::on_stop`].
+    pub(crate) on_stop: Option<OnStopConfig>,
+    /// Show the raw memory bytes of variables. See [`CoreConfig::show_raw_bytes`].
+    pub(crate) show_raw_bytes: bool,
+    /// Use software breakpoints in RAM when the hardware breakpoint units are exhausted. See [`CoreConfig::software_breakpoint_fallback`].
+    pub(crate) software_breakpoint_fallback: bool,
+    /// The exceptions that resume the core when it halts because of them. See [`CoreConfig::ignored_exceptions`].
+    pub(crate) ignored_exceptions: Vec<u32>,
+    /// The number of times that each of the [`CoreData::ignored_exceptions`] resumed the core, by exception number.
+    pub(crate) ignored_exception_counts: HashMap<u32, u64>,
+    /// The values of the expressions in the watch pane of the client, keyed by the expression, and refreshed by [`CoreHandle::refresh_watch_values`].
+    pub(crate) watch_values: HashMap<String, expression::WatchValue>,
+    /// The file where the source breakpoints are saved. See [`CoreConfig::persist_breakpoints`].
+    pub(crate) breakpoints_file: Option<PathBuf>,
+    /// The files that the target opened with semihosting calls.
+    pub(crate) semihosting_files: semihosting::SemihostingFiles,
+    /// The RTOS whose tasks are shown as threads. See [`CoreConfig::rtos`].
+    pub(crate) rtos: Option<Rtos>,
+    /// The layout of the RTOS kernel data structures, or the reason why it could not be read, once it was read from the `debug_info`.
+    pub(crate) rtos_layout: Option<Result<rtos::FreeRtosLayout, String>>,
+    /// How long a step may run the core, before it is halted by [`CoreHandle::poll_core`]. See [`CoreConfig::step_timeout_ms`].
+    pub(crate) step_timeout: Option<Duration>,
+    /// The time at which the step that is currently running the core times out. See [`CoreHandle::start_step_timeout`].
+    pub(crate) step_deadline: Option<Instant>,
+    /// Halt the core when the first RTT data is received. This is cleared once the data was received. See [`CoreConfig::halt_on_rtt_output`].
+    pub(crate) halt_on_rtt_output: bool,
+    /// RTT data was received while [`CoreData::halt_on_rtt_output`] was set, so the core is halted by the next [`CoreHandle::poll_core`].
+    pub(crate) rtt_output_received: bool,
+}
+
+impl CoreData {
+    /// `true` if `reference` is the id of one of the stack frames of this core, or the key of one of its variables.
+    /// The ids and keys are unique across the cores, so this finds the core that a `scopes`, `variables` or `evaluate` request refers to.
+    pub(crate) fn owns_reference(&self, reference: i64) -> bool {
+        self.stack_frames.iter().any(|stack_frame| {
+            stack_frame.id == reference
+                || [&stack_frame.local_variables, &stack_frame.static_variables]
+                    .into_iter()
+                    .flatten()
+                    .any(|variable_cache| variable_cache.get_variable_by_key(reference).is_some())
+        }) || self
+            .register_groups
+            .iter()
+            .any(|register_group| register_group.variables_reference == reference)
+            || self
+                .global_variables
+                .as_ref()
+                .map_or(false, |variable_cache| {
+                    variable_cache.get_variable_by_key(reference).is_some()
+                })
+            || self
+                .core_peripherals
+                .as_ref()
+                .map_or(false, |core_peripherals| {
+                    core_peripherals
+                        .svd_variable_cache
+                        .get_variable_by_key(reference)
+                        .is_some()
+                })
+    }
+}
+
+/// A single line of the coverage report produced by [`CoreHandle::coverage_report`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageReportEntry {
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) line: Option<u64>,
+    pub(crate) address: u64,
+    pub(crate) hit_count: u64,
+}
+
+/// The breakpoints that [`CoreHandle::restore_breakpoints`] set, as their id, location and source,
+/// and a description of each saved breakpoint that could not be set.
+pub(crate) type RestoredBreakpoints = (Vec<(i64, VerifiedBreakpoint, Source)>, Vec<String>);
+
+/// The kind of memory region that an address belongs to, in the target's memory map. See [`CoreHandle::memory_region_kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MemoryRegionKind {
+    Ram,
+    Flash,
+    /// A generic region, which is neither RAM nor Flash, e.g. peripheral registers.
+    Peripheral,
+    /// The address is not part of any memory region.
+    Unmapped,
+}
+
+impl std::fmt::Display for MemoryRegionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoryRegionKind::Ram => write!(f, "RAM"),
+            MemoryRegionKind::Flash => write!(f, "Flash"),
+            MemoryRegionKind::Peripheral => write!(f, "peripheral memory"),
+            MemoryRegionKind::Unmapped => write!(f, "unmapped memory"),
+        }
+    }
 }
 
 /// [CoreHandle] provides handles to various data structures required to debug a single instance of a core. The actual state is stored in [session_data::SessionData].
@@ -49,6 +234,22 @@ pub struct CoreHandle<'p> {
 }
 
 impl<'p> CoreHandle<'p> {
+    /// Reset the core according to `reset_mode`, and set `last_known_status` to the status of the core after the reset,
+    /// so that [`CoreHandle::poll_core()`] does not report the reset as a status change.
+    pub(crate) fn reset(&mut self, reset_mode: ResetMode) -> Result<CoreStatus, DebuggerError> {
+        match reset_mode {
+            ResetMode::ResetAndHalt => {
+                self.core.reset_and_halt(Duration::from_millis(500))?;
+                // Ensure ebreak enters debug mode, this is necessary for soft breakpoints to work on architectures like RISC-V.
+                self.core.debug_on_sw_breakpoint(true)?;
+            }
+            ResetMode::ResetAndRun => self.core.reset()?,
+        }
+        let status = self.core.status()?;
+        self.core_data.last_known_status = status;
+        Ok(status)
+    }
+
     /// Some MS DAP requests (e.g. `step`) implicitly expect the core to resume processing and then to optionally halt again, before the request completes.
     ///
     /// This method is used to set the `last_known_status` to [`CoreStatus::Unknown`] (because we cannot verify that it will indeed resume running until we have polled it again),
@@ -58,25 +259,71 @@ impl<'p> CoreHandle<'p> {
         debug_adapter: &mut DebugAdapter<P>,
     ) {
         self.core_data.last_known_status = CoreStatus::Running;
+        self.core_data.step_deadline = None;
+        // The stack frames are unwound again after the core halts.
+        self.core_data.stack_frames.clear();
         debug_adapter.all_cores_halted = false;
     }
 
+    /// Start the [`CoreData::step_timeout`] of a step that runs the core until it halts at a [`BreakpointType::TemporaryBreakpoint`].
+    /// If the core is still running when the timeout expires, [`CoreHandle::poll_core`] halts it, and reports that the step timed out.
+    pub(crate) fn start_step_timeout(&mut self) {
+        self.core_data.step_deadline = self
+            .core_data
+            .step_timeout
+            .map(|step_timeout| Instant::now() + step_timeout);
+    }
+
     /// - Whenever we check the status, we compare it against `last_known_status` and send the appropriate event to the client.
+    /// - The `current_status` is queried by the caller, so that the status of all cores is known before any events are sent.
     /// - If we cannot determine the core status, then there is no sense in continuing the debug session, so please propogate the error.
     /// - If the core status has changed, then we update `last_known_status` to the new value, and return `true` as part of the Result<>.
     pub(crate) fn poll_core<P: ProtocolAdapter>(
         &mut self,
         debug_adapter: &mut DebugAdapter<P>,
+        current_status: Result<CoreStatus, Error>,
     ) -> Result<CoreStatus, Error> {
+        let step_timed_out = matches!(
+            current_status,
+            Ok(CoreStatus::Running | CoreStatus::Sleeping)
+        ) && self
+            .core_data
+            .step_deadline
+            .map_or(false, |step_deadline| Instant::now() >= step_deadline);
+        let rtt_output_halt = !step_timed_out
+            && matches!(
+                current_status,
+                Ok(CoreStatus::Running | CoreStatus::Sleeping)
+            )
+            && self.core_data.rtt_output_received;
+        if self.core_data.rtt_output_received {
+            // Only the first RTT output halts the core, also if the core was already halted for another reason.
+            self.core_data.rtt_output_received = false;
+            self.core_data.halt_on_rtt_output = false;
+        }
+        let current_status = if step_timed_out || rtt_output_halt {
+            if step_timed_out {
+                tracing::warn!(
+                    "The step did not complete before the timeout, so the core is halted."
+                );
+            } else {
+                tracing::info!("The target produced its first RTT output, so the core is halted.");
+            }
+            self.core
+                .halt(Duration::from_millis(500))
+                .map(|_| CoreStatus::Halted(HaltReason::Request))
+        } else {
+            current_status
+        };
         if debug_adapter.configuration_is_done() {
-            match self.core.status() {
+            match current_status {
                 Ok(status) => {
                     let has_changed_state = status != self.core_data.last_known_status;
                     if has_changed_state {
                         match status {
                             CoreStatus::Running | CoreStatus::Sleeping => {
                                 let event_body = Some(ContinuedEventBody {
-                                    all_threads_continued: Some(true), // TODO: Implement multi-core awareness here
+                                    all_threads_continued: Some(debug_adapter.all_cores_running),
                                     thread_id: self.core.id() as i64,
                                 });
                                 debug_adapter.send_event("continued", event_body)?;
@@ -96,19 +343,232 @@ impl<'p> CoreHandle<'p> {
                                         .core
                                         .read_core_reg(self.core.registers().program_counter())
                                         .ok();
-                                    let event_body = Some(StoppedEventBody {
-                                        reason: status
-                                            .short_long_status(program_counter)
-                                            .0
-                                            .to_owned(),
-                                        description: Some(
-                                            status.short_long_status(program_counter).1,
+                                    let mut hit_breakpoint_ids = None;
+                                    if let (
+                                        CoreStatus::Halted(HaltReason::Breakpoint(_)),
+                                        Some(program_counter),
+                                    ) = (status, program_counter)
+                                    {
+                                        if self.perform_semihosting_call(
+                                            debug_adapter,
+                                            program_counter,
+                                        )? {
+                                            self.core_data.last_known_status = CoreStatus::Running;
+                                            debug_adapter.all_cores_halted = false;
+                                            return Ok(CoreStatus::Running);
+                                        }
+                                        match self
+                                            .process_breakpoint_hit(debug_adapter, program_counter)
+                                        {
+                                            Some(breakpoint_ids) => {
+                                                self.apply_isr_breakpoint_action(debug_adapter);
+                                                hit_breakpoint_ids =
+                                                    Some(breakpoint_ids).filter(|breakpoint_ids| {
+                                                        !breakpoint_ids.is_empty()
+                                                    });
+                                            }
+                                            None => {
+                                                // None of the breakpoints at this address require the debug client's attention.
+                                                // Resume the core, without notifying the client of the intermediate halt.
+                                                self.step_over_software_breakpoint()?;
+                                                self.core.run()?;
+                                                self.core_data.last_known_status =
+                                                    CoreStatus::Running;
+                                                debug_adapter.all_cores_halted = false;
+                                                return Ok(CoreStatus::Running);
+                                            }
+                                        }
+                                    }
+                                    if status == CoreStatus::Halted(HaltReason::Watchpoint) {
+                                        let comparators = self
+                                            .core_data
+                                            .data_watchpoints
+                                            .iter()
+                                            .map(|watchpoint| watchpoint.comparator)
+                                            .collect::<Vec<usize>>();
+                                        let matched_comparators =
+                                            data_watchpoints::matched_comparators(
+                                                &mut self.core,
+                                                &comparators,
+                                            );
+                                        hit_breakpoint_ids = Some(
+                                            self.core_data
+                                                .data_watchpoints
+                                                .iter()
+                                                .filter(|watchpoint| {
+                                                    matched_comparators
+                                                        .contains(&watchpoint.comparator)
+                                                })
+                                                .map(|watchpoint| watchpoint.id)
+                                                .collect::<Vec<i64>>(),
+                                        )
+                                        .filter(|breakpoint_ids| !breakpoint_ids.is_empty());
+                                    }
+                                    if matches!(
+                                        status,
+                                        CoreStatus::Halted(
+                                            HaltReason::Breakpoint(_) | HaltReason::Exception
+                                        )
+                                    ) && self.resume_ignored_exception()?
+                                    {
+                                        self.core_data.last_known_status = CoreStatus::Running;
+                                        debug_adapter.all_cores_halted = false;
+                                        return Ok(CoreStatus::Running);
+                                    }
+                                    if let (
+                                        CoreStatus::Halted(
+                                            HaltReason::Breakpoint(_)
+                                            | HaltReason::Watchpoint
+                                            | HaltReason::Exception,
+                                        ),
+                                        Some(program_counter),
+                                    ) = (status, program_counter)
+                                    {
+                                        match self
+                                            .run_on_stop_action(debug_adapter, program_counter)?
+                                        {
+                                            OnStopOutcome::Halt => {}
+                                            OnStopOutcome::Resume => {
+                                                self.step_over_software_breakpoint()?;
+                                                self.core.run()?;
+                                                self.core_data.last_known_status =
+                                                    CoreStatus::Running;
+                                                debug_adapter.all_cores_halted = false;
+                                                return Ok(CoreStatus::Running);
+                                            }
+                                            OnStopOutcome::SessionEnded => {
+                                                // The client was told that the session ended, so the halt is not reported with a `stopped` event.
+                                                self.core_data.last_known_status = status;
+                                                return Ok(status);
+                                            }
+                                        }
+                                    }
+                                    // A `stepOut` or `goto` that halts at its temporary breakpoint is reported as such, rather than as a breakpoint,
+                                    // unless a breakpoint of the client halted the core at the same address.
+                                    let temporary_breakpoint_stop_reason =
+                                        match (status, program_counter) {
+                                            (
+                                                CoreStatus::Halted(HaltReason::Breakpoint(_)),
+                                                Some(program_counter),
+                                            ) if hit_breakpoint_ids.is_none()
+                                                && self.core_data.breakpoints.iter().any(
+                                                    |breakpoint| {
+                                                        breakpoint.address == program_counter
+                                                            && matches!(
+                                                                breakpoint
+                                                                    .breakpoint_type
+                                                                    .requested_type(),
+                                                                BreakpointType::TemporaryBreakpoint
+                                                            )
+                                                    },
+                                                ) =>
+                                            {
+                                                Some(
+                                                    self.core_data.temporary_breakpoint_stop_reason,
+                                                )
+                                            }
+                                            _ => None,
+                                        };
+                                    // A temporary breakpoint is only used until the core halts, irrespective of where it halts.
+                                    if let Err(error) =
+                                        self.clear_breakpoints(BreakpointType::TemporaryBreakpoint)
+                                    {
+                                        tracing::warn!(
+                                            "Failed to clear the temporary breakpoint: {error}"
+                                        );
+                                    }
+                                    self.core_data.step_out_stack_pointer = None;
+                                    self.core_data.step_deadline = None;
+                                    // A catchpoint is reported as a caught panic, with the name of the function where the core halted.
+                                    let catchpoint = match (status, program_counter) {
+                                        (
+                                            CoreStatus::Halted(HaltReason::Breakpoint(_)),
+                                            Some(program_counter),
+                                        ) => self.catchpoint_at(program_counter),
+                                        _ => None,
+                                    };
+                                    // On RISC-V, show the cause of the trap, if the core halted at the trap vector,
+                                    // and on Cortex-M, show the cause of the fault, if the core halted because of a fault exception.
+                                    let trap_description = match program_counter {
+                                        _ if step_timed_out => Some((
+                                            "step timeout".to_string(),
+                                            format!(
+                                                "Step timed out after {} ms",
+                                                self.core_data
+                                                    .step_timeout
+                                                    .unwrap_or_default()
+                                                    .as_millis()
+                                            ),
+                                        )),
+                                        _ if rtt_output_halt => Some((
+                                            "rtt output".to_string(),
+                                            "Halted on the first RTT output".to_string(),
+                                        )),
+                                        _ if catchpoint.is_some() => {
+                                            catchpoint.as_ref().map(|function_name| {
+                                                (
+                                                    "panic caught".to_string(),
+                                                    format!("Panic caught in {function_name}"),
+                                                )
+                                            })
+                                        }
+                                        Some(program_counter)
+                                            if self.core.architecture() == Architecture::Riscv =>
+                                        {
+                                            riscv_csrs::trap_description(
+                                                &mut self.core,
+                                                program_counter,
+                                            )
+                                        }
+                                        Some(_)
+                                            if status
+                                                == CoreStatus::Halted(HaltReason::Exception) =>
+                                        {
+                                            cortex_m_faults::fault_description(&mut self.core)
+                                        }
+                                        _ => None,
+                                    };
+                                    let (trap_name, trap_summary) = trap_description.unzip();
+                                    let mut description = match (trap_summary, program_counter) {
+                                        (Some(trap_summary), Some(program_counter)) => format!(
+                                            "{trap_summary}, halted at {}",
+                                            self.halt_location(program_counter)
+                                        ),
+                                        (None, Some(program_counter)) => format!(
+                                            "Halted at {}",
+                                            self.halt_location(program_counter)
                                         ),
+                                        (Some(trap_summary), None) => trap_summary,
+                                        (None, None) => status.short_long_status(None).1,
+                                    };
+                                    if let Some(ignored_exceptions) =
+                                        self.ignored_exceptions_summary()
+                                    {
+                                        description.push_str(&format!(" ({ignored_exceptions})"));
+                                    }
+                                    if let Some(program_counter) = program_counter {
+                                        self.refresh_watch_values(program_counter);
+                                    }
+                                    let event_body = Some(StoppedEventBody {
+                                        reason: if step_timed_out {
+                                            "step timeout".to_owned()
+                                        } else if rtt_output_halt {
+                                            "rtt output".to_owned()
+                                        } else if catchpoint.is_some() {
+                                            "exception".to_owned()
+                                        } else if let Some(stop_reason) =
+                                            temporary_breakpoint_stop_reason
+                                        {
+                                            stop_reason.to_owned()
+                                        } else {
+                                            status.short_long_status(program_counter).0.to_owned()
+                                        },
+                                        description: Some(description),
                                         thread_id: Some(self.core.id() as i64),
                                         preserve_focus_hint: Some(false),
-                                        text: None,
+                                        text: trap_name,
                                         all_threads_stopped: Some(debug_adapter.all_cores_halted),
-                                        hit_breakpoint_ids: None,
+                                        hit_breakpoint_ids,
                                     });
                                     debug_adapter.send_event("stopped", event_body)?;
                                     tracing::trace!(
@@ -127,10 +587,8 @@ impl<'p> CoreHandle<'p> {
                                 )));
                             }
                             CoreStatus::Unknown => {
-                                debug_adapter.send_error_response(&DebuggerError::Other(
-                                    anyhow!("Unknown Device status reveived from Probe-rs"),
-                                ))?;
-
+                                // The caller will report the error, or downgrade it to a warning, depending on the `error_policy`.
+                                self.core_data.last_known_status = status;
                                 return Err(Error::Other(anyhow!(
                                     "Unknown Device status reveived from Probe-rs"
                                 )));
@@ -155,215 +613,2391 @@ impl<'p> CoreHandle<'p> {
         }
     }
 
-    /// Search available [`probe_rs::debug::StackFrame`]'s for the given `id`
-    pub(crate) fn get_stackframe(
-        &'p self,
-        id: i64,
-    ) -> Option<&'p probe_rs::debug::stack_frame::StackFrame> {
-        self.core_data
-            .stack_frames
+    /// Unwind the stack of the halted core, from the current program counter, into [`CoreData::stack_frames`].
+    pub(crate) fn refresh_stack_frames(&mut self) -> Result<(), DebuggerError> {
+        let program_counter = self
+            .core
+            .read_core_reg(self.core.registers().program_counter())?;
+        tracing::debug!("Updating the stack frame data for core #{}", self.core.id());
+        self.core_data.stack_frames = self
+            .core_data
+            .debug_info
+            .unwind(&mut self.core, program_counter)?;
+        Ok(())
+    }
+
+    /// Enumerate the global variables the first time they are needed, or discard the values that were read before the core last halted,
+    /// so that they are read again when the client expands them.
+    pub(crate) fn refresh_global_variables(&mut self) {
+        let global_variables = match &mut self.core_data.global_variables {
+            Some(global_variables) => global_variables,
+            None => {
+                self.core_data.global_variables = match self
+                    .core_data
+                    .debug_info
+                    .create_global_scope_cache(&mut self.core)
+                {
+                    Ok(global_variables) => Some(global_variables),
+                    Err(error) => {
+                        tracing::warn!("Failed to enumerate the global variables: {error}");
+                        None
+                    }
+                };
+                return;
+            }
+        };
+        let compile_units = global_variables
+            .get_variable_by_name_and_parent(&VariableName::GlobalScopeRoot, None)
+            .and_then(|global_root_variable| {
+                global_variables
+                    .get_children(Some(global_root_variable.variable_key))
+                    .ok()
+            })
+            .unwrap_or_default();
+        for compile_unit in compile_units {
+            if let Err(error) =
+                global_variables.remove_cache_entry_children(compile_unit.variable_key)
+            {
+                tracing::warn!("Failed to refresh the global variables: {error}");
+            }
+        }
+    }
+
+    /// Evaluate the expressions of the watch pane at the top stack frame when the core halts, and keep their previous values,
+    /// so that the `evaluate` request can tell the client which values changed since the previous halt.
+    /// Expressions that the client did not evaluate since the previous halt are removed first, see [`expression::prune_watch_values`].
+    pub(crate) fn refresh_watch_values(&mut self, program_counter: u64) {
+        expression::prune_watch_values(&mut self.core_data.watch_values);
+        if self.core_data.watch_values.is_empty() {
+            return;
+        }
+        let mut top_stack_frame = match self
+            .core_data
+            .debug_info
+            .unwind(&mut self.core, program_counter)
+        {
+            Ok(stack_frames) => stack_frames.into_iter().next(),
+            Err(error) => {
+                tracing::warn!("Failed to unwind the stack to refresh the watch values: {error}");
+                None
+            }
+        };
+        for (watched_expression, watch_value) in self.core_data.watch_values.iter_mut() {
+            let value = match top_stack_frame.as_mut() {
+                Some(stack_frame) => expression::watch_value(
+                    watched_expression,
+                    stack_frame,
+                    &self.core_data.debug_info,
+                    &mut self.core,
+                ),
+                None => expression::OUT_OF_SCOPE.to_string(),
+            };
+            watch_value.previous_value = Some(std::mem::replace(&mut watch_value.value, value));
+        }
+    }
+
+    /// Describe where the core halted, e.g. ``0x08001234 in `foo::bar` (main.rs:42)``. The function and source location are resolved with the [`DebugInfo`].
+    /// Without debug information, the nearest symbol from the ELF symbol table is used, and if there is none, only the address.
+    fn halt_location(&self, program_counter: u64) -> String {
+        let function_name = self
+            .core_data
+            .debug_info
+            .function_name(program_counter, true)
+            .ok()
+            .flatten();
+        let source_location = self
+            .core_data
+            .debug_info
+            .get_source_location(program_counter)
+            .and_then(|source_location| {
+                let file = source_location.file?;
+                Some(match source_location.line {
+                    Some(line) => format!("{file}:{line}"),
+                    None => file,
+                })
+            });
+        // Without debug information for the function, the nearest ELF symbol is the best description.
+        let function_name = function_name.or_else(|| {
+            elf_symbols::nearest_elf_symbol(&self.core_data.elf_symbols, program_counter).map(
+                |(symbol, offset)| match offset {
+                    0 => symbol.demangled_name.clone(),
+                    offset => format!("{}+{offset:#x}", symbol.demangled_name),
+                },
+            )
+        });
+        let mut halt_location = format!("{program_counter:#010x}");
+        if let Some(function_name) = function_name {
+            halt_location.push_str(&format!(" in `{function_name}`"));
+        }
+        if let Some(source_location) = source_location {
+            halt_location.push_str(&format!(" ({source_location})"));
+        }
+        halt_location
+    }
+
+    /// The number of the exception that the core is handling, from the `IPSR` bits of `xPSR`, or `None` if the core is in thread mode.
+    /// This is only available for Cortex-M cores.
+    fn active_exception_number(&mut self) -> Option<u32> {
+        if !self.core.core_type().is_cortex_m() {
+            return None;
+        }
+        let psr: RegisterId = self.core.registers().psr()?.into();
+        let xpsr: u32 = self.core.read_core_reg(psr).ok()?;
+        ipsr_exception_number(xpsr)
+    }
+
+    /// Resume the core if it halted at a breakpoint or exception while it is handling one of the [`CoreData::ignored_exceptions`],
+    /// and count it in [`CoreData::ignored_exception_counts`].
+    /// Exceptions like SysTick and PendSV are not caught by the vector catch, so they only halt the core at a breakpoint in their handler,
+    /// or in a function that the handler calls.
+    /// Returns `true` if the core was resumed.
+    fn resume_ignored_exception(&mut self) -> Result<bool, Error> {
+        if self.core_data.ignored_exceptions.is_empty() {
+            return Ok(false);
+        }
+        match self.active_exception_number() {
+            Some(exception_number)
+                if self
+                    .core_data
+                    .ignored_exceptions
+                    .contains(&exception_number) =>
+            {
+                self.step_over_software_breakpoint()?;
+                *self
+                    .core_data
+                    .ignored_exception_counts
+                    .entry(exception_number)
+                    .or_default() += 1;
+                self.core.run()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// The number of times that each ignored exception resumed the core, e.g. `ignored exceptions: PendSV 3x, SysTick 12x`,
+    /// or `None` if none of them occurred.
+    fn ignored_exceptions_summary(&self) -> Option<String> {
+        if self.core_data.ignored_exception_counts.is_empty() {
+            return None;
+        }
+        let mut ignored_exception_counts = self
+            .core_data
+            .ignored_exception_counts
             .iter()
-            .find(|stack_frame| stack_frame.id == id)
+            .collect::<Vec<_>>();
+        ignored_exception_counts.sort_unstable();
+        Some(format!(
+            "ignored exceptions: {}",
+            ignored_exception_counts
+                .into_iter()
+                .map(|(exception_number, count)| format!(
+                    "{} {count}x",
+                    exception_name(*exception_number)
+                ))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ))
     }
 
-    /// Confirm RTT initialization on the target, and use the RTT channel configurations to initialize the output windows on the DAP Client.
-    pub fn attach_to_rtt<P: ProtocolAdapter>(
+    /// Apply the [`IsrBreakpointAction`] if the core halted at a breakpoint while it is handling an exception.
+    /// The peripherals are frozen after the core halted, so they may have advanced briefly before they stopped.
+    fn apply_isr_breakpoint_action<P: ProtocolAdapter>(
         &mut self,
         debug_adapter: &mut DebugAdapter<P>,
-        target_memory_map: &[probe_rs::config::MemoryRegion],
-        program_binary: &std::path::Path,
-        rtt_config: &rtt::RttConfig,
-        timestamp_offset: UtcOffset,
-    ) -> Result<()> {
-        let mut debugger_rtt_channels: Vec<debug_rtt::DebuggerRttChannel> = vec![];
-        // Attach to RTT by using the RTT control block address from the ELF file. Do not scan the memory for the control block.
-        match File::open(program_binary)
-            .map_err(|error| anyhow!("Error attempting to attach to RTT: {}", error))
-            .and_then(|mut open_file| {
-                RttActiveTarget::get_rtt_symbol(&mut open_file).map_or_else(
-                    || Err(anyhow!("No RTT control block found in ELF file")),
-                    |rtt_header_address| Ok(ScanRegion::Exact(rtt_header_address as u32)),
-                )
-            })
-            .and_then(|scan_region| {
-                Rtt::attach_region(&mut self.core, target_memory_map, &scan_region)
-                    .map_err(|error| anyhow!("Error attempting to attach to RTT: {}", error))
-            })
-            .and_then(|rtt| {
-                tracing::info!("RTT initialized.");
-                RttActiveTarget::new(rtt, program_binary, rtt_config, timestamp_offset)
-            }) {
-            Ok(target_rtt) => {
-                for any_channel in target_rtt.active_channels.iter() {
-                    if let Some(up_channel) = &any_channel.up_channel {
-                        if any_channel.data_format == DataFormat::Defmt {
-                            // For defmt, we set the channel to be blocking when full.
-                            up_channel.set_mode(&mut self.core, ChannelMode::BlockIfFull)?;
-                        }
-                        debugger_rtt_channels.push(debug_rtt::DebuggerRttChannel {
-                            channel_number: up_channel.number(),
-                            // This value will eventually be set to true by a VSCode client request "rttWindowOpened"
-                            has_client_window: false,
-                        });
-                        debug_adapter.rtt_window(
-                            up_channel.number(),
-                            any_channel.channel_name.clone(),
-                            any_channel.data_format,
+    ) {
+        if self.core_data.isr_breakpoint_action == IsrBreakpointAction::Proceed {
+            return;
+        }
+        let exception_name = match self.active_exception_number() {
+            Some(exception_number) => exception_name(exception_number),
+            None => return,
+        };
+        match self.core_data.isr_breakpoint_action {
+            IsrBreakpointAction::Proceed => {}
+            IsrBreakpointAction::Warn => {
+                debug_adapter.show_message(
+                    MessageSeverity::Warning,
+                    format!("The core halted at a breakpoint while handling the {exception_name} exception. Peripherals, like watchdogs and timers, may keep running while the core is halted."),
+                );
+            }
+            IsrBreakpointAction::FreezePeripherals => {
+                let frozen_peripherals = match &self.core_data.core_peripherals {
+                    Some(core_peripherals) => {
+                        core_peripherals.freeze_peripherals_when_halted(&mut self.core)
+                    }
+                    None => Ok(vec![]),
+                };
+                match frozen_peripherals {
+                    Ok(frozen_peripherals) if !frozen_peripherals.is_empty() => {
+                        debug_adapter.log_to_console(format!(
+                            "The core halted at a breakpoint while handling the {exception_name} exception. Peripherals that are stopped while the core is halted: {}",
+                            frozen_peripherals.join(", ")
+                        ));
+                    }
+                    Ok(_) => {
+                        debug_adapter.show_message(
+                            MessageSeverity::Warning,
+                            format!("The core halted at a breakpoint while handling the {exception_name} exception, but the `svdFile` does not describe any supported debug freeze registers, so peripherals will keep running."),
+                        );
+                    }
+                    Err(error) => {
+                        debug_adapter.show_message(
+                            MessageSeverity::Warning,
+                            format!("The core halted at a breakpoint while handling the {exception_name} exception, but the peripherals could not be stopped: {error}"),
                         );
                     }
                 }
-                self.core_data.rtt_connection = Some(debug_rtt::RttConnection {
-                    target_rtt,
-                    debugger_rtt_channels,
-                });
             }
-            Err(_error) => {
-                tracing::warn!("Failed to initalize RTT. Will try again on the next request... ");
-            }
-        };
-        Ok(())
+        }
     }
 
-    /// Set a single breakpoint in target configuration as well as [`super::core_data::CoreHandle`]
-    pub(crate) fn set_breakpoint(
+    /// Run the [`OnStopConfig`] action, if one is configured, after the core halted at `program_counter`.
+    /// If the test passed or failed, the client is notified that the debug session ended, with the matching exit code.
+    fn run_on_stop_action<P: ProtocolAdapter>(
         &mut self,
-        address: u64,
-        breakpoint_type: session_data::BreakpointType,
-    ) -> Result<(), DebuggerError> {
-        self.core
-            .set_hw_breakpoint(address)
-            .map_err(DebuggerError::ProbeRs)?;
-        self.core_data
-            .breakpoints
-            .push(session_data::ActiveBreakpoint {
-                breakpoint_type,
-                address,
-            });
-        Ok(())
-    }
+        debug_adapter: &mut DebugAdapter<P>,
+        program_counter: u64,
+    ) -> Result<OnStopOutcome, Error> {
+        let on_stop = match &self.core_data.on_stop {
+            Some(on_stop) => on_stop.clone(),
+            None => return Ok(OnStopOutcome::Halt),
+        };
+        let stack_frame = self
+            .core_data
+            .debug_info
+            .unwind(&mut self.core, program_counter)
+            .ok()
+            .and_then(|stack_frames| stack_frames.into_iter().next());
 
-    /// Clear a single breakpoint from target configuration.
-    pub(crate) fn clear_breakpoint(&mut self, address: u64) -> Result<()> {
-        self.core
-            .clear_hw_breakpoint(address)
-            .map_err(DebuggerError::ProbeRs)?;
-        let mut breakpoint_position: Option<usize> = None;
-        for (position, active_breakpoint) in self.core_data.breakpoints.iter().enumerate() {
-            if active_breakpoint.address == address {
-                breakpoint_position = Some(position);
-                break;
+        let mut failure = None;
+        match &stack_frame {
+            Some(stack_frame) => {
+                for variable_name in &on_stop.read_variables {
+                    let value = breakpoint_condition::evaluate_value(variable_name, stack_frame)
+                        .unwrap_or_else(|error| format!("<{error}>"));
+                    debug_adapter.log_to_console(format!("ON STOP: {variable_name} = {value}"));
+                }
+                if let Some(fail_condition) = &on_stop.fail_condition {
+                    match breakpoint_condition::evaluate_condition(fail_condition, stack_frame) {
+                        Ok(true) => failure = Some(format!("`{fail_condition}` is true")),
+                        Ok(false) => {}
+                        Err(error) => {
+                            failure =
+                                Some(format!("Failed to evaluate `{fail_condition}`: {error}"))
+                        }
+                    }
+                }
+            }
+            None => {
+                failure = Some(format!(
+                    "No stack frame available at {program_counter:#010x}"
+                ))
             }
         }
-        if let Some(breakpoint_position) = breakpoint_position {
-            self.core_data.breakpoints.remove(breakpoint_position);
-        }
-        Ok(())
+
+        let exit_code = if let Some(failure) = failure {
+            debug_adapter.log_to_console(format!("ON STOP: FAIL: {failure}"));
+            1
+        } else {
+            match (&on_stop.pass_condition, &stack_frame) {
+                (Some(pass_condition), Some(stack_frame))
+                    if matches!(
+                        breakpoint_condition::evaluate_condition(pass_condition, stack_frame),
+                        Ok(true)
+                    ) =>
+                {
+                    debug_adapter
+                        .log_to_console(format!("ON STOP: PASS: `{pass_condition}` is true"));
+                    0
+                }
+                _ if on_stop.continue_after_stop => return Ok(OnStopOutcome::Resume),
+                _ => return Ok(OnStopOutcome::Halt),
+            }
+        };
+        debug_adapter.send_event("exited", Some(ExitedEventBody { exit_code }))?;
+        debug_adapter.send_event("terminated", Some(TerminatedEventBody { restart: None }))?;
+        Ok(OnStopOutcome::SessionEnded)
     }
 
-    /// Clear all breakpoints of a specified [`super::session_data::BreakpointType`].
-    /// Affects target configuration as well as [`super::core_data::CoreHandle`].
-    /// If `breakpoint_type` is `None`, all breakpoints of type [`super::session_data::BreakpointType::SourceBreakpoint`] will be cleared.
-    pub(crate) fn clear_breakpoints(
+    /// Evaluate the conditions of conditional breakpoints, update the `hit_count` of every [`session_data::ActiveBreakpoint`]
+    /// at `program_counter` where the condition is `true`, evaluate the hit conditions, and log any function trace messages.
+    ///
+    /// Returns the ids of the breakpoints that caused the halt, if the halt should be reported to the debug client, or `None` if none of the
+    /// breakpoints at this address need to halt the debug session (e.g. [`BreakpointType::CoverageBreakpoint`], or a breakpoint
+    /// with a condition that is `false`), in which case the caller should resume the core.
+    fn process_breakpoint_hit<P: ProtocolAdapter>(
         &mut self,
-        breakpoint_type: Option<session_data::BreakpointType>,
-    ) -> Result<()> {
-        let target_breakpoints = self
+        debug_adapter: &mut DebugAdapter<P>,
+        program_counter: u64,
+    ) -> Option<Vec<i64>> {
+        // Breakpoints at the same address share a hardware breakpoint unit, so we only unwind the stack once to evaluate all their conditions.
+        let stack_frame = if self.core_data.breakpoints.iter().any(|breakpoint| {
+            breakpoint.address == program_counter
+                && (breakpoint.condition.is_some()
+                    || matches!(
+                        breakpoint.breakpoint_type.requested_type(),
+                        BreakpointType::SourceBreakpoint(_, _, Some(_))
+                    ))
+        }) {
+            Some(
+                self.core_data
+                    .debug_info
+                    .unwind(&mut self.core, program_counter)
+                    .map_err(DebuggerError::ProbeRs)
+                    .and_then(|stack_frames| {
+                        stack_frames.into_iter().next().ok_or_else(|| {
+                            DebuggerError::Other(anyhow!(
+                                "No stack frame available at {program_counter:#010x}"
+                            ))
+                        })
+                    }),
+            )
+        } else {
+            None
+        };
+        // A recursive call reaches the return address of a `stepOut` with a stack pointer below that of the calling frame.
+        let step_out_returned = match self.core_data.step_out_stack_pointer {
+            Some(step_out_stack_pointer) => self
+                .core
+                .read_core_reg::<u64>(self.core.registers().stack_pointer())
+                .map_or(true, |stack_pointer| {
+                    stack_pointer >= step_out_stack_pointer
+                }),
+            None => true,
+        };
+        let mut breakpoint_found = false;
+        let mut temporary_breakpoint_hit = false;
+        let mut catchpoint_hit = false;
+        let mut hit_breakpoint_ids = vec![];
+        for breakpoint in self
             .core_data
             .breakpoints
-            .iter()
-            .filter(|breakpoint| {
-                if let Some(breakpoint_type) = breakpoint_type.as_ref() {
-                    breakpoint.breakpoint_type == *breakpoint_type
-                } else {
-                    matches!(
-                        breakpoint.breakpoint_type,
-                        BreakpointType::SourceBreakpoint(_, _)
-                    )
+            .iter_mut()
+            .filter(|breakpoint| breakpoint.address == program_counter)
+        {
+            breakpoint_found = true;
+            if !step_out_returned
+                && matches!(
+                    breakpoint.breakpoint_type.requested_type(),
+                    BreakpointType::TemporaryBreakpoint
+                )
+            {
+                continue;
+            }
+            if let Some(condition) = &breakpoint.condition {
+                let condition_result = match &stack_frame {
+                    Some(Ok(stack_frame)) => {
+                        breakpoint_condition::evaluate_condition(condition, stack_frame)
+                    }
+                    Some(Err(error)) => Err(DebuggerError::Other(anyhow!(
+                        "Failed to unwind the stack: {error}"
+                    ))),
+                    None => Ok(true),
+                };
+                match condition_result {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(error) => {
+                        // Halt anyway, as if the breakpoint was unconditional, so that the user can correct the condition.
+                        // Only the first failure shows a message box, so that a breakpoint in a loop does not flood the user with them.
+                        let message = format!("Failed to evaluate the breakpoint condition `{condition}`, so the breakpoint will halt unconditionally: {error}");
+                        if breakpoint.condition_error_reported {
+                            debug_adapter.log_to_console(message);
+                        } else {
+                            breakpoint.condition_error_reported = true;
+                            debug_adapter.show_message(MessageSeverity::Warning, message);
+                        }
+                    }
                 }
-            })
-            .map(|breakpoint| breakpoint.address)
-            .collect::<Vec<u64>>();
-        for breakpoint in target_breakpoints {
-            self.clear_breakpoint(breakpoint)?;
+            }
+            breakpoint.hit_count += 1;
+            if let Some(hit_condition) = &breakpoint.hit_condition {
+                match breakpoint_condition::evaluate_hit_condition(
+                    hit_condition,
+                    breakpoint.hit_count,
+                ) {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(error) => {
+                        debug_adapter.show_message(
+                            MessageSeverity::Warning,
+                            format!("Failed to evaluate the breakpoint hit condition `{hit_condition}`, so the breakpoint will halt unconditionally: {error}"),
+                        );
+                    }
+                }
+            }
+            match breakpoint.breakpoint_type.requested_type() {
+                BreakpointType::CoverageBreakpoint(_) => {}
+                // The debug client does not know about temporary breakpoints, so the halt is reported without a breakpoint id.
+                BreakpointType::TemporaryBreakpoint => temporary_breakpoint_hit = true,
+                BreakpointType::Catchpoint(_) => catchpoint_hit = true,
+                BreakpointType::FunctionTraceBreakpoint(function_name) => {
+                    let timestamp = OffsetDateTime::now_utc()
+                        .to_offset(self.core_data.timestamp_offset)
+                        .format(TRACE_TIMESTAMP_FORMAT)
+                        .unwrap_or_default();
+                    debug_adapter.log_to_console(format!("TRACE [{timestamp}] {function_name}"));
+                }
+                BreakpointType::SourceBreakpoint(source, source_location, Some(log_message)) => {
+                    let message = breakpoint_condition::format_log_message(
+                        log_message,
+                        stack_frame
+                            .as_ref()
+                            .and_then(|stack_frame| stack_frame.as_ref().ok()),
+                    );
+                    debug_adapter
+                        .send_event(
+                            "output",
+                            Some(OutputEventBody {
+                                output: format!("{message}\n"),
+                                category: Some("console".to_owned()),
+                                variables_reference: None,
+                                source: Some(source.clone()),
+                                line: source_location.line.map(|line| line as i64),
+                                column: None,
+                                data: None,
+                                group: None,
+                            }),
+                        )
+                        .map_err(|error| {
+                            tracing::warn!("Failed to send a logpoint message: {error}")
+                        })
+                        .ok();
+                }
+                _ => hit_breakpoint_ids.push(breakpoint.id),
+            }
+        }
+        if !hit_breakpoint_ids.is_empty()
+            || !breakpoint_found
+            || temporary_breakpoint_hit
+            || catchpoint_hit
+        {
+            Some(hit_breakpoint_ids)
+        } else {
+            None
         }
-        Ok(())
     }
 
-    /// Set a breakpoint at the requested address. If the requested source location is not specific, or
-    /// if the requested address is not a valid breakpoint location,
-    /// the debugger will attempt to find the closest location to the requested location, and set a breakpoint there.
-    /// The Result<> contains the "verified" `address` and `SourceLocation` where the breakpoint that was set.
-    pub(crate) fn verify_and_set_breakpoint(
+    /// If the core halted at a semihosting call, perform the host side of the call, send any output to the debug console,
+    /// and resume the core after the semihosting instruction, with the result of the call.
+    /// Returns `true` if the core was resumed, or `false` if it did not halt at a supported semihosting call.
+    fn perform_semihosting_call<P: ProtocolAdapter>(
         &mut self,
-        source_path: &Path,
-        requested_breakpoint_line: u64,
-        requested_breakpoint_column: Option<u64>,
-        requested_source: &Source,
-    ) -> Result<VerifiedBreakpoint, DebuggerError> {
-        let VerifiedBreakpoint {
-                 address,
-                 source_location,
-             } = self.core_data
-            .debug_info
-            .get_breakpoint_location(
-                source_path,
-                requested_breakpoint_line,
-                requested_breakpoint_column,
-            )
-            .map_err(|debug_error|
-                DebuggerError::Other(anyhow!("Cannot set breakpoint here. Try reducing compile time-, and link time-, optimization in your build configuration, or choose a different source location: {debug_error}")))?;
-        self.set_breakpoint(
-            address,
-            BreakpointType::SourceBreakpoint(requested_source.clone(), source_location.clone()),
+        debug_adapter: &mut DebugAdapter<P>,
+        program_counter: u64,
+    ) -> Result<bool, Error> {
+        let call = match semihosting::semihosting_call(&mut self.core, program_counter) {
+            Some(call) => call,
+            None => return Ok(false),
+        };
+        let result = match self
+            .core_data
+            .semihosting_files
+            .perform_call(&mut self.core, &call)
+        {
+            Ok(Some(result)) => result,
+            Ok(None) => {
+                debug_adapter.show_message(
+                    MessageSeverity::Warning,
+                    format!(
+                        "Unsupported semihosting operation {:#04x} at {program_counter:#010x}",
+                        call.operation
+                    ),
+                );
+                return Ok(false);
+            }
+            Err(error) => {
+                debug_adapter.show_message(
+                    MessageSeverity::Warning,
+                    format!(
+                        "Failed to perform semihosting operation {:#04x}: {error}",
+                        call.operation
+                    ),
+                );
+                return Ok(false);
+            }
+        };
+        if let Some((output, category)) = result.output {
+            debug_adapter
+                .send_event(
+                    "output",
+                    Some(OutputEventBody {
+                        output,
+                        category: Some(category.to_owned()),
+                        variables_reference: None,
+                        source: None,
+                        line: None,
+                        column: None,
+                        data: None,
+                        group: None,
+                    }),
+                )
+                .map_err(|error| tracing::warn!("Failed to send semihosting output: {error}"))
+                .ok();
+        }
+        let registers = self.core.registers();
+        self.core.write_core_reg(
+            registers.result_register(0).into(),
+            result.return_value as u32,
         )?;
-        Ok(VerifiedBreakpoint {
-            address,
-            source_location,
-        })
+        self.core
+            .write_core_reg(registers.program_counter().into(), call.resume_address)?;
+        self.core.run()?;
+        Ok(true)
     }
 
-    /// In the case where a new binary is flashed as part of a restart, we need to recompute the breakpoint address,
-    /// for a specified source location, of any [`super::session_data::BreakpointType::SourceBreakpoint`].
-    /// This is because the address of the breakpoint may have changed based on changes in the source file that created the new binary.
-    pub(crate) fn recompute_breakpoints(&mut self) -> Result<(), DebuggerError> {
-        let target_breakpoints = self.core_data.breakpoints.clone();
-        for breakpoint in target_breakpoints
+    /// Classify `address` by the kind of region that contains it, in the target's memory map.
+    pub(crate) fn memory_region_kind(&self, address: u64) -> MemoryRegionKind {
+        self.core_data
+            .memory_map
             .iter()
-            .cloned()
-            // If the breakpoint type is not a source breakpoint, we don't need to recompute anything.
-            .filter(|breakpoint| {
-                matches!(
-                    breakpoint.breakpoint_type,
-                    BreakpointType::SourceBreakpoint(..)
-                )
-            })
+            .find(|memory_region| memory_region_range(memory_region).contains(&address))
+            .map_or(
+                MemoryRegionKind::Unmapped,
+                |memory_region| match memory_region {
+                    MemoryRegion::Ram(_) => MemoryRegionKind::Ram,
+                    MemoryRegion::Nvm(_) => MemoryRegionKind::Flash,
+                    MemoryRegion::Generic(_) => MemoryRegionKind::Peripheral,
+                },
+            )
+    }
+
+    /// Confirm that every byte in the range `start..start + length` is part of a region in the target's memory map, and that none of it is in Flash,
+    /// which can only be written by flashing a program binary.
+    pub(crate) fn check_memory_write(&self, start: u64, length: u64) -> Result<(), DebuggerError> {
+        self.check_memory_range(start, length)?;
+        if let Some(flash_address) = self
+            .core_data
+            .memory_map
+            .iter()
+            .filter(|memory_region| matches!(memory_region, MemoryRegion::Nvm(_)))
+            .map(memory_region_range)
+            .filter(|region_range| region_range.start < start + length && start < region_range.end)
+            .map(|region_range| region_range.start.max(start))
+            .min()
         {
-            self.clear_breakpoint(breakpoint.address)?;
-            if let BreakpointType::SourceBreakpoint(source, source_location) =
-                breakpoint.breakpoint_type
+            return Err(DebuggerError::Other(anyhow!(
+                "Memory at {flash_address:#010x} is in Flash, and cannot be written while debugging"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Confirm that every byte in the range `start..start + length` is part of a region in the target's memory map.
+    pub(crate) fn check_memory_range(&self, start: u64, length: u64) -> Result<(), DebuggerError> {
+        start.checked_add(length).ok_or_else(|| {
+            DebuggerError::Other(anyhow!(
+                "Memory range at {start:#010x} with length {length} overflows the address space"
+            ))
+        })?;
+        let mapped_length = self.mapped_length(start, length);
+        if mapped_length < length {
+            let next_address = start + mapped_length;
+            return Err(DebuggerError::Other(anyhow!(
+                "Memory at {next_address:#010x} is not part of any memory region of the target"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Capture the core registers, the contents of all the RAM regions in the target's memory map, and the current stack frames, into the core dump file at `path`.
+    /// RAM regions that cannot be read are left out. Returns the number of bytes of RAM in the dump.
+    pub(crate) fn save_core_dump(&mut self, path: &Path) -> Result<u64, DebuggerError> {
+        let ram_ranges: Vec<std::ops::Range<u64>> = self
+            .core_data
+            .memory_map
+            .iter()
+            .filter(|memory_region| matches!(memory_region, MemoryRegion::Ram(_)))
+            .map(|memory_region| memory_region_range(memory_region).clone())
+            .collect();
+        let mut core_dump = CoreDump::capture(&mut self.core, &ram_ranges)?;
+        core_dump.set_stack_frames(&self.core_data.stack_frames);
+        core_dump.store(path)?;
+        Ok(core_dump
+            .memory_ranges()
+            .iter()
+            .map(|ram_range| ram_range.end - ram_range.start)
+            .sum())
+    }
+
+    /// The number of bytes, starting at `start`, and up to `length`, that are part of contiguous regions in the target's memory map.
+    fn mapped_length(&self, start: u64, length: u64) -> u64 {
+        let end = start.saturating_add(length);
+        let mut next_address = start;
+        while next_address < end {
+            match self
+                .core_data
+                .memory_map
+                .iter()
+                .map(memory_region_range)
+                .find(|region_range| region_range.contains(&next_address))
             {
-                if let Err(breakpoint_error) =
-                    source_location
-                        .combined_path()
-                        .as_ref()
-                        .map(|requested_path| {
-                            self.verify_and_set_breakpoint(
-                                requested_path,
-                                source_location.line.unwrap_or(0),
-                                source_location.column.map(|col| match col {
-                                    ColumnType::LeftEdge => 0_u64,
-                                    ColumnType::Column(c) => c,
-                                }),
-                                &source,
-                            )
-                        })
-                {
-                    return Err(DebuggerError::Other(anyhow!(
-                        "Failed to recompute breakpoint at {source_location:?} in {source:?}. Error: {breakpoint_error:?}"
-                    )));
-                }
+                Some(region_range) => next_address = region_range.end,
+                None => break,
             }
         }
-        Ok(())
+        next_address.min(end) - start
+    }
+
+    /// Read up to `count` bytes of target memory, starting at `address`, which does not have to be aligned.
+    /// The bytes that are not part of the target's memory map are not read, so that they fail cleanly, instead of with a probe error.
+    /// If only part of the memory can be read, the bytes up to the first unreadable byte are returned. If none of the bytes can be read, an error is returned.
+    pub(crate) fn read_memory(
+        &mut self,
+        address: u64,
+        count: usize,
+    ) -> Result<Vec<u8>, DebuggerError> {
+        let mapped_length = self.mapped_length(address, count as u64) as usize;
+        if mapped_length == 0 && count > 0 {
+            return Err(DebuggerError::Other(anyhow!(
+                "Memory at {address:#010x} is not part of any memory region of the target"
+            )));
+        }
+        let mut data = Vec::with_capacity(mapped_length);
+        // The probe-rs API does not return partially read data. It either succeeds for the whole buffer or not.
+        // Single byte reads are slow, so we read in chunks, and only fall back to single byte reads for the chunk that fails,
+        // to get all the data we can.
+        let mut chunk = vec![0_u8; MEMORY_READ_CHUNK_SIZE];
+        while data.len() < mapped_length {
+            let chunk_address = address + data.len() as u64;
+            let chunk_length = (mapped_length - data.len()).min(MEMORY_READ_CHUNK_SIZE);
+            if self
+                .core
+                .read(chunk_address, &mut chunk[..chunk_length])
+                .is_ok()
+            {
+                data.extend_from_slice(&chunk[..chunk_length]);
+                continue;
+            }
+            for byte_address in chunk_address..chunk_address + chunk_length as u64 {
+                match self.core.read_word_8(byte_address) {
+                    Ok(byte) => data.push(byte),
+                    Err(error) if data.is_empty() => {
+                        return Err(DebuggerError::Other(anyhow!(
+                            "Could not read any data at address {address:#010x}: {error}"
+                        )))
+                    }
+                    Err(_) => return Ok(data),
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    /// Write `data` to target memory, starting at `address`, which does not have to be aligned.
+    /// All of the memory has to be part of the target's memory map, and cannot be in Flash.
+    pub(crate) fn write_memory(&mut self, address: u64, data: &[u8]) -> Result<(), DebuggerError> {
+        self.check_memory_write(address, data.len() as u64)?;
+        self.core.write_8(address, data)?;
+        Ok(())
+    }
+
+    /// Disassemble exactly `instruction_count` instructions, starting `instruction_offset` instructions and `byte_offset` bytes from `memory_reference`,
+    /// and annotate them with the source locations from the [`DebugInfo`], and the function symbols from the ELF symbol table.
+    /// Bytes that cannot be decoded as instructions (e.g. data in between functions) are returned as raw `.byte` values.
+    pub(crate) fn get_disassembled_source(
+        &mut self,
+        // The program_counter where our desired instruction range is based.
+        memory_reference: i64,
+        // The number of bytes offset from the memory reference. Can be zero.
+        byte_offset: i64,
+        // The number of instruction offset from the memory reference. Can be zero.
+        instruction_offset: i64,
+        // The EXACT number of instructions to return in the result.
+        instruction_count: i64,
+    ) -> Result<Vec<DisassembledInstruction>, DebuggerError> {
+        let target_instruction_set = self.core.instruction_set()?;
+        let mut cs = match target_instruction_set {
+            InstructionSet::Thumb2 => {
+                let mut capstone_builder = Capstone::new()
+                    .arm()
+                    .mode(armArchMode::Thumb)
+                    .endian(Endian::Little);
+                if matches!(self.core.core_type(), CoreType::Armv8m) {
+                    capstone_builder = capstone_builder
+                        .extra_mode(std::iter::once(capstone::arch::arm::ArchExtraMode::V8));
+                }
+                capstone_builder.build()
+            }
+            InstructionSet::A32 => Capstone::new()
+                .arm()
+                .mode(armArchMode::Arm)
+                .endian(Endian::Little)
+                .build(),
+            InstructionSet::A64 => Capstone::new()
+                .arm64()
+                .mode(aarch64ArchMode::Arm)
+                .endian(Endian::Little)
+                .build(),
+            InstructionSet::RV32 => Capstone::new()
+                .riscv()
+                .mode(riscvArchMode::RiscV32)
+                .endian(Endian::Little)
+                .build(),
+            InstructionSet::RV32C => Capstone::new()
+                .riscv()
+                .mode(riscvArchMode::RiscV32)
+                .endian(Endian::Little)
+                .extra_mode(std::iter::once(
+                    capstone::arch::riscv::ArchExtraMode::RiscVC,
+                ))
+                .build(),
+        }
+        .map_err(|err| anyhow!("Error creating capstone: {:?}", err))?;
+        let _ = cs.set_skipdata(true);
+
+        // Adjust instruction offset as required for variable length instruction sets.
+        let instruction_offset_as_bytes = match target_instruction_set {
+            InstructionSet::Thumb2 | InstructionSet::RV32C => {
+                // Since we cannot guarantee the size of individual instructions, let's assume we will read the 120% of the requested number of 16-bit instructions.
+                (instruction_offset
+                    * self.core.instruction_set()?.get_minimum_instruction_size() as i64)
+                    / 4
+                    * 5
+            }
+            InstructionSet::A32 | InstructionSet::A64 | InstructionSet::RV32 => {
+                instruction_offset
+                    * self.core.instruction_set()?.get_minimum_instruction_size() as i64
+            }
+        };
+
+        // The vector we will use to return results.
+        let mut assembly_lines: Vec<DisassembledInstruction> = vec![];
+
+        // The buffer to hold data we read from our target.
+        let mut code_buffer: Vec<u8> = vec![];
+
+        // Control whether we need to read target memory in order to disassemble the next instruction.
+        let mut read_more_bytes = true;
+
+        // The memory address for the next read from target memory. We have to manually adjust it to be word aligned, and make sure it doesn't underflow/overflow.
+        let mut read_pointer = if byte_offset.is_negative() {
+            Some(memory_reference.saturating_sub(byte_offset.abs()) as u64)
+        } else {
+            Some(memory_reference.saturating_add(byte_offset) as u64)
+        };
+        // We can't rely on the MSDAP arguments to result in a memory aligned address for us to read from, so we force the read_pointer to be a 32-bit memory_aligned address.
+        read_pointer = if instruction_offset_as_bytes.is_negative() {
+            read_pointer
+                .and_then(|rp| {
+                    rp.saturating_sub(instruction_offset_as_bytes.unsigned_abs())
+                        .checked_div(4)
+                })
+                .map(|rp_memory_aligned| rp_memory_aligned * 4)
+        } else {
+            read_pointer
+                .and_then(|rp| {
+                    rp.saturating_add(instruction_offset_as_bytes as u64)
+                        .checked_div(4)
+                })
+                .map(|rp_memory_aligned| rp_memory_aligned * 4)
+        };
+
+        // The memory address for the next instruction to be disassembled
+        let mut instruction_pointer = if let Some(read_pointer) = read_pointer {
+            read_pointer
+        } else {
+            let error_message = format!("Unable to calculate starting address for disassembly request with memory reference:{memory_reference:#010X}, byte offset:{byte_offset:#010X}, and instruction offset:{instruction_offset:#010X}.");
+            return Err(DebuggerError::Other(anyhow!(error_message)));
+        };
+
+        // We will only include source location data in a resulting instruction, if it is different from the previous one.
+        let mut stored_source_location = None;
+
+        // The MS DAP spec requires that we always have to return a fixed number of instructions.
+        while assembly_lines.len() < instruction_count as usize {
+            if read_more_bytes {
+                if let Some(current_read_pointer) = read_pointer {
+                    // All supported architectures use maximum 32-bit instructions, and require 32-bit memory aligned reads.
+                    match self.core.read_word_32(current_read_pointer) {
+                        Ok(new_word) => {
+                            // Advance the read pointer for next time we need it.
+                            read_pointer = if let Some(valid_read_pointer) =
+                                current_read_pointer.checked_add(4)
+                            {
+                                Some(valid_read_pointer)
+                            } else {
+                                // If this happens, the next loop will generate "invalid instruction" records.
+                                read_pointer = None;
+                                continue;
+                            };
+                            // Update the code buffer.
+                            for new_byte in new_word.to_le_bytes() {
+                                code_buffer.push(new_byte);
+                            }
+                        }
+                        Err(memory_read_error) => {
+                            // If we can't read data at a given address, then create a "invalid instruction" record, and keep trying.
+                            assembly_lines.push(DisassembledInstruction {
+                                address: format!("{current_read_pointer:#010X}"),
+                                column: None,
+                                end_column: None,
+                                end_line: None,
+                                instruction: format!(
+                                    "<instruction address not readable : {memory_read_error:?}>"
+                                ),
+                                instruction_bytes: None,
+                                line: None,
+                                location: None,
+                                symbol: None,
+                            });
+                            read_pointer = Some(current_read_pointer.saturating_add(4));
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            match cs.disasm_all(&code_buffer, instruction_pointer) {
+                Ok(instructions) => {
+                    if instructions.is_empty() {
+                        // The capstone library sometimes returns an empty result set, instead of an Err, for bytes that cannot be decoded.
+                        // Return them as raw bytes, or else we risk an infinite loop looking for a valid instruction.
+                        assembly_lines.push(DisassembledInstruction {
+                            address: format!("{instruction_pointer:#010X}"),
+                            column: None,
+                            end_column: None,
+                            end_line: None,
+                            instruction: format!(
+                                ".byte  {}",
+                                code_buffer
+                                    .iter()
+                                    .map(|byte| format!("{byte:#04x}"))
+                                    .collect::<Vec<String>>()
+                                    .join(", ")
+                            ),
+                            instruction_bytes: Some(hex_bytes(&code_buffer)),
+                            line: None,
+                            location: None,
+                            symbol: None,
+                        });
+                        instruction_pointer += code_buffer.len() as u64;
+                        code_buffer.clear();
+                        read_more_bytes = true;
+                        continue;
+                    }
+
+                    let mut result_instruction = instructions
+                        .iter()
+                        .map(|instruction| {
+                            // Before processing, update the code buffer appropriately
+                            code_buffer = code_buffer.split_at(instruction.len()).1.to_vec();
+
+                            // Variable width instruction sets my not use the full `code_buffer`, so we need to read ahead, to ensure we have enough code in the buffer to disassemble the 'widest' of instructions in the instruction set.
+                            read_more_bytes = code_buffer.len() < target_instruction_set.get_maximum_instruction_size() as usize;
+
+                            // Move the instruction_pointer for the next read.
+                            instruction_pointer += instruction.len() as u64;
+
+                            // Try to resolve the source location for this instruction.
+                            // If we find one, we use it ONLY if it is different from the previous one (stored_source_location).
+                            // - This helps to reduce visual noise in the VSCode UX, by not displaying the same line of source code multiple times over.
+                            // If we do not find a source location, then just return the raw assembly without file/line/column information.
+                            let mut location = None;
+                            let mut line = None;
+                            let mut column = None;
+                            if let Some(current_source_location) = self
+                                .core_data
+                                .debug_info
+                                .get_source_location(instruction.address()) {
+                                if stored_source_location.as_ref() != Some(&current_source_location) {
+                                    location = get_dap_source(&current_source_location);
+                                    line = current_source_location.line.map(|line| line as i64);
+                                    column = current_source_location.column.map(|col| match col {
+                                        ColumnType::LeftEdge => 0_i64,
+                                        ColumnType::Column(c) => c as i64,
+                                    });
+                                    stored_source_location = Some(current_source_location);
+                                }
+                            } else {
+                                // It won't affect the outcome, but log it for completeness.
+                                tracing::debug!("The request `Disassemble` could not resolve a source location for memory reference: {:#010}", instruction.address());
+                            }
+
+                            // Create the instruction data.
+                            DisassembledInstruction {
+                                address: format!("{:#010X}", instruction.address()),
+                                column,
+                                end_column: None,
+                                end_line: None,
+                                instruction: format!(
+                                    "{}  {}",
+                                    instruction.mnemonic().unwrap_or("<unknown>"),
+                                    instruction.op_str().unwrap_or("")
+                                ),
+                                instruction_bytes: Some(hex_bytes(instruction.bytes())),
+                                line,
+                                location,
+                                // Show the function name at the first instruction of every function.
+                                symbol: self
+                                    .core_data
+                                    .elf_symbols
+                                    .iter()
+                                    .find(|symbol| {
+                                        !symbol.is_data && symbol.address == instruction.address()
+                                    })
+                                    .map(|symbol| symbol.demangled_name.clone()),
+                            }
+                        })
+                        .collect::<Vec<DisassembledInstruction>>();
+                    assembly_lines.append(&mut result_instruction);
+                }
+                Err(error) => {
+                    return Err(DebuggerError::Other(anyhow!(error)));
+                }
+            };
+        }
+
+        if assembly_lines.is_empty() {
+            Err(DebuggerError::Other(anyhow!(
+                "No valid instructions found at memory reference {:#010x?}",
+                memory_reference
+            )))
+        } else {
+            Ok(assembly_lines)
+        }
+    }
+
+    /// Search available [`probe_rs::debug::StackFrame`]'s for the given `id`
+    pub(crate) fn get_stackframe(
+        &'p self,
+        id: i64,
+    ) -> Option<&'p probe_rs::debug::stack_frame::StackFrame> {
+        self.core_data
+            .stack_frames
+            .iter()
+            .find(|stack_frame| stack_frame.id == id)
+    }
+
+    /// Detach from RTT, e.g. because the core is reset, and the firmware will initialize the control block again.
+    /// The output that was kept for open client windows is sent first. RTT is attached again by [`CoreHandle::attach_to_rtt`], once the core runs.
+    pub(crate) fn detach_rtt<P: ProtocolAdapter>(&mut self, debug_adapter: &mut DebugAdapter<P>) {
+        if let Some(mut rtt_connection) = self.core_data.rtt_connection.take() {
+            for debugger_rtt_channel in rtt_connection
+                .debugger_rtt_channels
+                .iter_mut()
+                .filter(|debugger_rtt_channel| debugger_rtt_channel.has_client_window)
+            {
+                debugger_rtt_channel.flush_pending_data(debug_adapter);
+            }
+            // The channels without a window keep their pending data, until the window is opened after RTT is attached again.
+            self.core_data.detached_rtt_channels = rtt_connection.debugger_rtt_channels;
+            tracing::debug!("Detached from RTT on core #{}", self.core.id());
+        }
+        self.core_data.rtt_reattach_after_run = true;
+        self.core_data.rtt_attach_attempts = 0;
+        self.core_data.rtt_next_attach_attempt = None;
+    }
+
+    /// Confirm RTT initialization on the target, and use the RTT channel configurations to initialize the output windows on the DAP Client.
+    /// This is called by [`super::session_data::SessionData::poll_cores`] until it succeeds. Each call makes a single attempt,
+    /// and the next attempt is delayed, so that the retries are spread over the `timeout_ms` of the `rtt_config`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn attach_to_rtt<P: ProtocolAdapter>(
+        &mut self,
+        debug_adapter: &mut DebugAdapter<P>,
+        target_memory_map: &[probe_rs::config::MemoryRegion],
+        program_binary: &std::path::Path,
+        rtt_config: &rtt::RttConfig,
+        rtt_window_mode: RttWindowMode,
+        rtt_window_buffer_size: usize,
+        timestamp_offset: UtcOffset,
+    ) -> Result<()> {
+        if self
+            .core_data
+            .rtt_next_attach_attempt
+            .map_or(false, |next_attach_attempt| {
+                Instant::now() < next_attach_attempt
+            })
+        {
+            return Ok(());
+        }
+        let mut debugger_rtt_channels: Vec<debug_rtt::DebuggerRttChannel> = vec![];
+        let mut debugger_rtt_down_channels: Vec<debug_rtt::DebuggerRttDownChannel> = vec![];
+        // Attach to the configured control blocks, if there are any, where the channels of the first one are extended with those of the others.
+        // Otherwise, use the `scan_mode` to locate the control block, which by default uses the control block address from the device configuration,
+        // if there is one, or else the address from the ELF file.
+        let rtt_symbol_address = if rtt_config.control_blocks.is_empty() {
+            File::open(program_binary)
+                .map_err(|error| anyhow!("Error attempting to attach to RTT: {}", error))
+                .map(|mut open_file| RttActiveTarget::get_rtt_symbol(&mut open_file))
+        } else {
+            Ok(None)
+        };
+        let scan_regions = rtt_symbol_address.and_then(|rtt_symbol_address| {
+            rtt_config.control_block_scan_regions(
+                self.core_data.rtt_control_block_address,
+                rtt_symbol_address,
+                target_memory_map,
+            )
+        });
+        // Retry until every control block is attached, but once the retries are exhausted, use the control blocks that were found.
+        let final_attempt = self.core_data.rtt_attach_attempts >= rtt_config.retries;
+        let attach_result = match &scan_regions {
+            Ok(scan_regions) => {
+                let mut control_block_rtts = Vec::new();
+                let mut failed_control_blocks = Vec::new();
+                for (label, scan_region) in scan_regions {
+                    match Rtt::attach_region(&mut self.core, target_memory_map, scan_region) {
+                        Ok(rtt) => control_block_rtts.push((label, rtt)),
+                        Err(error) => failed_control_blocks.push(match label {
+                            Some(label) => format!("{label:?}: {error}"),
+                            None => error.to_string(),
+                        }),
+                    }
+                }
+                if control_block_rtts.is_empty()
+                    || (!failed_control_blocks.is_empty() && !final_attempt)
+                {
+                    Err(anyhow!(
+                        "Error attempting to attach to RTT: {}",
+                        failed_control_blocks.join(", ")
+                    ))
+                } else {
+                    for failed_control_block in failed_control_blocks {
+                        debug_adapter.show_message(
+                            MessageSeverity::Warning,
+                            format!(
+                                "Failed to attach to the RTT control block {failed_control_block}"
+                            ),
+                        );
+                    }
+                    tracing::info!("RTT initialized.");
+                    // The recording and the log files are only used by the channels of the first control block.
+                    let mut control_block_rtt_config = rtt_config.clone();
+                    control_block_rtt_config.recording_path = None;
+                    for channel_config in control_block_rtt_config.channels.iter_mut() {
+                        channel_config.log_file = None;
+                    }
+                    control_block_rtts
+                        .into_iter()
+                        .try_fold(None, |target_rtt: Option<RttActiveTarget>, (label, rtt)| {
+                            let mut control_block_rtt = RttActiveTarget::new(
+                                rtt,
+                                program_binary,
+                                if target_rtt.is_none() {
+                                    rtt_config
+                                } else {
+                                    &control_block_rtt_config
+                                },
+                                timestamp_offset,
+                            )?;
+                            if let Some(label) = label {
+                                control_block_rtt.label_channels(label);
+                            }
+                            Ok::<_, anyhow::Error>(Some(match target_rtt {
+                                Some(mut target_rtt) => {
+                                    target_rtt.merge(control_block_rtt);
+                                    target_rtt
+                                }
+                                None => control_block_rtt,
+                            }))
+                        })
+                        .and_then(|target_rtt| {
+                            target_rtt.ok_or_else(|| anyhow!("No RTT control block was attached"))
+                        })
+                }
+            }
+            Err(error) => Err(anyhow!("{error}")),
+        };
+        match attach_result {
+            Ok(target_rtt) => {
+                self.core_data.rtt_attach_attempts = 0;
+                self.core_data.rtt_next_attach_attempt = None;
+                for any_channel in target_rtt.active_channels.iter() {
+                    // The channel numbers of the `RttActiveChannel` are unique across all the control blocks, unlike those of the `UpChannel` and `DownChannel`.
+                    if let (Some(up_channel), Some(channel_number)) =
+                        (&any_channel.up_channel, any_channel.number())
+                    {
+                        if let Some(channel_mode) = any_channel.channel_mode {
+                            up_channel.set_mode(&mut self.core, channel_mode)?;
+                            if any_channel.data_format == DataFormat::Defmt
+                                && channel_mode != ChannelMode::BlockIfFull
+                            {
+                                debug_adapter.show_message(
+                                    MessageSeverity::Warning,
+                                    format!("RTT channel {:?} uses the {channel_mode:?} mode, so the target does not wait when the buffer is full. This avoids stalling the target, but defmt frames that do not fit are lost, or cannot be decoded.", any_channel.channel_name),
+                                );
+                            }
+                        }
+                        // A channel that existed before RTT was detached keeps its client window, and the data that was not sent yet.
+                        let detached_channel = self
+                            .core_data
+                            .detached_rtt_channels
+                            .iter()
+                            .position(|detached_channel| {
+                                detached_channel.channel_number == channel_number
+                            })
+                            .map(|position| self.core_data.detached_rtt_channels.remove(position));
+                        debugger_rtt_channels.push(detached_channel.unwrap_or_else(|| {
+                            // In lazy mode, the window is only requested once the channel produces output. See [`debug_rtt::DebuggerRttChannel::poll_rtt_data`].
+                            let window_requested = rtt_window_mode == RttWindowMode::Eager
+                                && debug_adapter.rtt_window(
+                                    channel_number,
+                                    any_channel.channel_name.clone(),
+                                    any_channel.data_format,
+                                );
+                            debug_rtt::DebuggerRttChannel {
+                                channel_number,
+                                // This value will eventually be set to true by a VSCode client request "rttWindowOpened"
+                                has_client_window: false,
+                                window_requested,
+                                pending_data: vec![],
+                                max_pending_data: rtt_window_buffer_size,
+                            }
+                        }));
+                    }
+                    if let Some(channel_number) = any_channel.down_number() {
+                        debugger_rtt_down_channels.push(debug_rtt::DebuggerRttDownChannel {
+                            channel_number,
+                            pending_data: vec![],
+                        });
+                    }
+                }
+                self.core_data.detached_rtt_channels.clear();
+                self.core_data.rtt_connection = Some(debug_rtt::RttConnection {
+                    target_rtt,
+                    debugger_rtt_channels,
+                    debugger_rtt_down_channels,
+                });
+            }
+            Err(error) => {
+                // The firmware may still be initializing the control block, so retry (while the core keeps running) before giving up.
+                // Without a control block location, retrying will not help, but it is checked again on the next attempt.
+                let retry_delay = Duration::from_millis(
+                    rtt_config.timeout_ms / u64::from(rtt_config.retries.max(1)),
+                );
+                self.core_data.rtt_attach_attempts += 1;
+                self.core_data.rtt_next_attach_attempt = Some(Instant::now() + retry_delay);
+                if scan_regions.is_ok() && self.core_data.rtt_attach_attempts <= rtt_config.retries
+                {
+                    tracing::debug!(
+                        "Failed to attach to RTT, retry {} of {} in {retry_delay:?}",
+                        self.core_data.rtt_attach_attempts,
+                        rtt_config.retries
+                    );
+                } else {
+                    tracing::warn!(
+                        "Failed to initalize RTT after {} attempts: {error}. Will try again on the next request... ",
+                        self.core_data.rtt_attach_attempts
+                    );
+                    self.core_data.rtt_attach_attempts = 0;
+                }
+            }
+        };
+        Ok(())
+    }
+
+    /// Write `data` to the RTT down channel `channel`. The data is not encoded, and does not wait for space in the target buffer.
+    /// See [`debug_rtt::RttConnection::write_rtt`]. Returns the number of bytes that were written to the target immediately.
+    pub(crate) fn write_rtt(
+        &mut self,
+        channel: usize,
+        data: &[u8],
+    ) -> Result<usize, DebuggerError> {
+        self.core_data
+            .rtt_connection
+            .as_mut()
+            .ok_or_else(|| DebuggerError::Other(anyhow!("RTT is not attached to the target")))?
+            .write_rtt(&mut self.core, channel, data)
+    }
+
+    /// Set a single breakpoint in target configuration as well as [`super::core_data::CoreHandle`]
+    /// Breakpoints at the same address (e.g. with different conditions) share a hardware breakpoint unit.
+    /// Returns the id of the new [`session_data::ActiveBreakpoint`].
+    pub(crate) fn set_breakpoint(
+        &mut self,
+        address: u64,
+        breakpoint_type: session_data::BreakpointType,
+        condition: Option<String>,
+        hit_condition: Option<String>,
+    ) -> Result<i64, DebuggerError> {
+        let hit_condition = hit_condition.filter(|hit_condition| !hit_condition.trim().is_empty());
+        if let Some(hit_condition) = &hit_condition {
+            // Reject an invalid hit condition now, rather than when the breakpoint is hit.
+            breakpoint_condition::evaluate_hit_condition(hit_condition, 0)?;
+        }
+        let id = self.core_data.next_breakpoint_id;
+        self.insert_breakpoint(session_data::ActiveBreakpoint {
+            id,
+            breakpoint_type,
+            condition: condition.filter(|condition| !condition.trim().is_empty()),
+            hit_condition,
+            address,
+            hit_count: 0,
+            condition_error_reported: false,
+        })?;
+        self.core_data.next_breakpoint_id += 1;
+        Ok(id)
+    }
+
+    /// Program a free DWT comparator to halt the core when `size` bytes at `address` are accessed as described by `access`.
+    /// Returns the id of the new [`data_watchpoints::ActiveDataWatchpoint`], or an error if no free comparators remain.
+    pub(crate) fn set_data_watchpoint(
+        &mut self,
+        address: u64,
+        size: u8,
+        access: WatchKind,
+    ) -> Result<i64, DebuggerError> {
+        let comparator_count = data_watchpoints::comparator_count(&mut self.core)?;
+        let comparator = (0..comparator_count)
+            .find(|comparator| {
+                !self
+                    .core_data
+                    .data_watchpoints
+                    .iter()
+                    .any(|watchpoint| watchpoint.comparator == *comparator)
+            })
+            .ok_or_else(|| {
+                DebuggerError::Other(anyhow!(
+                    "No free data watchpoint comparators remain, all {comparator_count} are in use"
+                ))
+            })?;
+        data_watchpoints::program_comparator(&mut self.core, comparator, address, size, access)
+            .map_err(|error| {
+                DebuggerError::Other(anyhow!(
+                    "Failed to set a data watchpoint at {address:#010x}, in {}: {error}",
+                    self.memory_region_kind(address)
+                ))
+            })?;
+        let id = self.core_data.next_breakpoint_id;
+        self.core_data
+            .data_watchpoints
+            .push(data_watchpoints::ActiveDataWatchpoint {
+                id,
+                address,
+                comparator,
+            });
+        self.core_data.next_breakpoint_id += 1;
+        Ok(id)
+    }
+
+    /// Clear all the data watchpoints, and release their DWT comparators.
+    pub(crate) fn clear_data_watchpoints(&mut self) -> Result<(), DebuggerError> {
+        for watchpoint in std::mem::take(&mut self.core_data.data_watchpoints) {
+            data_watchpoints::clear_comparator(&mut self.core, watchpoint.comparator)?;
+        }
+        Ok(())
+    }
+
+    /// The number of hardware breakpoint units of the core. It is read from the core once, and then cached in [`CoreData::breakpoint_units`].
+    pub(crate) fn available_breakpoint_units(&mut self) -> Result<u32, DebuggerError> {
+        if let Some(breakpoint_units) = self.core_data.breakpoint_units {
+            return Ok(breakpoint_units);
+        }
+        let breakpoint_units = self.core.available_breakpoint_units()?;
+        self.core_data.breakpoint_units = Some(breakpoint_units);
+        Ok(breakpoint_units)
+    }
+
+    /// The number of hardware breakpoint units that are in use. Breakpoints at the same address share a unit,
+    /// and software breakpoints do not use one.
+    pub(crate) fn hardware_breakpoints_used(&self) -> usize {
+        let mut armed_addresses = self
+            .core_data
+            .breakpoints
+            .iter()
+            .filter(|breakpoint| breakpoint.breakpoint_type.original_instruction().is_none())
+            .map(|breakpoint| breakpoint.address)
+            .collect::<Vec<u64>>();
+        armed_addresses.sort_unstable();
+        armed_addresses.dedup();
+        armed_addresses.len()
+    }
+
+    /// Set the hardware breakpoint for an existing `breakpoint`, and add it to the [`CoreData::breakpoints`].
+    /// If all the hardware breakpoint units are in use, and [`CoreData::software_breakpoint_fallback`] is enabled, a software breakpoint is used instead.
+    /// Returns an error if the breakpoint needs a new hardware breakpoint unit, and all of them are in use.
+    fn insert_breakpoint(
+        &mut self,
+        mut breakpoint: session_data::ActiveBreakpoint,
+    ) -> Result<(), DebuggerError> {
+        let shared_breakpoint = self
+            .core_data
+            .breakpoints
+            .iter()
+            .find(|active_breakpoint| active_breakpoint.address == breakpoint.address)
+            .map(|active_breakpoint| {
+                active_breakpoint
+                    .breakpoint_type
+                    .original_instruction()
+                    .map(<[u8]>::to_vec)
+            });
+        let original_instruction = match shared_breakpoint {
+            // Share the hardware breakpoint unit, or the software breakpoint instruction, that is already at this address.
+            Some(original_instruction) => original_instruction,
+            None => {
+                let breakpoints_used = self.hardware_breakpoints_used();
+                // If the number of units cannot be read, leave it to the probe to report the error.
+                match self.available_breakpoint_units() {
+                    Ok(breakpoint_units) if breakpoints_used >= breakpoint_units as usize => {
+                        if !self.core_data.software_breakpoint_fallback {
+                            return Err(DebuggerError::Other(anyhow!(
+                                "No free hardware breakpoints ({breakpoints_used}/{breakpoint_units} used)"
+                            )));
+                        }
+                        let original_instruction = self
+                            .write_software_breakpoint(breakpoint.address)
+                            .map_err(|error| {
+                                DebuggerError::Other(anyhow!(
+                                    "No free hardware breakpoints ({breakpoints_used}/{breakpoint_units} used), and a software breakpoint cannot be used: {error}"
+                                ))
+                            })?;
+                        Some(original_instruction)
+                    }
+                    _ => None,
+                }
+            }
+        };
+        match original_instruction {
+            Some(original_instruction) => {
+                breakpoint.breakpoint_type = BreakpointType::SoftwareBreakpoint(
+                    Box::new(breakpoint.breakpoint_type),
+                    original_instruction,
+                );
+            }
+            None => {
+                if let Err(error) = self.core.set_hw_breakpoint(breakpoint.address) {
+                    return Err(DebuggerError::Other(anyhow!(
+                        "Failed to set a breakpoint at {:#010x}, in {}: {error}",
+                        breakpoint.address,
+                        self.memory_region_kind(breakpoint.address)
+                    )));
+                }
+            }
+        }
+        self.core_data.breakpoints.push(breakpoint);
+        Ok(())
+    }
+
+    /// Replace the instruction at `address` with a `BKPT` instruction, and return the original instruction bytes.
+    /// This requires a Cortex-M core, and an `address` in a writable (RAM) memory region.
+    fn write_software_breakpoint(&mut self, address: u64) -> Result<Vec<u8>, DebuggerError> {
+        if !self.core.core_type().is_cortex_m() {
+            return Err(DebuggerError::Other(anyhow!(
+                "Software breakpoints are only supported on Cortex-M cores"
+            )));
+        }
+        let instruction_end = address + THUMB_BKPT_INSTRUCTION.len() as u64 - 1;
+        for memory_region_kind in [
+            self.memory_region_kind(address),
+            self.memory_region_kind(instruction_end),
+        ] {
+            if memory_region_kind != MemoryRegionKind::Ram {
+                return Err(DebuggerError::Other(anyhow!(
+                    "The instruction at {address:#010x} is in {memory_region_kind}, and not in a writable (RAM) memory region"
+                )));
+            }
+        }
+        let mut original_instruction = vec![0_u8; THUMB_BKPT_INSTRUCTION.len()];
+        self.core.read_8(address, &mut original_instruction)?;
+        self.core.write_8(address, &THUMB_BKPT_INSTRUCTION)?;
+        Ok(original_instruction)
+    }
+
+    /// Write the `BKPT` instruction of every software breakpoint again, e.g. after flashing a new binary has overwritten the code in RAM.
+    /// If the instruction at the address is no longer a `BKPT` instruction, it is saved as the new original instruction.
+    pub(crate) fn rewrite_software_breakpoints(&mut self) -> Result<(), DebuggerError> {
+        let mut software_breakpoint_addresses = self
+            .core_data
+            .breakpoints
+            .iter()
+            .filter(|breakpoint| breakpoint.breakpoint_type.original_instruction().is_some())
+            .map(|breakpoint| breakpoint.address)
+            .collect::<Vec<u64>>();
+        software_breakpoint_addresses.sort_unstable();
+        software_breakpoint_addresses.dedup();
+        for address in software_breakpoint_addresses {
+            let mut current_instruction = vec![0_u8; THUMB_BKPT_INSTRUCTION.len()];
+            self.core.read_8(address, &mut current_instruction)?;
+            if current_instruction == THUMB_BKPT_INSTRUCTION {
+                continue;
+            }
+            self.core.write_8(address, &THUMB_BKPT_INSTRUCTION)?;
+            for breakpoint in self
+                .core_data
+                .breakpoints
+                .iter_mut()
+                .filter(|breakpoint| breakpoint.address == address)
+            {
+                if let BreakpointType::SoftwareBreakpoint(_, original_instruction) =
+                    &mut breakpoint.breakpoint_type
+                {
+                    *original_instruction = current_instruction.clone();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Restore the original instruction of every software breakpoint, e.g. before the debugger detaches, or the core is reset,
+    /// so that the target does not execute a `BKPT` instruction without a debugger. The breakpoints are kept in [`CoreData::breakpoints`],
+    /// so that [`CoreHandle::rewrite_software_breakpoints`] can write the `BKPT` instructions again.
+    pub(crate) fn restore_software_breakpoints(&mut self) -> Result<(), DebuggerError> {
+        let mut restored_addresses = Vec::new();
+        let software_breakpoints = self
+            .core_data
+            .breakpoints
+            .iter()
+            .filter_map(|breakpoint| {
+                breakpoint
+                    .breakpoint_type
+                    .original_instruction()
+                    .map(|original_instruction| (breakpoint.address, original_instruction.to_vec()))
+            })
+            .collect::<Vec<(u64, Vec<u8>)>>();
+        for (address, original_instruction) in software_breakpoints {
+            if !restored_addresses.contains(&address) {
+                restored_addresses.push(address);
+                self.core.write_8(address, &original_instruction)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// If the core is halted at a software breakpoint, restore the original instruction, step over it, and write the `BKPT` instruction again.
+    /// This has to be done before the core is resumed or stepped, because stepping from a `BKPT` instruction skips the original instruction.
+    /// Returns `true` if the core was stepped.
+    pub(crate) fn step_over_software_breakpoint(&mut self) -> Result<bool, Error> {
+        let program_counter: u64 = self
+            .core
+            .read_core_reg(self.core.registers().program_counter())?;
+        let original_instruction = match self
+            .core_data
+            .breakpoints
+            .iter()
+            .find(|breakpoint| breakpoint.address == program_counter)
+            .and_then(|breakpoint| breakpoint.breakpoint_type.original_instruction())
+        {
+            Some(original_instruction) => original_instruction.to_vec(),
+            None => return Ok(false),
+        };
+        self.core.write_8(program_counter, &original_instruction)?;
+        let step_result = self.core.step();
+        self.core
+            .write_8(program_counter, &THUMB_BKPT_INSTRUCTION)?;
+        step_result?;
+        Ok(true)
+    }
+
+    /// Step a single instruction, for the MS DAP `next` and `stepIn` requests with [`SteppingGranularity::Instruction`](crate::debug_adapter::dap_types::SteppingGranularity::Instruction).
+    /// The core status is reset with [`CoreHandle::reset_core_status`] first, so that [`CoreHandle::poll_core`] does not notify the client that the core resumed,
+    /// and the caller reports the halt with [`HaltReason::Step`].
+    /// Returns the new [`CoreStatus`] and program counter.
+    pub(crate) fn step_instruction<P: ProtocolAdapter>(
+        &mut self,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> Result<(CoreStatus, u64), Error> {
+        self.reset_core_status(debug_adapter);
+        self.single_step()
+    }
+
+    /// Execute one instruction, stepping over a software breakpoint at the current program counter, if there is one.
+    fn single_step(&mut self) -> Result<(CoreStatus, u64), Error> {
+        let program_counter = if self.step_over_software_breakpoint()? {
+            // Stepping over the software breakpoint already executed the instruction.
+            self.core
+                .read_core_reg(self.core.registers().program_counter())?
+        } else {
+            self.core.step()?.pc
+        };
+        Ok((self.core.status()?, program_counter))
+    }
+
+    /// Step the core using `stepping_mode`, after stepping over a software breakpoint at the current program counter.
+    /// Source level steps do not halt in the handler of an interrupt that fires during the step, see [`CoreHandle::run_to_interrupt_return`].
+    /// Returns the new [`CoreStatus`] and program counter.
+    pub(crate) fn step(
+        &mut self,
+        stepping_mode: &SteppingMode,
+    ) -> Result<(CoreStatus, u64), DebugError> {
+        if matches!(stepping_mode, SteppingMode::StepInstruction) {
+            return Ok(self.single_step()?);
+        }
+        self.step_over_software_breakpoint()?;
+        // An interrupt that fires during a source level step is stepped over, by running to the end of its handler, and then resuming the step.
+        let exception_number = self.active_exception_number();
+        let mut stepped_over_interrupts = 0;
+        loop {
+            let (new_status, program_counter) =
+                stepping_mode.step(&mut self.core, &self.core_data.debug_info)?;
+            let entered_interrupt = new_status.is_halted()
+                && self
+                    .active_exception_number()
+                    .map_or(false, |new_exception_number| {
+                        Some(new_exception_number) != exception_number
+                    });
+            if !entered_interrupt || stepped_over_interrupts >= MAX_STEPPED_OVER_INTERRUPTS {
+                return Ok((new_status, program_counter));
+            }
+            stepped_over_interrupts += 1;
+            if !self.run_to_interrupt_return()? {
+                let program_counter = self
+                    .core
+                    .read_core_reg(self.core.registers().program_counter())?;
+                return Ok((self.core.status()?, program_counter));
+            }
+        }
+    }
+
+    /// Run the core until the interrupt handler that it entered during a step returns, by setting a [`BreakpointType::TemporaryBreakpoint`]
+    /// at the return address in the exception stack frame. This is only supported for Cortex-M cores, while the core is halted at the start of the handler,
+    /// where the link register still holds the `EXC_RETURN` value.
+    /// The exception stack frame is on the stack that `EXC_RETURN` selects, and the stack pointer from before the exception is restored when the handler returns,
+    /// which guards against the same address being reached in another context.
+    /// Returns `false` if the return address is not known, or if the core halted somewhere else, e.g. at a breakpoint in the handler.
+    fn run_to_interrupt_return(&mut self) -> Result<bool, DebugError> {
+        let exception_return: u32 = self
+            .core
+            .read_core_reg(self.core.registers().return_address())?;
+        if exception_return & 0xFF00_0000 != 0xFF00_0000 {
+            tracing::debug!("The core entered an interrupt handler while stepping, but the link register does not hold an `EXC_RETURN` value: {exception_return:#010x}");
+            return Ok(false);
+        }
+        let frame_stack_pointer = if exception_frame_on_process_stack(exception_return) {
+            self.core.registers().psp()
+        } else {
+            self.core.registers().msp()
+        };
+        let frame_address: u64 = match frame_stack_pointer {
+            Some(frame_stack_pointer) => self.core.read_core_reg(frame_stack_pointer)?,
+            None => return Ok(false),
+        };
+        let return_address = (self.core.read_word_32(frame_address + 0x18)? & !0x1) as u64;
+        let stacked_xpsr = self.core.read_word_32(frame_address + 0x1C)?;
+        let stack_pointer =
+            exception_return_stack_pointer(exception_return, frame_address, stacked_xpsr);
+        tracing::debug!(
+            "Stepping over an interrupt handler, by running to its return address {return_address:#010x}"
+        );
+        self.set_temporary_breakpoint(return_address, "step")
+            .map_err(|error| DebugError::Other(anyhow!("{error}")))?;
+        let deadline = Instant::now() + INTERRUPT_RETURN_TIMEOUT;
+        let returned = loop {
+            self.core.run()?;
+            if self
+                .core
+                .wait_for_core_halted(deadline.saturating_duration_since(Instant::now()))
+                .is_err()
+            {
+                self.core.halt(Duration::from_millis(100))?;
+                break false;
+            }
+            let program_counter: u64 = self
+                .core
+                .read_core_reg(self.core.registers().program_counter())?;
+            if program_counter != return_address {
+                break false;
+            }
+            let current_stack_pointer: u64 = self
+                .core
+                .read_core_reg(self.core.registers().stack_pointer())?;
+            if current_stack_pointer == stack_pointer {
+                break true;
+            }
+            if Instant::now() >= deadline {
+                break false;
+            }
+            // The return address was reached in another context, e.g. by a nested interrupt, so step past the breakpoint, and keep running.
+            if !self.step_over_software_breakpoint()? {
+                self.core.step()?;
+            }
+        };
+        self.clear_breakpoints(BreakpointType::TemporaryBreakpoint)
+            .map_err(DebugError::Other)?;
+        Ok(returned)
+    }
+
+    /// Read the unique device id of the target named `target_name`, and apply the matching [`DeviceConfig`] from `device_configs`.
+    /// Returns the unique device id, or `None` if the location of the unique device id is not known for this target.
+    pub(crate) fn apply_device_config(
+        &mut self,
+        target_name: &str,
+        device_configs: &HashMap<String, DeviceConfig>,
+    ) -> Result<Option<String>, DebuggerError> {
+        let unique_id = match device_identity::read_unique_id(&mut self.core, target_name)? {
+            Some(unique_id) => unique_id,
+            None => return Ok(None),
+        };
+        let device_config = device_configs
+            .iter()
+            .find(|(device_id, _)| device_id.eq_ignore_ascii_case(&unique_id))
+            .map(|(_, device_config)| device_config);
+        self.core_data.rtt_control_block_address =
+            device_config.and_then(|device_config| device_config.rtt_control_block_address);
+        for memory_write in device_config
+            .iter()
+            .flat_map(|device_config| device_config.memory_writes.iter())
+        {
+            let data = std::fs::read(&memory_write.file).map_err(|error| {
+                DebuggerError::Other(anyhow!(
+                    "Failed to read {:?} for device {unique_id}: {error}",
+                    memory_write.file
+                ))
+            })?;
+            self.check_memory_write(memory_write.address, data.len() as u64)?;
+            self.core.write_8(memory_write.address, &data)?;
+        }
+        Ok(Some(unique_id))
+    }
+
+    /// Set the hardware breakpoint of every [`session_data::ActiveBreakpoint`] again, e.g. after the connection to the target was restored.
+    /// Software breakpoints are still in the target memory, so they are not affected.
+    /// Returns the number of hardware breakpoint addresses that were re-armed,
+    /// or an error with that number, if the hardware breakpoint could not be set at every address.
+    pub(crate) fn rearm_breakpoints(&mut self) -> Result<usize, DebuggerError> {
+        let mut breakpoint_addresses = self
+            .core_data
+            .breakpoints
+            .iter()
+            .filter(|breakpoint| breakpoint.breakpoint_type.original_instruction().is_none())
+            .map(|breakpoint| breakpoint.address)
+            .collect::<Vec<u64>>();
+        breakpoint_addresses.sort_unstable();
+        breakpoint_addresses.dedup();
+        let mut rearmed_count = 0;
+        let mut last_error = None;
+        for address in &breakpoint_addresses {
+            match self.core.set_hw_breakpoint(*address) {
+                Ok(()) => rearmed_count += 1,
+                Err(error) => last_error = Some((*address, error)),
+            }
+        }
+        match last_error {
+            None => Ok(rearmed_count),
+            Some((address, error)) => Err(DebuggerError::Other(anyhow!(
+                "Re-armed {rearmed_count} of {} hardware breakpoints. Failed to set the breakpoint at {address:#010x}: {error}",
+                breakpoint_addresses.len()
+            ))),
+        }
+    }
+
+    /// Clear the hardware breakpoint at `address`, unless it is still in use by another [`session_data::ActiveBreakpoint`].
+    /// For a software breakpoint, the `original_instruction` is restored instead.
+    fn release_breakpoint(
+        &mut self,
+        address: u64,
+        original_instruction: Option<&[u8]>,
+    ) -> Result<()> {
+        if !self
+            .core_data
+            .breakpoints
+            .iter()
+            .any(|active_breakpoint| active_breakpoint.address == address)
+        {
+            match original_instruction {
+                Some(original_instruction) => self.core.write_8(address, original_instruction)?,
+                None => self
+                    .core
+                    .clear_hw_breakpoint(address)
+                    .map_err(DebuggerError::ProbeRs)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Clear all breakpoints of a specified [`super::session_data::BreakpointType`].
+    /// Affects target configuration as well as [`super::core_data::CoreHandle`].
+    pub(crate) fn clear_breakpoints(
+        &mut self,
+        breakpoint_type: session_data::BreakpointType,
+    ) -> Result<()> {
+        self.clear_matching_breakpoints(|breakpoint| {
+            breakpoint.breakpoint_type.requested_type() == &breakpoint_type
+        })
+    }
+
+    /// Clear all the [`super::session_data::BreakpointType::SourceBreakpoint`]s that were requested for the source file at `source_path`.
+    /// Breakpoints in other source files, and breakpoints of other types (e.g. instruction breakpoints), are not affected.
+    pub(crate) fn clear_source_breakpoints(&mut self, source_path: &Path) -> Result<()> {
+        self.core_data
+            .pending_breakpoints
+            .retain(|pending_breakpoint| pending_breakpoint.source_path != source_path);
+        self.clear_matching_breakpoints(|breakpoint| {
+            if let BreakpointType::SourceBreakpoint(source, ..) =
+                breakpoint.breakpoint_type.requested_type()
+            {
+                source.path.as_deref().map(Path::new) == Some(source_path)
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Clear all the breakpoints for which `is_target_breakpoint` returns `true`.
+    fn clear_matching_breakpoints(
+        &mut self,
+        is_target_breakpoint: impl Fn(&session_data::ActiveBreakpoint) -> bool,
+    ) -> Result<()> {
+        let target_breakpoints = self
+            .core_data
+            .breakpoints
+            .iter()
+            .filter(|breakpoint| is_target_breakpoint(breakpoint))
+            .map(|breakpoint| {
+                (
+                    breakpoint.address,
+                    breakpoint
+                        .breakpoint_type
+                        .original_instruction()
+                        .map(<[u8]>::to_vec),
+                )
+            })
+            .collect::<Vec<(u64, Option<Vec<u8>>)>>();
+        self.core_data
+            .breakpoints
+            .retain(|breakpoint| !is_target_breakpoint(breakpoint));
+        for (address, original_instruction) in target_breakpoints {
+            self.release_breakpoint(address, original_instruction.as_deref())?;
+        }
+        Ok(())
+    }
+
+    /// Set a [`BreakpointType::CoverageBreakpoint`] for each of the source lines in `coverage_config`,
+    /// replacing any coverage breakpoints from a previous run.
+    pub(crate) fn set_coverage_breakpoints(
+        &mut self,
+        coverage_config: &CoverageConfig,
+    ) -> Result<(), DebuggerError> {
+        self.clear_matching_breakpoints(|breakpoint| {
+            matches!(
+                breakpoint.breakpoint_type.requested_type(),
+                BreakpointType::CoverageBreakpoint(_)
+            )
+        })?;
+
+        for coverage_line in &coverage_config.lines {
+            let VerifiedBreakpoint {
+                address,
+                source_location,
+            } = self
+                .core_data
+                .debug_info
+                .get_breakpoint_location(
+                    &coverage_line.path,
+                    coverage_line.line,
+                    coverage_line.column,
+                )
+                .map_err(|debug_error| {
+                    DebuggerError::Other(anyhow!(
+                        "Cannot track coverage for {:?}:{}: {debug_error}",
+                        coverage_line.path,
+                        coverage_line.line
+                    ))
+                })?;
+            self.set_breakpoint(
+                address,
+                BreakpointType::CoverageBreakpoint(source_location),
+                None,
+                None,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Set a [`BreakpointType::FunctionTraceBreakpoint`] at the entry of every function in `program_binary` that matches
+    /// the `function_trace_config`, replacing any function trace breakpoints from a previous run.
+    /// The number of functions is capped by `max_functions`, as well as by the number of free breakpoint units.
+    /// Returns the number of functions that were instrumented, and the number that matched.
+    pub(crate) fn set_function_trace_breakpoints(
+        &mut self,
+        function_trace_config: &FunctionTraceConfig,
+        program_binary: &Path,
+    ) -> Result<(usize, usize), DebuggerError> {
+        self.clear_matching_breakpoints(|breakpoint| {
+            matches!(
+                breakpoint.breakpoint_type.requested_type(),
+                BreakpointType::FunctionTraceBreakpoint(_)
+            )
+        })?;
+
+        let trace_functions =
+            function_trace::find_trace_functions(program_binary, &function_trace_config.functions)?;
+        let free_breakpoint_units = (self.available_breakpoint_units()? as usize)
+            .saturating_sub(self.hardware_breakpoints_used());
+        let instrumented_count = trace_functions
+            .len()
+            .min(function_trace_config.max_functions)
+            .min(free_breakpoint_units);
+        for trace_function in trace_functions.iter().take(instrumented_count) {
+            self.set_breakpoint(
+                trace_function.address,
+                BreakpointType::FunctionTraceBreakpoint(trace_function.name.clone()),
+                None,
+                None,
+            )?;
+        }
+        Ok((instrumented_count, trace_functions.len()))
+    }
+
+    /// Set a [`BreakpointType::Catchpoint`] at the entry of every function in `function_names`, replacing any catchpoints from a previous run.
+    /// The functions are found by their (raw or demangled) name in the ELF symbol table.
+    /// Returns the names of the functions that were not found.
+    pub(crate) fn set_catchpoints(
+        &mut self,
+        function_names: &[String],
+    ) -> Result<Vec<String>, DebuggerError> {
+        self.clear_matching_breakpoints(|breakpoint| {
+            matches!(
+                breakpoint.breakpoint_type.requested_type(),
+                BreakpointType::Catchpoint(_)
+            )
+        })?;
+        let mut missing_functions = vec![];
+        for function_name in function_names {
+            match elf_symbols::find_elf_symbol(&self.core_data.elf_symbols, function_name) {
+                Some(symbol) => {
+                    let address = symbol.address;
+                    self.set_breakpoint(
+                        address,
+                        BreakpointType::Catchpoint(function_name.clone()),
+                        None,
+                        None,
+                    )?;
+                }
+                None => missing_functions.push(function_name.clone()),
+            }
+        }
+        Ok(missing_functions)
+    }
+
+    /// The function name of the [`BreakpointType::Catchpoint`] at `program_counter`, if there is one.
+    fn catchpoint_at(&self, program_counter: u64) -> Option<String> {
+        self.core_data.breakpoints.iter().find_map(|breakpoint| {
+            match breakpoint.breakpoint_type.requested_type() {
+                BreakpointType::Catchpoint(function_name)
+                    if breakpoint.address == program_counter =>
+                {
+                    Some(function_name.clone())
+                }
+                _ => None,
+            }
+        })
+    }
+
+    /// Summarize the `hit_count` of every [`BreakpointType::CoverageBreakpoint`].
+    pub(crate) fn coverage_report(&self) -> Vec<CoverageReportEntry> {
+        self.core_data
+            .breakpoints
+            .iter()
+            .filter_map(|breakpoint| {
+                if let BreakpointType::CoverageBreakpoint(source_location) =
+                    breakpoint.breakpoint_type.requested_type()
+                {
+                    Some(CoverageReportEntry {
+                        path: source_location.combined_path().ok(),
+                        line: source_location.line,
+                        address: breakpoint.address,
+                        hit_count: breakpoint.hit_count,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Set a breakpoint at the requested address. If the requested source location is not specific, or
+    /// if the requested address is not a valid breakpoint location,
+    /// the debugger will attempt to find the closest location to the requested location, and set a breakpoint there.
+    /// The Result<> contains the "verified" `address` and `SourceLocation` where the breakpoint that was set, as well as the breakpoint id.
+    pub(crate) fn verify_and_set_breakpoint(
+        &mut self,
+        source_path: &Path,
+        requested_breakpoint_line: u64,
+        requested_breakpoint_column: Option<u64>,
+        requested_source: &Source,
+        condition: Option<String>,
+        hit_condition: Option<String>,
+        log_message: Option<String>,
+    ) -> Result<(VerifiedBreakpoint, i64), DebuggerError> {
+        let VerifiedBreakpoint {
+                 address,
+                 source_location,
+             } = self.core_data
+            .debug_info
+            .get_breakpoint_location(
+                source_path,
+                requested_breakpoint_line,
+                requested_breakpoint_column,
+            )
+            .map_err(|debug_error| match debug_error {
+                DebugError::NoBreakpointLocation { .. } => DebuggerError::NoBreakpointLocation(debug_error),
+                debug_error => DebuggerError::Other(anyhow!("Cannot set breakpoint here. Try reducing compile time-, and link time-, optimization in your build configuration, or choose a different source location: {debug_error}")),
+            })?;
+        let id = self.set_breakpoint(
+            address,
+            BreakpointType::SourceBreakpoint(
+                requested_source.clone(),
+                source_location.clone(),
+                log_message.filter(|log_message| !log_message.trim().is_empty()),
+            ),
+            condition,
+            hit_condition,
+        )?;
+        Ok((
+            VerifiedBreakpoint {
+                address,
+                source_location,
+            },
+            id,
+        ))
+    }
+
+    /// Keep a source breakpoint whose source location is not in the debug information, i.e. [`CoreHandle::verify_and_set_breakpoint`]
+    /// failed with [`DebuggerError::NoBreakpointLocation`], as a [`session_data::PendingBreakpoint`],
+    /// so that it is set when a new binary is flashed. Returns the id of the pending breakpoint.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn set_pending_breakpoint(
+        &mut self,
+        source_path: &Path,
+        requested_breakpoint_line: u64,
+        requested_breakpoint_column: Option<u64>,
+        requested_source: &Source,
+        condition: Option<String>,
+        hit_condition: Option<String>,
+        log_message: Option<String>,
+    ) -> Result<i64, DebuggerError> {
+        let hit_condition = hit_condition.filter(|hit_condition| !hit_condition.trim().is_empty());
+        if let Some(hit_condition) = &hit_condition {
+            // An invalid hit condition would also prevent the breakpoint from being set later.
+            breakpoint_condition::evaluate_hit_condition(hit_condition, 0)?;
+        }
+        let id = self.core_data.next_breakpoint_id;
+        self.core_data
+            .pending_breakpoints
+            .push(session_data::PendingBreakpoint {
+                id,
+                source: requested_source.clone(),
+                source_path: source_path.to_path_buf(),
+                line: requested_breakpoint_line,
+                column: requested_breakpoint_column,
+                condition: condition.filter(|condition| !condition.trim().is_empty()),
+                hit_condition,
+                log_message: log_message.filter(|log_message| !log_message.trim().is_empty()),
+            });
+        self.core_data.next_breakpoint_id += 1;
+        Ok(id)
+    }
+
+    /// Set the [`CoreData::pending_breakpoints`] that can be resolved in the debug information of the current binary, e.g. after a new binary was flashed.
+    /// The breakpoints that still cannot be set remain pending.
+    /// Returns the id, and the verified location, of each breakpoint that was set, so that the debug client can be notified.
+    pub(crate) fn resolve_pending_breakpoints(&mut self) -> Vec<(i64, VerifiedBreakpoint)> {
+        let mut resolved_breakpoints = Vec::new();
+        for pending_breakpoint in std::mem::take(&mut self.core_data.pending_breakpoints) {
+            let set_result = self
+                .core_data
+                .debug_info
+                .get_breakpoint_location(
+                    &pending_breakpoint.source_path,
+                    pending_breakpoint.line,
+                    pending_breakpoint.column,
+                )
+                .map_err(|debug_error| match debug_error {
+                    DebugError::NoBreakpointLocation { .. } => {
+                        DebuggerError::NoBreakpointLocation(debug_error)
+                    }
+                    debug_error => DebuggerError::Other(anyhow!(debug_error)),
+                })
+                .and_then(|verified_breakpoint| {
+                    self.insert_breakpoint(session_data::ActiveBreakpoint {
+                        id: pending_breakpoint.id,
+                        breakpoint_type: BreakpointType::SourceBreakpoint(
+                            pending_breakpoint.source.clone(),
+                            verified_breakpoint.source_location.clone(),
+                            pending_breakpoint.log_message.clone(),
+                        ),
+                        condition: pending_breakpoint.condition.clone(),
+                        hit_condition: pending_breakpoint.hit_condition.clone(),
+                        address: verified_breakpoint.address,
+                        hit_count: 0,
+                        condition_error_reported: false,
+                    })?;
+                    Ok(verified_breakpoint)
+                });
+            match set_result {
+                Ok(verified_breakpoint) => {
+                    resolved_breakpoints.push((pending_breakpoint.id, verified_breakpoint))
+                }
+                Err(error @ DebuggerError::NoBreakpointLocation(_)) => {
+                    tracing::debug!(
+                        "The breakpoint at {:?}:{} is still pending: {error}",
+                        pending_breakpoint.source_path,
+                        pending_breakpoint.line
+                    );
+                    self.core_data.pending_breakpoints.push(pending_breakpoint);
+                }
+                Err(error) => {
+                    // The source location exists, but the breakpoint cannot be set, e.g. because all the hardware breakpoint units are in use.
+                    // Keep it pending, so that it is retried when the next binary is flashed.
+                    tracing::warn!(
+                        "Failed to set the pending breakpoint at {:?}:{}: {error}",
+                        pending_breakpoint.source_path,
+                        pending_breakpoint.line
+                    );
+                    self.core_data.pending_breakpoints.push(pending_breakpoint);
+                }
+            }
+        }
+        resolved_breakpoints
+    }
+
+    /// Save the source breakpoints to the `breakpoints_file`, if [`CoreConfig::persist_breakpoints`] is enabled.
+    pub(crate) fn save_breakpoints(&self) -> Result<(), DebuggerError> {
+        match &self.core_data.breakpoints_file {
+            Some(breakpoints_file) => persisted_breakpoints::write_breakpoints(
+                breakpoints_file,
+                &self.core_data.breakpoints,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Set the source breakpoints that were saved in the `breakpoints_file` by a previous debug session, if [`CoreConfig::persist_breakpoints`] is enabled.
+    /// This is skipped if there are source breakpoints already, e.g. after a restart, because they are more recent than the saved ones.
+    /// Returns the id, location and source of each breakpoint that was set, so that the client can be notified of them,
+    /// and a description of each saved breakpoint that could not be set, e.g. because the source file changed.
+    pub(crate) fn restore_breakpoints(&mut self) -> Result<RestoredBreakpoints, DebuggerError> {
+        let breakpoints_file = match &self.core_data.breakpoints_file {
+            Some(breakpoints_file) => breakpoints_file.clone(),
+            None => return Ok(Default::default()),
+        };
+        if self.core_data.breakpoints.iter().any(|breakpoint| {
+            matches!(
+                breakpoint.breakpoint_type.requested_type(),
+                BreakpointType::SourceBreakpoint(..)
+            )
+        }) {
+            return Ok(Default::default());
+        }
+        let mut restored_breakpoints = Vec::new();
+        let mut failed_breakpoints = Vec::new();
+        for persisted_breakpoint in persisted_breakpoints::read_breakpoints(&breakpoints_file)? {
+            let source = Source {
+                name: persisted_breakpoint.source_name,
+                path: Some(persisted_breakpoint.source_path.clone()),
+                source_reference: None,
+                presentation_hint: None,
+                origin: None,
+                sources: None,
+                adapter_data: None,
+                checksums: None,
+            };
+            match self.verify_and_set_breakpoint(
+                Path::new(&persisted_breakpoint.source_path),
+                persisted_breakpoint.line,
+                persisted_breakpoint.column,
+                &source,
+                persisted_breakpoint.condition,
+                persisted_breakpoint.hit_condition,
+                persisted_breakpoint.log_message,
+            ) {
+                Ok((verified_breakpoint, id)) => {
+                    restored_breakpoints.push((id, verified_breakpoint, source))
+                }
+                Err(error) => failed_breakpoints.push(format!(
+                    "{}:{}: {error}",
+                    persisted_breakpoint.source_path, persisted_breakpoint.line
+                )),
+            }
+        }
+        if !failed_breakpoints.is_empty() {
+            // Remove the breakpoints that no longer resolve from the file.
+            self.save_breakpoints()?;
+        }
+        Ok((restored_breakpoints, failed_breakpoints))
+    }
+
+    /// Set a [`BreakpointType::TemporaryBreakpoint`] at `address`, replacing any previous one.
+    /// It uses a hardware breakpoint unit (or a software breakpoint) like any other breakpoint, which is released when the core halts.
+    /// A halt at the temporary breakpoint is reported with `stop_reason`, e.g. `step` or `goto`.
+    pub(crate) fn set_temporary_breakpoint(
+        &mut self,
+        address: u64,
+        stop_reason: &'static str,
+    ) -> Result<(), DebuggerError> {
+        self.clear_breakpoints(BreakpointType::TemporaryBreakpoint)?;
+        self.set_breakpoint(address, BreakpointType::TemporaryBreakpoint, None, None)?;
+        self.core_data.step_out_stack_pointer = None;
+        self.core_data.temporary_breakpoint_stop_reason = stop_reason;
+        Ok(())
+    }
+
+    /// Set a [`BreakpointType::TemporaryBreakpoint`] at the return address of the function at `program_counter`,
+    /// by unwinding the stack to the calling frame, and remember the stack pointer of that frame in [`CoreData::step_out_stack_pointer`].
+    ///
+    /// Returns the return address, or `None` if the function is inlined, or has no calling frame,
+    /// in which case the caller should fall back to [`SteppingMode::OutOfStatement`].
+    pub(crate) fn set_step_out_breakpoint(
+        &mut self,
+        program_counter: u64,
+    ) -> Result<Option<u64>, DebuggerError> {
+        let stack_frames = self
+            .core_data
+            .debug_info
+            .unwind(&mut self.core, program_counter)?;
+        let calling_frame = match stack_frames.as_slice() {
+            [current_frame, calling_frame, ..] if !current_frame.is_inlined => calling_frame,
+            _ => return Ok(None),
+        };
+        let return_address: u64 = calling_frame.pc.try_into()?;
+        let stack_pointer: Option<u64> = calling_frame
+            .registers
+            .get_stack_pointer()
+            .and_then(|stack_pointer| stack_pointer.value)
+            .and_then(|stack_pointer| stack_pointer.try_into().ok());
+        self.set_temporary_breakpoint(return_address, "step")?;
+        self.core_data.step_out_stack_pointer = stack_pointer;
+        Ok(Some(return_address))
+    }
+
+    /// In the case where a new binary is flashed as part of a restart, we need to recompute the breakpoint address,
+    /// for a specified source location, of any [`super::session_data::BreakpointType::SourceBreakpoint`].
+    /// This is because the address of the breakpoint may have changed based on changes in the source file that created the new binary.
+    /// The `hit_count` of the source and instruction breakpoints is reset, so that hit conditions apply to the restarted session.
+    ///
+    /// To limit the probe transactions on slow links, this is done in three passes: all the source breakpoints are released,
+    /// then their new addresses are computed from the debug info (without accessing the target), and then they are set again.
+    /// A breakpoint that cannot be recomputed, or set, does not prevent the others from being set.
+    /// Returns a description of each breakpoint that failed.
+    pub(crate) fn recompute_breakpoints(&mut self) -> Result<Vec<String>, DebuggerError> {
+        // The location of a temporary breakpoint may no longer be valid, and the client will request it again if needed.
+        self.clear_breakpoints(BreakpointType::TemporaryBreakpoint)?;
+        for breakpoint in self.core_data.breakpoints.iter_mut().filter(|breakpoint| {
+            matches!(
+                breakpoint.breakpoint_type.requested_type(),
+                BreakpointType::SourceBreakpoint(..) | BreakpointType::InstructionBreakpoint
+            )
+        }) {
+            breakpoint.hit_count = 0;
+        }
+
+        // 1. Release the hardware breakpoint units, and software breakpoints, of all the source breakpoints.
+        // Other breakpoints may share an address, in which case it is not released.
+        // This happens before the new binary is flashed, so the original instruction of a software breakpoint is still valid,
+        // and the instructions at the new addresses are saved again by `rewrite_software_breakpoints` after flashing.
+        let (source_breakpoints, other_breakpoints): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.core_data.breakpoints)
+                .into_iter()
+                .partition(|breakpoint| {
+                    matches!(
+                        breakpoint.breakpoint_type.requested_type(),
+                        BreakpointType::SourceBreakpoint(..)
+                    )
+                });
+        self.core_data.breakpoints = other_breakpoints;
+        let mut released_addresses = Vec::new();
+        for breakpoint in &source_breakpoints {
+            if !released_addresses.contains(&breakpoint.address) {
+                released_addresses.push(breakpoint.address);
+                self.release_breakpoint(
+                    breakpoint.address,
+                    breakpoint.breakpoint_type.original_instruction(),
+                )?;
+            }
+        }
+
+        // 2. Compute the new addresses.
+        let mut failed_breakpoints = Vec::new();
+        let mut recomputed_breakpoints = Vec::new();
+        for breakpoint in source_breakpoints {
+            if let BreakpointType::SourceBreakpoint(source, source_location, log_message) =
+                breakpoint.breakpoint_type.requested_type().clone()
+            {
+                match source_location.combined_path().and_then(|requested_path| {
+                    self.core_data.debug_info.get_breakpoint_location(
+                        &requested_path,
+                        source_location.line.unwrap_or(0),
+                        source_location.column.map(|col| match col {
+                            ColumnType::LeftEdge => 0_u64,
+                            ColumnType::Column(c) => c,
+                        }),
+                    )
+                }) {
+                    Ok(VerifiedBreakpoint {
+                        address,
+                        source_location: recomputed_location,
+                    }) => recomputed_breakpoints.push(session_data::ActiveBreakpoint {
+                        // Keep the id that the debug client already uses for this breakpoint.
+                        id: breakpoint.id,
+                        breakpoint_type: BreakpointType::SourceBreakpoint(
+                            source,
+                            recomputed_location,
+                            log_message,
+                        ),
+                        condition: breakpoint.condition,
+                        hit_condition: breakpoint.hit_condition,
+                        address,
+                        hit_count: 0,
+                        condition_error_reported: false,
+                    }),
+                    Err(breakpoint_error) => {
+                        tracing::warn!(
+                            "Failed to recompute breakpoint at {source_location:?} in {source:?}. Error: {breakpoint_error}"
+                        );
+                        failed_breakpoints.push(format!(
+                            "{}: {breakpoint_error}",
+                            source_breakpoint_description(&source_location)
+                        ));
+                    }
+                }
+            }
+        }
+
+        // 3. Set the breakpoints at the new addresses. Breakpoints that share an address also share a hardware breakpoint unit.
+        for breakpoint in recomputed_breakpoints {
+            let description = match breakpoint.breakpoint_type.requested_type() {
+                BreakpointType::SourceBreakpoint(_, source_location, _) => {
+                    source_breakpoint_description(source_location)
+                }
+                _ => format!("{:#010X}", breakpoint.address),
+            };
+            if let Err(breakpoint_error) = self.insert_breakpoint(breakpoint) {
+                tracing::warn!("Failed to set recomputed breakpoint at {description}. Error: {breakpoint_error}");
+                failed_breakpoints.push(format!("{description}: {breakpoint_error}"));
+            }
+        }
+        Ok(failed_breakpoints)
+    }
+}
+
+/// A short description of a source breakpoint location, e.g. `main.rs:42`, for messages to the user.
+fn source_breakpoint_description(source_location: &SourceLocation) -> String {
+    format!(
+        "{}:{}",
+        source_location.file.as_deref().unwrap_or("<unknown file>"),
+        source_location.line.unwrap_or(0)
+    )
+}
+
+/// Format `bytes` as a string of hexadecimal values, e.g. `00 BE`, for the `instructionBytes` of a [`DisassembledInstruction`].
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// The name of the Cortex-M exception with `exception_number`, e.g. `HardFault`, or `IRQ 5` for external interrupts.
+fn exception_name(exception_number: u32) -> String {
+    match exception_number {
+        2 => "NMI".to_string(),
+        3 => "HardFault".to_string(),
+        4 => "MemManage".to_string(),
+        5 => "BusFault".to_string(),
+        6 => "UsageFault".to_string(),
+        7 => "SecureFault".to_string(),
+        11 => "SVCall".to_string(),
+        12 => "DebugMonitor".to_string(),
+        14 => "PendSV".to_string(),
+        15 => "SysTick".to_string(),
+        16.. => format!("IRQ {}", exception_number - 16),
+        _ => format!("reserved ({exception_number})"),
+    }
+}
+
+/// The exception number in the `IPSR` bits `8:0` of `xpsr`, or `None` in thread mode.
+fn ipsr_exception_number(xpsr: u32) -> Option<u32> {
+    let exception_number = xpsr & 0x1FF;
+    (exception_number != 0).then_some(exception_number)
+}
+
+/// What to do after the [`OnStopConfig`] action ran. See [`CoreHandle::run_on_stop_action`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OnStopOutcome {
+    /// Report the halt to the client, as usual.
+    Halt,
+    /// Resume the core, without reporting the halt.
+    Resume,
+    /// The test passed or failed, and the client was notified that the debug session ended, so the halt is not reported.
+    SessionEnded,
+}
+
+/// Bit 2 of `EXC_RETURN` is set if the exception stack frame was stacked on the process stack (PSP), instead of the main stack (MSP).
+fn exception_frame_on_process_stack(exception_return: u32) -> bool {
+    exception_return & 0x4 != 0
+}
+
+/// The stack pointer that is restored when the exception with `exception_return` returns, from the stack frame at `frame_address`.
+fn exception_return_stack_pointer(
+    exception_return: u32,
+    frame_address: u64,
+    stacked_xpsr: u32,
+) -> u64 {
+    // Bit 4 of `EXC_RETURN` is cleared if the floating point context was stacked too.
+    let frame_size = if exception_return & 0x10 == 0 {
+        0x68
+    } else {
+        0x20
+    };
+    // Bit 9 of the stacked xPSR is set if a padding word was stacked, to align the frame to 8 bytes.
+    let alignment_padding = if stacked_xpsr & (1 << 9) != 0 { 4 } else { 0 };
+    frame_address + frame_size + alignment_padding
+}
+
+/// The address range of `memory_region`.
+fn memory_region_range(memory_region: &MemoryRegion) -> &std::ops::Range<u64> {
+    match memory_region {
+        MemoryRegion::Ram(region) => &region.range,
+        MemoryRegion::Generic(region) => &region.range,
+        MemoryRegion::Nvm(region) => &region.range,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_exception_is_read_from_ipsr() {
+        assert_eq!(ipsr_exception_number(0x0100_0000), None);
+        assert_eq!(ipsr_exception_number(0x6100_0003), Some(3));
+        assert_eq!(ipsr_exception_number(0x0100_0025), Some(37));
+    }
+
+    #[test]
+    fn exceptions_are_named() {
+        for (exception_number, expected) in [
+            (2, "NMI"),
+            (3, "HardFault"),
+            (6, "UsageFault"),
+            (8, "reserved (8)"),
+            (11, "SVCall"),
+            (15, "SysTick"),
+            (16, "IRQ 0"),
+            (37, "IRQ 21"),
+        ] {
+            assert_eq!(exception_name(exception_number), expected);
+        }
+    }
+
+    #[test]
+    fn exception_frame_stack_is_selected_by_exc_return() {
+        // Return to thread mode with the process stack, and to thread or handler mode with the main stack.
+        assert!(exception_frame_on_process_stack(0xFFFF_FFFD));
+        assert!(exception_frame_on_process_stack(0xFFFF_FFED));
+        assert!(!exception_frame_on_process_stack(0xFFFF_FFF9));
+        assert!(!exception_frame_on_process_stack(0xFFFF_FFF1));
+    }
+
+    #[test]
+    fn exception_return_stack_pointer_skips_the_stacked_frame() {
+        assert_eq!(
+            exception_return_stack_pointer(0xFFFF_FFFD, 0x2000_0F00, 0x0100_0000),
+            0x2000_0F20
+        );
+        // The floating point context is stacked too.
+        assert_eq!(
+            exception_return_stack_pointer(0xFFFF_FFED, 0x2000_0F00, 0x0100_0000),
+            0x2000_0F68
+        );
+        // The frame was aligned with a padding word.
+        assert_eq!(
+            exception_return_stack_pointer(0xFFFF_FFF9, 0x2000_0F00, 0x0100_0200),
+            0x2000_0F24
+        );
     }
 }
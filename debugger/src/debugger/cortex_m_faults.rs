@@ -0,0 +1,189 @@
+use probe_rs::{Core, CoreType, MemoryInterface};
+
+/// The Configurable Fault Status Register, which combines the `MMFSR` (bits `7:0`), `BFSR` (bits `15:8`) and `UFSR` (bits `31:16`).
+const CFSR_ADDRESS: u64 = 0xE000_ED28;
+/// The HardFault Status Register.
+const HFSR_ADDRESS: u64 = 0xE000_ED2C;
+/// The MemManage Fault Address Register, which is valid if `CFSR.MMARVALID` is set.
+const MMFAR_ADDRESS: u64 = 0xE000_ED34;
+/// The BusFault Address Register, which is valid if `CFSR.BFARVALID` is set.
+const BFAR_ADDRESS: u64 = 0xE000_ED38;
+
+/// The `CFSR` causes that record the fault address, and the bits that indicate if the address is valid.
+const CFSR_DACCVIOL: u32 = 1 << 1;
+const CFSR_MMARVALID: u32 = 1 << 7;
+const CFSR_PRECISERR: u32 = 1 << 9;
+const CFSR_BFARVALID: u32 = 1 << 15;
+/// The `HFSR` bits for a bus fault on a vector table read, and a fault that was escalated to a HardFault.
+const HFSR_VECTTBL: u32 = 1 << 1;
+const HFSR_FORCED: u32 = 1 << 30;
+
+/// The `CFSR` bits, with the fault they belong to, and a description of the cause.
+/// Causes that have a valid fault address in `MMFAR` or `BFAR` are described with the address appended.
+const CFSR_FAULTS: [(u32, &str, &str); 18] = [
+    (1 << 0, "MemManage", "instruction access violation"),
+    (1 << 1, "MemManage", "data access violation"),
+    (
+        1 << 3,
+        "MemManage",
+        "fault on unstacking for a return from exception",
+    ),
+    (1 << 4, "MemManage", "fault on stacking for exception entry"),
+    (
+        1 << 5,
+        "MemManage",
+        "fault during floating-point lazy state preservation",
+    ),
+    (1 << 8, "BusFault", "instruction bus error"),
+    (1 << 9, "BusFault", "precise data bus error"),
+    (1 << 10, "BusFault", "imprecise data bus error"),
+    (
+        1 << 11,
+        "BusFault",
+        "fault on unstacking for a return from exception",
+    ),
+    (1 << 12, "BusFault", "fault on stacking for exception entry"),
+    (
+        1 << 13,
+        "BusFault",
+        "fault during floating-point lazy state preservation",
+    ),
+    (1 << 16, "UsageFault", "undefined instruction"),
+    (
+        1 << 17,
+        "UsageFault",
+        "invalid state (e.g. a branch to an address without the Thumb bit set)",
+    ),
+    (
+        1 << 18,
+        "UsageFault",
+        "invalid PC load on return from exception",
+    ),
+    (
+        1 << 19,
+        "UsageFault",
+        "no coprocessor (e.g. the FPU is not enabled)",
+    ),
+    (1 << 20, "UsageFault", "stack overflow"),
+    (1 << 24, "UsageFault", "unaligned access"),
+    (1 << 25, "UsageFault", "divide by zero"),
+];
+
+/// If the Cortex-M core halted because of a fault, read the fault status registers, and return the name of the fault,
+/// and a description of its causes to show when the core halts, e.g. `BusFault: precise data bus error at 0x20001234`.
+/// The registers are at fixed addresses in the System Control Space, so they are read directly, rather than from the SVD file, which usually does not describe them.
+/// Returns `None` for ARMv6-M cores (which do not implement these registers), if no fault status bits are set, or the registers cannot be read.
+pub(crate) fn fault_description(core: &mut Core) -> Option<(String, String)> {
+    if !matches!(
+        core.core_type(),
+        CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m
+    ) {
+        return None;
+    }
+    let mut read = |address: u64| {
+        core.read_word_32(address)
+            .map_err(|error| tracing::debug!("Failed to read the fault status registers: {error}"))
+            .ok()
+    };
+    let cfsr = read(CFSR_ADDRESS)?;
+    let hfsr = read(HFSR_ADDRESS)?;
+    let mmfar = (cfsr & CFSR_MMARVALID != 0)
+        .then(|| read(MMFAR_ADDRESS))
+        .flatten();
+    let bfar = (cfsr & CFSR_BFARVALID != 0)
+        .then(|| read(BFAR_ADDRESS))
+        .flatten();
+    decode_fault(cfsr, hfsr, mmfar, bfar)
+}
+
+/// Decode the fault status registers into the name of the fault and a description of its causes.
+/// `mmfar` and `bfar` are the fault addresses, if they are valid.
+fn decode_fault(
+    cfsr: u32,
+    hfsr: u32,
+    mmfar: Option<u32>,
+    bfar: Option<u32>,
+) -> Option<(String, String)> {
+    let mut fault_name = None;
+    let mut causes = vec![];
+    for (bit, name, cause) in CFSR_FAULTS {
+        if cfsr & bit == 0 {
+            continue;
+        }
+        fault_name.get_or_insert(name);
+        let fault_address = match bit {
+            CFSR_DACCVIOL => mmfar,
+            CFSR_PRECISERR => bfar,
+            _ => None,
+        };
+        causes.push(match fault_address {
+            Some(address) => format!("{name}: {cause} at {address:#010x}"),
+            None => format!("{name}: {cause}"),
+        });
+    }
+    if hfsr & HFSR_VECTTBL != 0 {
+        fault_name.get_or_insert("HardFault");
+        causes.push("HardFault: bus fault on a vector table read".to_string());
+    }
+    let fault_name = fault_name?;
+    let mut summary = causes.join(", ");
+    if hfsr & HFSR_FORCED != 0 && fault_name != "HardFault" {
+        summary.push_str(" (escalated to HardFault)");
+    }
+    Some((fault_name.to_string(), summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_fault;
+
+    #[test]
+    fn fault_status_registers_are_decoded() {
+        for (cfsr, hfsr, mmfar, bfar, expected) in [
+            (0, 0, None, None, None),
+            (
+                0x0000_0082,
+                0,
+                Some(0x2000_1000),
+                None,
+                Some((
+                    "MemManage",
+                    "MemManage: data access violation at 0x20001000",
+                )),
+            ),
+            (
+                0x0000_8200,
+                0x4000_0000,
+                None,
+                Some(0x2000_1234),
+                Some((
+                    "BusFault",
+                    "BusFault: precise data bus error at 0x20001234 (escalated to HardFault)",
+                )),
+            ),
+            (
+                0x0200_0001,
+                0,
+                None,
+                None,
+                Some((
+                    "MemManage",
+                    "MemManage: instruction access violation, UsageFault: divide by zero",
+                )),
+            ),
+            (
+                0,
+                0x4000_0002,
+                None,
+                None,
+                Some(("HardFault", "HardFault: bus fault on a vector table read")),
+            ),
+        ] {
+            assert_eq!(
+                decode_fault(cfsr, hfsr, mmfar, bfar),
+                expected.map(|(name, summary)| (name.to_string(), summary.to_string())),
+                "CFSR {cfsr:#010x}, HFSR {hfsr:#010x}"
+            );
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{debug_adapter::dap_types::DataBreakpointAccessType, DebuggerError};
+use anyhow::anyhow;
+use parse_int::parse;
+use probe_rs::{Core, CoreType, MemoryInterface};
+
+/// The Cortex-M `DEMCR` register, with the `TRCENA` bit that enables the DWT unit.
+const DEMCR_ADDRESS: u64 = 0xE000_EDFC;
+const DEMCR_TRCENA: u32 = 1 << 24;
+/// The `DWT_CTRL` register, with the number of comparators in bits `31:28`.
+const DWT_CTRL_ADDRESS: u64 = 0xE000_1000;
+/// The `DWT_COMP0` register. The `DWT_MASKn` and `DWT_FUNCTIONn` registers follow it, and every comparator uses 16 bytes.
+const DWT_COMP0_ADDRESS: u64 = 0xE000_1020;
+const DWT_COMPARATOR_STRIDE: u64 = 0x10;
+/// The `MATCHED` bit of `DWT_FUNCTIONn`, which is set when the comparator matched, and cleared when the register is read.
+const DWT_FUNCTION_MATCHED: u32 = 1 << 24;
+
+/// The access to a memory location that triggers a data watchpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl From<DataBreakpointAccessType> for WatchKind {
+    fn from(access_type: DataBreakpointAccessType) -> Self {
+        match access_type {
+            DataBreakpointAccessType::Read => WatchKind::Read,
+            DataBreakpointAccessType::Write => WatchKind::Write,
+            DataBreakpointAccessType::ReadWrite => WatchKind::ReadWrite,
+        }
+    }
+}
+
+/// A data watchpoint that is programmed into a DWT comparator.
+#[derive(Clone, Debug)]
+pub(crate) struct ActiveDataWatchpoint {
+    /// The id that identifies this data watchpoint to the debug client. It is unique across all breakpoint types.
+    pub(crate) id: i64,
+    pub(crate) address: u64,
+    /// The index of the DWT comparator that is used by this watchpoint.
+    pub(crate) comparator: usize,
+}
+
+/// Parse a `dataId`, as it is returned in the response to the `dataBreakpointInfo` request, into the address and size of the data.
+/// The format is `<address>/<size>`, e.g. `0x20000100/4`.
+pub(crate) fn parse_data_id(data_id: &str) -> Result<(u64, u8), DebuggerError> {
+    data_id
+        .split_once('/')
+        .and_then(|(address, size)| Some((parse::<u64>(address).ok()?, size.parse::<u8>().ok()?)))
+        .ok_or_else(|| {
+            DebuggerError::Other(anyhow!(
+                "Invalid data breakpoint id {data_id:?}, expected `<address>/<size>`"
+            ))
+        })
+}
+
+/// The number of DWT comparators that are implemented by the core.
+pub(crate) fn comparator_count(core: &mut Core) -> Result<usize, DebuggerError> {
+    if !core.core_type().is_cortex_m() {
+        return Err(DebuggerError::Other(anyhow!(
+            "Data watchpoints are only supported on Cortex-M cores"
+        )));
+    }
+    enable_dwt(core)?;
+    Ok((core.read_word_32(DWT_CTRL_ADDRESS)? >> 28) as usize)
+}
+
+/// Program the DWT `comparator` to halt the core when `size` bytes at `address` are accessed.
+/// The `address` has to be aligned to the `size`, which is 1, 2 or 4 bytes.
+pub(crate) fn program_comparator(
+    core: &mut Core,
+    comparator: usize,
+    address: u64,
+    size: u8,
+    access: WatchKind,
+) -> Result<(), DebuggerError> {
+    let (mask, function) = comparator_encoding(core.core_type(), address, size, access)?;
+    let comparator_address = DWT_COMP0_ADDRESS + comparator as u64 * DWT_COMPARATOR_STRIDE;
+    if let Some(mask) = mask {
+        core.write_word_32(comparator_address + 4, mask)?;
+    }
+    core.write_word_32(comparator_address, address as u32)?;
+    core.write_word_32(comparator_address + 8, function)?;
+    Ok(())
+}
+
+/// The values of `DWT_MASKn` (if the core uses it) and `DWT_FUNCTIONn` that watch `size` bytes at `address` for `access`.
+fn comparator_encoding(
+    core_type: CoreType,
+    address: u64,
+    size: u8,
+    access: WatchKind,
+) -> Result<(Option<u32>, u32), DebuggerError> {
+    if !matches!(size, 1 | 2 | 4) || address % size as u64 != 0 {
+        return Err(DebuggerError::Other(anyhow!(
+            "Data watchpoints require a size of 1, 2 or 4 bytes, at an aligned address, not {size} bytes at {address:#010x}"
+        )));
+    }
+    let size_bits = size.trailing_zeros();
+    if core_type == CoreType::Armv8m {
+        // ARMv8-M: MATCH in bits 3:0, ACTION (0b01 = debug event) in bits 5:4, and DATAVSIZE in bits 11:10.
+        let match_type = match access {
+            WatchKind::ReadWrite => 0b0100,
+            WatchKind::Write => 0b0101,
+            WatchKind::Read => 0b0110,
+        };
+        Ok((None, match_type | (0b01 << 4) | (size_bits << 10)))
+    } else {
+        // ARMv6-M and ARMv7-M: FUNCTION in bits 3:0, with the size of the address range in `DWT_MASKn`.
+        let function = match access {
+            WatchKind::Read => 0b0101,
+            WatchKind::Write => 0b0110,
+            WatchKind::ReadWrite => 0b0111,
+        };
+        Ok((Some(size_bits), function))
+    }
+}
+
+/// Disable the DWT `comparator`.
+pub(crate) fn clear_comparator(core: &mut Core, comparator: usize) -> Result<(), DebuggerError> {
+    let comparator_address = DWT_COMP0_ADDRESS + comparator as u64 * DWT_COMPARATOR_STRIDE;
+    core.write_word_32(comparator_address + 8, 0)?;
+    Ok(())
+}
+
+/// The comparators, of those in `comparators`, that matched since the last time they were checked.
+pub(crate) fn matched_comparators(core: &mut Core, comparators: &[usize]) -> Vec<usize> {
+    comparators
+        .iter()
+        .copied()
+        .filter(|comparator| {
+            core.read_word_32(DWT_COMP0_ADDRESS + *comparator as u64 * DWT_COMPARATOR_STRIDE + 8)
+                .map_or(false, |function| function & DWT_FUNCTION_MATCHED != 0)
+        })
+        .collect()
+}
+
+/// Set `DEMCR.TRCENA`, which is required to access the DWT registers.
+fn enable_dwt(core: &mut Core) -> Result<(), DebuggerError> {
+    let demcr = core.read_word_32(DEMCR_ADDRESS)?;
+    if demcr & DEMCR_TRCENA == 0 {
+        core.write_word_32(DEMCR_ADDRESS, demcr | DEMCR_TRCENA)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{comparator_encoding, parse_data_id, WatchKind};
+    use probe_rs::CoreType;
+
+    #[test]
+    fn data_ids_are_parsed() {
+        assert_eq!(parse_data_id("0x20000100/4").unwrap(), (0x2000_0100, 4));
+        assert_eq!(parse_data_id("536871168/1").unwrap(), (0x2000_0100, 1));
+        assert!(parse_data_id("0x20000100").is_err());
+        assert!(parse_data_id("0x20000100/x").is_err());
+        assert!(parse_data_id("counter/4").is_err());
+    }
+
+    #[test]
+    fn comparators_are_encoded_for_the_core_type() {
+        for (core_type, address, size, access, expected) in [
+            (
+                CoreType::Armv7m,
+                0x2000_0100,
+                4,
+                WatchKind::Read,
+                (Some(2), 0b0101),
+            ),
+            (
+                CoreType::Armv7em,
+                0x2000_0102,
+                2,
+                WatchKind::Write,
+                (Some(1), 0b0110),
+            ),
+            (
+                CoreType::Armv6m,
+                0x2000_0101,
+                1,
+                WatchKind::ReadWrite,
+                (Some(0), 0b0111),
+            ),
+            (
+                CoreType::Armv8m,
+                0x2000_0100,
+                4,
+                WatchKind::ReadWrite,
+                (None, 0x814),
+            ),
+            (
+                CoreType::Armv8m,
+                0x2000_0102,
+                2,
+                WatchKind::Write,
+                (None, 0x415),
+            ),
+            (
+                CoreType::Armv8m,
+                0x2000_0101,
+                1,
+                WatchKind::Read,
+                (None, 0x016),
+            ),
+        ] {
+            assert_eq!(
+                comparator_encoding(core_type, address, size, access).unwrap(),
+                expected,
+                "{core_type:?} {size} bytes {access:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unaligned_or_unsupported_sizes_are_rejected() {
+        assert!(comparator_encoding(CoreType::Armv7m, 0x2000_0102, 4, WatchKind::Read).is_err());
+        assert!(comparator_encoding(CoreType::Armv8m, 0x2000_0101, 2, WatchKind::Write).is_err());
+        assert!(comparator_encoding(CoreType::Armv7m, 0x2000_0100, 8, WatchKind::Read).is_err());
+    }
+}
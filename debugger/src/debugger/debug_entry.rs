@@ -1,4 +1,4 @@
-use super::session_data;
+use super::{core_data::CoreData, session_data};
 use crate::{
     debug_adapter::{
         dap_adapter::*,
@@ -12,7 +12,7 @@ use crate::{
 use anyhow::{anyhow, Context, Result};
 use probe_rs::{
     flashing::{download_file_with_options, DownloadOptions, FlashProgress, Format},
-    Architecture, CoreStatus, Probe,
+    Architecture, CoreStatus, HaltReason, Probe,
 };
 use serde::Deserialize;
 use std::{
@@ -94,6 +94,16 @@ impl Debugger {
         debug_adapter: &mut DebugAdapter<P>,
     ) -> Result<DebugSessionStatus, DebuggerError> {
         match debug_adapter.listen_for_request()? {
+            // The health check is handled before the cores are polled, so that it only uses a single transaction with the probe.
+            Some(request) if request.command == "healthCheck" => {
+                let core_index = self
+                    .config
+                    .core_configs
+                    .first()
+                    .map_or(0, |core_config| core_config.core_index);
+                debug_adapter.health_check(session_data, core_index, request)?;
+                Ok(DebugSessionStatus::Continue)
+            }
             None => {
                 if debug_adapter.all_cores_halted {
                     // Once all cores are halted, then we can skip polling the core for status, and just wait for the next DAP Client request.
@@ -118,11 +128,33 @@ impl Debugger {
 
                 Ok(DebugSessionStatus::Continue)
             }
+            Some(request) if request.command == "pauseAll" => {
+                let core_indexes = self
+                    .config
+                    .core_configs
+                    .iter()
+                    .map(|core_config| core_config.core_index)
+                    .collect::<Vec<_>>();
+                debug_adapter.pause_all(session_data, &core_indexes, request)?;
+                Ok(DebugSessionStatus::Continue)
+            }
             Some(request) => {
+                let configuration_was_done = debug_adapter.configuration_is_done();
                 // Poll ALL target cores for status, which includes synching status with the DAP client, and handling RTT data.
                 let (core_statuses, _) = session_data.poll_cores(&self.config, debug_adapter)?;
-                // TODO: Currently, we only use `poll_cores()` results from the first core and need to expand to a multi-core implementation that understands which MS DAP requests are core specific.
-                if let (core_id, Some(new_status)) = (0_usize, core_statuses.first().cloned()) {
+                // TODO: Expand the multi-core implementation beyond the requests that reference a thread, stack frame or variable, e.g. to breakpoints.
+                let core_id = requested_core_position(
+                    &request,
+                    &self.config.core_configs,
+                    &session_data.core_data,
+                );
+                let new_status = self
+                    .config
+                    .core_configs
+                    .get(core_id)
+                    .and_then(|core_config| core_statuses.get(&core_config.core_index))
+                    .copied();
+                let debug_session_status = if let Some(new_status) = new_status {
                     // Attach to the core. so that we have the handle available for processing the request.
                     let mut target_core = if let Some(target_core_config) =
                         self.config.core_configs.get_mut(core_id)
@@ -152,6 +184,7 @@ impl Debugger {
                         | "setBreakpoint"
                         | "setBreakpoints"
                         | "setInstructionBreakpoints"
+                        | "setDataBreakpoints"
                         | "clearBreakpoint"
                         | "stackTrace"
                         | "threads"
@@ -159,7 +192,14 @@ impl Debugger {
                         | "variables"
                         | "readMemory"
                         | "writeMemory"
-                        | "disassemble" => {
+                        | "disassemble"
+                        | "resetCause"
+                        | "deviceInfo"
+                        | "stackUsage"
+                        | "listBreakpoints"
+                        | "riscvCsr"
+                        | "dumpMemory"
+                        | "saveCoreDump" => {
                             if new_status == CoreStatus::Sleeping {
                                 match target_core.core.halt(Duration::from_millis(100)) {
                                     Ok(_) => {
@@ -196,7 +236,9 @@ impl Debugger {
                                             .map_or(false, |rtt_channel| {
                                                 rtt_channel.has_client_window =
                                                     arguments.window_is_open;
+                                                // Replay the output that was kept while the window was not open yet.
                                                 arguments.window_is_open
+                                                    && rtt_channel.flush_pending_data(debug_adapter)
                                             });
                                         debug_adapter.send_response::<()>(request, Ok(None))?;
                                     }
@@ -213,9 +255,19 @@ impl Debugger {
                             }
                             Ok(DebugSessionStatus::Continue)
                         }
-                        "disconnect" => debug_adapter
-                            .disconnect(&mut target_core, request)
-                            .and(Ok(DebugSessionStatus::Terminate)),
+                        "disconnect" => {
+                            if let Some(coverage_config) = self
+                                .config
+                                .core_configs
+                                .get(core_id)
+                                .and_then(|core_config| core_config.coverage.as_ref())
+                            {
+                                debug_adapter.report_coverage(&target_core, coverage_config);
+                            }
+                            debug_adapter
+                                .disconnect(&mut target_core, request)
+                                .and(Ok(DebugSessionStatus::Terminate))
+                        }
                         "next" => debug_adapter
                             .next(&mut target_core, request)
                             .and(Ok(DebugSessionStatus::Continue)),
@@ -243,23 +295,50 @@ impl Debugger {
                         "threads" => debug_adapter
                             .threads(&mut target_core, request)
                             .and(Ok(DebugSessionStatus::Continue)),
+                        "modules" => debug_adapter
+                            .modules(&self.config, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
                         "restart" => {
-                            // Reset RTT so that the link can be re-established
-                            if target_core.core.architecture() == Architecture::Riscv {
+                            let reset_only = get_arguments::<RestartModeArguments>(&request)
+                                .map_or(false, |arguments| arguments.reset_only);
+                            if reset_only {
+                                // The binary is unchanged, so the breakpoints are still at the correct addresses, and don't have to be recomputed.
+                                // Resetting RTT will re-attach to the control block, once the target has initialized it again.
+                                target_core.detach_rtt(debug_adapter);
+                                debug_adapter
+                                    .restart(&mut target_core, Some(request))
+                                    .and(Ok(DebugSessionStatus::Continue))
+                            } else if target_core.core.architecture() == Architecture::Riscv {
                                 debug_adapter.show_message(
                                     MessageSeverity::Information,
                                     "In-session `restart` is not currently supported for RISC-V.",
                                 );
                                 Ok(DebugSessionStatus::Continue)
                             } else {
-                                target_core.core_data.rtt_connection = None;
+                                // Reset RTT so that the link can be re-established
+                                target_core.detach_rtt(debug_adapter);
                                 target_core
                                     .core
                                     .halt(Duration::from_millis(500))
                                     .map_err(|error| anyhow!("Failed to halt core: {}", error))
+                                    .and_then(|_| {
+                                        // The breakpoints are set again after the binary is flashed, and the debug session is restarted.
+                                        target_core
+                                            .restore_software_breakpoints()
+                                            .map_err(|error| anyhow!("{error}"))
+                                    })
                                     .and(Ok(DebugSessionStatus::Restart(request)))
                             }
                         }
+                        "breakpointLocations" => debug_adapter
+                            .breakpoint_locations(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "dataBreakpointInfo" => debug_adapter
+                            .data_breakpoint_info(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "setDataBreakpoints" => debug_adapter
+                            .set_data_breakpoints(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
                         "setBreakpoints" => debug_adapter
                             .set_breakpoints(&mut target_core, request)
                             .and(Ok(DebugSessionStatus::Continue)),
@@ -281,9 +360,57 @@ impl Debugger {
                         "continue" => debug_adapter
                             .r#continue(&mut target_core, request)
                             .and(Ok(DebugSessionStatus::Continue)),
+                        "gotoTargets" => debug_adapter
+                            .goto_targets(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "goto" => debug_adapter
+                            .goto(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
                         "evaluate" => debug_adapter
                             .evaluate(&mut target_core, request)
                             .and(Ok(DebugSessionStatus::Continue)),
+                        "resetCause" => debug_adapter
+                            .reset_cause(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "stepUntil" => debug_adapter
+                            .step_until(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "stackUsage" => debug_adapter
+                            .stack_usage(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "deviceInfo" => debug_adapter
+                            .device_info(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "listBreakpoints" => debug_adapter
+                            .list_breakpoints(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "riscvCsr" => debug_adapter
+                            .riscv_csr(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "rttWrite" => debug_adapter
+                            .rtt_write(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "rttChannels" => debug_adapter
+                            .rtt_channels(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "rttDefmtFilter" => debug_adapter
+                            .rtt_defmt_filter(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "dumpMemory" => debug_adapter
+                            .dump_memory(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "saveCoreDump" => debug_adapter
+                            .save_core_dump(&mut target_core, request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "setVariablesFilter" => debug_adapter
+                            .set_variables_filter(request)
+                            .and(Ok(DebugSessionStatus::Continue)),
+                        "cancel" => {
+                            // Long running requests check for cancellation while they execute, so any `cancel` request that arrives here has nothing left to cancel.
+                            debug_adapter
+                                .send_response::<()>(request, Ok(None))
+                                .and(Ok(DebugSessionStatus::Continue))
+                        }
                         other_command => {
                             // Unimplemented command.
                             debug_adapter.send_response::<()>(
@@ -294,7 +421,10 @@ impl Debugger {
                     } {
                         Ok(debug_session_status) => {
                             if unhalt_me {
-                                match target_core.core.run() {
+                                match target_core
+                                    .step_over_software_breakpoint()
+                                    .and_then(|_| target_core.core.run())
+                                {
                                     Ok(_) => {}
                                     Err(error) => {
                                         debug_adapter.send_error_response(
@@ -316,13 +446,43 @@ impl Debugger {
                 } else {
                     // Keep processing "configuration" requests until we've passed `configuration_done` and have a valid `target_core`.
                     Ok(DebugSessionStatus::Continue)
+                };
+                if !configuration_was_done && debug_adapter.configuration_is_done() {
+                    self.resume_other_cores(session_data, debug_adapter)?;
                 }
+                debug_session_status
             }
         }
 
         // Now we can process the next (if any) DAP request.
     }
 
+    /// The initial `threads` request resumes the first core, unless the core halts after the reset, or the debugger attached to the target. See [`DebugAdapter::threads`].
+    /// The other cores were halted when they were initialized, and are resumed in the same way, unless they halted at a breakpoint.
+    fn resume_other_cores<P: ProtocolAdapter>(
+        &self,
+        session_data: &mut session_data::SessionData,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> Result<(), DebuggerError> {
+        if debug_adapter.halt_after_reset
+            || debug_adapter.target_session_type == Some(TargetSessionType::AttachRequest)
+        {
+            return Ok(());
+        }
+        for core_config in self.config.core_configs.iter().skip(1) {
+            let mut target_core = session_data.attach_core(core_config.core_index)?;
+            let core_status = target_core.core.status()?;
+            if core_status.is_halted()
+                && !matches!(core_status, CoreStatus::Halted(HaltReason::Breakpoint(_)))
+            {
+                target_core.step_over_software_breakpoint()?;
+                target_core.core.run()?;
+                target_core.reset_core_status(debug_adapter);
+            }
+        }
+        Ok(())
+    }
+
     /// `debug_session` is where the primary _debug processing_ for the DAP (Debug Adapter Protocol) adapter happens.
     /// All requests are interpreted, actions taken, and responses formulated here. This function is self contained and returns nothing.
     /// The [`DebugAdapter`] takes care of _implementing the DAP Base Protocol_ and _communicating with the DAP client_ and _probe_.
@@ -392,6 +552,10 @@ impl Debugger {
             debug_adapter.supports_progress_reporting = progress_support;
         }
 
+        if let Some(invalidated_event_support) = initialize_arguments.supports_invalidated_event {
+            debug_adapter.supports_invalidated_event = invalidated_event_support;
+        }
+
         if let Some(lines_start_at_1) = initialize_arguments.lines_start_at_1 {
             debug_adapter.lines_start_at_1 = lines_start_at_1;
         }
@@ -412,9 +576,17 @@ impl Debugger {
             supports_set_variable: Some(true),
             supports_clipboard_context: Some(true),
             supports_disassemble_request: Some(true),
+            supports_modules_request: Some(true),
+            supports_goto_targets_request: Some(true),
             supports_instruction_breakpoints: Some(true),
+            supports_conditional_breakpoints: Some(true),
+            supports_hit_conditional_breakpoints: Some(true),
+            supports_log_points: Some(true),
+            supports_data_breakpoints: Some(true),
+            supports_breakpoint_locations_request: Some(true),
             supports_stepping_granularity: Some(true),
-            // supports_value_formatting_options: Some(true),
+            supports_cancel_request: Some(true),
+            supports_value_formatting_options: Some(true),
             // supports_function_breakpoints: Some(true),
             // TODO: Use DEMCR register to implement exception breakpoints
             // supports_exception_options: Some(true),
@@ -465,15 +637,16 @@ impl Debugger {
                             || self.config.flashing_config.halt_after_reset
                             || self.config.flashing_config.full_chip_erase
                             || self.config.flashing_config.restore_unwritten_bytes
+                            || self.config.reset_mode.is_some()
                         {
                             debug_adapter.send_response::<()>(
                                         launch_attach_request,
                                         Err(DebuggerError::Other(anyhow!(
-                                            "Please do not use any of the `flashing_enabled`, `reset_after_flashing`, halt_after_reset`, `full_chip_erase`, or `restore_unwritten_bytes` options when using `attach` request type."))),
+                                            "Please do not use any of the `flashing_enabled`, `reset_after_flashing`, halt_after_reset`, `full_chip_erase`, `restore_unwritten_bytes`, or `reset_mode` options when using `attach` request type."))),
                                     )?;
 
                             return Err(DebuggerError::Other(anyhow!(
-                                            "Please do not use any of the `flashing_enabled`, `reset_after_flashing`, halt_after_reset`, `full_chip_erase`, or `restore_unwritten_bytes` options when using `attach` request type.")));
+                                            "Please do not use any of the `flashing_enabled`, `reset_after_flashing`, halt_after_reset`, `full_chip_erase`, `restore_unwritten_bytes`, or `reset_mode` options when using `attach` request type.")));
                         }
                     }
                 }
@@ -519,13 +692,68 @@ impl Debugger {
                 }
             };
 
-        debug_adapter.halt_after_reset = self.config.flashing_config.halt_after_reset;
+        // The stack frames of the core dump were unwound when it was saved, with the debug information that was loaded at the time.
+        if let Some(stack_frames) = session_data.replayed_stack_frames() {
+            debug_adapter.log_to_console("Replaying a core dump. The saved stack frames are:");
+            for stack_frame in stack_frames {
+                debug_adapter.log_to_console(format!(
+                    "  {:#010x} {} {}",
+                    stack_frame.pc,
+                    stack_frame.function_name,
+                    stack_frame.source_location.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+
+        // Report the speed that the probe actually uses, because probes may not support the requested speed.
+        if let (Some(requested_speed), Some(probe_speed)) =
+            (self.config.speed, session_data.probe_speed)
+        {
+            if probe_speed == requested_speed {
+                debug_adapter.log_to_console(format!("Protocol speed: {probe_speed} kHz"));
+            } else {
+                debug_adapter.show_message(
+                    MessageSeverity::Warning,
+                    format!("The probe does not support the requested protocol speed of {requested_speed} kHz, and uses {probe_speed} kHz instead."),
+                );
+            }
+        }
+
+        // When flashing is enabled, the flash contents are replaced anyway, and `verifyAfterFlashing` applies instead.
+        if self.config.verify && !self.config.flashing_config.flashing_enabled {
+            for program_binary in self
+                .config
+                .core_configs
+                .iter()
+                .filter_map(|core_config| core_config.program_binary.as_ref())
+            {
+                debug_adapter.log_to_console(format!(
+                    "Verifying that the flash contents match {program_binary:?}"
+                ));
+                if let Err(error) = session_data.verify_program_binary(program_binary) {
+                    debug_adapter.send_error_response(&error)?;
+                    return Err(error);
+                }
+            }
+        }
+
+        debug_adapter.halt_after_reset = match self.config.reset_mode {
+            Some(reset_mode) => reset_mode == configuration::ResetMode::ResetAndHalt,
+            None => self.config.flashing_config.halt_after_reset,
+        };
+        // The replayed core of a core dump can not run, so it stays halted where the dump was saved.
+        if self.config.core_dump.is_some() {
+            debug_adapter.halt_after_reset = true;
+        }
+        debug_adapter.variables_filter = self.config.variables_filter.clone();
 
         // This loops allows us to restart the debug session if the user requests it.
         // We maintain everything that happened up to the launch/attach request,
         // because DAP/VSCode doesn't repeat the those requests on a restart.
         let mut debug_session_status = DebugSessionStatus::New(launch_attach_request);
-        let mut saved_binary_timestamp: Option<Duration> = None;
+        // The timestamp of the program binary of each core, by its position in `core_configs`.
+        let mut saved_binary_timestamps: Vec<Option<Duration>> =
+            vec![None; self.config.core_configs.len()];
 
         while let Some(session_request) = match debug_session_status.clone() {
             DebugSessionStatus::New(request) => Some(request),
@@ -533,18 +761,24 @@ impl Debugger {
             DebugSessionStatus::Terminate => None,
             DebugSessionStatus::Restart(request) => Some(request),
         } {
-            // TODO: Currently the logic of processing MS DAP requests and executing them, is based on having a single core. It needs to be re-thought for multiple cores. Not all DAP requests require access to the core. One possible is to do the core attach inside each of the request implementations for those that need it, because the applicable core_index can be read from the request arguments.
-            // TODO: Until we refactor this, we only support a single core (always the first one specified in `SessionConfig::core_configs`)
-            let target_core_config =
-                if let Some(target_core_config) = self.config.core_configs.first_mut() {
-                    target_core_config
-                } else {
-                    return Err(DebuggerError::Other(anyhow!(
-                        "Cannot continue unless one target core configuration is defined."
-                    )));
-                };
+            if self.config.core_configs.is_empty() {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Cannot continue unless one target core configuration is defined."
+                )));
+            }
+
+            // Every configured core is flashed with its program binary, and initialized, in the order of `core_configs`.
+            for core_position in 0..self.config.core_configs.len() {
+                // Cores that share a program binary (e.g. the two cores of an RP2040) only flash it once.
+                let flashed_by_previous_core = self.config.core_configs[..core_position]
+                    .iter()
+                    .any(|previous_core_config| {
+                        previous_core_config.program_binary.is_some()
+                            && previous_core_config.program_binary
+                                == self.config.core_configs[core_position].program_binary
+                    });
+                let target_core_config = &mut self.config.core_configs[core_position];
 
-            {
                 if self.config.flashing_config.flashing_enabled {
                     let path_to_elf = match &target_core_config.program_binary {
                         Some(program_binary) => program_binary,
@@ -557,59 +791,84 @@ impl Debugger {
                         }
                     };
 
-                    if is_file_newer(&mut saved_binary_timestamp, path_to_elf) {
+                    if is_file_newer(&mut saved_binary_timestamps[core_position], path_to_elf) {
                         // If there is a new binary as part of a restart, there are some key things that
                         // need to be 'reset' for things to work properly.
                         if session_request.command == "restart" {
                             session_data.load_debug_info_for_core(target_core_config)?;
-                            session_data
-                                .attach_core(target_core_config.core_index)
-                                .map(|mut target_core| target_core.recompute_breakpoints())??;
+                            let failed_breakpoints = {
+                                let mut target_core =
+                                    session_data.attach_core(target_core_config.core_index)?;
+                                let failed_breakpoints = target_core.recompute_breakpoints()?;
+                                // Breakpoints that could not be set in the previous binary may be in this one.
+                                for (id, verified_breakpoint) in
+                                    target_core.resolve_pending_breakpoints()
+                                {
+                                    debug_adapter
+                                        .send_breakpoint_verified_event(id, &verified_breakpoint)?;
+                                }
+                                failed_breakpoints
+                            };
+                            if !failed_breakpoints.is_empty() {
+                                debug_adapter.show_message(
+                                    MessageSeverity::Warning,
+                                    format!(
+                                        "The following breakpoints could not be set in the new binary:\n{}",
+                                        failed_breakpoints.join("\n")
+                                    ),
+                                );
+                            }
                         }
 
                         // Do the flashing.
-                        // TODO: Multi-core ... needs to flash multiple binaries
-
-                        debug_adapter.log_to_console(format!(
-                            "FLASHING: Starting write of {path_to_elf:?} to device memory"
-                        ));
-
-                        let progress_id = debug_adapter
-                            .start_progress("Flashing device", Some(session_request.seq))
-                            .ok();
-
-                        let mut download_options = DownloadOptions::default();
-                        download_options.keep_unwritten_bytes =
-                            self.config.flashing_config.restore_unwritten_bytes;
-                        download_options.do_chip_erase =
-                            self.config.flashing_config.full_chip_erase;
-                        let flash_result = {
-                            let rc_debug_adapter = Rc::new(RefCell::new(debug_adapter));
-                            let rc_debug_adapter_clone = rc_debug_adapter.clone();
+                        if !flashed_by_previous_core {
+                            debug_adapter.log_to_console(format!(
+                                "FLASHING: Starting write of {path_to_elf:?} to device memory"
+                            ));
+
+                            let progress_id = debug_adapter
+                                .start_progress("Flashing device", Some(session_request.seq))
+                                .ok();
+
+                            let mut download_options = DownloadOptions::default();
+                            download_options.keep_unwritten_bytes =
+                                self.config.flashing_config.restore_unwritten_bytes;
+                            // A chip erase would also erase the program binaries that were flashed for the previous cores.
+                            download_options.do_chip_erase =
+                                self.config.flashing_config.full_chip_erase && core_position == 0;
+                            download_options.flash_algorithm_ram_address =
+                                self.config.flashing_config.flash_algorithm_ram_address;
+                            let verify_after_flashing =
+                                self.config.flashing_config.verify_after_flashing;
+                            download_options.verify = verify_after_flashing;
                             let flash_result = {
-                                struct ProgressState {
-                                    total_page_size: usize,
-                                    total_sector_size: usize,
-                                    total_fill_size: usize,
-                                    page_size_done: usize,
-                                    sector_size_done: usize,
-                                    fill_size_done: usize,
-                                }
+                                let rc_debug_adapter = Rc::new(RefCell::new(debug_adapter));
+                                let rc_debug_adapter_clone = rc_debug_adapter.clone();
+                                let flash_result = {
+                                    struct ProgressState {
+                                        total_page_size: usize,
+                                        total_sector_size: usize,
+                                        total_fill_size: usize,
+                                        page_size_done: usize,
+                                        sector_size_done: usize,
+                                        fill_size_done: usize,
+                                    }
 
-                                let flash_progress = Rc::new(RefCell::new(ProgressState {
-                                    total_page_size: 0,
-                                    total_sector_size: 0,
-                                    total_fill_size: 0,
-                                    page_size_done: 0,
-                                    sector_size_done: 0,
-                                    fill_size_done: 0,
-                                }));
-
-                                let flash_progress = if let Some(id) = progress_id {
-                                    FlashProgress::new(move |event| {
-                                        let mut flash_progress = flash_progress.borrow_mut();
-                                        let mut debug_adapter = rc_debug_adapter_clone.borrow_mut();
-                                        match event {
+                                    let flash_progress = Rc::new(RefCell::new(ProgressState {
+                                        total_page_size: 0,
+                                        total_sector_size: 0,
+                                        total_fill_size: 0,
+                                        page_size_done: 0,
+                                        sector_size_done: 0,
+                                        fill_size_done: 0,
+                                    }));
+
+                                    let flash_progress = if let Some(id) = progress_id {
+                                        FlashProgress::new(move |event| {
+                                            let mut flash_progress = flash_progress.borrow_mut();
+                                            let mut debug_adapter =
+                                                rc_debug_adapter_clone.borrow_mut();
+                                            match event {
                                         probe_rs::flashing::ProgressEvent::Initialized {
                                             flash_layout,
                                         } => {
@@ -650,7 +909,10 @@ impl Debugger {
                                             debug_adapter
                                                 .update_progress(
                                                     Some(progress),
-                                                    Some(format!("Reading Old Pages ({progress})")),
+                                                    Some(format!(
+                                                        "Reading Old Pages ({:02.0}%)",
+                                                        progress.mul(100_f64)
+                                                    )),
                                                     id,
                                                 )
                                                 .ok();
@@ -692,7 +954,10 @@ impl Debugger {
                                             debug_adapter
                                                 .update_progress(
                                                     Some(progress),
-                                                    Some(format!("Erasing Sectors ({progress})")),
+                                                    Some(format!(
+                                                        "Erasing Sectors ({:02.0}%)",
+                                                        progress.mul(100_f64)
+                                                    )),
                                                     id,
                                                 )
                                                 .ok();
@@ -755,115 +1020,346 @@ impl Debugger {
                                             debug_adapter
                                                 .update_progress(
                                                     Some(1.0),
-                                                    Some("Flashing Pages Complete!"),
+                                                    Some(if verify_after_flashing {
+                                                        // The flashed data is read back after this event, which can take a while for large binaries.
+                                                        "Flashing Pages Complete! Verifying ..."
+                                                    } else {
+                                                        "Flashing Pages Complete!"
+                                                    }),
                                                     id,
                                                 )
                                                 .ok();
                                         }
                                         probe_rs::flashing::ProgressEvent::DiagnosticMessage {
-                                            ..
-                                        } => (),
+                                            message,
+                                        } => {
+                                            debug_adapter.log_to_console(format!(
+                                                "FLASHING: {message}"
+                                            ));
+                                        }
                                     }
-                                    })
-                                } else {
-                                    FlashProgress::new(|_event| {})
+                                        })
+                                    } else {
+                                        FlashProgress::new(|_event| {})
+                                    };
+                                    download_options.progress = Some(flash_progress);
+                                    download_file_with_options(
+                                        session_data.session()?,
+                                        path_to_elf,
+                                        Format::Elf,
+                                        download_options,
+                                    )
                                 };
-                                download_options.progress = Some(flash_progress);
-                                download_file_with_options(
-                                    &mut session_data.session,
-                                    path_to_elf,
-                                    Format::Elf,
-                                    download_options,
-                                )
-                            };
-                            debug_adapter = match Rc::try_unwrap(rc_debug_adapter) {
-                                Ok(debug_adapter) => debug_adapter.into_inner(),
-                                Err(too_many_strong_references) => {
-                                    let other_error = DebuggerError::Other(anyhow!("Unexpected error while dereferencing the `debug_adapter` (It has {} strong references). Please report this as a bug.", Rc::strong_count(&too_many_strong_references)));
-                                    return Err(other_error);
+                                debug_adapter = match Rc::try_unwrap(rc_debug_adapter) {
+                                    Ok(debug_adapter) => debug_adapter.into_inner(),
+                                    Err(too_many_strong_references) => {
+                                        let other_error = DebuggerError::Other(anyhow!("Unexpected error while dereferencing the `debug_adapter` (It has {} strong references). Please report this as a bug.", Rc::strong_count(&too_many_strong_references)));
+                                        return Err(other_error);
+                                    }
+                                };
+
+                                if let Some(id) = progress_id {
+                                    let _ = debug_adapter.end_progress(id);
                                 }
+                                flash_result
                             };
 
-                            if let Some(id) = progress_id {
-                                let _ = debug_adapter.end_progress(id);
-                            }
-                            flash_result
-                        };
-
-                        match flash_result {
-                            Ok(_) => {
-                                debug_adapter.log_to_console(format!(
-                                    "FLASHING: Completed write of {:?} to device memory",
-                                    &path_to_elf
-                                ));
-                            }
-                            Err(error) => {
-                                let error = DebuggerError::FileDownload(error);
-                                debug_adapter.send_error_response(&error)?;
-                                return Err(error);
+                            match flash_result {
+                                Ok(_) => {
+                                    debug_adapter.log_to_console(format!(
+                                        "FLASHING: Completed write of {:?} to device memory",
+                                        &path_to_elf
+                                    ));
+                                    let post_flash_delay_ms =
+                                        self.config.flashing_config.post_flash_delay_ms;
+                                    if post_flash_delay_ms > 0 {
+                                        debug_adapter.log_to_console(format!(
+                                        "FLASHING: Waiting {post_flash_delay_ms} ms before resetting the target"
+                                    ));
+                                        thread::sleep(Duration::from_millis(post_flash_delay_ms));
+                                    }
+                                }
+                                Err(error) => {
+                                    let error = DebuggerError::FileDownload(error);
+                                    debug_adapter.send_error_response(&error)?;
+                                    return Err(error);
+                                }
                             }
                         }
                     }
                 }
-            }
 
-            // This is the first attach to the requested core. If this one works, all subsequent ones will be no-op requests for a Core reference. Do NOT hold onto this reference for the duration of the session ... that is why this code is in a block of its own.
-            {
-                // First, attach to the core
-                let mut target_core = match session_data.attach_core(target_core_config.core_index)
+                // This is the first attach to the requested core. If this one works, all subsequent ones will be no-op requests for a Core reference. Do NOT hold onto this reference for the duration of the session ... that is why this code is in a block of its own.
                 {
-                    Ok(mut target_core) => {
-                        // Immediately after attaching, halt the core, so that we can finish initalization without bumping into user code.
-                        // Depending on supplied `config`, the core will be restarted at the end of initialization in the `configuration_done` request.
-                        match halt_core(&mut target_core.core) {
-                            Ok(_) => {
-                                // Ensure ebreak enters debug mode, this is necessary for soft breakpoints to work on architectures like RISC-V.
-                                target_core.core.debug_on_sw_breakpoint(true)?;
+                    let target_name = session_data.target_name();
+                    // First, attach to the core
+                    let mut target_core = match session_data
+                        .attach_core(target_core_config.core_index)
+                    {
+                        Ok(mut target_core) => {
+                            // Immediately after attaching, halt the core, so that we can finish initalization without bumping into user code.
+                            // Depending on supplied `config`, the core will be restarted at the end of initialization in the `configuration_done` request.
+                            match halt_core(&mut target_core.core) {
+                                Ok(_) => {
+                                    // Ensure ebreak enters debug mode, this is necessary for soft breakpoints to work on architectures like RISC-V.
+                                    target_core.core.debug_on_sw_breakpoint(true)?;
+                                }
+                                Err(error) => {
+                                    debug_adapter.send_error_response(&error)?;
+                                    return Err(error);
+                                }
                             }
-                            Err(error) => {
-                                debug_adapter.send_error_response(&error)?;
-                                return Err(error);
+
+                            // Before we complete, load the (optional) CMSIS-SVD file and its variable cache.
+                            // Configure the [CorePeripherals].
+                            if matches!(debug_session_status, DebugSessionStatus::New(_)) {
+                                if let Some(svd_file) = &target_core_config.svd_file {
+                                    target_core.core_data.core_peripherals = match SvdCache::new(
+                                        svd_file,
+                                        &mut target_core.core,
+                                        &mut debug_adapter,
+                                        session_request.seq,
+                                    ) {
+                                        Ok(core_peripherals) => Some(core_peripherals),
+                                        Err(error) => {
+                                            debug_adapter.send_error_response(&error)?;
+                                            tracing::error!("{:?}", error);
+                                            None
+                                        }
+                                    };
+                                }
                             }
-                        }
 
-                        // Before we complete, load the (optional) CMSIS-SVD file and its variable cache.
-                        // Configure the [CorePeripherals].
-                        if matches!(debug_session_status, DebugSessionStatus::New(_)) {
-                            if let Some(svd_file) = &target_core_config.svd_file {
-                                target_core.core_data.core_peripherals = match SvdCache::new(
-                                    svd_file,
-                                    &mut target_core.core,
-                                    &mut debug_adapter,
-                                    session_request.seq,
+                            // Without this, a watchdog will reset the target while it is halted at a breakpoint.
+                            if target_core_config.freeze_peripherals_when_halted {
+                                match target_core.core_data.core_peripherals.as_mut().map(
+                                    |core_peripherals| {
+                                        core_peripherals
+                                            .freeze_peripherals_when_halted(&mut target_core.core)
+                                    },
                                 ) {
-                                    Ok(core_peripherals) => Some(core_peripherals),
+                                    Some(Ok(frozen_peripherals))
+                                        if !frozen_peripherals.is_empty() =>
+                                    {
+                                        debug_adapter.log_to_console(format!(
+                                        "Peripherals that are stopped while the core is halted: {}",
+                                        frozen_peripherals.join(", ")
+                                    ));
+                                    }
+                                    Some(Ok(_)) => {
+                                        debug_adapter.show_message(
+                                        MessageSeverity::Warning,
+                                        "The `svdFile` does not describe any supported debug freeze registers, so peripherals will keep running while the core is halted.".to_string(),
+                                    );
+                                    }
+                                    Some(Err(error)) => {
+                                        debug_adapter.show_message(
+                                        MessageSeverity::Warning,
+                                        format!("Failed to stop peripherals while the core is halted: {error}"),
+                                    );
+                                    }
+                                    None => {
+                                        debug_adapter.show_message(
+                                        MessageSeverity::Warning,
+                                        "`freezePeripheralsWhenHalted` requires a valid `svdFile`."
+                                            .to_string(),
+                                    );
+                                    }
+                                }
+                            }
+
+                            // Report the unique device id, so that users can configure `deviceConfigs`, and apply the settings for this device.
+                            match target_core
+                                .apply_device_config(&target_name, &self.config.device_configs)
+                            {
+                                Ok(Some(unique_id)) => {
+                                    debug_adapter.log_to_console(format!(
+                                    "Connected to {target_name} with unique device id {unique_id}"
+                                ));
+                                }
+                                Ok(None) => {
+                                    if !self.config.device_configs.is_empty() {
+                                        debug_adapter.show_message(
+                                        MessageSeverity::Warning,
+                                        format!("The unique device id of {target_name} cannot be read, so `deviceConfigs` will not be applied."),
+                                    );
+                                    }
+                                }
+                                Err(error) => {
+                                    debug_adapter.show_message(
+                                    MessageSeverity::Warning,
+                                    format!("Failed to apply the device specific configuration: {error}"),
+                                );
+                                }
+                            }
+
+                            // Without the split DWARF files, breakpoints and variables are not available for parts of the program.
+                            let missing_split_dwarf_files =
+                                target_core.core_data.debug_info.missing_split_dwarf_files();
+                            if !missing_split_dwarf_files.is_empty() {
+                                let missing_file_names = missing_split_dwarf_files
+                                    .iter()
+                                    .map(|missing_file| missing_file.display().to_string())
+                                    .collect::<Vec<String>>()
+                                    .join(", ");
+                                debug_adapter.show_message(
+                                MessageSeverity::Warning,
+                                format!("Breakpoints and variables will not be available for some compilation units, because these split DWARF files are missing: {missing_file_names}"),
+                            );
+                            }
+
+                            // Flashing a new binary may have overwritten the instructions of software breakpoints in RAM.
+                            if let Err(error) = target_core.rewrite_software_breakpoints() {
+                                debug_adapter.show_message(
+                                    MessageSeverity::Warning,
+                                    format!("Failed to restore the software breakpoints: {error}"),
+                                );
+                            }
+
+                            // Coverage breakpoints are (re-)applied for every run, because the binary may have changed.
+                            if let Some(coverage_config) = &target_core_config.coverage {
+                                if let Err(error) =
+                                    target_core.set_coverage_breakpoints(coverage_config)
+                                {
+                                    debug_adapter.show_message(
+                                        MessageSeverity::Warning,
+                                        format!("Line coverage will not be available: {error}"),
+                                    );
+                                }
+                            }
+
+                            // Function trace breakpoints are (re-)applied for every run, because the binary may have changed.
+                            if let (Some(function_trace_config), Some(program_binary)) = (
+                                &target_core_config.function_trace,
+                                &target_core_config.program_binary,
+                            ) {
+                                match target_core.set_function_trace_breakpoints(
+                                    function_trace_config,
+                                    program_binary,
+                                ) {
+                                    Ok((instrumented_count, matched_count)) => {
+                                        debug_adapter.show_message(
+                                        MessageSeverity::Information,
+                                        format!("Function trace: Instrumented {instrumented_count} of {matched_count} matching functions. Every call halts and resumes the core, which will significantly slow down the target application."),
+                                    );
+                                    }
                                     Err(error) => {
-                                        debug_adapter.send_error_response(&error)?;
-                                        tracing::error!("{:?}", error);
-                                        None
+                                        debug_adapter.show_message(
+                                            MessageSeverity::Warning,
+                                            format!(
+                                                "Function trace will not be available: {error}"
+                                            ),
+                                        );
                                     }
-                                };
+                                }
+                            }
+
+                            // Restore the source breakpoints of a previous debug session, if they are persisted.
+                            match target_core.restore_breakpoints() {
+                                Ok((restored_breakpoints, failed_breakpoints)) => {
+                                    // The client did not request these breakpoints in this session, so it has to be told about them to show them.
+                                    for (id, verified_breakpoint, source) in restored_breakpoints {
+                                        debug_adapter.send_breakpoint_new_event(
+                                            id,
+                                            &verified_breakpoint,
+                                            source,
+                                        )?;
+                                    }
+                                    if !failed_breakpoints.is_empty() {
+                                        debug_adapter.show_message(
+                                            MessageSeverity::Warning,
+                                            format!(
+                                                "The following saved breakpoints could not be restored, and will be removed:\n{}",
+                                                failed_breakpoints.join("\n")
+                                            ),
+                                        );
+                                    }
+                                }
+                                Err(error) => {
+                                    debug_adapter.show_message(
+                                        MessageSeverity::Warning,
+                                        format!("Failed to restore the saved breakpoints: {error}"),
+                                    );
+                                }
                             }
+
+                            // Catchpoints are (re-)applied for every run, because the binary may have changed.
+                            if !target_core_config.catchpoints.is_empty() {
+                                match target_core.set_catchpoints(&target_core_config.catchpoints) {
+                                    Ok(missing_functions) if !missing_functions.is_empty() => {
+                                        debug_adapter.show_message(
+                                        MessageSeverity::Warning,
+                                        format!(
+                                            "Catchpoints: The functions {} were not found in the program binary.",
+                                            missing_functions.join(", ")
+                                        ),
+                                    );
+                                    }
+                                    Ok(_) => {}
+                                    Err(error) => {
+                                        debug_adapter.show_message(
+                                            MessageSeverity::Warning,
+                                            format!("Catchpoints will not be available: {error}"),
+                                        );
+                                    }
+                                }
+                            }
+
+                            // Report the number of hardware breakpoint units as a probe-rs specific capability, so that the client can warn before they are exhausted.
+                            match target_core.available_breakpoint_units() {
+                                Ok(breakpoint_units) => {
+                                    debug_adapter.send_event(
+                                        "capabilities",
+                                        Some(serde_json::json!({
+                                            "capabilities": {
+                                                "hardwareBreakpointUnits": breakpoint_units
+                                            }
+                                        })),
+                                    )?;
+                                }
+                                Err(error) => {
+                                    tracing::warn!(
+                                        "Failed to read the number of breakpoint units: {error}"
+                                    );
+                                }
+                            }
+                            target_core
                         }
-                        target_core
-                    }
-                    Err(error) => {
-                        debug_adapter.send_error_response(&error)?;
-                        return Err(error);
+                        Err(error) => {
+                            debug_adapter.send_error_response(&error)?;
+                            return Err(error);
+                        }
+                    };
+
+                    // An `attach` session does not reset or halt the core, so the debugger starts with the status that the core is in.
+                    if debug_adapter.target_session_type == Some(TargetSessionType::AttachRequest)
+                        && matches!(debug_session_status, DebugSessionStatus::New(_))
+                    {
+                        target_core.core_data.last_known_status = target_core.core.status()?;
                     }
-                };
 
-                if self.config.flashing_config.flashing_enabled
-                    && self.config.flashing_config.reset_after_flashing
-                {
-                    if matches!(debug_session_status, DebugSessionStatus::New(_)) {
-                        debug_adapter
-                            .restart(&mut target_core, None)
-                            .context("Failed to restart core")?;
-                    } else {
-                        debug_adapter
-                            .restart(&mut target_core, Some(session_request.clone()))
-                            .context("Failed to restart core")?;
+                    // The target is reset through the first core, before the other cores are attached, so that they are initialized after the reset.
+                    if core_position == 0 {
+                        if let (Some(reset_mode), DebugSessionStatus::New(_)) =
+                            (self.config.reset_mode, &debug_session_status)
+                        {
+                            target_core
+                                .reset(reset_mode)
+                                .context("Failed to reset core")?;
+                        } else if self.config.flashing_config.flashing_enabled
+                            && (self.config.flashing_config.reset_after_flashing
+                                || self.config.reset_mode.is_some())
+                        {
+                            if matches!(debug_session_status, DebugSessionStatus::New(_)) {
+                                debug_adapter
+                                    .restart(&mut target_core, None)
+                                    .context("Failed to restart core")?;
+                            } else {
+                                debug_adapter
+                                    .restart(&mut target_core, Some(session_request.clone()))
+                                    .context("Failed to restart core")?;
+                            }
+                        }
                     }
                 }
             }
@@ -906,11 +1402,35 @@ impl Debugger {
                     Ok(DebugSessionStatus::Terminate) => {
                         return Ok(DebugSessionStatus::Terminate);
                     }
+                    Err(DebuggerError::ProbeDisconnected) => {
+                        // End the session cleanly, because there is nothing left to debug without the probe.
+                        debug_adapter.show_message(
+                            MessageSeverity::Error,
+                            DebuggerError::ProbeDisconnected.to_string(),
+                        );
+                        debug_adapter
+                            .send_event("exited", Some(ExitedEventBody { exit_code: 1 }))?;
+                        debug_adapter.send_event(
+                            "terminated",
+                            Some(TerminatedEventBody { restart: None }),
+                        )?;
+                        return Ok(DebugSessionStatus::Terminate);
+                    }
                     Err(e) => {
                         debug_adapter.show_message(
                             MessageSeverity::Error,
                             format!("Debug Adapter terminated unexpectedly with an error: {e:?}"),
                         );
+                        // Leave the target without `BKPT` instructions, as far as the probe still allows it.
+                        for core_config in self.config.core_configs.iter() {
+                            if let Err(error) =
+                                session_data.attach_core(core_config.core_index).and_then(
+                                    |mut target_core| target_core.restore_software_breakpoints(),
+                                )
+                            {
+                                tracing::warn!("Failed to restore the instructions of the software breakpoints: {error}");
+                            }
+                        }
                         debug_adapter.send_event(
                             "terminated",
                             Some(TerminatedEventBody { restart: None }),
@@ -934,6 +1454,42 @@ impl Debugger {
     }
 }
 
+/// The position in `core_configs` of the core that a request refers to.
+/// The `scopes`, `variables`, `setVariable` and `evaluate` requests refer to a core with a stack frame or variable of the core. See [`CoreData::owns_reference`].
+/// Every other request whose arguments have a `threadId` (which is the core index), e.g. `continue`, `stepUntil` or `goto`, refers to that core.
+/// All other requests are processed by the first core.
+fn requested_core_position(
+    request: &Request,
+    core_configs: &[configuration::CoreConfig],
+    core_data: &[CoreData],
+) -> usize {
+    let argument = |name: &str| {
+        request
+            .arguments
+            .as_ref()
+            .and_then(|arguments| arguments.get(name))
+            .and_then(|value| value.as_i64())
+    };
+    let owning_core_index = |reference: i64| {
+        core_data
+            .iter()
+            .find(|core_data| core_data.owns_reference(reference))
+            .map(|core_data| core_data.core_index)
+    };
+    let core_index = match request.command.as_str() {
+        "scopes" | "evaluate" => argument("frameId").and_then(owning_core_index),
+        "variables" | "setVariable" => argument("variablesReference").and_then(owning_core_index),
+        _ => argument("threadId").map(|thread_id| thread_id as usize),
+    };
+    core_index
+        .and_then(|core_index| {
+            core_configs
+                .iter()
+                .position(|core_config| core_config.core_index == core_index)
+        })
+        .unwrap_or(0)
+}
+
 fn is_file_newer(
     saved_binary_timestamp: &mut Option<Duration>,
     path_to_elf: &std::path::PathBuf,
@@ -2,51 +2,174 @@ use crate::{
     debug_adapter::{dap_adapter::*, protocol::ProtocolAdapter},
     DebuggerError,
 };
+use anyhow::anyhow;
 use probe_rs::Core;
 use probe_rs_cli_util::rtt;
 
+/// The maximum number of bytes that are kept for a down channel, while the target buffer is full.
+const MAX_PENDING_DOWN_DATA: usize = 4096;
+
+/// The default maximum number of bytes that are kept for an up channel, until the client window is open. See [`crate::debugger::configuration::CoreConfig::rtt_window_buffer_size`].
+pub(crate) const DEFAULT_RTT_WINDOW_BUFFER_SIZE: usize = 64 * 1024;
+
 /// Manage the active RTT target for a specific SessionData, as well as provide methods to reliably move RTT from target, through the debug_adapter, to the client.
 pub(crate) struct RttConnection {
     /// The connection to RTT on the target
     pub(crate) target_rtt: rtt::RttActiveTarget,
     /// Some status fields and methods to ensure continuity in flow of data from target to debugger to client.
     pub(crate) debugger_rtt_channels: Vec<DebuggerRttChannel>,
+    /// The down channels, with the data that is waiting for space in the target buffer.
+    pub(crate) debugger_rtt_down_channels: Vec<DebuggerRttDownChannel>,
 }
 
 impl RttConnection {
     /// Polls all the available channels for data and transmits data to the client.
     /// If at least one channel had data, then return a `true` status.
+    /// If `errors_are_warnings` is `true`, errors are logged as warnings, rather than reported to the client.
     pub fn process_rtt_data<P: ProtocolAdapter>(
         &mut self,
         debug_adapter: &mut DebugAdapter<P>,
         target_core: &mut Core,
+        errors_are_warnings: bool,
     ) -> bool {
+        self.flush_down_channels(target_core);
         let mut at_least_one_channel_had_data = false;
         for debugger_rtt_channel in self.debugger_rtt_channels.iter_mut() {
-            at_least_one_channel_had_data |=
-                debugger_rtt_channel.poll_rtt_data(target_core, debug_adapter, &mut self.target_rtt)
+            at_least_one_channel_had_data |= debugger_rtt_channel.poll_rtt_data(
+                target_core,
+                debug_adapter,
+                &mut self.target_rtt,
+                errors_are_warnings,
+            )
         }
         at_least_one_channel_had_data
     }
+
+    /// Write `data` to the down channel `channel_number`, without waiting for the target to read it.
+    /// Data that does not fit in the target buffer is kept, and written when the channels are polled again, so that a full buffer does not block the debugger.
+    /// Returns the number of bytes that were written to the target immediately.
+    pub(crate) fn write_rtt(
+        &mut self,
+        target_core: &mut Core,
+        channel_number: usize,
+        data: &[u8],
+    ) -> Result<usize, DebuggerError> {
+        let down_channel = self
+            .debugger_rtt_down_channels
+            .iter_mut()
+            .find(|down_channel| down_channel.channel_number == channel_number)
+            .ok_or_else(|| {
+                DebuggerError::Other(anyhow!(
+                    "There is no RTT down channel with number {channel_number}"
+                ))
+            })?;
+        if down_channel.pending_data.len() + data.len() > MAX_PENDING_DOWN_DATA {
+            return Err(DebuggerError::Other(anyhow!(
+                "The buffer of RTT down channel {channel_number} is full, and {} bytes are still waiting for the target to read them",
+                down_channel.pending_data.len()
+            )));
+        }
+        // Data that is already waiting has to be written first, to keep the order.
+        let previously_pending = down_channel.pending_data.len();
+        down_channel.pending_data.extend_from_slice(data);
+        let bytes_written = down_channel.flush(target_core, &mut self.target_rtt)?;
+        Ok(bytes_written.saturating_sub(previously_pending))
+    }
+
+    /// Replace the filter for the defmt frames of the up channel `channel_number`. See [`rtt::DefmtFilter`].
+    pub(crate) fn set_defmt_filter(
+        &mut self,
+        channel_number: usize,
+        defmt_filter: Option<rtt::DefmtFilter>,
+    ) -> Result<(), DebuggerError> {
+        let active_channel = self
+            .target_rtt
+            .active_channels
+            .iter_mut()
+            .find(|active_channel| active_channel.number() == Some(channel_number))
+            .ok_or_else(|| {
+                DebuggerError::Other(anyhow!(
+                    "There is no RTT up channel with number {channel_number}"
+                ))
+            })?;
+        if active_channel.data_format != rtt::DataFormat::Defmt {
+            return Err(DebuggerError::Other(anyhow!(
+                "RTT channel {channel_number} does not use the defmt format"
+            )));
+        }
+        active_channel.set_defmt_filter(defmt_filter);
+        Ok(())
+    }
+
+    /// Write the pending data of every down channel, as far as there is space in the target buffers.
+    fn flush_down_channels(&mut self, target_core: &mut Core) {
+        for down_channel in self.debugger_rtt_down_channels.iter_mut() {
+            if let Err(error) = down_channel.flush(target_core, &mut self.target_rtt) {
+                tracing::warn!(
+                    "Failed to write to RTT down channel {}: {error}",
+                    down_channel.channel_number
+                );
+            }
+        }
+    }
+}
+
+/// An RTT down channel, that is used to send data from the debugger to the target.
+pub(crate) struct DebuggerRttDownChannel {
+    pub(crate) channel_number: usize,
+    /// Data that did not fit in the target buffer yet.
+    pub(crate) pending_data: Vec<u8>,
+}
+
+impl DebuggerRttDownChannel {
+    /// Write as much of the `pending_data` as there is space for in the target buffer, and return the number of bytes that were written.
+    fn flush(
+        &mut self,
+        core: &mut Core,
+        rtt_target: &mut rtt::RttActiveTarget,
+    ) -> Result<usize, DebuggerError> {
+        if self.pending_data.is_empty() {
+            return Ok(0);
+        }
+        let bytes_written = rtt_target
+            .write_rtt(core, self.channel_number, &self.pending_data)
+            .map_err(DebuggerError::Other)?;
+        self.pending_data.drain(..bytes_written);
+        Ok(bytes_written)
+    }
 }
 
 pub(crate) struct DebuggerRttChannel {
     pub(crate) channel_number: usize,
     // We will not poll target RTT channels until we have confirmation from the client that the output window has been opened.
     pub(crate) has_client_window: bool,
+    /// `true` once the client has been asked to create a window for this channel. With [`crate::debugger::configuration::RttWindowMode::Lazy`],
+    /// this only happens when the channel first produces output.
+    pub(crate) window_requested: bool,
+    /// Data that was read from the target before the client window was opened. It is sent as soon as the window is open.
+    pub(crate) pending_data: Vec<String>,
+    /// The maximum number of bytes in `pending_data`. Once it is reached, data is left in the target buffer until the window is open.
+    pub(crate) max_pending_data: usize,
 }
 
 impl DebuggerRttChannel {
     /// Poll and retrieve data from the target, and send it to the client, depending on the state of `hasClientWindow`.
     /// Doing this selectively ensures that we don't pull data from target buffers until we have an output window, and also helps us drain buffers after the target has entered a `is_halted` state.
+    /// If no window has been requested yet, the first data that is read from the channel requests the window, and is sent once the window is open.
     /// Errors will be reported back to the `debug_adapter`, and the return `bool` value indicates whether there was available data that was processed.
     pub(crate) fn poll_rtt_data<P: ProtocolAdapter>(
         &mut self,
         core: &mut Core,
         debug_adapter: &mut DebugAdapter<P>,
         rtt_target: &mut rtt::RttActiveTarget,
+        errors_are_warnings: bool,
     ) -> bool {
-        if self.has_client_window {
+        let pending_data_size = self.pending_data.iter().map(String::len).sum::<usize>();
+        if !self.has_client_window && pending_data_size >= self.max_pending_data {
+            // Leave the data in the target buffer until the client has opened the window.
+            return false;
+        }
+        let rtt_channel = if let Some(rtt_channel) =
             rtt_target
                 .active_channels
                 .iter_mut()
@@ -56,30 +179,69 @@ impl DebuggerRttChannel {
                     } else {
                         false
                     }
-                })
-                .and_then(|rtt_channel| {
-                    match rtt_channel.get_rtt_data(core, rtt_target.defmt_state.as_ref()) {
-                        Ok(data_result) => data_result,
-                        Err(rtt_error) => {
-                            debug_adapter
-                                .send_error_response(&DebuggerError::Other(rtt_error))
-                                .ok();
-                            None
-                        }
-                    }
-                })
-                .and_then(|(channel_number, channel_data)| {
-                    if debug_adapter
-                        .rtt_output(channel_number.parse::<usize>().unwrap_or(0), channel_data)
-                    {
-                        Some(true)
-                    } else {
-                        None
-                    }
-                })
-                .is_some()
+                }) {
+            rtt_channel
         } else {
-            false
+            return false;
+        };
+        let channel_data = match rtt_channel.get_rtt_data(core, rtt_target.defmt_state.as_ref()) {
+            Ok(data_result) => data_result.map(|(_, channel_data)| channel_data),
+            Err(rtt_error) => {
+                if errors_are_warnings {
+                    tracing::warn!(
+                        "Discarded RTT data on channel {}: {}",
+                        self.channel_number,
+                        rtt_error
+                    );
+                } else {
+                    debug_adapter
+                        .send_error_response(&DebuggerError::Other(rtt_error))
+                        .ok();
+                }
+                None
+            }
+        };
+
+        if !self.window_requested {
+            return if let Some(channel_data) = channel_data {
+                self.window_requested = debug_adapter.rtt_window(
+                    self.channel_number,
+                    rtt_channel.channel_name.clone(),
+                    rtt_channel.data_format,
+                );
+                self.pending_data.push(channel_data);
+                true
+            } else {
+                false
+            };
+        }
+
+        if !self.has_client_window {
+            // Keep the data until the client has opened the window, so that early output is not lost.
+            return if let Some(channel_data) = channel_data {
+                self.pending_data.push(channel_data);
+                true
+            } else {
+                false
+            };
+        }
+
+        let mut data_was_sent = self.flush_pending_data(debug_adapter);
+        if let Some(channel_data) = channel_data {
+            data_was_sent |= debug_adapter.rtt_output(self.channel_number, channel_data);
+        }
+        data_was_sent
+    }
+
+    /// Send the data that was read from the target before the client window was opened, and return `true` if there was any.
+    pub(crate) fn flush_pending_data<P: ProtocolAdapter>(
+        &mut self,
+        debug_adapter: &mut DebugAdapter<P>,
+    ) -> bool {
+        let mut data_was_sent = false;
+        for pending_data in self.pending_data.drain(..) {
+            data_was_sent |= debug_adapter.rtt_output(self.channel_number, pending_data);
         }
+        data_was_sent
     }
 }
@@ -0,0 +1,254 @@
+use crate::DebuggerError;
+use probe_rs::{Core, CoreType, MemoryInterface};
+
+/// The address of the Cortex-M `CPUID` register in the System Control Space.
+const CPUID_ADDRESS: u64 = 0xE000_ED00;
+
+/// The location of the device identification registers for a family of targets.
+struct KnownDeviceIdRegisters {
+    /// The prefix of the target name, as it is used in the target description, e.g. `STM32F4` for `STM32F407VGTx`.
+    target_name_prefix: &'static str,
+    id_code: IdCode,
+    /// The address of the factory programmed unique device id.
+    unique_id_address: u64,
+    /// The size of the unique device id, in 32-bit words.
+    unique_id_words: usize,
+}
+
+/// How the device and revision ids are stored for a family of targets.
+#[derive(Clone, Copy)]
+enum IdCode {
+    /// The STM32 `DBGMCU_IDCODE` register, with the device id in bits `11:0`, and the revision id in bits `31:16`.
+    Stm32Dbgmcu(u64),
+    /// The nRF52 `FICR.INFO.PART` and `FICR.INFO.VARIANT` registers. The variant is an ASCII code, e.g. `AAC0`.
+    NrfFicr { part: u64, variant: u64 },
+}
+
+/// The targets for which the device identification registers are currently supported.
+/// The first entry where the prefix matches the name of the target will be used.
+static KNOWN_DEVICE_ID_REGISTERS: [KnownDeviceIdRegisters; 11] = [
+    KnownDeviceIdRegisters {
+        target_name_prefix: "STM32F0",
+        id_code: IdCode::Stm32Dbgmcu(0x4001_5800),
+        unique_id_address: 0x1FFF_F7AC,
+        unique_id_words: 3,
+    },
+    KnownDeviceIdRegisters {
+        target_name_prefix: "STM32F1",
+        id_code: IdCode::Stm32Dbgmcu(0xE004_2000),
+        unique_id_address: 0x1FFF_F7E8,
+        unique_id_words: 3,
+    },
+    KnownDeviceIdRegisters {
+        target_name_prefix: "STM32F2",
+        id_code: IdCode::Stm32Dbgmcu(0xE004_2000),
+        unique_id_address: 0x1FFF_7A10,
+        unique_id_words: 3,
+    },
+    KnownDeviceIdRegisters {
+        target_name_prefix: "STM32F3",
+        id_code: IdCode::Stm32Dbgmcu(0xE004_2000),
+        unique_id_address: 0x1FFF_F7AC,
+        unique_id_words: 3,
+    },
+    KnownDeviceIdRegisters {
+        target_name_prefix: "STM32F4",
+        id_code: IdCode::Stm32Dbgmcu(0xE004_2000),
+        unique_id_address: 0x1FFF_7A10,
+        unique_id_words: 3,
+    },
+    KnownDeviceIdRegisters {
+        target_name_prefix: "STM32F7",
+        id_code: IdCode::Stm32Dbgmcu(0xE004_2000),
+        unique_id_address: 0x1FF0_F420,
+        unique_id_words: 3,
+    },
+    KnownDeviceIdRegisters {
+        target_name_prefix: "STM32G0",
+        id_code: IdCode::Stm32Dbgmcu(0x4001_5800),
+        unique_id_address: 0x1FFF_7590,
+        unique_id_words: 3,
+    },
+    KnownDeviceIdRegisters {
+        target_name_prefix: "STM32G4",
+        id_code: IdCode::Stm32Dbgmcu(0xE004_2000),
+        unique_id_address: 0x1FFF_7590,
+        unique_id_words: 3,
+    },
+    KnownDeviceIdRegisters {
+        target_name_prefix: "STM32H7",
+        id_code: IdCode::Stm32Dbgmcu(0x5C00_1000),
+        unique_id_address: 0x1FF1_E800,
+        unique_id_words: 3,
+    },
+    KnownDeviceIdRegisters {
+        target_name_prefix: "STM32L4",
+        id_code: IdCode::Stm32Dbgmcu(0xE004_2000),
+        unique_id_address: 0x1FFF_7590,
+        unique_id_words: 3,
+    },
+    KnownDeviceIdRegisters {
+        target_name_prefix: "nRF52",
+        id_code: IdCode::NrfFicr {
+            part: 0x1000_0100,
+            variant: 0x1000_0104,
+        },
+        unique_id_address: 0x1000_0060,
+        unique_id_words: 2,
+    },
+];
+
+/// The identification of the connected device, as far as it can be read from the target.
+#[derive(Debug, Default)]
+pub(crate) struct DeviceIdentity {
+    /// A human readable summary, e.g. `STM32F407VGTx (device id 0x413, revision 0x1001), Cortex-M4F r0p1`.
+    pub(crate) label: String,
+    /// The raw value of the Cortex-M `CPUID` register.
+    pub(crate) cpuid: Option<u32>,
+    /// The decoded core name and revision, e.g. `Cortex-M4F r0p1`.
+    pub(crate) core: Option<String>,
+    /// The vendor specific device (or part) id.
+    pub(crate) device_id: Option<u32>,
+    /// The vendor specific silicon revision.
+    pub(crate) revision: Option<String>,
+    /// The factory programmed unique device id, as hexadecimal words in address order.
+    pub(crate) unique_id: Option<String>,
+}
+
+/// Read and decode the device identification registers of the target named `target_name`.
+/// Registers that are not known for the target are left as `None`. Errors are only returned if a known register could not be read.
+pub(crate) fn read_device_identity(
+    core: &mut Core,
+    target_name: &str,
+) -> Result<DeviceIdentity, DebuggerError> {
+    let mut device_identity = DeviceIdentity::default();
+    if core.core_type().is_cortex_m() {
+        let cpuid = core.read_word_32(CPUID_ADDRESS)?;
+        let has_fpu = core.fpu_support().unwrap_or(false);
+        device_identity.cpuid = Some(cpuid);
+        device_identity.core = Some(decode_cpuid(cpuid, has_fpu));
+    } else if core.core_type() == CoreType::Riscv {
+        device_identity.core = Some("RISC-V".to_string());
+    }
+
+    if let Some(known_registers) = known_device_id_registers(target_name) {
+        match known_registers.id_code {
+            IdCode::Stm32Dbgmcu(address) => {
+                let id_code = core.read_word_32(address)?;
+                device_identity.device_id = Some(id_code & 0xFFF);
+                device_identity.revision = Some(format!("{:#06x}", id_code >> 16));
+            }
+            IdCode::NrfFicr { part, variant } => {
+                device_identity.device_id = Some(core.read_word_32(part)?);
+                let variant = core.read_word_32(variant)?.to_be_bytes();
+                device_identity.revision = Some(String::from_utf8_lossy(&variant).into_owned());
+            }
+        }
+    }
+    device_identity.unique_id = read_unique_id(core, target_name)?;
+
+    let mut label = target_name.to_string();
+    match (device_identity.device_id, &device_identity.revision) {
+        (Some(device_id), Some(revision)) => {
+            label.push_str(&format!(" (device id {device_id:#x}, revision {revision})"))
+        }
+        (Some(device_id), None) => label.push_str(&format!(" (device id {device_id:#x})")),
+        _ => {}
+    }
+    if let Some(core_name) = &device_identity.core {
+        label.push_str(&format!(", {core_name}"));
+    }
+    device_identity.label = label;
+    Ok(device_identity)
+}
+
+/// Read the factory programmed unique device id of the target named `target_name`, formatted as hexadecimal words in address order.
+/// Returns `Ok(None)` if the location of the unique id is not known for the target.
+pub(crate) fn read_unique_id(
+    core: &mut Core,
+    target_name: &str,
+) -> Result<Option<String>, DebuggerError> {
+    let known_registers = match known_device_id_registers(target_name) {
+        Some(known_registers) => known_registers,
+        None => return Ok(None),
+    };
+    let mut unique_id = vec![0_u32; known_registers.unique_id_words];
+    core.read_32(known_registers.unique_id_address, &mut unique_id)?;
+    Ok(Some(
+        unique_id
+            .iter()
+            .map(|word| format!("{word:08X}"))
+            .collect::<String>(),
+    ))
+}
+
+/// Find the device identification registers for the target named `target_name`.
+fn known_device_id_registers(target_name: &str) -> Option<&'static KnownDeviceIdRegisters> {
+    KNOWN_DEVICE_ID_REGISTERS.iter().find(|known_registers| {
+        target_name
+            .to_ascii_lowercase()
+            .starts_with(&known_registers.target_name_prefix.to_ascii_lowercase())
+    })
+}
+
+/// Decode the Cortex-M `CPUID` register, e.g. `Cortex-M4F r0p1`.
+/// The `F` suffix for a core with an FPU is only added to known core names.
+fn decode_cpuid(cpuid: u32, has_fpu: bool) -> String {
+    let implementer = cpuid >> 24;
+    let variant = (cpuid >> 20) & 0xF;
+    let part_number = (cpuid >> 4) & 0xFFF;
+    let revision = cpuid & 0xF;
+    let core_name = match (implementer, part_number) {
+        (0x41, 0xC20) => "Cortex-M0",
+        (0x41, 0xC60) => "Cortex-M0+",
+        (0x41, 0xC21) => "Cortex-M1",
+        (0x41, 0xC23) => "Cortex-M3",
+        (0x41, 0xC24) => "Cortex-M4",
+        (0x41, 0xC27) => "Cortex-M7",
+        (0x41, 0xD20) => "Cortex-M23",
+        (0x41, 0xD21) => "Cortex-M33",
+        (0x41, 0xD22) => "Cortex-M55",
+        (0x41, 0xD23) => "Cortex-M85",
+        _ => {
+            return format!("Unknown core (implementer {implementer:#04x}, part number {part_number:#05x}) r{variant}p{revision}");
+        }
+    };
+    let fpu_suffix = if has_fpu { "F" } else { "" };
+    format!("{core_name}{fpu_suffix} r{variant}p{revision}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_cpuid;
+
+    #[test]
+    fn known_cortex_m_cores_are_named() {
+        for (cpuid, expected) in [
+            (0x410C_C601, "Cortex-M0+ r0p1"),
+            (0x412F_C231, "Cortex-M3 r2p1"),
+            (0x410F_C241, "Cortex-M4 r0p1"),
+            (0x411F_C272, "Cortex-M7 r1p2"),
+            (0x410F_D214, "Cortex-M33 r0p4"),
+        ] {
+            assert_eq!(decode_cpuid(cpuid, false), expected, "CPUID {cpuid:#010x}");
+        }
+    }
+
+    #[test]
+    fn cores_with_an_fpu_have_the_f_suffix() {
+        assert_eq!(decode_cpuid(0x410F_C241, true), "Cortex-M4F r0p1");
+        assert_eq!(decode_cpuid(0x411F_C272, true), "Cortex-M7F r1p2");
+    }
+
+    #[test]
+    fn unknown_implementers_and_part_numbers_are_described() {
+        assert_eq!(
+            decode_cpuid(0x440F_C241, true),
+            "Unknown core (implementer 0x44, part number 0xc24) r0p1"
+        );
+        assert_eq!(
+            decode_cpuid(0x410F_C990, false),
+            "Unknown core (implementer 0x41, part number 0xc99) r0p0"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use super::function_trace::demangle;
+use crate::DebuggerError;
+use anyhow::anyhow;
+use goblin::elf::{
+    sym::{STT_FUNC, STT_NOTYPE, STT_OBJECT},
+    Elf,
+};
+use std::path::Path;
+
+/// A symbol from the ELF symbol table, including the symbols that are not described by DWARF,
+/// e.g. assembly labels, and symbols defined by the linker script (`_stack_start`, `__data_end__`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ElfSymbol {
+    /// The symbol name, as it appears in the symbol table.
+    pub(crate) name: String,
+    /// The demangled symbol name, e.g. `my_app::module::STATIC`. This is the same as `name` for symbols that are not mangled.
+    pub(crate) demangled_name: String,
+    /// The value of the symbol, which is the address for functions and data. For functions on ARM, the Thumb bit is cleared.
+    pub(crate) address: u64,
+    /// The size of the symbol in bytes, or `0` if it is unknown (e.g. for linker defined symbols).
+    pub(crate) size: u64,
+    /// `true` for data objects, which have a value that can be read from the target.
+    pub(crate) is_data: bool,
+}
+
+/// Read the function, data, and untyped symbols from the symbol table of `program_binary`.
+/// ARM mapping symbols (`$t`, `$d`, ...) are not included.
+pub(crate) fn read_elf_symbols(program_binary: &Path) -> Result<Vec<ElfSymbol>, DebuggerError> {
+    let elf_data = std::fs::read(program_binary)?;
+    let elf = Elf::parse(&elf_data).map_err(|error| {
+        DebuggerError::Other(anyhow!("Failed to parse {:?}: {error}", program_binary))
+    })?;
+    Ok(elf
+        .syms
+        .iter()
+        .filter(|symbol| matches!(symbol.st_type(), STT_FUNC | STT_OBJECT | STT_NOTYPE))
+        .filter_map(|symbol| {
+            let name = elf.strtab.get_at(symbol.st_name)?;
+            if name.is_empty() || name.starts_with('$') {
+                return None;
+            }
+            Some(ElfSymbol {
+                name: name.to_string(),
+                demangled_name: demangle(name),
+                // On ARM, bit 0 of a function address indicates Thumb mode, and is not part of the address.
+                address: if symbol.st_type() == STT_FUNC {
+                    symbol.st_value & !1
+                } else {
+                    symbol.st_value
+                },
+                size: symbol.st_size,
+                is_data: symbol.st_type() == STT_OBJECT,
+            })
+        })
+        .collect())
+}
+
+/// Find the symbol with the (raw or demangled) `name`.
+pub(crate) fn find_elf_symbol<'a>(symbols: &'a [ElfSymbol], name: &str) -> Option<&'a ElfSymbol> {
+    symbols
+        .iter()
+        .find(|symbol| symbol.name == name || symbol.demangled_name == name)
+}
+
+/// Find the code symbol that contains `address`, or the closest one before it, if its size is unknown.
+/// Returns the symbol, and the offset of `address` from the start of the symbol.
+pub(crate) fn nearest_elf_symbol(symbols: &[ElfSymbol], address: u64) -> Option<(&ElfSymbol, u64)> {
+    symbols
+        .iter()
+        .filter(|symbol| {
+            !symbol.is_data
+                && symbol.address <= address
+                && (symbol.size == 0 || address < symbol.address + symbol.size)
+        })
+        .max_by_key(|symbol| symbol.address)
+        .map(|symbol| (symbol, address - symbol.address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_elf_symbol, nearest_elf_symbol, ElfSymbol};
+
+    fn symbol(name: &str, address: u64, size: u64, is_data: bool) -> ElfSymbol {
+        ElfSymbol {
+            name: name.to_string(),
+            demangled_name: name.to_string(),
+            address,
+            size,
+            is_data,
+        }
+    }
+
+    fn symbols() -> Vec<ElfSymbol> {
+        vec![
+            symbol("main", 0x1000, 0x40, false),
+            symbol("handler", 0x1040, 0x20, false),
+            // An assembly label, without a size.
+            symbol("asm_label", 0x2000, 0, false),
+            symbol("COUNTER", 0x1050, 4, true),
+        ]
+    }
+
+    #[test]
+    fn nearest_symbol_contains_the_address() {
+        let symbols = symbols();
+        let (symbol, offset) = nearest_elf_symbol(&symbols, 0x1000).unwrap();
+        assert_eq!((symbol.name.as_str(), offset), ("main", 0));
+        let (symbol, offset) = nearest_elf_symbol(&symbols, 0x103E).unwrap();
+        assert_eq!((symbol.name.as_str(), offset), ("main", 0x3E));
+        // Data symbols are ignored, also when they overlap the address.
+        let (symbol, offset) = nearest_elf_symbol(&symbols, 0x1052).unwrap();
+        assert_eq!((symbol.name.as_str(), offset), ("handler", 0x12));
+    }
+
+    #[test]
+    fn address_outside_of_sized_symbols_has_no_symbol() {
+        let symbols = symbols();
+        assert!(nearest_elf_symbol(&symbols, 0x0FFF).is_none());
+        assert!(nearest_elf_symbol(&symbols, 0x1060).is_none());
+    }
+
+    #[test]
+    fn symbol_without_a_size_covers_the_following_addresses() {
+        let symbols = symbols();
+        let (symbol, offset) = nearest_elf_symbol(&symbols, 0x2010).unwrap();
+        assert_eq!((symbol.name.as_str(), offset), ("asm_label", 0x10));
+    }
+
+    #[test]
+    fn symbol_is_found_by_raw_or_demangled_name() {
+        let mut symbols = symbols();
+        symbols.push(ElfSymbol {
+            demangled_name: "my_app::run".to_string(),
+            ..symbol("_ZN6my_app3run17h0123456789abcdefE", 0x3000, 0x10, false)
+        });
+        assert_eq!(
+            find_elf_symbol(&symbols, "my_app::run").map(|symbol| symbol.address),
+            Some(0x3000)
+        );
+        assert_eq!(
+            find_elf_symbol(&symbols, "_ZN6my_app3run17h0123456789abcdefE")
+                .map(|symbol| symbol.address),
+            Some(0x3000)
+        );
+        assert!(find_elf_symbol(&symbols, "my_app::stop").is_none());
+    }
+}
@@ -0,0 +1,474 @@
+use crate::DebuggerError;
+use anyhow::anyhow;
+use parse_int::parse;
+use probe_rs::{
+    debug::{
+        debug_info::DebugInfo, stack_frame::StackFrame, DebugRegisters, Variable, VariableCache,
+        VariableLocation, VariableName, VariableType,
+    },
+    Core, MemoryInterface,
+};
+use std::collections::HashMap;
+
+/// The value of a watched expression that cannot be evaluated, e.g. because the variable is not in scope of the current stack frame.
+pub(crate) const OUT_OF_SCOPE: &str = "<out of scope>";
+
+/// One step on the path from the variable at the root of an [`Expression`], to the value that the expression refers to.
+#[derive(Debug, PartialEq)]
+enum Accessor {
+    /// A struct member, e.g. `.field`, or a tuple member, e.g. `.0`.
+    Member(String),
+    /// An array element, e.g. `[2]`.
+    Index(i64),
+    /// A pointer dereference, e.g. `*ptr`.
+    Dereference,
+}
+
+/// An expression that refers to a variable in a stack frame, e.g. `my_struct.field[2]` or `*ptr`, as it is typed in the debug console, or hovered over in the editor.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Expression {
+    root: String,
+    accessors: Vec<Accessor>,
+}
+
+impl Expression {
+    /// `true` if the expression does more than name a variable, which the `evaluate` request can already look up by name.
+    pub(crate) fn has_accessors(&self) -> bool {
+        !self.accessors.is_empty()
+    }
+}
+
+/// Parse an expression of a variable name, followed by any number of `.member` and `[index]` accessors, optionally preceded by `*` to dereference the result.
+/// Like in Rust, the leading `*` applies to the whole expression, i.e. `*a.b` is `*(a.b)`.
+pub(crate) fn parse_expression(expression: &str) -> Result<Expression, DebuggerError> {
+    let expression = expression.trim();
+    let dereferences = expression
+        .chars()
+        .take_while(|character| *character == '*')
+        .count();
+    let mut remainder = expression[dereferences..].trim_start();
+
+    let root_length = identifier_length(remainder);
+    if root_length == 0 {
+        return Err(DebuggerError::Other(anyhow!(
+            "Expected a variable name in `{expression}`"
+        )));
+    }
+    let root = remainder[..root_length].to_string();
+    remainder = &remainder[root_length..];
+
+    let mut accessors = vec![];
+    loop {
+        remainder = remainder.trim_start();
+        if remainder.is_empty() {
+            break;
+        } else if let Some(member) = remainder.strip_prefix('.') {
+            let member = member.trim_start();
+            let member_length = identifier_length(member);
+            if member_length == 0 {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Expected a member name after `.` in `{expression}`"
+                )));
+            }
+            accessors.push(Accessor::Member(member[..member_length].to_string()));
+            remainder = &member[member_length..];
+        } else if let Some(index) = remainder.strip_prefix('[') {
+            let (index, after_index) = index
+                .split_once(']')
+                .ok_or_else(|| DebuggerError::Other(anyhow!("Missing `]` in `{expression}`")))?;
+            let index = parse::<i64>(index.trim()).map_err(|error| {
+                DebuggerError::Other(anyhow!(
+                    "Invalid array index `{}` in `{expression}`: {error}",
+                    index.trim()
+                ))
+            })?;
+            accessors.push(Accessor::Index(index));
+            remainder = after_index;
+        } else {
+            return Err(DebuggerError::Other(anyhow!(
+                "Unexpected `{remainder}` in `{expression}`"
+            )));
+        }
+    }
+    accessors.extend((0..dereferences).map(|_| Accessor::Dereference));
+
+    Ok(Expression { root, accessors })
+}
+
+/// Resolve `expression` against the local and static variables of `stack_frame`.
+/// The deferred children of the variables on the path are cached as they are needed, and the addresses are read from the target.
+///
+/// Returns the variable that the expression refers to, and the cache that holds it, so that aggregates can be expanded by the `variables` request.
+/// Array indices outside the bounds of the array, and null pointers, are reported as errors.
+pub(crate) fn evaluate_expression<'a>(
+    expression: &Expression,
+    stack_frame: &'a mut StackFrame,
+    debug_info: &DebugInfo,
+    core: &mut Core,
+) -> Result<(Variable, &'a mut VariableCache), DebuggerError> {
+    let StackFrame {
+        registers,
+        frame_base,
+        local_variables,
+        static_variables,
+        ..
+    } = stack_frame;
+    let root_name = VariableName::Named(expression.root.clone());
+    let (mut variable, variable_cache) = [local_variables.as_mut(), static_variables.as_mut()]
+        .into_iter()
+        .flatten()
+        .find_map(|variable_cache| {
+            variable_cache
+                .get_variable_by_name(&root_name)
+                .map(|variable| (variable, variable_cache))
+        })
+        .ok_or_else(|| {
+            DebuggerError::Other(anyhow!(
+                "No variable named `{}` in the current stack frame",
+                expression.root
+            ))
+        })?;
+
+    let mut path = expression.root.clone();
+    for accessor in &expression.accessors {
+        if let (Accessor::Member(_), VariableType::Pointer(_)) = (accessor, &variable.type_name) {
+            // Like in Rust, a member of a reference, or pointer, is accessed through the value that it points to.
+            variable = dereference(
+                &mut variable,
+                &path,
+                variable_cache,
+                debug_info,
+                core,
+                registers,
+                *frame_base,
+            )?;
+            path = format!("(*{path})");
+        }
+        variable = match accessor {
+            Accessor::Member(member_name) => {
+                // Tuple members are named `__0`, `__1`, ... in the debug information.
+                let member_names = [
+                    VariableName::Named(member_name.clone()),
+                    VariableName::Named(format!("__{member_name}")),
+                ];
+                child_variables(
+                    &mut variable,
+                    variable_cache,
+                    debug_info,
+                    core,
+                    registers,
+                    *frame_base,
+                )?
+                .into_iter()
+                .find(|child_variable| member_names.contains(&child_variable.name))
+                .ok_or_else(|| {
+                    DebuggerError::Other(anyhow!("`{path}` has no member `{member_name}`"))
+                })?
+            }
+            Accessor::Index(index) => {
+                if !matches!(variable.type_name, VariableType::Array { .. }) {
+                    return Err(DebuggerError::Other(anyhow!(
+                        "`{path}` is not an array, it is {:?}",
+                        variable.type_name
+                    )));
+                }
+                if *index < variable.range_lower_bound || *index >= variable.range_upper_bound {
+                    return Err(DebuggerError::Other(anyhow!(
+                        "Index {index} is out of bounds for `{path}`, which has {} elements",
+                        variable.range_upper_bound - variable.range_lower_bound
+                    )));
+                }
+                let element_name = VariableName::Named(format!("__{index}"));
+                child_variables(
+                    &mut variable,
+                    variable_cache,
+                    debug_info,
+                    core,
+                    registers,
+                    *frame_base,
+                )?
+                .into_iter()
+                .find(|child_variable| child_variable.name == element_name)
+                .ok_or_else(|| {
+                    DebuggerError::Other(anyhow!("Element {index} of `{path}` is not available"))
+                })?
+            }
+            Accessor::Dereference => dereference(
+                &mut variable,
+                &path,
+                variable_cache,
+                debug_info,
+                core,
+                registers,
+                *frame_base,
+            )?,
+        };
+        path = match accessor {
+            Accessor::Member(member_name) => format!("{path}.{member_name}"),
+            Accessor::Index(index) => format!("{path}[{index}]"),
+            Accessor::Dereference => format!("(*{path})"),
+        };
+    }
+
+    Ok((variable, variable_cache))
+}
+
+/// The value that the pointer, or reference, `variable` points to.
+/// A null pointer is an error, rather than a read of the memory at address zero.
+#[allow(clippy::too_many_arguments)]
+fn dereference(
+    variable: &mut Variable,
+    path: &str,
+    variable_cache: &mut VariableCache,
+    debug_info: &DebugInfo,
+    core: &mut Core,
+    registers: &DebugRegisters,
+    frame_base: Option<u64>,
+) -> Result<Variable, DebuggerError> {
+    if !matches!(variable.type_name, VariableType::Pointer(_)) {
+        return Err(DebuggerError::Other(anyhow!(
+            "`{path}` is not a pointer, it is {:?}",
+            variable.type_name
+        )));
+    }
+    if read_pointer_value(core, variable) == Some(0) {
+        return Err(DebuggerError::Other(anyhow!("`{path}` is a null pointer")));
+    }
+    let referenced_variable = child_variables(
+        variable,
+        variable_cache,
+        debug_info,
+        core,
+        registers,
+        frame_base,
+    )?
+    .into_iter()
+    .next()
+    .ok_or_else(|| {
+        DebuggerError::Other(anyhow!(
+            "`{path}` points to a value that cannot be shown, e.g. of the unit type"
+        ))
+    })?;
+    if referenced_variable.memory_location == VariableLocation::Address(0) {
+        return Err(DebuggerError::Other(anyhow!("`{path}` is a null pointer")));
+    }
+    Ok(referenced_variable)
+}
+
+/// The children of `variable`, after caching them if they were deferred.
+fn child_variables(
+    variable: &mut Variable,
+    variable_cache: &mut VariableCache,
+    debug_info: &DebugInfo,
+    core: &mut Core,
+    registers: &DebugRegisters,
+    frame_base: Option<u64>,
+) -> Result<Vec<Variable>, DebuggerError> {
+    if variable.variable_node_type.is_deferred() && !variable_cache.has_children(variable)? {
+        debug_info
+            .cache_deferred_variables(variable_cache, core, variable, registers, frame_base)
+            .map_err(|error| {
+                DebuggerError::Other(anyhow!(
+                    "Failed to read the members of `{}`: {error}",
+                    variable.name
+                ))
+            })?;
+    }
+    Ok(variable_cache.get_children(Some(variable.variable_key))?)
+}
+
+/// Read the address that the pointer `variable` holds, if the pointer itself is stored in memory.
+fn read_pointer_value(core: &mut Core, variable: &Variable) -> Option<u64> {
+    let address = match variable.memory_location {
+        VariableLocation::Address(address) => address,
+        _ => return None,
+    };
+    let byte_size = variable
+        .byte_size
+        .filter(|byte_size| (1..=8).contains(byte_size))?;
+    let mut value_bytes = vec![0_u8; byte_size as usize];
+    core.read_8(address, &mut value_bytes).ok()?;
+    // The value is assumed to be little-endian, like the supported targets.
+    Some(
+        value_bytes
+            .iter()
+            .rev()
+            .fold(0_u64, |value, byte| (value << 8) | *byte as u64),
+    )
+}
+
+/// The length of the identifier at the start of `text`.
+fn identifier_length(text: &str) -> usize {
+    text.find(|character: char| !(character.is_alphanumeric() || character == '_'))
+        .unwrap_or(text.len())
+}
+
+/// The value of a watched expression at the latest halt, and at the halt before that.
+pub(crate) struct WatchValue {
+    /// `None` until the core halts again after the expression was first watched.
+    pub(crate) previous_value: Option<String>,
+    pub(crate) value: String,
+    /// `true` if the client evaluated the expression in the watch pane since the previous halt. See [`prune_watch_values`].
+    pub(crate) requested: bool,
+}
+
+impl WatchValue {
+    /// `true` if `value` is different from the value at the previous halt.
+    /// An expression that comes into scope is not reported as changed.
+    pub(crate) fn changed(&self, value: &str) -> bool {
+        self.previous_value
+            .as_deref()
+            .map_or(false, |previous_value| {
+                previous_value != OUT_OF_SCOPE && previous_value != value
+            })
+    }
+}
+
+/// Remove the expressions that the client did not evaluate since the previous halt, because they were removed from the watch pane,
+/// so that they are not evaluated again on every halt. The remaining expressions have to be requested again before the next halt.
+pub(crate) fn prune_watch_values(watch_values: &mut HashMap<String, WatchValue>) {
+    watch_values.retain(|_, watch_value| watch_value.requested);
+    for watch_value in watch_values.values_mut() {
+        watch_value.requested = false;
+    }
+}
+
+/// The value of the watched `expression` in `stack_frame`, or [`OUT_OF_SCOPE`] if the expression cannot be evaluated in this frame.
+pub(crate) fn watch_value(
+    expression: &str,
+    stack_frame: &mut StackFrame,
+    debug_info: &DebugInfo,
+    core: &mut Core,
+) -> String {
+    if let Some(register_value) = stack_frame
+        .registers
+        .get_register_by_name(expression)
+        .and_then(|register| register.value)
+    {
+        return format!("{register_value}");
+    }
+    match parse_expression(expression)
+        .and_then(|expression| evaluate_expression(&expression, stack_frame, debug_info, core))
+    {
+        Ok((variable, variable_cache)) => variable.get_value(variable_cache),
+        Err(_) => OUT_OF_SCOPE.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch_value(value: &str, requested: bool) -> WatchValue {
+        WatchValue {
+            previous_value: None,
+            value: value.to_string(),
+            requested,
+        }
+    }
+
+    fn parsed(expression: &str) -> Expression {
+        parse_expression(expression).unwrap()
+    }
+
+    #[test]
+    fn variable_name_has_no_accessors() {
+        let expression = parsed(" counter ");
+        assert_eq!(expression.root, "counter");
+        assert!(!expression.has_accessors());
+    }
+
+    #[test]
+    fn member_access_is_parsed() {
+        assert_eq!(
+            parsed("config.uart.baud_rate"),
+            Expression {
+                root: "config".to_string(),
+                accessors: vec![
+                    Accessor::Member("uart".to_string()),
+                    Accessor::Member("baud_rate".to_string()),
+                ],
+            }
+        );
+        assert_eq!(
+            parsed("pair.0").accessors,
+            vec![Accessor::Member("0".to_string())]
+        );
+    }
+
+    #[test]
+    fn array_index_is_parsed() {
+        assert_eq!(parsed("buffer[3]").accessors, vec![Accessor::Index(3)]);
+        assert_eq!(
+            parsed("buffer[ 0x10 ]").accessors,
+            vec![Accessor::Index(16)]
+        );
+    }
+
+    #[test]
+    fn dereference_applies_to_the_whole_expression() {
+        assert_eq!(parsed("*ptr").accessors, vec![Accessor::Dereference]);
+        assert_eq!(
+            parsed("**node.next").accessors,
+            vec![
+                Accessor::Member("next".to_string()),
+                Accessor::Dereference,
+                Accessor::Dereference,
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_accessors_are_parsed_in_order() {
+        assert_eq!(
+            parsed("state.channels[2].samples[10].value"),
+            Expression {
+                root: "state".to_string(),
+                accessors: vec![
+                    Accessor::Member("channels".to_string()),
+                    Accessor::Index(2),
+                    Accessor::Member("samples".to_string()),
+                    Accessor::Index(10),
+                    Accessor::Member("value".to_string()),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for expression in [
+            "",
+            "*",
+            "[1]",
+            "config.",
+            "config..uart",
+            "buffer[1",
+            "buffer[]",
+            "buffer[one]",
+            "a + b",
+            "call()",
+        ] {
+            assert!(
+                parse_expression(expression).is_err(),
+                "`{expression}` should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn watch_values_that_were_not_requested_are_pruned() {
+        let mut watch_values = HashMap::new();
+        watch_values.insert("counter".to_string(), watch_value("1", true));
+        watch_values.insert("removed".to_string(), watch_value("2", false));
+
+        prune_watch_values(&mut watch_values);
+        assert!(watch_values.contains_key("counter"));
+        assert!(!watch_values.contains_key("removed"));
+        assert!(!watch_values["counter"].requested);
+
+        // Without a new request before the next halt, the expression is no longer watched.
+        prune_watch_values(&mut watch_values);
+        assert!(watch_values.is_empty());
+    }
+}
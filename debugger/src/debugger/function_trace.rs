@@ -0,0 +1,113 @@
+use crate::DebuggerError;
+use anyhow::anyhow;
+use goblin::elf::{sym::STT_FUNC, Elf};
+use std::path::Path;
+
+/// A function from the ELF symbol table that matched one of the [`super::configuration::FunctionTraceConfig`] patterns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TraceFunction {
+    /// The demangled function name, e.g. `my_app::module::function`.
+    pub(crate) name: String,
+    /// The entry address of the function, with the Thumb bit cleared.
+    pub(crate) address: u64,
+}
+
+/// Find all the function symbols in `program_binary` where the demangled name matches at least one of `patterns`.
+/// The results are sorted by address, and do not contain duplicate addresses.
+pub(crate) fn find_trace_functions(
+    program_binary: &Path,
+    patterns: &[String],
+) -> Result<Vec<TraceFunction>, DebuggerError> {
+    let elf_data = std::fs::read(program_binary)?;
+    let elf = Elf::parse(&elf_data).map_err(|error| {
+        DebuggerError::Other(anyhow!("Failed to parse {:?}: {error}", program_binary))
+    })?;
+    let mut trace_functions = elf
+        .syms
+        .iter()
+        .filter(|symbol| symbol.st_type() == STT_FUNC && symbol.st_value != 0)
+        .filter_map(|symbol| {
+            let name = demangle(elf.strtab.get_at(symbol.st_name)?);
+            patterns
+                .iter()
+                .any(|pattern| matches_pattern(pattern, &name))
+                .then_some(TraceFunction {
+                    name,
+                    // On ARM, bit 0 of a function address indicates Thumb mode, and is not part of the address.
+                    address: symbol.st_value & !1,
+                })
+        })
+        .collect::<Vec<TraceFunction>>();
+    trace_functions.sort_by_key(|trace_function| trace_function.address);
+    trace_functions.dedup_by_key(|trace_function| trace_function.address);
+    Ok(trace_functions)
+}
+
+/// Demangle a Rust symbol (legacy or `v0` mangling scheme), without the trailing hash.
+/// Other symbols (e.g. C functions) are returned unchanged.
+pub(crate) fn demangle(symbol_name: &str) -> String {
+    format!("{:#}", rustc_demangle::demangle(symbol_name))
+}
+
+/// Match `name` against `pattern`, where `*` in the pattern matches any sequence of characters (including none).
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut pattern_parts = pattern.split('*');
+    // The first part has to match the start of the name.
+    let mut remainder = match pattern_parts
+        .next()
+        .and_then(|first| name.strip_prefix(first))
+    {
+        Some(remainder) => remainder,
+        None => return false,
+    };
+    let mut pattern_parts = pattern_parts.peekable();
+    while let Some(pattern_part) = pattern_parts.next() {
+        if pattern_parts.peek().is_none() {
+            // The last part has to match the end of the name.
+            return remainder.ends_with(pattern_part);
+        }
+        match remainder.find(pattern_part) {
+            Some(position) => remainder = &remainder[position + pattern_part.len()..],
+            None => return false,
+        }
+    }
+    // There were no wildcards, so the pattern has to match the complete name.
+    remainder.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches_pattern;
+
+    #[test]
+    fn pattern_without_wildcard_matches_the_complete_name() {
+        assert!(matches_pattern("app::init", "app::init"));
+        assert!(!matches_pattern("app::init", "app::init_clocks"));
+        assert!(!matches_pattern("app::init", "my_app::init"));
+    }
+
+    #[test]
+    fn leading_and_trailing_wildcards() {
+        assert!(matches_pattern("*::init", "app::init"));
+        assert!(matches_pattern("*::init", "::init"));
+        assert!(!matches_pattern("*::init", "app::init_clocks"));
+        assert!(matches_pattern("app::*", "app::init"));
+        assert!(matches_pattern("app::*", "app::"));
+        assert!(!matches_pattern("app::*", "hal::init"));
+    }
+
+    #[test]
+    fn wildcard_alone_matches_every_name() {
+        assert!(matches_pattern("*", "app::init"));
+        assert!(matches_pattern("*", ""));
+    }
+
+    #[test]
+    fn parts_are_matched_in_sequence() {
+        assert!(matches_pattern("app::*::read*", "app::uart::read_byte"));
+        assert!(matches_pattern("*uart*read*", "app::uart::read_byte"));
+        assert!(!matches_pattern("*read*uart*", "app::uart::read_byte"));
+        // The last part must not overlap with a part that already matched.
+        assert!(!matches_pattern("a*bc*c", "abc"));
+    }
+}
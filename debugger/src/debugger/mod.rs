@@ -1,10 +1,38 @@
+/// Evaluate the conditions of conditional breakpoints.
+pub(crate) mod breakpoint_condition;
 /// All the shared options that control the behaviour of the debugger.
 pub(crate) mod configuration;
 /// The data structures borrowed from the [`session_data::SessionData`], that applies to a specific core.
 pub(crate) mod core_data;
+/// Decode the fault status registers of Cortex-M cores.
+pub(crate) mod cortex_m_faults;
+/// Program the DWT comparators of Cortex-M cores for data watchpoints.
+pub(crate) mod data_watchpoints;
 /// This is where the primary processing for the debugger is driven from.
 pub(crate) mod debug_entry;
 /// The debugger support for rtt.
 pub(crate) mod debug_rtt;
+/// Read and decode the device identification registers of the target.
+pub(crate) mod device_identity;
+/// Resolve symbols from the ELF symbol table, that may not be described by DWARF.
+pub(crate) mod elf_symbols;
+/// Parse and evaluate the expressions of the `evaluate` request, e.g. `my_struct.field[2]` or `*ptr`.
+pub(crate) mod expression;
+/// Locate the functions to be instrumented by [`configuration::FunctionTraceConfig`].
+pub(crate) mod function_trace;
+/// Save and restore the source breakpoints of a program binary across debug sessions.
+pub(crate) mod persisted_breakpoints;
+/// Describe the program binaries of the debug session as DAP modules.
+pub(crate) mod program_modules;
+/// Read the system and FPU registers of a core, for the register groups in the registers view.
+pub(crate) mod register_groups;
+/// Read, write and decode the control and status registers (CSRs) of RISC-V cores.
+pub(crate) mod riscv_csrs;
+/// Show the tasks of an RTOS as threads.
+pub(crate) mod rtos;
+/// Perform the host side of ARM and RISC-V semihosting calls.
+pub(crate) mod semihosting;
 /// The data structures needed to keep track of a [`session_data::SessionData`].
 pub(crate) mod session_data;
+/// Interpret and write new values for the variables that are changed with the `setVariable` request.
+pub(crate) mod set_variable;
@@ -0,0 +1,139 @@
+use super::session_data::{ActiveBreakpoint, BreakpointType};
+use crate::DebuggerError;
+use anyhow::anyhow;
+use probe_rs::debug::ColumnType;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A source breakpoint, as it is saved in the breakpoints file of a program binary, so that it can be set again in the next debug session.
+/// The `line` and `column` are the (one based) location where the breakpoint was verified.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PersistedBreakpoint {
+    /// The path of the source file, as it was requested by the client.
+    pub(crate) source_path: String,
+    /// The name of the source file, as it was requested by the client.
+    pub(crate) source_name: Option<String>,
+    pub(crate) line: u64,
+    pub(crate) column: Option<u64>,
+    pub(crate) condition: Option<String>,
+    pub(crate) hit_condition: Option<String>,
+    pub(crate) log_message: Option<String>,
+}
+
+/// The file where the breakpoints of `program_binary` are saved, e.g. `firmware.elf.breakpoints.json`.
+pub(crate) fn breakpoints_file(program_binary: &Path) -> PathBuf {
+    let mut breakpoints_file = program_binary.as_os_str().to_owned();
+    breakpoints_file.push(".breakpoints.json");
+    PathBuf::from(breakpoints_file)
+}
+
+/// Read the breakpoints from `breakpoints_file`. If the file does not exist (yet), there are no breakpoints.
+pub(crate) fn read_breakpoints(
+    breakpoints_file: &Path,
+) -> Result<Vec<PersistedBreakpoint>, DebuggerError> {
+    if !breakpoints_file.exists() {
+        return Ok(Vec::new());
+    }
+    let breakpoints_data = std::fs::read(breakpoints_file)?;
+    serde_json::from_slice(&breakpoints_data).map_err(|error| {
+        DebuggerError::Other(anyhow!(
+            "Failed to read the saved breakpoints from {breakpoints_file:?}: {error}"
+        ))
+    })
+}
+
+/// Write the source breakpoints of `breakpoints` to `breakpoints_file`. Breakpoints of other types are managed by the debugger, and are not saved.
+pub(crate) fn write_breakpoints(
+    breakpoints_file: &Path,
+    breakpoints: &[ActiveBreakpoint],
+) -> Result<(), DebuggerError> {
+    let persisted_breakpoints = breakpoints
+        .iter()
+        .filter_map(
+            |breakpoint| match breakpoint.breakpoint_type.requested_type() {
+                BreakpointType::SourceBreakpoint(source, source_location, log_message) => {
+                    Some(PersistedBreakpoint {
+                        source_path: source.path.clone()?,
+                        source_name: source.name.clone(),
+                        line: source_location.line?,
+                        column: source_location.column.map(|column| match column {
+                            ColumnType::LeftEdge => 0,
+                            ColumnType::Column(column) => column,
+                        }),
+                        condition: breakpoint.condition.clone(),
+                        hit_condition: breakpoint.hit_condition.clone(),
+                        log_message: log_message.clone(),
+                    })
+                }
+                _ => None,
+            },
+        )
+        .collect::<Vec<_>>();
+    let breakpoints_data = serde_json::to_vec_pretty(&persisted_breakpoints).map_err(|error| {
+        DebuggerError::Other(anyhow!("Failed to serialize the breakpoints: {error}"))
+    })?;
+    std::fs::write(breakpoints_file, breakpoints_data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{breakpoints_file, read_breakpoints, PersistedBreakpoint};
+    use std::path::Path;
+
+    fn persisted_breakpoint() -> PersistedBreakpoint {
+        PersistedBreakpoint {
+            source_path: "/project/src/main.rs".to_string(),
+            source_name: Some("main.rs".to_string()),
+            line: 42,
+            column: Some(5),
+            condition: Some("counter > 3".to_string()),
+            hit_condition: None,
+            log_message: Some("counter = {counter}".to_string()),
+        }
+    }
+
+    #[test]
+    fn breakpoints_file_is_next_to_the_program_binary() {
+        assert_eq!(
+            breakpoints_file(Path::new("/project/target/firmware.elf")),
+            Path::new("/project/target/firmware.elf.breakpoints.json")
+        );
+    }
+
+    #[test]
+    fn breakpoints_file_round_trip() {
+        let breakpoints_file = std::env::temp_dir().join(format!(
+            "probe-rs-debugger-breakpoints-{}.json",
+            std::process::id()
+        ));
+        let breakpoints = vec![persisted_breakpoint()];
+        std::fs::write(
+            &breakpoints_file,
+            serde_json::to_vec_pretty(&breakpoints).unwrap(),
+        )
+        .unwrap();
+
+        let read_back = read_breakpoints(&breakpoints_file);
+        std::fs::remove_file(&breakpoints_file).unwrap();
+        assert_eq!(read_back.unwrap(), breakpoints);
+    }
+
+    #[test]
+    fn breakpoints_file_uses_camel_case_names() {
+        let json = serde_json::to_value(persisted_breakpoint()).unwrap();
+        assert_eq!(json["sourcePath"], "/project/src/main.rs");
+        assert_eq!(json["logMessage"], "counter = {counter}");
+        assert!(json["hitCondition"].is_null());
+    }
+
+    #[test]
+    fn missing_breakpoints_file_has_no_breakpoints() {
+        let breakpoints_file = std::env::temp_dir().join(format!(
+            "probe-rs-debugger-no-breakpoints-{}.json",
+            std::process::id()
+        ));
+        assert!(read_breakpoints(&breakpoints_file).unwrap().is_empty());
+    }
+}
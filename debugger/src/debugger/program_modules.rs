@@ -0,0 +1,108 @@
+use crate::{debug_adapter::dap_types::Module, DebuggerError};
+use anyhow::anyhow;
+use goblin::elf::{program_header::PT_LOAD, Elf};
+use std::{ops::Range, path::Path};
+
+/// The name of the ELF section that holds the DWARF debug information.
+const DEBUG_INFO_SECTION: &str = ".debug_info";
+
+/// The address ranges that the loadable segments of an ELF file occupy on the target.
+/// These are the physical addresses, which are also used to flash the program binary.
+fn load_address_ranges(elf: &Elf) -> Vec<Range<u64>> {
+    elf.program_headers
+        .iter()
+        .filter(|program_header| program_header.p_type == PT_LOAD && program_header.p_filesz > 0)
+        .map(|program_header| {
+            program_header.p_paddr..program_header.p_paddr + program_header.p_filesz
+        })
+        .collect()
+}
+
+/// Describe `program_binary` as a DAP module, with the address ranges of its loadable segments, and whether it has debug information.
+///
+/// Only the `programBinary` of each core is described. Every core has a single program binary, so an image that is
+/// flashed to the same core from a separate file (e.g. a bootloader below the application) is not listed as a module.
+pub(crate) fn program_module(
+    module_id: usize,
+    program_binary: &Path,
+) -> Result<Module, DebuggerError> {
+    let elf_data = std::fs::read(program_binary)?;
+    let elf = Elf::parse(&elf_data).map_err(|error| {
+        DebuggerError::Other(anyhow!("Failed to parse {:?}: {error}", program_binary))
+    })?;
+    let has_debug_info = elf.section_headers.iter().any(|section_header| {
+        elf.shdr_strtab.get_at(section_header.sh_name) == Some(DEBUG_INFO_SECTION)
+    });
+    let address_range = load_address_ranges(&elf)
+        .iter()
+        .map(|range| format!("{:#010x}-{:#010x}", range.start, range.end))
+        .collect::<Vec<String>>()
+        .join(", ");
+    Ok(Module {
+        id: serde_json::Value::from(module_id),
+        name: program_binary.file_name().map_or_else(
+            || program_binary.display().to_string(),
+            |file_name| file_name.to_string_lossy().to_string(),
+        ),
+        path: Some(program_binary.display().to_string()),
+        symbol_status: Some(if has_debug_info {
+            "Symbols loaded".to_string()
+        } else {
+            "No debug information".to_string()
+        }),
+        symbol_file_path: has_debug_info.then(|| program_binary.display().to_string()),
+        address_range: Some(address_range).filter(|address_range| !address_range.is_empty()),
+        is_optimized: None,
+        is_user_code: None,
+        version: None,
+        date_time_stamp: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_address_ranges, program_module};
+    use goblin::elf::Elf;
+    use std::path::PathBuf;
+
+    fn test_binary() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../probe-rs/tests/probe-rs-debugger-test")
+    }
+
+    #[test]
+    fn load_address_ranges_skip_segments_without_file_data() {
+        let elf_data = std::fs::read(test_binary()).unwrap();
+        let elf = Elf::parse(&elf_data).unwrap();
+        // The `.bss` segment is not loaded, and `.data` is loaded at its physical (flash) address.
+        assert_eq!(
+            load_address_ranges(&elf),
+            vec![
+                0x0800_0000..0x0800_0400,
+                0x0800_0400..0x0800_5C2C,
+                0x0800_5C30..0x0800_7400,
+                0x0800_7400..0x0800_7448,
+            ]
+        );
+    }
+
+    #[test]
+    fn program_module_describes_the_binary() {
+        let module = program_module(3, &test_binary()).unwrap();
+        assert_eq!(module.id, serde_json::Value::from(3));
+        assert_eq!(module.name, "probe-rs-debugger-test");
+        assert_eq!(module.symbol_status.as_deref(), Some("Symbols loaded"));
+        assert_eq!(
+            module.address_range.as_deref(),
+            Some("0x08000000-0x08000400, 0x08000400-0x08005c2c, 0x08005c30-0x08007400, 0x08007400-0x08007448")
+        );
+    }
+
+    #[test]
+    fn program_module_rejects_files_that_are_not_elf() {
+        assert!(program_module(
+            0,
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")
+        )
+        .is_err());
+    }
+}
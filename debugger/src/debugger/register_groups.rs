@@ -0,0 +1,299 @@
+use crate::DebuggerError;
+use anyhow::anyhow;
+use probe_rs::{debug::get_sequential_key, Core, CoreType, RegisterDescription};
+
+/// A named group of registers, e.g. the FPU registers, that is shown as a separate scope in the registers view of the DAP client.
+/// Unlike the [`probe_rs::debug::DebugRegisters`] of a stack frame, these registers are not unwound, and always hold the values of the halted core.
+pub(crate) struct RegisterGroup {
+    pub(crate) name: &'static str,
+    /// The `variablesReference` of the scope, so that the `variables` request can find the registers of the group.
+    pub(crate) variables_reference: i64,
+    pub(crate) registers: Vec<GroupRegister>,
+}
+
+/// The name and the value of a register in a [`RegisterGroup`].
+pub(crate) struct GroupRegister {
+    pub(crate) name: String,
+    pub(crate) value: u128,
+    pub(crate) size_in_bits: usize,
+    /// Floating point registers also show the number that they hold.
+    pub(crate) floating_point: bool,
+}
+
+impl GroupRegister {
+    /// Format the value of the register as hexadecimal when `hex` is `true`, and as decimal otherwise.
+    pub(crate) fn formatted_value(&self, hex: bool) -> String {
+        match (self.floating_point, self.size_in_bits, hex) {
+            (true, 32, true) => format!(
+                "{} ({})",
+                format_hex(self.value, self.size_in_bits),
+                f32::from_bits(self.value as u32)
+            ),
+            (true, 32, false) => f32::from_bits(self.value as u32).to_string(),
+            (true, 64, true) => format!(
+                "{} ({})",
+                format_hex(self.value, self.size_in_bits),
+                f64::from_bits(self.value as u64)
+            ),
+            (true, 64, false) => f64::from_bits(self.value as u64).to_string(),
+            (_, _, true) => format_hex(self.value, self.size_in_bits),
+            (_, _, false) => self.value.to_string(),
+        }
+    }
+}
+
+/// Read the system and FPU register groups of a halted core.
+/// The FPU group is omitted for cores without an FPU, because the FPU registers cannot be read on those cores.
+pub(crate) fn read_register_groups(core: &mut Core) -> Result<Vec<RegisterGroup>, DebuggerError> {
+    let mut register_groups = vec![];
+
+    let system_registers = read_system_registers(core)?;
+    if !system_registers.is_empty() {
+        register_groups.push(RegisterGroup {
+            name: "System Registers",
+            variables_reference: get_sequential_key(),
+            registers: system_registers,
+        });
+    }
+
+    if core.registers().fpu_registers().is_some() && core.fpu_support()? {
+        register_groups.push(RegisterGroup {
+            name: "FPU Registers",
+            variables_reference: get_sequential_key(),
+            registers: read_fpu_registers(core)?,
+        });
+    }
+
+    Ok(register_groups)
+}
+
+/// The status and stack pointer registers, and the architecture specific registers, of the core.
+/// On Cortex-M cores, the `EXTRA` register is split into the CONTROL, FAULTMASK, BASEPRI and PRIMASK registers that it combines.
+fn read_system_registers(core: &mut Core) -> Result<Vec<GroupRegister>, DebuggerError> {
+    let register_file = core.registers();
+    let mut registers = vec![];
+
+    for register in [
+        register_file.psr(),
+        register_file.msp(),
+        register_file.psp(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        // Some cores use the same register for the main and process stack pointers.
+        if registers
+            .iter()
+            .any(|existing: &GroupRegister| existing.name == register.name())
+        {
+            continue;
+        }
+        let value: u128 = read_register(core, register)?;
+        registers.push(GroupRegister {
+            name: register.name().to_string(),
+            value,
+            size_in_bits: register.size_in_bits(),
+            floating_point: false,
+        });
+    }
+
+    for register in register_file.other() {
+        let value: u128 = read_register(core, register)?;
+        if register.name() == "EXTRA" {
+            let has_priority_masks = core.core_type() != CoreType::Armv6m;
+            registers.extend(split_extra_register(value, has_priority_masks));
+        } else {
+            registers.push(GroupRegister {
+                name: register.name().to_string(),
+                value,
+                size_in_bits: register.size_in_bits(),
+                floating_point: false,
+            });
+        }
+    }
+
+    Ok(registers)
+}
+
+/// The FPU status register, and the floating point registers of the core.
+/// When the core only describes single precision `S<n>` registers, the double precision `D<n>` registers that overlay them are added as well.
+fn read_fpu_registers(core: &mut Core) -> Result<Vec<GroupRegister>, DebuggerError> {
+    let register_file = core.registers();
+    let mut registers = vec![];
+
+    if let Some(fpscr) = register_file.fpscr() {
+        let value: u128 = read_register(core, fpscr)?;
+        registers.push(GroupRegister {
+            name: fpscr.name().to_string(),
+            value,
+            size_in_bits: fpscr.size_in_bits(),
+            floating_point: false,
+        });
+    }
+
+    let mut single_precision_values = vec![];
+    let mut only_single_precision = true;
+    for register in register_file.fpu_registers().into_iter().flatten() {
+        let value: u128 = read_register(core, register)?;
+        if register.size_in_bits() == 32 && register.name().starts_with('S') {
+            single_precision_values.push(value as u32);
+        } else {
+            only_single_precision = false;
+        }
+        registers.push(GroupRegister {
+            name: register.name().to_string(),
+            value,
+            size_in_bits: register.size_in_bits(),
+            floating_point: true,
+        });
+    }
+
+    if only_single_precision {
+        registers.extend(double_precision_registers(&single_precision_values));
+    }
+
+    Ok(registers)
+}
+
+/// Split the value of the Cortex-M `EXTRA` register into CONTROL bits [31:24], FAULTMASK bits [23:16], BASEPRI bits [15:8], and PRIMASK bits [7:0].
+/// FAULTMASK and BASEPRI are omitted for cores without them (ARMv6-M), when `has_priority_masks` is `false`.
+fn split_extra_register(value: u128, has_priority_masks: bool) -> Vec<GroupRegister> {
+    [
+        ("CONTROL", 24),
+        ("FAULTMASK", 16),
+        ("BASEPRI", 8),
+        ("PRIMASK", 0),
+    ]
+    .into_iter()
+    .filter(|(name, _)| has_priority_masks || !matches!(*name, "FAULTMASK" | "BASEPRI"))
+    .map(|(name, shift)| GroupRegister {
+        name: name.to_string(),
+        value: (value >> shift) & 0xFF,
+        size_in_bits: 8,
+        floating_point: false,
+    })
+    .collect()
+}
+
+/// The double precision `D<n>` registers that overlay the pairs of single precision registers `S<2n>` (low word) and `S<2n+1>` (high word).
+/// An unpaired last single precision register is ignored.
+fn double_precision_registers(single_precision_values: &[u32]) -> Vec<GroupRegister> {
+    single_precision_values
+        .chunks_exact(2)
+        .enumerate()
+        .map(|(index, pair)| GroupRegister {
+            name: format!("D{index}"),
+            value: ((u64::from(pair[1]) << 32) | u64::from(pair[0])).into(),
+            size_in_bits: 64,
+            floating_point: true,
+        })
+        .collect()
+}
+
+/// Read `register` as the largest integer type, so that registers of any size can be read.
+fn read_register(core: &mut Core, register: &RegisterDescription) -> Result<u128, DebuggerError> {
+    core.read_core_reg(register).map_err(|error| {
+        DebuggerError::Other(anyhow!(
+            "Failed to read the {} register: {error}",
+            register.name()
+        ))
+    })
+}
+
+/// Format a register value as zero padded hexadecimal.
+pub(crate) fn format_hex(value: u128, size_in_bits: usize) -> String {
+    format!("{value:#0width$x}", width = size_in_bits / 4 + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{double_precision_registers, split_extra_register, GroupRegister};
+
+    fn register(value: u128, size_in_bits: usize, floating_point: bool) -> GroupRegister {
+        GroupRegister {
+            name: "register".to_string(),
+            value,
+            size_in_bits,
+            floating_point,
+        }
+    }
+
+    #[test]
+    fn integer_register_is_zero_padded_in_hex() {
+        let register = register(0x2a, 32, false);
+        assert_eq!(register.formatted_value(true), "0x0000002a");
+        assert_eq!(register.formatted_value(false), "42");
+    }
+
+    #[test]
+    fn single_precision_register_shows_the_number() {
+        let register = register(1.5_f32.to_bits() as u128, 32, true);
+        assert_eq!(register.formatted_value(true), "0x3fc00000 (1.5)");
+        assert_eq!(register.formatted_value(false), "1.5");
+    }
+
+    #[test]
+    fn double_precision_register_shows_the_number() {
+        let register = register((-2.0_f64).to_bits() as u128, 64, true);
+        assert_eq!(register.formatted_value(true), "0xc000000000000000 (-2)");
+        assert_eq!(register.formatted_value(false), "-2");
+    }
+
+    fn names_and_values(registers: &[GroupRegister]) -> Vec<(&str, u128)> {
+        registers
+            .iter()
+            .map(|register| (register.name.as_str(), register.value))
+            .collect()
+    }
+
+    #[test]
+    fn extra_register_is_split_into_the_special_registers() {
+        let registers = split_extra_register(0x0201_8001, true);
+        assert_eq!(
+            names_and_values(&registers),
+            vec![
+                ("CONTROL", 0x02),
+                ("FAULTMASK", 0x01),
+                ("BASEPRI", 0x80),
+                ("PRIMASK", 0x01)
+            ]
+        );
+        assert!(registers
+            .iter()
+            .all(|register| register.size_in_bits == 8 && !register.floating_point));
+    }
+
+    #[test]
+    fn armv6m_extra_register_has_no_priority_masks() {
+        let registers = split_extra_register(0x0201_8001, false);
+        assert_eq!(
+            names_and_values(&registers),
+            vec![("CONTROL", 0x02), ("PRIMASK", 0x01)]
+        );
+    }
+
+    #[test]
+    fn single_precision_registers_are_paired_into_double_precision_registers() {
+        let low_word = (1.5_f64.to_bits() & 0xFFFF_FFFF) as u32;
+        let high_word = (1.5_f64.to_bits() >> 32) as u32;
+        // S4 has no pair, so there is no D2.
+        let registers = double_precision_registers(&[
+            low_word,
+            high_word,
+            0x1111_1111,
+            0x2222_2222,
+            0x3333_3333,
+        ]);
+        assert_eq!(
+            names_and_values(&registers),
+            vec![
+                ("D0", 1.5_f64.to_bits() as u128),
+                ("D1", 0x2222_2222_1111_1111)
+            ]
+        );
+        assert!(registers
+            .iter()
+            .all(|register| register.size_in_bits == 64 && register.floating_point));
+        assert_eq!(registers[0].formatted_value(false), "1.5");
+    }
+}
@@ -0,0 +1,246 @@
+use crate::DebuggerError;
+use anyhow::anyhow;
+use parse_int::parse;
+use probe_rs::{
+    architecture::riscv::{CsrDescription, MACHINE_CSRS},
+    Core,
+};
+
+/// The number of entries in a vectored trap table that are considered part of the trap vector, when the core halts there.
+const TRAP_VECTOR_ENTRIES: u64 = 64;
+
+/// Find the CSR with `name` (case insensitive) in [`MACHINE_CSRS`], or any other CSR by its address, e.g. `0x7A0`.
+pub(crate) fn find_csr(name: &str) -> Result<CsrDescription, DebuggerError> {
+    if let Some(csr) = MACHINE_CSRS
+        .iter()
+        .find(|csr| csr.name.eq_ignore_ascii_case(name))
+    {
+        return Ok(*csr);
+    }
+    match parse::<u16>(name) {
+        Ok(address) if address <= 0xFFF => Ok(MACHINE_CSRS
+            .iter()
+            .find(|csr| csr.address == address)
+            .copied()
+            .unwrap_or(CsrDescription {
+                name: "csr",
+                address,
+            })),
+        _ => Err(DebuggerError::Other(anyhow!(
+            "`{name}` is not a known CSR name, or a CSR address between 0x000 and 0xFFF"
+        ))),
+    }
+}
+
+/// Read the value of `csr` from a halted RISC-V core.
+pub(crate) fn read_csr(core: &mut Core, csr: &CsrDescription) -> Result<u32, DebuggerError> {
+    core.read_core_reg(csr.id()).map_err(|error| {
+        DebuggerError::Other(anyhow!(
+            "Failed to read the {} CSR ({:#05x}): {error}",
+            csr.name,
+            csr.address
+        ))
+    })
+}
+
+/// Write `value` to `csr` on a halted RISC-V core.
+pub(crate) fn write_csr(
+    core: &mut Core,
+    csr: &CsrDescription,
+    value: u32,
+) -> Result<(), DebuggerError> {
+    core.write_core_reg(csr.id(), value).map_err(|error| {
+        DebuggerError::Other(anyhow!(
+            "Failed to write the {} CSR ({:#05x}): {error}",
+            csr.name,
+            csr.address
+        ))
+    })
+}
+
+/// Decode the most important fields of a CSR value, e.g. the exception name of `mcause`.
+/// Returns `None` for CSRs that do not have fields to decode.
+pub(crate) fn decode_csr(csr: &CsrDescription, value: u32) -> Option<String> {
+    match csr.name {
+        "mcause" => Some(mcause_name(value)),
+        "mstatus" => Some(format!(
+            "MIE={}, MPIE={}, MPP={}",
+            (value >> 3) & 1,
+            (value >> 7) & 1,
+            privilege_name((value >> 11) & 0b11)
+        )),
+        "mtvec" => Some(format!(
+            "base {:#010x}, {} mode",
+            value & !0b11,
+            if value & 0b11 == 1 {
+                "vectored"
+            } else {
+                "direct"
+            }
+        )),
+        "mie" | "mip" => Some(format!(
+            "software={}, timer={}, external={}",
+            (value >> 3) & 1,
+            (value >> 7) & 1,
+            (value >> 11) & 1
+        )),
+        "misa" => {
+            let xlen = match value >> 30 {
+                1 => "RV32",
+                2 => "RV64",
+                3 => "RV128",
+                _ => "RV??",
+            };
+            let extensions = (0..26)
+                .filter(|bit| value & (1 << bit) != 0)
+                .map(|bit| (b'A' + bit as u8) as char)
+                .collect::<String>();
+            Some(format!("{xlen}{extensions}"))
+        }
+        "dcsr" => Some(format!(
+            "cause={}, prv={}, step={}",
+            match (value >> 6) & 0b111 {
+                1 => "ebreak",
+                2 => "trigger",
+                3 => "haltreq",
+                4 => "step",
+                5 => "resethaltreq",
+                _ => "none",
+            },
+            privilege_name(value & 0b11),
+            (value >> 2) & 1
+        )),
+        _ => None,
+    }
+}
+
+/// The name of the trap that is described by the `mcause` value, e.g. `Exception: Illegal instruction`.
+pub(crate) fn mcause_name(mcause: u32) -> String {
+    let code = mcause & 0x7FFF_FFFF;
+    if mcause & 0x8000_0000 != 0 {
+        let name = match code {
+            1 => "Supervisor software interrupt",
+            3 => "Machine software interrupt",
+            5 => "Supervisor timer interrupt",
+            7 => "Machine timer interrupt",
+            9 => "Supervisor external interrupt",
+            11 => "Machine external interrupt",
+            _ => return format!("Interrupt: platform interrupt {code}"),
+        };
+        format!("Interrupt: {name}")
+    } else {
+        let name = match code {
+            0 => "Instruction address misaligned",
+            1 => "Instruction access fault",
+            2 => "Illegal instruction",
+            3 => "Breakpoint",
+            4 => "Load address misaligned",
+            5 => "Load access fault",
+            6 => "Store/AMO address misaligned",
+            7 => "Store/AMO access fault",
+            8 => "Environment call from U-mode",
+            9 => "Environment call from S-mode",
+            11 => "Environment call from M-mode",
+            12 => "Instruction page fault",
+            13 => "Load page fault",
+            15 => "Store/AMO page fault",
+            _ => return format!("Exception: reserved or custom exception {code}"),
+        };
+        format!("Exception: {name}")
+    }
+}
+
+/// The name of a RISC-V privilege level, as it is encoded in `mstatus.MPP` and `dcsr.prv`.
+fn privilege_name(level: u32) -> &'static str {
+    match level {
+        0 => "User",
+        1 => "Supervisor",
+        3 => "Machine",
+        _ => "Reserved",
+    }
+}
+
+/// If the core halted at the trap vector in `mtvec`, read the trap CSRs, and return the name of the trap,
+/// and a summary of `mcause`, `mepc` and `mtval` to show when the core halts.
+/// Returns `None` if the core did not halt at the trap vector, or the CSRs cannot be read.
+pub(crate) fn trap_description(core: &mut Core, program_counter: u64) -> Option<(String, String)> {
+    let mut read = |name: &str| {
+        find_csr(name)
+            .and_then(|csr| read_csr(core, &csr))
+            .map_err(|error| tracing::debug!("{error}"))
+            .ok()
+    };
+    let mtvec = read("mtvec")?;
+    let trap_vector = (mtvec & !0b11) as u64;
+    let trap_vector_size = if mtvec & 0b11 == 1 {
+        4 * TRAP_VECTOR_ENTRIES
+    } else {
+        4
+    };
+    if !(trap_vector..trap_vector + trap_vector_size).contains(&program_counter) {
+        return None;
+    }
+    let mcause = read("mcause")?;
+    let mepc = read("mepc")?;
+    let mtval = read("mtval")?;
+    let trap_name = mcause_name(mcause);
+    let summary =
+        format!("{trap_name} (mcause {mcause:#010x}), at mepc {mepc:#010x}, mtval {mtval:#010x}");
+    Some((trap_name, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_csr, find_csr, mcause_name};
+
+    #[test]
+    fn csrs_are_found_by_name_or_address() {
+        assert_eq!(find_csr("MCAUSE").unwrap().address, 0x342);
+        assert_eq!(find_csr("0x342").unwrap().name, "mcause");
+        let custom = find_csr("0x7A0").unwrap();
+        assert_eq!((custom.name, custom.address), ("csr", 0x7A0));
+        assert!(find_csr("0x1000").is_err());
+        assert!(find_csr("mfoo").is_err());
+    }
+
+    #[test]
+    fn mcause_values_are_named() {
+        for (mcause, expected) in [
+            (0x0000_0002, "Exception: Illegal instruction"),
+            (0x0000_000B, "Exception: Environment call from M-mode"),
+            (0x0000_0018, "Exception: reserved or custom exception 24"),
+            (0x8000_0007, "Interrupt: Machine timer interrupt"),
+            (0x8000_000B, "Interrupt: Machine external interrupt"),
+            (0x8000_0010, "Interrupt: platform interrupt 16"),
+        ] {
+            assert_eq!(mcause_name(mcause), expected, "mcause {mcause:#010x}");
+        }
+    }
+
+    #[test]
+    fn csr_fields_are_decoded() {
+        for (name, value, expected) in [
+            ("mstatus", 0x0000_1888, Some("MIE=1, MPIE=1, MPP=Machine")),
+            ("mstatus", 0x0000_0000, Some("MIE=0, MPIE=0, MPP=User")),
+            ("mtvec", 0x2000_0101, Some("base 0x20000100, vectored mode")),
+            ("mtvec", 0x2000_0100, Some("base 0x20000100, direct mode")),
+            ("mie", 0x0000_0888, Some("software=1, timer=1, external=1")),
+            ("mip", 0x0000_0080, Some("software=0, timer=1, external=0")),
+            ("misa", 0x4000_1105, Some("RV32ACIM")),
+            (
+                "dcsr",
+                0x0000_00C3,
+                Some("cause=haltreq, prv=Machine, step=0"),
+            ),
+            ("dcsr", 0x0000_0107, Some("cause=step, prv=Machine, step=1")),
+            ("mepc", 0x0800_0000, None),
+        ] {
+            let csr = find_csr(name).unwrap();
+            assert_eq!(
+                decode_csr(&csr, value).as_deref(),
+                expected,
+                "{name} {value:#010x}"
+            );
+        }
+    }
+}
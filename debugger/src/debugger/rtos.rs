@@ -0,0 +1,300 @@
+use super::{
+    configuration::Rtos,
+    elf_symbols::{find_elf_symbol, ElfSymbol},
+};
+use crate::DebuggerError;
+use anyhow::anyhow;
+use probe_rs::{debug::debug_info::DebugInfo, Core, MemoryInterface};
+use std::fmt::Display;
+
+/// The lowest thread id of an RTOS task that is not running on the core. The running task uses the core index as its thread id,
+/// so that requests like `continue` and `stackTrace` continue to refer to the core. See [`RtosTask::thread_id`].
+pub(crate) const FIRST_TASK_THREAD_ID: i64 = 1000;
+
+/// The maximum number of tasks that are read from a single task list, to guard against corrupt, or not yet initialized, lists.
+const MAX_TASKS_PER_LIST: u32 = 256;
+
+/// The maximum length of a task name. FreeRTOS uses `configMAX_TASK_NAME_LEN`, which is 16 by default.
+const MAX_TASK_NAME_LENGTH: usize = 32;
+
+/// The state of an RTOS task, as it is shown in the name of its thread.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TaskState {
+    Running,
+    Ready,
+    Blocked,
+    Suspended,
+    /// The task was deleted, but its memory has not been freed yet by the idle task.
+    Deleted,
+}
+
+impl Display for TaskState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskState::Running => write!(f, "Running"),
+            TaskState::Ready => write!(f, "Ready"),
+            TaskState::Blocked => write!(f, "Blocked"),
+            TaskState::Suspended => write!(f, "Suspended"),
+            TaskState::Deleted => write!(f, "Deleted"),
+        }
+    }
+}
+
+/// A task of the RTOS, that is shown as a thread.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RtosTask {
+    /// The address of the task control block.
+    pub(crate) tcb_address: u64,
+    pub(crate) name: String,
+    pub(crate) state: TaskState,
+    pub(crate) priority: Option<u32>,
+}
+
+impl RtosTask {
+    /// The id of the DAP thread for this task. It is derived from the address of the task control block,
+    /// so that a task keeps its thread id when it moves between the task lists.
+    pub(crate) fn thread_id(&self) -> i64 {
+        FIRST_TASK_THREAD_ID + self.tcb_address as i64
+    }
+
+    /// The name of the DAP thread for this task, e.g. `IDLE (Ready, priority 0)`.
+    pub(crate) fn thread_name(&self) -> String {
+        match self.priority {
+            Some(priority) => format!("{} ({}, priority {priority})", self.name, self.state),
+            None => format!("{} ({})", self.name, self.state),
+        }
+    }
+}
+
+/// Read the tasks of `rtos` from the target.
+/// The layout of the kernel data structures is read from `debug_info` on the first call, and kept in `layout`,
+/// because it takes several scans of the debug information. The `layout` has to be reset when the debug information changes.
+pub(crate) fn read_rtos_tasks(
+    rtos: Rtos,
+    core: &mut Core,
+    debug_info: &DebugInfo,
+    elf_symbols: &[ElfSymbol],
+    layout: &mut Option<Result<FreeRtosLayout, String>>,
+) -> Result<Vec<RtosTask>, DebuggerError> {
+    match rtos {
+        Rtos::FreeRtos => {
+            let layout = layout.get_or_insert_with(|| {
+                FreeRtosLayout::from_debug_info(debug_info).map_err(|error| error.to_string())
+            });
+            match layout {
+                Ok(layout) => layout.read_tasks(core, elf_symbols),
+                Err(error) => Err(DebuggerError::Other(anyhow!("{error}"))),
+            }
+        }
+    }
+}
+
+/// The offsets of the members of the FreeRTOS kernel data structures, which depend on the FreeRTOS configuration, and are therefore read from the debug information.
+pub(crate) struct FreeRtosLayout {
+    /// The size of a `List_t`, to index into `pxReadyTasksLists`.
+    list_size: u64,
+    /// `List_t::uxNumberOfItems`
+    list_number_of_items: u64,
+    /// `List_t::xListEnd`, the marker at the end (and start) of the list.
+    list_end: u64,
+    /// `ListItem_t::pxNext`, which has the same offset in a `MiniListItem_t`.
+    list_item_next: u64,
+    /// `ListItem_t::pvOwner`, which points to the task control block.
+    list_item_owner: u64,
+    /// `TCB_t::pcTaskName`
+    task_name: u64,
+    /// `TCB_t::uxPriority`, and its size in bytes, which depends on `UBaseType_t`.
+    task_priority: Option<(u64, u64)>,
+}
+
+impl FreeRtosLayout {
+    fn from_debug_info(debug_info: &DebugInfo) -> Result<Self, DebuggerError> {
+        let member_offset = |struct_name: &str, member_name: &str| {
+            debug_info
+                .struct_member_offset(struct_name, member_name)
+                .ok_or_else(|| {
+                    DebuggerError::Other(anyhow!(
+                        "The FreeRTOS structure member `{struct_name}::{member_name}` was not found in the debug information"
+                    ))
+                })
+        };
+        Ok(FreeRtosLayout {
+            list_size: debug_info.struct_byte_size("xLIST").ok_or_else(|| {
+                DebuggerError::Other(anyhow!(
+                    "The FreeRTOS structure `xLIST` was not found in the debug information"
+                ))
+            })?,
+            list_number_of_items: member_offset("xLIST", "uxNumberOfItems")?,
+            list_end: member_offset("xLIST", "xListEnd")?,
+            list_item_next: member_offset("xLIST_ITEM", "pxNext")?,
+            list_item_owner: member_offset("xLIST_ITEM", "pvOwner")?,
+            task_name: member_offset("tskTaskControlBlock", "pcTaskName")?,
+            task_priority: debug_info
+                .struct_member_offset("tskTaskControlBlock", "uxPriority")
+                .map(|offset| {
+                    let byte_size = debug_info
+                        .struct_member_byte_size("tskTaskControlBlock", "uxPriority")
+                        .unwrap_or(4);
+                    (offset, byte_size)
+                }),
+        })
+    }
+
+    /// Read the tasks from all the task lists of the kernel. The task that `pxCurrentTCB` points to is reported as running.
+    fn read_tasks(
+        &self,
+        core: &mut Core,
+        elf_symbols: &[ElfSymbol],
+    ) -> Result<Vec<RtosTask>, DebuggerError> {
+        let current_tcb_address = match find_elf_symbol(elf_symbols, "pxCurrentTCB") {
+            Some(symbol) => core.read_word_32(symbol.address)? as u64,
+            None => {
+                return Err(DebuggerError::Other(anyhow!(
+                    "The FreeRTOS symbol `pxCurrentTCB` was not found"
+                )))
+            }
+        };
+
+        let mut task_lists = vec![];
+        if let Some(ready_lists) = find_elf_symbol(elf_symbols, "pxReadyTasksLists") {
+            // There is one ready list for each priority, up to `configMAX_PRIORITIES`.
+            let priority_count = ready_lists.size / self.list_size.max(1);
+            for priority in 0..priority_count {
+                task_lists.push((
+                    ready_lists.address + priority * self.list_size,
+                    TaskState::Ready,
+                ));
+            }
+        }
+        for (symbol_name, state) in [
+            ("xDelayedTaskList1", TaskState::Blocked),
+            ("xDelayedTaskList2", TaskState::Blocked),
+            ("xPendingReadyList", TaskState::Ready),
+            ("xSuspendedTaskList", TaskState::Suspended),
+            ("xTasksWaitingTermination", TaskState::Deleted),
+        ] {
+            if let Some(symbol) = find_elf_symbol(elf_symbols, symbol_name) {
+                task_lists.push((symbol.address, state));
+            }
+        }
+
+        let mut tasks: Vec<RtosTask> = vec![];
+        for (list_address, state) in task_lists {
+            for tcb_address in self.read_list_owners(core, list_address)? {
+                // A task can be in more than one list, e.g. a task that is both delayed and waiting for an event.
+                if tasks.iter().any(|task| task.tcb_address == tcb_address) {
+                    continue;
+                }
+                let mut name_bytes = [0_u8; MAX_TASK_NAME_LENGTH];
+                core.read_8(tcb_address + self.task_name, &mut name_bytes)?;
+                let name_length = name_bytes
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .unwrap_or(name_bytes.len());
+                let priority = match self.task_priority {
+                    Some((offset, byte_size)) => {
+                        let mut priority_bytes = vec![0_u8; byte_size.min(8) as usize];
+                        core.read_8(tcb_address + offset, &mut priority_bytes)?;
+                        decode_priority(&priority_bytes)
+                    }
+                    None => None,
+                };
+                tasks.push(RtosTask {
+                    tcb_address,
+                    name: String::from_utf8_lossy(&name_bytes[..name_length]).into_owned(),
+                    state: if tcb_address == current_tcb_address {
+                        TaskState::Running
+                    } else {
+                        state
+                    },
+                    priority,
+                });
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Read the `pvOwner` of each item in the `List_t` at `list_address`.
+    fn read_list_owners(
+        &self,
+        core: &mut Core,
+        list_address: u64,
+    ) -> Result<Vec<u64>, DebuggerError> {
+        let number_of_items = core
+            .read_word_32(list_address + self.list_number_of_items)?
+            .min(MAX_TASKS_PER_LIST);
+        let list_end_address = list_address + self.list_end;
+        let mut owners = vec![];
+        let mut item_address = core.read_word_32(list_end_address + self.list_item_next)? as u64;
+        for _ in 0..number_of_items {
+            if item_address == list_end_address || item_address == 0 {
+                break;
+            }
+            let owner = core.read_word_32(item_address + self.list_item_owner)? as u64;
+            if owner != 0 {
+                owners.push(owner);
+            }
+            item_address = core.read_word_32(item_address + self.list_item_next)? as u64;
+        }
+        Ok(owners)
+    }
+}
+
+/// Decode a little endian `uxPriority` of 1, 2, 4 or 8 bytes. Other sizes, and priorities that do not fit in a `u32`, are not decoded.
+fn decode_priority(priority_bytes: &[u8]) -> Option<u32> {
+    match priority_bytes.len() {
+        1 => Some(priority_bytes[0] as u32),
+        2 => Some(u16::from_le_bytes(priority_bytes.try_into().ok()?) as u32),
+        4 => Some(u32::from_le_bytes(priority_bytes.try_into().ok()?)),
+        8 => u32::try_from(u64::from_le_bytes(priority_bytes.try_into().ok()?)).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_priority, RtosTask, TaskState, FIRST_TASK_THREAD_ID};
+
+    fn task(tcb_address: u64, state: TaskState, priority: Option<u32>) -> RtosTask {
+        RtosTask {
+            tcb_address,
+            name: "IDLE".to_string(),
+            state,
+            priority,
+        }
+    }
+
+    #[test]
+    fn thread_name_shows_state_and_priority() {
+        assert_eq!(
+            task(0x2000_0100, TaskState::Ready, Some(0)).thread_name(),
+            "IDLE (Ready, priority 0)"
+        );
+        assert_eq!(
+            task(0x2000_0100, TaskState::Blocked, None).thread_name(),
+            "IDLE (Blocked)"
+        );
+    }
+
+    #[test]
+    fn thread_id_does_not_depend_on_the_task_state() {
+        let ready_task = task(0x2000_0100, TaskState::Ready, Some(1));
+        let blocked_task = task(0x2000_0100, TaskState::Blocked, Some(1));
+        assert_eq!(ready_task.thread_id(), blocked_task.thread_id());
+        assert_eq!(ready_task.thread_id(), FIRST_TASK_THREAD_ID + 0x2000_0100);
+        assert_ne!(
+            ready_task.thread_id(),
+            task(0x2000_0200, TaskState::Ready, Some(1)).thread_id()
+        );
+    }
+
+    #[test]
+    fn priority_is_decoded_by_its_size() {
+        assert_eq!(decode_priority(&[5]), Some(5));
+        assert_eq!(decode_priority(&[0x02, 0x01]), Some(0x0102));
+        assert_eq!(decode_priority(&[7, 0, 0, 0]), Some(7));
+        assert_eq!(decode_priority(&[3, 0, 0, 0, 0, 0, 0, 0]), Some(3));
+        assert_eq!(decode_priority(&[0, 0, 0, 0, 1, 0, 0, 0]), None);
+        assert_eq!(decode_priority(&[1, 2, 3]), None);
+    }
+}
@@ -0,0 +1,351 @@
+use crate::DebuggerError;
+use probe_rs::{Architecture, Core, MemoryInterface};
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+/// The semihosting operation numbers that are currently supported.
+const SYS_OPEN: u32 = 0x01;
+const SYS_CLOSE: u32 = 0x02;
+const SYS_WRITEC: u32 = 0x03;
+const SYS_WRITE0: u32 = 0x04;
+const SYS_WRITE: u32 = 0x05;
+
+/// The Thumb `BKPT #0xAB` instruction, in little endian byte order, that is used for semihosting calls on Cortex-M.
+const THUMB_SEMIHOSTING_INSTRUCTION: [u8; 2] = [0xAB, 0xBE];
+/// The RISC-V semihosting sequence of `slli x0, x0, 0x1f`, `ebreak` and `srai x0, x0, 7`. The core halts at the `ebreak`.
+const RISCV_SEMIHOSTING_SEQUENCE: [u32; 3] = [0x01F0_1013, 0x0010_0073, 0x4070_5013];
+/// The maximum length of a string that is written with `SYS_WRITE0`, to avoid reading memory indefinitely if the string is not terminated.
+const MAX_WRITE0_LENGTH: usize = 4096;
+/// The maximum length of a file name for `SYS_OPEN`. Longer names are rejected, instead of allocating the buffer for them.
+const MAX_FILE_NAME_LENGTH: u32 = 4096;
+/// The maximum length of the data of a single `SYS_WRITE`. Longer writes are rejected, instead of allocating the buffer for them.
+const MAX_WRITE_LENGTH: u32 = 1024 * 1024;
+/// The special file name that is used to open the debug console, instead of a file on the host.
+const CONSOLE_FILE_NAME: &str = ":tt";
+
+/// A semihosting call, that was detected when the core halted.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SemihostingCall {
+    /// The operation number, from `R0` on ARM, or `a0` on RISC-V.
+    pub(crate) operation: u32,
+    /// The parameter, or the address of the parameter block, from `R1` on ARM, or `a1` on RISC-V.
+    pub(crate) parameter: u32,
+    /// The address of the instruction after the semihosting instruction, where the core has to resume.
+    pub(crate) resume_address: u64,
+}
+
+/// The result of a semihosting call, that has been performed by the debugger.
+#[derive(Debug)]
+pub(crate) struct SemihostingResult {
+    /// The value that is returned to the target, in `R0` on ARM, or `a0` on RISC-V.
+    pub(crate) return_value: i32,
+    /// The text that was written to the console by the target, and the DAP `output` category (`stdout` or `stderr`) to use for it.
+    pub(crate) output: Option<(String, &'static str)>,
+}
+
+/// The parameter block of `SYS_OPEN`.
+#[derive(Debug, PartialEq, Eq)]
+struct OpenParameters {
+    name_address: u64,
+    mode: u32,
+    name_length: usize,
+}
+
+impl OpenParameters {
+    /// Decode the parameter block of three words. Returns `None` if the name is longer than [`MAX_FILE_NAME_LENGTH`].
+    fn decode([name_address, mode, name_length]: [u32; 3]) -> Option<Self> {
+        (name_length <= MAX_FILE_NAME_LENGTH).then_some(OpenParameters {
+            name_address: name_address as u64,
+            mode,
+            name_length: name_length as usize,
+        })
+    }
+}
+
+/// The parameter block of `SYS_WRITE`.
+#[derive(Debug, PartialEq, Eq)]
+struct WriteParameters {
+    handle: u32,
+    data_address: u64,
+    data_length: usize,
+}
+
+impl WriteParameters {
+    /// Decode the parameter block of three words. Returns `None` if the data is longer than [`MAX_WRITE_LENGTH`].
+    fn decode([handle, data_address, data_length]: [u32; 3]) -> Option<Self> {
+        (data_length <= MAX_WRITE_LENGTH).then_some(WriteParameters {
+            handle,
+            data_address: data_address as u64,
+            data_length: data_length as usize,
+        })
+    }
+}
+
+/// The files, and the console streams, that have been opened by the target with `SYS_OPEN`.
+enum SemihostingHandle {
+    /// `:tt` opened for reading. Reading from the console is not supported, but the handle can be opened and closed.
+    ConsoleInput,
+    /// `:tt` opened for writing, with the DAP `output` category (`stdout` or `stderr`) to use.
+    ConsoleOutput(&'static str),
+    File(File),
+}
+
+/// The host side state of the semihosting calls of a core.
+pub(crate) struct SemihostingFiles {
+    /// The directory that relative file names are resolved against.
+    cwd: Option<PathBuf>,
+    handles: HashMap<u32, SemihostingHandle>,
+    next_handle: u32,
+}
+
+/// Detect if the core halted at `program_counter` because of a semihosting call, and read the operation and its parameter.
+/// Returns `None` if the instruction at `program_counter` is not a semihosting call, or the registers cannot be read.
+pub(crate) fn semihosting_call(core: &mut Core, program_counter: u64) -> Option<SemihostingCall> {
+    let resume_address = match core.architecture() {
+        Architecture::Arm if core.core_type().is_cortex_m() => {
+            let mut instruction = [0_u8; 2];
+            core.read_8(program_counter, &mut instruction).ok()?;
+            if instruction != THUMB_SEMIHOSTING_INSTRUCTION {
+                return None;
+            }
+            program_counter + 2
+        }
+        Architecture::Riscv => {
+            let mut instructions = [0_u32; 3];
+            core.read_32(program_counter.checked_sub(4)?, &mut instructions)
+                .ok()?;
+            if instructions != RISCV_SEMIHOSTING_SEQUENCE {
+                return None;
+            }
+            program_counter + 4
+        }
+        _ => return None,
+    };
+    let operation = core
+        .read_core_reg(core.registers().argument_register(0))
+        .ok()?;
+    let parameter = core
+        .read_core_reg(core.registers().argument_register(1))
+        .ok()?;
+    Some(SemihostingCall {
+        operation,
+        parameter,
+        resume_address,
+    })
+}
+
+impl SemihostingFiles {
+    pub(crate) fn new(cwd: Option<PathBuf>) -> Self {
+        SemihostingFiles {
+            cwd,
+            handles: HashMap::new(),
+            next_handle: 1,
+        }
+    }
+
+    /// Perform the host side of the semihosting `call`.
+    /// Returns `Ok(None)` if the operation is not supported, in which case the caller should report the halt to the debug client.
+    /// Errors are only returned if the target memory could not be accessed. Failed file operations, and names or data that are too long,
+    /// return an error value to the target.
+    pub(crate) fn perform_call(
+        &mut self,
+        core: &mut Core,
+        call: &SemihostingCall,
+    ) -> Result<Option<SemihostingResult>, DebuggerError> {
+        let parameter = call.parameter as u64;
+        let result = match call.operation {
+            SYS_OPEN => {
+                let mut parameters = [0_u32; 3];
+                core.read_32(parameter, &mut parameters)?;
+                let return_value = match OpenParameters::decode(parameters) {
+                    Some(open_parameters) => {
+                        let mut name = vec![0_u8; open_parameters.name_length];
+                        core.read_8(open_parameters.name_address, &mut name)?;
+                        self.open(&String::from_utf8_lossy(&name), open_parameters.mode)
+                    }
+                    None => {
+                        tracing::warn!("Semihosting open failed, because the file name is longer than {MAX_FILE_NAME_LENGTH} bytes");
+                        -1
+                    }
+                };
+                SemihostingResult {
+                    return_value,
+                    output: None,
+                }
+            }
+            SYS_CLOSE => {
+                let handle = core.read_word_32(parameter)?;
+                SemihostingResult {
+                    return_value: if self.handles.remove(&handle).is_some() {
+                        0
+                    } else {
+                        -1
+                    },
+                    output: None,
+                }
+            }
+            SYS_WRITEC => {
+                let character = core.read_word_8(parameter)?;
+                SemihostingResult {
+                    return_value: 0,
+                    output: Some(((character as char).to_string(), "stdout")),
+                }
+            }
+            SYS_WRITE0 => {
+                let mut text = vec![];
+                let mut address = parameter;
+                while text.len() < MAX_WRITE0_LENGTH {
+                    match core.read_word_8(address)? {
+                        0 => break,
+                        character => text.push(character),
+                    }
+                    address += 1;
+                }
+                SemihostingResult {
+                    return_value: 0,
+                    output: Some((String::from_utf8_lossy(&text).into_owned(), "stdout")),
+                }
+            }
+            SYS_WRITE => {
+                let mut parameters = [0_u32; 3];
+                core.read_32(parameter, &mut parameters)?;
+                let WriteParameters {
+                    handle,
+                    data_address,
+                    data_length,
+                } = match WriteParameters::decode(parameters) {
+                    Some(write_parameters) => write_parameters,
+                    None => {
+                        tracing::warn!("Semihosting write failed, because the data is longer than {MAX_WRITE_LENGTH} bytes");
+                        return Ok(Some(SemihostingResult {
+                            return_value: -1,
+                            output: None,
+                        }));
+                    }
+                };
+                let mut data = vec![0_u8; data_length];
+                core.read_8(data_address, &mut data)?;
+                // On success, the number of bytes that were *not* written is returned.
+                match self.handles.get_mut(&handle) {
+                    Some(SemihostingHandle::ConsoleOutput(category)) => SemihostingResult {
+                        return_value: 0,
+                        output: Some((String::from_utf8_lossy(&data).into_owned(), *category)),
+                    },
+                    Some(SemihostingHandle::File(file)) => SemihostingResult {
+                        return_value: match file.write_all(&data) {
+                            Ok(()) => 0,
+                            Err(error) => {
+                                tracing::warn!("Semihosting write failed: {error}");
+                                data_length as i32
+                            }
+                        },
+                        output: None,
+                    },
+                    Some(SemihostingHandle::ConsoleInput) | None => SemihostingResult {
+                        return_value: data_length as i32,
+                        output: None,
+                    },
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(result))
+    }
+
+    /// Open the file `name`, or the console if it is `:tt`, with the `mode` of `SYS_OPEN`, which corresponds to the ISO C `fopen` modes
+    /// `r`, `rb`, `r+`, `r+b`, `w`, `wb`, `w+`, `w+b`, `a`, `ab`, `a+` and `a+b`. Returns the new handle, or `-1` if the file cannot be opened.
+    fn open(&mut self, name: &str, mode: u32) -> i32 {
+        let semihosting_handle = if name == CONSOLE_FILE_NAME {
+            match mode {
+                0..=3 => SemihostingHandle::ConsoleInput,
+                4..=7 => SemihostingHandle::ConsoleOutput("stdout"),
+                _ => SemihostingHandle::ConsoleOutput("stderr"),
+            }
+        } else {
+            let path = match &self.cwd {
+                Some(cwd) => cwd.join(name),
+                None => PathBuf::from(name),
+            };
+            let mut options = OpenOptions::new();
+            let update = mode & 0b10 != 0;
+            match mode >> 2 {
+                0 => options.read(true).write(update),
+                1 => options.write(true).read(update).create(true).truncate(true),
+                _ => options.append(true).read(update).create(true),
+            };
+            match options.open(&path) {
+                Ok(file) => SemihostingHandle::File(file),
+                Err(error) => {
+                    tracing::warn!("Semihosting failed to open {:?}: {error}", path);
+                    return -1;
+                }
+            }
+        };
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.handles.insert(handle, semihosting_handle);
+        handle as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_parameters_are_decoded_and_the_name_length_is_capped() {
+        assert_eq!(
+            OpenParameters::decode([0x2000_0100, 4, 3]),
+            Some(OpenParameters {
+                name_address: 0x2000_0100,
+                mode: 4,
+                name_length: 3,
+            })
+        );
+        assert!(OpenParameters::decode([0x2000_0100, 0, MAX_FILE_NAME_LENGTH]).is_some());
+        assert_eq!(
+            OpenParameters::decode([0x2000_0100, 0, MAX_FILE_NAME_LENGTH + 1]),
+            None
+        );
+        assert_eq!(OpenParameters::decode([0x2000_0100, 0, u32::MAX]), None);
+    }
+
+    #[test]
+    fn write_parameters_are_decoded_and_the_data_length_is_capped() {
+        assert_eq!(
+            WriteParameters::decode([1, 0x2000_0200, 16]),
+            Some(WriteParameters {
+                handle: 1,
+                data_address: 0x2000_0200,
+                data_length: 16,
+            })
+        );
+        assert!(WriteParameters::decode([1, 0x2000_0200, MAX_WRITE_LENGTH]).is_some());
+        assert_eq!(WriteParameters::decode([1, 0x2000_0200, u32::MAX]), None);
+    }
+
+    #[test]
+    fn console_is_opened_with_the_category_of_the_mode() {
+        let mut semihosting_files = SemihostingFiles::new(None);
+        let input = semihosting_files.open(CONSOLE_FILE_NAME, 0);
+        let output = semihosting_files.open(CONSOLE_FILE_NAME, 4);
+        let error = semihosting_files.open(CONSOLE_FILE_NAME, 8);
+        assert_eq!((input, output, error), (1, 2, 3));
+        assert!(matches!(
+            semihosting_files.handles.get(&1),
+            Some(SemihostingHandle::ConsoleInput)
+        ));
+        assert!(matches!(
+            semihosting_files.handles.get(&2),
+            Some(SemihostingHandle::ConsoleOutput("stdout"))
+        ));
+        assert!(matches!(
+            semihosting_files.handles.get(&3),
+            Some(SemihostingHandle::ConsoleOutput("stderr"))
+        ));
+    }
+}
@@ -1,18 +1,36 @@
 use super::{
-    configuration::{self, CoreConfig, SessionConfig},
-    core_data::{CoreData, CoreHandle},
+    configuration::{self, CoreConfig, ErrorClass, SessionConfig},
+    core_data::{CoreData, CoreHandle, DEFAULT_STEP_TIMEOUT_MS},
+    debug_rtt,
+    elf_symbols::{self, ElfSymbol},
+    persisted_breakpoints, semihosting,
 };
 use crate::{
-    debug_adapter::{dap_adapter::DebugAdapter, dap_types::Source, protocol::ProtocolAdapter},
+    debug_adapter::{
+        dap_adapter::DebugAdapter,
+        dap_types::{MessageSeverity, Source},
+        protocol::ProtocolAdapter,
+    },
     DebuggerError,
 };
 use anyhow::{anyhow, Result};
 use probe_rs::{
-    config::TargetSelector,
+    architecture::arm::{
+        dp::{Ctrl, DebugPortError, DPIDR},
+        ArmError, ArmProbeInterface, DapAccess, DpAddress, Register,
+    },
+    config::{ArmCoreAccessOptions, CoreAccessOptions, MemoryRegion, RamRegion, TargetSelector},
     debug::{debug_info::DebugInfo, SourceLocation},
-    CoreStatus, DebugProbeError, Permissions, Probe, ProbeCreationError, Session,
+    CoreDump, CoreState, CoreStatus, DebugProbeError, DebugProbeInfo, DumpedStackFrame, Error,
+    Permissions, Probe, ProbeCreationError, Session,
+};
+use std::{
+    collections::HashMap,
+    env::set_current_dir,
+    fs::File,
+    path::{Path, PathBuf},
+    time::Duration,
 };
-use std::env::set_current_dir;
 use time::UtcOffset;
 
 /// The supported breakpoint types
@@ -23,23 +41,119 @@ pub enum BreakpointType {
     InstructionBreakpoint,
     /// A breakpoint was requested using a source location, and usually a result of a user requesting a
     /// breakpoint while in a 'source' view.
-    SourceBreakpoint(Source, SourceLocation),
+    /// If it has a log message, it is a logpoint, which logs the message to the debug console and resumes the core,
+    /// instead of halting the debug session.
+    SourceBreakpoint(Source, SourceLocation, Option<String>),
+    /// A breakpoint that is used to count how often a source location is executed. It does not halt the debug session,
+    /// and is configured using [`configuration::CoverageConfig`].
+    CoverageBreakpoint(SourceLocation),
+    /// A breakpoint at the entry of a function, which logs the function name before the core is resumed.
+    /// It is configured using [`configuration::FunctionTraceConfig`].
+    FunctionTraceBreakpoint(String),
+    /// A breakpoint that is set by a `goto` request, to run to a source location without setting a permanent breakpoint.
+    /// It is cleared as soon as the core halts, and is not recomputed when a new binary is flashed.
+    TemporaryBreakpoint,
+    /// A breakpoint at the entry of a function that is configured in [`configuration::CoreConfig::catchpoints`], e.g. the panic handler.
+    /// The client does not know about these breakpoints, so the halt is reported as a caught panic, without a breakpoint id.
+    Catchpoint(String),
+    /// A breakpoint of the requested type, that is implemented by replacing the instruction at its address with a `BKPT` instruction,
+    /// because all the hardware breakpoint units are in use. The original instruction bytes are saved, so that they can be restored
+    /// when the breakpoint is cleared. It is only used if [`configuration::CoreConfig::software_breakpoint_fallback`] is enabled.
+    SoftwareBreakpoint(Box<BreakpointType>, Vec<u8>),
+}
+
+impl BreakpointType {
+    /// The type of breakpoint that was requested, regardless of how it is implemented on the target.
+    pub(crate) fn requested_type(&self) -> &BreakpointType {
+        match self {
+            BreakpointType::SoftwareBreakpoint(requested_type, _) => {
+                requested_type.requested_type()
+            }
+            other_type => other_type,
+        }
+    }
+
+    /// The original instruction bytes that were replaced by a software breakpoint, or `None` for hardware breakpoints.
+    pub(crate) fn original_instruction(&self) -> Option<&[u8]> {
+        match self {
+            BreakpointType::SoftwareBreakpoint(_, original_instruction) => {
+                Some(original_instruction)
+            }
+            _ => None,
+        }
+    }
 }
 
 /// Provide the storage and methods to handle various [`BreakpointType`]
 #[derive(Clone, Debug)]
 pub struct ActiveBreakpoint {
+    /// The id that identifies this breakpoint to the debug client. It is unique, even for breakpoints that share an address.
+    pub(crate) id: i64,
     pub(crate) breakpoint_type: BreakpointType,
+    /// An optional condition, that has to evaluate to `true` before the debug session halts at this breakpoint.
+    /// See [`super::breakpoint_condition::evaluate_condition`] for the supported syntax.
+    pub(crate) condition: Option<String>,
+    /// An optional hit condition, that controls how many hits of the breakpoint are ignored before the debug session halts.
+    /// See [`super::breakpoint_condition::evaluate_hit_condition`] for the supported syntax.
+    pub(crate) hit_condition: Option<String>,
     pub(crate) address: u64,
+    /// The number of times the core halted at this breakpoint, while its `condition` was `true`.
+    /// It is reset when the breakpoints are recomputed for a restarted session.
+    pub(crate) hit_count: u64,
+    /// Set once a failure to evaluate the `condition` was shown to the user. Later failures are only logged to the debug console.
+    pub(crate) condition_error_reported: bool,
+}
+
+/// A source breakpoint that could not be set yet, e.g. because its source file is not in the debug information of the current `program_binary`.
+/// It is reported to the debug client as unverified, and set when a new binary is flashed. See [`super::core_data::CoreHandle::resolve_pending_breakpoints`].
+#[derive(Clone, Debug)]
+pub(crate) struct PendingBreakpoint {
+    /// The id that identifies this breakpoint to the debug client. It is kept once the breakpoint is set.
+    pub(crate) id: i64,
+    pub(crate) source: Source,
+    pub(crate) source_path: PathBuf,
+    pub(crate) line: u64,
+    pub(crate) column: Option<u64>,
+    pub(crate) condition: Option<String>,
+    pub(crate) hit_condition: Option<String>,
+    pub(crate) log_message: Option<String>,
+}
+
+/// Describe the probes in `probe_list`, one per line, with the `VID:PID:Serial` that selects them.
+fn probe_list_description(probe_list: &[DebugProbeInfo]) -> String {
+    if probe_list.is_empty() {
+        return "No probes found. Please check your USB connections.".to_string();
+    }
+    probe_list
+        .iter()
+        .map(|probe_info| {
+            format!(
+                "{} ({:04x}:{:04x}:{})",
+                probe_info.identifier,
+                probe_info.vendor_id,
+                probe_info.product_id,
+                probe_info
+                    .serial_number
+                    .as_deref()
+                    .unwrap_or("<no serial number>")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 /// SessionData is designed to be similar to [probe_rs::Session], in as much that it provides handles to the [CoreHandle] instances for each of the available [probe_rs::Core] involved in the debug session.
-/// To get access to the [CoreHandle] for a specific [probe_rs::Core], the
-/// TODO: Adjust [SessionConfig] to allow multiple cores (and if appropriate, their binaries) to be specified.
+/// To get access to the [CoreHandle] for a specific [probe_rs::Core], use [SessionData::attach_core] with its core index.
+/// Every core in [SessionConfig::core_configs] is part of the debug session, with its own program binary, if one is configured.
 pub struct SessionData {
-    pub(crate) session: Session,
+    /// The session of the target. This is `None` while a core dump is replayed. See [`SessionData::session`].
+    session: Option<Session>,
+    /// The core dump, and the state of the core that replays it, if [`SessionConfig::core_dump`] is set.
+    core_dump: Option<(CoreDump, CoreState)>,
     /// [SessionData] will manage one [CoreData] per target core, that is also present in [SessionConfig::core_configs]
     pub(crate) core_data: Vec<CoreData>,
+    /// The protocol speed in kHz that the probe uses, if a `speed` was requested. Probes may use a lower speed than requested.
+    pub(crate) probe_speed: Option<u32>,
 
     /// Offset used for RTC timestamps
     ///
@@ -52,26 +166,56 @@ impl SessionData {
         config: &mut configuration::SessionConfig,
         timestamp_offset: UtcOffset,
     ) -> Result<Self, DebuggerError> {
+        if let Some(core_dump_path) = config.core_dump.clone() {
+            return Self::replay_core_dump(config, &core_dump_path, timestamp_offset);
+        }
+
+        if config.speed == Some(0) {
+            return Err(DebuggerError::Other(anyhow!(
+                "The protocol `speed` must be a number of kHz greater than 0, e.g. `4000` for 4 MHz"
+            )));
+        }
+
         // `SessionConfig` Probe/Session level configurations initialization.
         let mut target_probe = match config.probe_selector.clone() {
-            Some(selector) => Probe::open(selector.clone()).map_err(|e| match e {
-                DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound) => {
-                    DebuggerError::Other(anyhow!(
-                        "Could not find the probe_selector specified as {:04x}:{:04x}:{:?}",
-                        selector.vendor_id,
-                        selector.product_id,
-                        selector.serial_number
-                    ))
+            Some(selector) => {
+                let list = Probe::list_all();
+                let matching_probes = list
+                    .iter()
+                    .filter(|probe_info| selector.matches(probe_info))
+                    .collect::<Vec<_>>();
+                match matching_probes.as_slice() {
+                    [probe_info] => Probe::open(*probe_info).map_err(|e| match e {
+                        DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound) => {
+                            DebuggerError::Other(anyhow!(
+                                "Could not open the probe_selector specified as {selector}"
+                            ))
+                        }
+                        other_error => DebuggerError::DebugProbe(other_error),
+                    }),
+                    [] => {
+                        return Err(DebuggerError::Other(anyhow!(
+                            "Could not find the probe_selector specified as {selector}. The available probes are:\n{}",
+                            probe_list_description(&list)
+                        )));
+                    }
+                    _ => {
+                        return Err(DebuggerError::Other(anyhow!(
+                            "The probe_selector specified as {selector} matches {} probes. Please use a more specific probe_selector, e.g. with the serial number:\n{}",
+                            matching_probes.len(),
+                            probe_list_description(&list)
+                        )));
+                    }
                 }
-                other_error => DebuggerError::DebugProbe(other_error),
-            }),
+            }
             None => {
                 // Only automatically select a probe if there is only a single probe detected.
                 let list = Probe::list_all();
                 if list.len() > 1 {
                     return Err(DebuggerError::Other(anyhow!(
-                        "Found multiple ({}) probes",
-                        list.len()
+                        "Found multiple ({}) probes. Please use the probe_selector to select one of them:\n{}",
+                        list.len(),
+                        probe_list_description(&list)
                     )));
                 }
 
@@ -95,17 +239,25 @@ impl SessionData {
             target_probe.select_protocol(wire_protocol)?;
         }
 
-        // Set the speed.
-        if let Some(speed) = config.speed {
-            let actual_speed = target_probe.set_speed(speed)?;
-            if actual_speed != speed {
-                tracing::warn!(
-                    "Protocol speed {} kHz not supported, actual speed is {} kHz",
-                    speed,
-                    actual_speed
-                );
+        // Set the speed, before attaching, so that the attach already uses it.
+        let probe_speed = match config.speed {
+            Some(speed) => {
+                let actual_speed = target_probe.set_speed(speed).map_err(|error| {
+                    DebuggerError::Other(anyhow!(
+                        "Failed to set the protocol speed to {speed} kHz: {error}"
+                    ))
+                })?;
+                if actual_speed != speed {
+                    tracing::warn!(
+                        "Protocol speed {} kHz not supported, actual speed is {} kHz",
+                        speed,
+                        actual_speed
+                    );
+                }
+                Some(actual_speed)
             }
-        }
+            None => None,
+        };
 
         let mut permissions = Permissions::new();
         if config.allow_erase_all {
@@ -113,76 +265,159 @@ impl SessionData {
         }
 
         // Attach to the probe.
-        let target_session = if config.connect_under_reset {
-            target_probe.attach_under_reset(target_selector, permissions)?
+        let attach_result = if config.connect_under_reset {
+            target_probe.attach_under_reset(target_selector, permissions)
         } else {
-            target_probe
-                .attach(target_selector, permissions)
-                .map_err(|err| anyhow!("Error attaching to the probe: {:?}.", err))?
+            target_probe.attach(target_selector, permissions)
         };
-
-        // Change the current working directory if `config.cwd` is `Some(T)`.
-        if let Some(new_cwd) = config.cwd.clone() {
-            set_current_dir(new_cwd.as_path()).map_err(|err| {
-                anyhow!(
-                    "Failed to set current working directory to: {:?}, {:?}",
-                    new_cwd,
-                    err
-                )
-            })?;
+        let target_session = match attach_result {
+            Ok(target_session) => target_session,
+            Err(error) if is_debug_power_error(&error) => {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Error attaching to the probe: {}",
+                    DEBUG_POWER_UNAVAILABLE_MESSAGE
+                )));
+            }
+            Err(error) if config.connect_under_reset => return Err(error.into()),
+            Err(error) => {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Error attaching to the probe: {:?}.",
+                    error
+                )));
+            }
         };
 
+        set_cwd(config)?;
+
         // `FlashingConfig` probe level initialization.
 
         // `CoreConfig` probe level initialization.
-        if config.core_configs.len() != 1 {
-            // TODO: For multi-core, allow > 1.
-            return Err(DebuggerError::Other(anyhow!("probe-rs-debugger requires that one, and only one, core  be configured for debugging.")));
+        if config.core_configs.is_empty() {
+            return Err(DebuggerError::Other(anyhow!(
+                "probe-rs-debugger requires that at least one core be configured for debugging."
+            )));
+        }
+        let core_count = target_session.list_cores().len();
+        for (position, core_config) in config.core_configs.iter().enumerate() {
+            check_core_index(core_count, core_config.core_index)?;
+            if config.core_configs[..position]
+                .iter()
+                .any(|other_core_config| other_core_config.core_index == core_config.core_index)
+            {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Core index {} is configured more than once",
+                    core_config.core_index
+                )));
+            }
         }
 
-        // Filter `CoreConfig` entries based on those that match an actual core on the target probe.
-        let valid_core_configs = config
+        let target_name = target_session.target().name.clone();
+        let core_data_vec = config
             .core_configs
             .iter()
-            .filter(|&core_config| {
-                matches!(
-                        target_session
-                            .list_cores()
-                            .iter()
-                            .find(|(target_core_index, _)| *target_core_index
-                                == core_config.core_index),
-                        Some(_)
-                    )
-            })
-            .cloned()
-            .collect::<Vec<CoreConfig>>();
-
-        let mut core_data_vec = vec![];
-
-        for core_configuration in &valid_core_configs {
-            core_data_vec.push(CoreData {
-                core_index: core_configuration.core_index,
-                last_known_status: CoreStatus::Unknown,
-                target_name: format!(
-                    "{}-{}",
-                    core_configuration.core_index,
-                    target_session.target().name
-                ),
-                debug_info: debug_info_from_binary(core_configuration)?,
-                core_peripherals: None,
-                stack_frames: Vec::<probe_rs::debug::stack_frame::StackFrame>::new(),
-                breakpoints: Vec::<ActiveBreakpoint>::new(),
-                rtt_connection: None,
+            .map(|core_configuration| {
+                new_core_data(
+                    core_configuration,
+                    &target_name,
+                    target_session.target().memory_map.clone(),
+                    config.cwd.clone(),
+                    timestamp_offset,
+                )
             })
+            .collect::<Result<Vec<CoreData>, DebuggerError>>()?;
+
+        Ok(SessionData {
+            session: Some(target_session),
+            core_dump: None,
+            core_data: core_data_vec,
+            probe_speed,
+            timestamp_offset,
+        })
+    }
+
+    /// Replay the core dump at `core_dump_path`, instead of attaching to a target. See [`SessionConfig::core_dump`].
+    fn replay_core_dump(
+        config: &mut configuration::SessionConfig,
+        core_dump_path: &Path,
+        timestamp_offset: UtcOffset,
+    ) -> Result<Self, DebuggerError> {
+        if config.core_configs.len() != 1 {
+            return Err(DebuggerError::Other(anyhow!(
+                "A core dump contains a single core, so exactly one core has to be configured to replay it."
+            )));
+        }
+        if config.flashing_config.flashing_enabled || config.reset_mode.is_some() {
+            return Err(DebuggerError::Other(anyhow!(
+                "A core dump can not be flashed or reset. Please disable `flashingEnabled`, and remove `resetMode`."
+            )));
+        }
+        let core_dump = CoreDump::load(core_dump_path)?;
+        // The memory of the core dump does not change, so there is no RTT data to poll.
+        for core_configuration in &mut config.core_configs {
+            core_configuration.rtt_config.enabled = false;
         }
+        set_cwd(config)?;
+
+        let memory_map = core_dump
+            .memory_ranges()
+            .into_iter()
+            .map(|range| {
+                MemoryRegion::Ram(RamRegion {
+                    name: Some("Core dump".to_string()),
+                    range,
+                    is_boot_memory: false,
+                    cores: vec![],
+                })
+            })
+            .collect::<Vec<MemoryRegion>>();
+        let core_data_vec = config
+            .core_configs
+            .iter()
+            .map(|core_configuration| {
+                new_core_data(
+                    core_configuration,
+                    "core dump",
+                    memory_map.clone(),
+                    config.cwd.clone(),
+                    timestamp_offset,
+                )
+            })
+            .collect::<Result<Vec<CoreData>, DebuggerError>>()?;
+        let core_state = core_dump.create_state();
 
         Ok(SessionData {
-            session: target_session,
+            session: None,
+            core_dump: Some((core_dump, core_state)),
             core_data: core_data_vec,
+            probe_speed: None,
             timestamp_offset,
         })
     }
 
+    /// The session of the target, or an error while a core dump is replayed, which has no target.
+    pub(crate) fn session(&mut self) -> Result<&mut Session, DebuggerError> {
+        self.session.as_mut().ok_or_else(|| {
+            DebuggerError::Other(anyhow!(
+                "This is not supported while a core dump is replayed."
+            ))
+        })
+    }
+
+    /// The name of the target, as it is shown to the user.
+    pub(crate) fn target_name(&self) -> String {
+        match &self.session {
+            Some(session) => session.target().name.clone(),
+            None => "core dump".to_string(),
+        }
+    }
+
+    /// The stack frames that were saved in the core dump, if a core dump is replayed.
+    pub(crate) fn replayed_stack_frames(&self) -> Option<&[DumpedStackFrame]> {
+        self.core_dump
+            .as_ref()
+            .map(|(core_dump, _)| core_dump.stack_frames())
+    }
+
     /// Reload the a specific core's debug info from the binary file.
     pub(crate) fn load_debug_info_for_core(
         &mut self,
@@ -194,6 +429,10 @@ impl SessionData {
             .find(|core_data| core_data.core_index == core_configuration.core_index)
         {
             core_data.debug_info = debug_info_from_binary(core_configuration)?;
+            // The global variables are enumerated again from the new debug info.
+            core_data.global_variables = None;
+            core_data.elf_symbols = elf_symbols_from_binary(core_configuration);
+            core_data.rtos_layout = None;
             Ok(())
         } else {
             Err(DebuggerError::UnableToOpenProbe(Some(
@@ -202,10 +441,68 @@ impl SessionData {
         }
     }
 
+    /// Read back the memory that is occupied by the loadable sections of `program_binary`, and compare it to the sections, without erasing or programming anything.
+    /// Returns an error that lists every mismatching memory range, with its memory region and the first mismatching address.
+    pub(crate) fn verify_program_binary(
+        &mut self,
+        program_binary: &Path,
+    ) -> Result<(), DebuggerError> {
+        let session = self.session()?;
+        let mut loader = session.target().flash_loader();
+        let mut file = File::open(program_binary)?;
+        loader.load_elf_data(&mut file).map_err(|error| {
+            DebuggerError::Other(anyhow!(
+                "Failed to read the program binary {program_binary:?} for verification: {error}"
+            ))
+        })?;
+        let mismatches = loader
+            .verify(session)
+            .map_err(|error| {
+                DebuggerError::Other(anyhow!("Failed to verify the flash contents: {error}"))
+            })?
+            .iter()
+            .filter_map(|result| {
+                let first_mismatch = result.first_mismatch?;
+                let region_name = match session
+                    .target()
+                    .get_memory_region_by_address(result.address)
+                {
+                    Some(MemoryRegion::Nvm(region)) => region.name.clone(),
+                    Some(MemoryRegion::Ram(region)) => region.name.clone(),
+                    Some(MemoryRegion::Generic(region)) => region.name.clone(),
+                    None => None,
+                };
+                Some(format!(
+                    "{:#010x}..{:#010x} ({}), first difference at {first_mismatch:#010x}",
+                    result.address,
+                    result.address + result.size,
+                    region_name.as_deref().unwrap_or("unnamed region")
+                ))
+            })
+            .collect::<Vec<_>>();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(DebuggerError::Other(anyhow!(
+                "The flash contents do not match the program binary {program_binary:?}:\n{}",
+                mismatches.join("\n")
+            )))
+        }
+    }
+
     /// Do a 'light weight'(just get references to existing data structures) attach to the core and return relevant debug data.
     pub(crate) fn attach_core(&mut self, core_index: usize) -> Result<CoreHandle, DebuggerError> {
-        if let (Ok(target_core), Some(core_data)) = (
-            self.session.core(core_index),
+        let target_core = match (&mut self.session, &mut self.core_dump) {
+            (Some(session), _) => {
+                check_core_index(session.list_cores().len(), core_index)?;
+                session.core(core_index).ok()
+            }
+            // The single core of the core dump replays the configured core.
+            (None, Some((core_dump, core_state))) => Some(core_dump.core(core_state)),
+            (None, None) => None,
+        };
+        if let (Some(target_core), Some(core_data)) = (
+            target_core,
             self.core_data
                 .iter_mut()
                 .find(|core_data| core_data.core_index == core_index),
@@ -236,34 +533,129 @@ impl SessionData {
     ///   - While the core is NOT halted, because core processing can generate new data at any time.
     ///   - The first time we have entered halted status, to ensure the buffers are drained. After that, for as long as we remain in halted state, we don't need to check RTT again.
     ///
-    /// Return a Vec of [`CoreStatus`] (one entry per core) after this process has completed, as well as a boolean indicating whether we should consider a short delay before the next poll.
+    /// Return the [`CoreStatus`] of each core that was polled, by core index, after this process has completed, as well as a boolean indicating whether we should consider a short delay before the next poll.
     pub(crate) fn poll_cores<P: ProtocolAdapter>(
         &mut self,
         session_config: &SessionConfig,
         debug_adapter: &mut DebugAdapter<P>,
-    ) -> Result<(Vec<CoreStatus>, bool), DebuggerError> {
+    ) -> Result<(HashMap<usize, CoreStatus>, bool), DebuggerError> {
         // By default, we will have a small delay between polls, and will disable it if we know the last poll returned data, on the assumption that there might be at least one more batch of data.
         let mut suggest_delay_required = true;
-        let mut status_of_cores: Vec<CoreStatus> = vec![];
-        let target_memory_map = &self.session.target().memory_map.clone();
+        // The status of each core that could be polled, by core index. Cores that could not be attached to are not included.
+        let mut status_of_cores: HashMap<usize, CoreStatus> = HashMap::new();
+        let target_memory_map = &match &self.session {
+            Some(session) => session.target().memory_map.clone(),
+            None => self
+                .core_data
+                .first()
+                .map(|core_data| core_data.memory_map.clone())
+                .unwrap_or_default(),
+        };
 
         let timestamp_offset = self.timestamp_offset;
 
-        // Always set `all_cores_halted` to true, until one core is found to be running.
-        debug_adapter.all_cores_halted = true;
+        // Query the status of every core before processing any of them, so that the `all_threads_stopped` flag
+        // of a `stopped` event reflects the status of all cores at the time the event is sent.
+        let mut current_core_statuses = vec![];
         for core_config in session_config.core_configs.iter() {
+            current_core_statuses.push(if debug_adapter.configuration_is_done() {
+                if session_config.keep_debug_power_on {
+                    self.debug_power_available(core_config.core_index, true);
+                }
+                let current_status = self
+                    .attach_core(core_config.core_index)
+                    .ok()
+                    .map(|mut target_core| target_core.core.status());
+                // The core status cannot be read while the debug power domain is powered down (e.g. while the target is in a
+                // deep sleep mode), so report that specifically, rather than the resulting communication error.
+                match current_status {
+                    Some(Err(error))
+                        if !is_debug_power_error(&error)
+                            && !is_probe_disconnected_error(&error)
+                            && !self.debug_power_available(core_config.core_index, false) =>
+                    {
+                        tracing::debug!("Failed to read the core status: {}", error);
+                        Some(Err(
+                            ArmError::from(DebugPortError::TargetPowerUpFailed).into()
+                        ))
+                    }
+                    other => other,
+                }
+            } else {
+                None
+            });
+        }
+        let known_core_statuses = current_core_statuses
+            .iter()
+            .filter_map(|current_status| current_status.as_ref()?.as_ref().ok().copied())
+            .collect::<Vec<CoreStatus>>();
+        debug_adapter.all_cores_halted = all_cores_halted(&known_core_statuses);
+        debug_adapter.all_cores_running = all_cores_running(&known_core_statuses);
+
+        for (core_config, current_status) in session_config
+            .core_configs
+            .iter()
+            .zip(current_core_statuses)
+        {
             if let Ok(mut target_core) = self.attach_core(core_config.core_index) {
+                // Classify any errors from `poll_core()`, so that they can be downgraded according to the `error_policy`.
+                let error_class = match &current_status {
+                    Some(Err(_)) => Some(ErrorClass::CoreStatusRead),
+                    Some(Ok(CoreStatus::Unknown)) => Some(ErrorClass::UnknownCoreStatus),
+                    _ => None,
+                };
+                let status_was_unknown =
+                    target_core.core_data.last_known_status == CoreStatus::Unknown;
                 // We need to poll the core to determine its status.
-                match target_core.poll_core(debug_adapter) {
+                match target_core.poll_core(
+                    debug_adapter,
+                    current_status.unwrap_or(Ok(CoreStatus::Unknown)),
+                ) {
                     Ok(current_core_status) => {
+                        if target_core.core_data.connection_lost
+                            && current_core_status != CoreStatus::Unknown
+                        {
+                            target_core.core_data.connection_lost = false;
+                            if session_config.rearm_breakpoints_on_reconnect {
+                                // RTT will be attached again once the core runs.
+                                target_core.detach_rtt(debug_adapter);
+                                match target_core.rearm_breakpoints() {
+                                    Ok(breakpoint_count) => debug_adapter.show_message(
+                                        MessageSeverity::Information,
+                                        format!("Reconnected to the target, re-armed {breakpoint_count} hardware breakpoints."),
+                                    ),
+                                    Err(error) => debug_adapter.show_message(
+                                        MessageSeverity::Warning,
+                                        format!("Reconnected to the target, but failed to re-arm the breakpoints: {error}"),
+                                    ),
+                                };
+                            }
+                        }
                         // If appropriate, check for RTT data.
                         if core_config.rtt_config.enabled {
+                            if target_core.core_data.rtt_reattach_after_run
+                                && matches!(
+                                    current_core_status,
+                                    CoreStatus::Running | CoreStatus::Sleeping
+                                )
+                            {
+                                target_core.core_data.rtt_reattach_after_run = false;
+                            }
                             if let Some(core_rtt) = &mut target_core.core_data.rtt_connection {
                                 // We should poll the target for rtt data, and if any RTT data was processed, we clear the flag.
-                                if core_rtt.process_rtt_data(debug_adapter, &mut target_core.core) {
+                                if core_rtt.process_rtt_data(
+                                    debug_adapter,
+                                    &mut target_core.core,
+                                    session_config.is_warning(ErrorClass::Rtt),
+                                ) {
                                     suggest_delay_required = false;
+                                    if target_core.core_data.halt_on_rtt_output {
+                                        target_core.core_data.rtt_output_received = true;
+                                    }
                                 }
-                            } else if debug_adapter.configuration_is_done() {
+                            } else if debug_adapter.configuration_is_done()
+                                && !target_core.core_data.rtt_reattach_after_run
+                            {
                                 // We have not yet reached the point in the target application where the RTT buffers are initialized,
                                 // so, provided we have processed the MSDAP request for "configurationDone" , we should check again.
                                 {
@@ -273,30 +665,67 @@ impl SessionData {
                                         target_memory_map,
                                         core_config.program_binary.as_ref().unwrap(),
                                         &core_config.rtt_config,
+                                        core_config.rtt_window_mode,
+                                        core_config
+                                            .rtt_window_buffer_size
+                                            .unwrap_or(debug_rtt::DEFAULT_RTT_WINDOW_BUFFER_SIZE),
                                         timestamp_offset,
                                     ) {
                                         Ok(_) => {
                                             // Nothing else to do.
                                         }
                                         Err(error) => {
-                                            debug_adapter
-                                                .send_error_response(&DebuggerError::Other(error))
-                                                .ok();
+                                            if session_config.is_warning(ErrorClass::Rtt) {
+                                                tracing::warn!(
+                                                    "Failed to attach to RTT: {}",
+                                                    error
+                                                );
+                                            } else {
+                                                debug_adapter
+                                                    .send_error_response(&DebuggerError::Other(
+                                                        error,
+                                                    ))
+                                                    .ok();
+                                            }
                                         }
                                     }
                                 }
                             }
                         }
 
-                        // If the core is running, we set the flag to indicate that at least one core is not halted.
-                        // By setting it here, we ensure that RTT will be checked at least once after the core has halted.
-                        if !current_core_status.is_halted() {
-                            debug_adapter.all_cores_halted = false;
-                        }
-                        status_of_cores.push(current_core_status);
+                        debug_adapter
+                            .core_statuses
+                            .insert(core_config.core_index, current_core_status);
+                        status_of_cores.insert(core_config.core_index, current_core_status);
                     }
                     Err(error) => {
-                        let error = DebuggerError::ProbeRs(error);
+                        // A disconnected probe cannot be recovered from, irrespective of the `error_policy`.
+                        if is_probe_disconnected_error(&error) {
+                            tracing::error!("The debug probe was disconnected: {error:?}");
+                            return Err(DebuggerError::ProbeDisconnected);
+                        }
+                        let error = if is_debug_power_error(&error) {
+                            DebuggerError::Other(anyhow!(DEBUG_POWER_UNAVAILABLE_MESSAGE))
+                        } else {
+                            DebuggerError::ProbeRs(error)
+                        };
+                        if let Some(error_class) = error_class
+                            .filter(|error_class| session_config.is_warning(*error_class))
+                        {
+                            tracing::warn!("Continuing after {:?} error: {}", error_class, error);
+                            if error_class == ErrorClass::CoreStatusRead {
+                                target_core.core_data.connection_lost = true;
+                            }
+                            // Only notify the client once, rather than on every poll, until the core status is known again.
+                            if !status_was_unknown {
+                                debug_adapter.show_message(
+                                    MessageSeverity::Warning,
+                                    format!("Continuing the debug session after an error: {error}"),
+                                );
+                            }
+                            status_of_cores.insert(core_config.core_index, CoreStatus::Unknown);
+                            continue;
+                        }
                         let _ = debug_adapter.send_error_response(&error);
                         return Err(error);
                     }
@@ -308,14 +737,232 @@ impl SessionData {
                 );
             }
         }
+        // Processing the core status may have resumed a core (e.g. after a coverage breakpoint), so update the aggregate status.
+        // By setting it here, we ensure that RTT will be checked at least once after the core has halted.
+        let polled_core_statuses = status_of_cores
+            .values()
+            .copied()
+            .collect::<Vec<CoreStatus>>();
+        debug_adapter.all_cores_halted = all_cores_halted(&polled_core_statuses);
+        debug_adapter.all_cores_running = all_cores_running(&polled_core_statuses);
         Ok((status_of_cores, suggest_delay_required))
     }
+
+    /// Check if the debug power domain of the debug port that is used by the core at `core_index` is powered.
+    /// If it is not, and `request_power` is `true`, the debug and system power-up requests are asserted again.
+    /// Cores that are not ARM cores, or where the debug port status cannot be read, are assumed to be powered.
+    pub(crate) fn debug_power_available(&mut self, core_index: usize, request_power: bool) -> bool {
+        let (dp, session) = match (self.debug_port_address(core_index), self.session.as_mut()) {
+            (Some(dp), Some(session)) => (dp, session),
+            _ => return true,
+        };
+        match session
+            .get_arm_interface()
+            .and_then(|interface| debug_power_acknowledged(interface, dp, request_power))
+        {
+            Ok(power_acknowledged) => power_acknowledged,
+            Err(error) => {
+                tracing::debug!("Failed to check the debug power domain: {}", error);
+                true
+            }
+        }
+    }
+
+    /// Check that the probe can communicate with the target, with a single read that does not change the state of the core.
+    /// For ARM cores, this reads the `DPIDR` register of the debug port. For other architectures, it reads the core status.
+    /// Returns a description of the value that was read.
+    pub(crate) fn health_check(&mut self, core_index: usize) -> Result<String, DebuggerError> {
+        if let (Some(dp), Some(session)) =
+            (self.debug_port_address(core_index), self.session.as_mut())
+        {
+            let dpidr = session
+                .get_arm_interface()
+                .and_then(|interface| interface.read_raw_dp_register(dp, DPIDR::ADDRESS))
+                .map_err(Error::from)?;
+            Ok(format!("DPIDR: {dpidr:#010x}"))
+        } else {
+            let core_status = self.attach_core(core_index)?.core.status()?;
+            Ok(format!("Core status: {core_status:?}"))
+        }
+    }
+
+    /// The address of the debug port that is used by the core at `core_index`, or `None` if it is not an ARM core.
+    fn debug_port_address(&self, core_index: usize) -> Option<DpAddress> {
+        match self
+            .session
+            .as_ref()?
+            .target()
+            .cores
+            .get(core_index)
+            .map(|core| &core.core_access_options)
+        {
+            Some(CoreAccessOptions::Arm(ArmCoreAccessOptions { psel: 0, .. })) => {
+                Some(DpAddress::Default)
+            }
+            Some(CoreAccessOptions::Arm(arm_core_access_options)) => {
+                Some(DpAddress::Multidrop(arm_core_access_options.psel))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Change the current working directory if `config.cwd` is `Some(T)`.
+fn set_cwd(config: &configuration::SessionConfig) -> Result<(), DebuggerError> {
+    if let Some(new_cwd) = config.cwd.clone() {
+        set_current_dir(new_cwd.as_path()).map_err(|err| {
+            anyhow!(
+                "Failed to set current working directory to: {:?}, {:?}",
+                new_cwd,
+                err
+            )
+        })?;
+    };
+    Ok(())
+}
+
+/// The [`CoreData`] of the core that is configured by `core_configuration`, on the target with `target_name` and `memory_map`.
+fn new_core_data(
+    core_configuration: &CoreConfig,
+    target_name: &str,
+    memory_map: Vec<MemoryRegion>,
+    cwd: Option<PathBuf>,
+    timestamp_offset: UtcOffset,
+) -> Result<CoreData, DebuggerError> {
+    Ok(CoreData {
+        core_index: core_configuration.core_index,
+        last_known_status: CoreStatus::Unknown,
+        target_name: format!("{}-{}", core_configuration.core_index, target_name),
+        debug_info: debug_info_from_binary(core_configuration)?,
+        elf_symbols: elf_symbols_from_binary(core_configuration),
+        core_peripherals: None,
+        stack_frames: Vec::<probe_rs::debug::stack_frame::StackFrame>::new(),
+        global_variables: None,
+        register_groups: Vec::new(),
+        breakpoints: Vec::<ActiveBreakpoint>::new(),
+        pending_breakpoints: Vec::new(),
+        data_watchpoints: Vec::new(),
+        breakpoint_units: None,
+        next_breakpoint_id: 1,
+        step_out_stack_pointer: None,
+        temporary_breakpoint_stop_reason: "step",
+        rtt_connection: None,
+        rtt_reattach_after_run: false,
+        detached_rtt_channels: Vec::new(),
+        rtt_attach_attempts: 0,
+        rtt_next_attach_attempt: None,
+        rtt_control_block_address: None,
+        connection_lost: false,
+        memory_map,
+        timestamp_offset,
+        isr_breakpoint_action: core_configuration.isr_breakpoint_action,
+        on_stop: core_configuration.on_stop.clone(),
+        show_raw_bytes: core_configuration.show_raw_bytes,
+        software_breakpoint_fallback: core_configuration.software_breakpoint_fallback,
+        ignored_exceptions: core_configuration.ignored_exceptions.clone(),
+        ignored_exception_counts: HashMap::new(),
+        watch_values: HashMap::new(),
+        breakpoints_file: core_configuration
+            .program_binary
+            .as_deref()
+            .filter(|_| core_configuration.persist_breakpoints)
+            .map(persisted_breakpoints::breakpoints_file),
+        semihosting_files: semihosting::SemihostingFiles::new(cwd),
+        rtos: core_configuration.rtos,
+        rtos_layout: None,
+        step_timeout: match core_configuration.step_timeout_ms {
+            Some(0) => None,
+            step_timeout_ms => Some(Duration::from_millis(
+                step_timeout_ms.unwrap_or(DEFAULT_STEP_TIMEOUT_MS),
+            )),
+        },
+        step_deadline: None,
+        halt_on_rtt_output: core_configuration.halt_on_rtt_output,
+        rtt_output_received: false,
+    })
+}
+
+/// The message that is reported when the debug power domain of the target is not powered.
+const DEBUG_POWER_UNAVAILABLE_MESSAGE: &str = "The debug power domain of the target is not powered. This usually happens when the target firmware enters a low power mode that switches off the debug logic. Set `keepDebugPowerOn` in the launch configuration to keep the debug power domain powered during the debug session, or configure the firmware to keep debug logic enabled in low power modes (e.g. with the `DBGMCU` registers on STM32).";
+
+/// Confirm that `core_index` refers to one of the `core_count` cores of the target.
+fn check_core_index(core_count: usize, core_index: usize) -> Result<(), DebuggerError> {
+    if core_index < core_count {
+        Ok(())
+    } else {
+        Err(DebuggerError::Other(anyhow!(
+            "The target has {core_count} {}, but core index {core_index} was requested",
+            if core_count == 1 { "core" } else { "cores" }
+        )))
+    }
+}
+
+/// Check if `error` was caused by a debug power domain that did not acknowledge the power-up request.
+fn is_debug_power_error(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Arm(ArmError::DebugPort(DebugPortError::TargetPowerUpFailed))
+    )
+}
+
+/// Check if `error` was caused by a failure to communicate with the debug probe over USB, which happens when the probe is unplugged.
+/// See [`DebugProbeError::is_probe_disconnected`].
+fn is_probe_disconnected_error(error: &Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(error) = source {
+        if error
+            .downcast_ref::<DebugProbeError>()
+            .map_or(false, DebugProbeError::is_probe_disconnected)
+        {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Read the `CTRL/STAT` register of the debug port, to check if both the debug and system power-up requests are acknowledged.
+/// If they are not, and `request_power` is `true`, the power-up request bits are set, before the acknowledge bits are checked again.
+/// The other fields of `CTRL/STAT` (e.g. `MASKLANE` and `TRNCNT`) keep the values that were read.
+fn debug_power_acknowledged(
+    interface: &mut dyn ArmProbeInterface,
+    dp: DpAddress,
+    request_power: bool,
+) -> Result<bool, ArmError> {
+    let mut ctrl = Ctrl::try_from(interface.read_raw_dp_register(dp, Ctrl::ADDRESS)?)?;
+    if (ctrl.csyspwrupack() && ctrl.cdbgpwrupack()) || !request_power {
+        return Ok(ctrl.csyspwrupack() && ctrl.cdbgpwrupack());
+    }
+    tracing::warn!("The debug power domain is powered down. Requesting debug power.");
+    ctrl.set_cdbgpwrupreq(true);
+    ctrl.set_csyspwrupreq(true);
+    interface.write_raw_dp_register(dp, Ctrl::ADDRESS, ctrl.into())?;
+    let ctrl = Ctrl::try_from(interface.read_raw_dp_register(dp, Ctrl::ADDRESS)?)?;
+    Ok(ctrl.csyspwrupack() && ctrl.cdbgpwrupack())
+}
+
+/// The aggregate halted state of the target, which is `true` only if every one of `core_statuses` is halted.
+/// Without any known core status, the target is not considered halted.
+pub(crate) fn all_cores_halted(core_statuses: &[CoreStatus]) -> bool {
+    !core_statuses.is_empty()
+        && core_statuses
+            .iter()
+            .all(|core_status| core_status.is_halted())
+}
+
+/// The aggregate running state of the target, which is `true` only if every one of `core_statuses` is running or sleeping.
+/// Without any known core status, the target is not considered running.
+pub(crate) fn all_cores_running(core_statuses: &[CoreStatus]) -> bool {
+    !core_statuses.is_empty()
+        && core_statuses
+            .iter()
+            .all(|core_status| matches!(core_status, CoreStatus::Running | CoreStatus::Sleeping))
 }
 
 pub(crate) fn debug_info_from_binary(
     core_configuration: &CoreConfig,
 ) -> Result<DebugInfo, DebuggerError> {
-    let debug_info = if let Some(binary_path) = &core_configuration.program_binary {
+    let mut debug_info = if let Some(binary_path) = &core_configuration.program_binary {
         DebugInfo::from_file(binary_path).map_err(|error| DebuggerError::Other(anyhow!(error)))?
     } else {
         return Err(anyhow!(
@@ -324,5 +971,104 @@ pub(crate) fn debug_info_from_binary(
         )
         .into());
     };
+    if let Some(collection_element_limit) = core_configuration.collection_element_limit {
+        debug_info.set_collection_element_limit(collection_element_limit);
+    }
+    // The same remaps apply to the defmt source locations. See [`rtt::RttConfig::map_source_path`].
+    debug_info.set_source_path_remaps(core_configuration.rtt_config.source_path_remaps.clone());
     Ok(debug_info)
 }
+
+/// Read the ELF symbol table of the program binary. The symbols are only used as a fallback for DWARF, so errors are logged, and not propagated.
+pub(crate) fn elf_symbols_from_binary(core_configuration: &CoreConfig) -> Vec<ElfSymbol> {
+    core_configuration
+        .program_binary
+        .as_ref()
+        .map_or_else(Vec::new, |binary_path| {
+            elf_symbols::read_elf_symbols(binary_path).unwrap_or_else(|error| {
+                tracing::warn!("Failed to read the ELF symbol table: {error}");
+                vec![]
+            })
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{all_cores_halted, all_cores_running, probe_list_description};
+    use probe_rs::{BreakpointCause, CoreStatus, DebugProbeInfo, DebugProbeType, HaltReason};
+
+    #[test]
+    fn all_cores_halted_with_two_cores() {
+        // Both cores start out running.
+        let mut core_statuses = [CoreStatus::Running, CoreStatus::Running];
+        assert!(!all_cores_halted(&core_statuses));
+
+        // The first core halts, while the second is still running.
+        core_statuses[0] = CoreStatus::Halted(HaltReason::Request);
+        assert!(!all_cores_halted(&core_statuses));
+
+        // The second core halts a little later.
+        core_statuses[1] = CoreStatus::Halted(HaltReason::Step);
+        assert!(all_cores_halted(&core_statuses));
+
+        // The first core resumes, and then goes to sleep.
+        core_statuses[0] = CoreStatus::Sleeping;
+        assert!(!all_cores_halted(&core_statuses));
+
+        // A core with an unknown status is not considered halted.
+        core_statuses[0] = CoreStatus::Unknown;
+        assert!(!all_cores_halted(&core_statuses));
+    }
+
+    #[test]
+    fn all_cores_halted_without_core_statuses() {
+        assert!(!all_cores_halted(&[]));
+    }
+
+    #[test]
+    fn all_cores_running_with_two_cores() {
+        // Core 0 halts at a breakpoint, while core 1 keeps running.
+        let mut core_statuses = [
+            CoreStatus::Halted(HaltReason::Breakpoint(BreakpointCause::Hardware)),
+            CoreStatus::Running,
+        ];
+        assert!(!all_cores_running(&core_statuses));
+
+        // Core 0 resumes, while core 1 is sleeping.
+        core_statuses[0] = CoreStatus::Running;
+        core_statuses[1] = CoreStatus::Sleeping;
+        assert!(all_cores_running(&core_statuses));
+    }
+
+    #[test]
+    fn all_cores_running_without_core_statuses() {
+        assert!(!all_cores_running(&[]));
+    }
+
+    #[test]
+    fn probe_list_description_lists_the_probe_selectors() {
+        let probe_list = [
+            DebugProbeInfo::new(
+                "STLink V2-1",
+                0x0483,
+                0x374b,
+                Some("0671FF".to_string()),
+                DebugProbeType::StLink,
+                None,
+            ),
+            DebugProbeInfo::new("J-Link", 0x1366, 0x0101, None, DebugProbeType::JLink, None),
+        ];
+        assert_eq!(
+            probe_list_description(&probe_list),
+            "STLink V2-1 (0483:374b:0671FF)\nJ-Link (1366:0101:<no serial number>)"
+        );
+    }
+
+    #[test]
+    fn probe_list_description_without_probes() {
+        assert_eq!(
+            probe_list_description(&[]),
+            "No probes found. Please check your USB connections."
+        );
+    }
+}
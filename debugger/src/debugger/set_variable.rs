@@ -0,0 +1,170 @@
+use crate::DebuggerError;
+use anyhow::anyhow;
+use parse_int::parse;
+use probe_rs::{
+    debug::{Variable, VariableCache, VariableName, VariableType, VariableValue},
+    Core, MemoryInterface,
+};
+
+/// The base types that are integers, and accept decimal, hexadecimal (`0x`), octal (`0o`) and binary (`0b`) values.
+const INTEGER_TYPES: [&str; 12] = [
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+];
+
+/// Interpret `new_value` according to the type of `variable`, and write it to the target memory of the variable.
+/// - Integers can be written in decimal, or with a `0x`, `0o` or `0b` prefix.
+/// - Booleans can be written as `true`/`false`, or `1`/`0`.
+/// - Enums are written using the name of a variant, e.g. `Green` or `Color::Green`, or the numeric value of its discriminant, e.g. `2`.
+///
+/// Returns the value to show for the variable. Composite types (structs, arrays, pointers, ...) cannot be assigned directly,
+/// and an error describes how to change them instead.
+pub(crate) fn write_variable(
+    core: &mut Core,
+    variable: &Variable,
+    variable_cache: &mut VariableCache,
+    new_value: &str,
+) -> Result<String, DebuggerError> {
+    let new_value = new_value.trim();
+    match &variable.type_name {
+        VariableType::Base(type_name) if INTEGER_TYPES.contains(&type_name.as_str()) => {
+            // The value is written with `FromStr`, which only accepts decimal values.
+            let decimal_value = if type_name.starts_with('i') {
+                parse::<i128>(new_value).map(|value| value.to_string())
+            } else {
+                parse::<u128>(new_value).map(|value| value.to_string())
+            }
+            .map_err(|error| {
+                DebuggerError::Other(anyhow!(
+                    "Invalid value {new_value:?} for {type_name}: {error}"
+                ))
+            })?;
+            update_base_value(core, variable, variable_cache, decimal_value)
+        }
+        VariableType::Base(type_name) if type_name == "bool" => {
+            let bool_value = match new_value {
+                "1" => "true",
+                "0" => "false",
+                other => other,
+            };
+            update_base_value(core, variable, variable_cache, bool_value.to_string())
+        }
+        VariableType::Base(_) => {
+            update_base_value(core, variable, variable_cache, new_value.to_string())
+        }
+        VariableType::Enum(type_name) => {
+            let address = variable
+                .memory_location
+                .memory_address()
+                .map_err(|error| DebuggerError::Other(anyhow!(error)))?;
+            let (variant_name, discriminant) =
+                enum_discriminant(type_name, &variable.enumerators, new_value)?;
+            // The discriminant is read as a single byte when the enum value is resolved, so it is written the same way.
+            core.write_word_8(address, discriminant)?;
+            let enum_value = format!("{type_name}::{variant_name}");
+            let mut cache_variable = variable.clone();
+            cache_variable.set_value(VariableValue::Valid(enum_value.clone()));
+            variable_cache.cache_variable(cache_variable.parent_key, cache_variable, core)?;
+            Ok(enum_value)
+        }
+        other_type => Err(DebuggerError::Other(anyhow!(
+            "Cannot assign a value to {} of type {other_type}. Only integers, floats, bools, chars and enum discriminants can be set. Please expand the variable, and set the value of its members instead.",
+            match &variable.name {
+                VariableName::Named(name) => name.as_str(),
+                _ => "this variable",
+            }
+        ))),
+    }
+}
+
+/// Find the variant of the enum `type_name` that `new_value` selects, by its name, optionally prefixed with the enum name, or by its discriminant.
+/// Returns the name and the discriminant of the variant, or an error if `new_value` is not one of the `enumerators`.
+fn enum_discriminant<'a>(
+    type_name: &str,
+    enumerators: &'a [(String, u8)],
+    new_value: &str,
+) -> Result<(&'a str, u8), DebuggerError> {
+    let variant_name = new_value
+        .strip_prefix(type_name)
+        .and_then(|variant_name| variant_name.strip_prefix("::"))
+        .unwrap_or(new_value);
+    let discriminant = parse::<u8>(new_value).ok();
+    enumerators
+        .iter()
+        .find(|(name, value)| name == variant_name || Some(*value) == discriminant)
+        .map(|(name, value)| (name.as_str(), *value))
+        .ok_or_else(|| {
+            DebuggerError::Other(anyhow!(
+                "Invalid value {new_value:?} for the enum {type_name}. Please use one of: {}",
+                enumerators
+                    .iter()
+                    .map(|(name, value)| format!("{name} ({value})"))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ))
+        })
+}
+
+/// Write a base type value, that has already been converted to the format that [`Variable::update_value`] expects.
+fn update_base_value(
+    core: &mut Core,
+    variable: &Variable,
+    variable_cache: &mut VariableCache,
+    new_value: String,
+) -> Result<String, DebuggerError> {
+    variable
+        .update_value(core, variable_cache, new_value)
+        .map_err(|error| DebuggerError::Other(anyhow!(error)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::enum_discriminant;
+
+    fn enumerators() -> Vec<(String, u8)> {
+        vec![
+            ("Red".to_string(), 0),
+            ("Green".to_string(), 1),
+            ("Blue".to_string(), 4),
+        ]
+    }
+
+    #[test]
+    fn variant_is_selected_by_name() {
+        let enumerators = enumerators();
+        assert_eq!(
+            enum_discriminant("Color", &enumerators, "Blue").unwrap(),
+            ("Blue", 4)
+        );
+    }
+
+    #[test]
+    fn variant_is_selected_by_qualified_name() {
+        let enumerators = enumerators();
+        assert_eq!(
+            enum_discriminant("Color", &enumerators, "Color::Green").unwrap(),
+            ("Green", 1)
+        );
+    }
+
+    #[test]
+    fn variant_is_selected_by_discriminant() {
+        let enumerators = enumerators();
+        assert_eq!(
+            enum_discriminant("Color", &enumerators, "0x4").unwrap(),
+            ("Blue", 4)
+        );
+    }
+
+    #[test]
+    fn unknown_discriminant_is_rejected() {
+        let enumerators = enumerators();
+        assert!(enum_discriminant("Color", &enumerators, "2").is_err());
+    }
+
+    #[test]
+    fn unknown_variant_name_is_rejected() {
+        let enumerators = enumerators();
+        assert!(enum_discriminant("Color", &enumerators, "Purple").is_err());
+        assert!(enum_discriminant("Color", &enumerators, "Shade::Red").is_err());
+    }
+}
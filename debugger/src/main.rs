@@ -39,8 +39,12 @@ pub enum DebuggerError {
     MissingArgument { argument_name: String },
     #[error("Missing session for interaction with probe")]
     MissingSession,
+    #[error("Cannot set breakpoint here, because the source location is not in the debug information of the binary: {0}")]
+    NoBreakpointLocation(probe_rs::debug::DebugError),
     #[error(transparent)]
     Other(#[from] anyhow::Error),
+    #[error("Debug probe disconnected")]
+    ProbeDisconnected,
     #[error(transparent)]
     ProbeRs(#[from] Error),
     #[error("Serialiazation error")]
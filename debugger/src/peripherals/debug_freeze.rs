@@ -0,0 +1,137 @@
+use super::svd_variables::{SvdCache, SvdFieldBits};
+use crate::DebuggerError;
+use anyhow::anyhow;
+use probe_rs::{
+    debug::{Variable, VariableCache, VariableName, VariableNodeType},
+    Core, MemoryInterface,
+};
+
+/// How the fields of a debug freeze register are set, to stop the peripherals while the core is halted.
+#[derive(Clone, Copy, Debug)]
+enum FreezeMethod {
+    /// Every field is a freeze bit for one peripheral (e.g. `DBG_IWDG_STOP` on STM32), and all of them are set.
+    SetAllFields,
+    /// The named field selects if the peripheral runs while the core is halted (e.g. `HALT` on nRF), and it is cleared.
+    ClearField(&'static str),
+}
+
+/// The SVD peripheral name, and the pattern for the register names, of the debug freeze registers of a target family.
+struct KnownFreezeRegister {
+    peripheral: &'static str,
+    /// Matches every register of the peripheral with a name that contains this pattern, e.g. `APB1_FZ` and `APB2_FZ`.
+    register_pattern: &'static str,
+    freeze_method: FreezeMethod,
+}
+
+/// The debug freeze registers that are currently supported. All the registers that are described in the SVD file will be used.
+/// - STM32 families have one or more `DBGMCU.*FZ*` registers (e.g. `APB1_FZ`, `APB1FZR1`, `APB1LFZ1`) with a stop bit per peripheral.
+/// - nRF52 keeps the watchdog running while halted, unless `WDT.CONFIG.HALT` is cleared.
+const KNOWN_FREEZE_REGISTERS: [KnownFreezeRegister; 2] = [
+    KnownFreezeRegister {
+        peripheral: "DBGMCU",
+        register_pattern: "FZ",
+        freeze_method: FreezeMethod::SetAllFields,
+    },
+    KnownFreezeRegister {
+        peripheral: "WDT",
+        register_pattern: "CONFIG",
+        freeze_method: FreezeMethod::ClearField("HALT"),
+    },
+];
+
+impl SvdCache {
+    /// Configure the debug freeze registers of the target, so that watchdogs and timers are stopped while the core is halted.
+    /// Returns the names of the freeze fields that were configured, as `PERIPHERAL.REGISTER.FIELD`, or an empty list if the SVD file does not describe any of the supported registers.
+    pub(crate) fn freeze_peripherals_when_halted(
+        &mut self,
+        core: &mut Core,
+    ) -> Result<Vec<String>, DebuggerError> {
+        let mut frozen_peripherals = vec![];
+        for known_register in &KNOWN_FREEZE_REGISTERS {
+            let name_suffix = format!(".{}", known_register.peripheral);
+            self.expand_peripherals_matching(core, &|name| name.ends_with(&name_suffix))?;
+            let name_infix = format!(".{}.", known_register.peripheral);
+            let mut register_variables = vec![];
+            find_svd_registers(
+                &self.svd_variable_cache,
+                None,
+                &|name| {
+                    name.contains(&name_infix)
+                        && name.rsplit('.').next().map_or(false, |register| {
+                            register.contains(known_register.register_pattern)
+                        })
+                },
+                &mut register_variables,
+            );
+            for register_variable in register_variables {
+                let register_name = svd_short_name(&register_variable, 2);
+                let address = register_variable
+                    .memory_location
+                    .memory_address()
+                    .map_err(|error| {
+                        anyhow!("Invalid address for the debug freeze register {register_name}: {error}")
+                    })?;
+                let value = core.read_word_32(address)?;
+
+                let mut new_value = value;
+                for field_variable in self
+                    .svd_variable_cache
+                    .get_children(Some(register_variable.variable_key))?
+                {
+                    let field_name = svd_short_name(&field_variable, 1);
+                    let field_mask = SvdFieldBits::of(&field_variable).mask();
+                    match known_register.freeze_method {
+                        FreezeMethod::SetAllFields => new_value |= field_mask,
+                        FreezeMethod::ClearField(freeze_field) if field_name == freeze_field => {
+                            new_value &= !field_mask
+                        }
+                        FreezeMethod::ClearField(_) => continue,
+                    }
+                    frozen_peripherals.push(format!("{register_name}.{field_name}"));
+                }
+
+                if new_value != value {
+                    core.write_word_32(address, new_value)?;
+                }
+            }
+        }
+        Ok(frozen_peripherals)
+    }
+}
+
+/// The last `segments` parts of the dotted name of an SVD variable, e.g. `DBGMCU.APB1_FZ` for a register.
+fn svd_short_name(variable: &Variable, segments: usize) -> String {
+    let name = variable.name.to_string();
+    let mut parts = name.rsplit('.').take(segments).collect::<Vec<&str>>();
+    parts.reverse();
+    parts.join(".")
+}
+
+/// Recursively search the SVD variables below `parent_key` for all the registers with a name that matches `is_match`.
+fn find_svd_registers(
+    svd_cache: &VariableCache,
+    parent_key: Option<i64>,
+    is_match: &dyn Fn(&str) -> bool,
+    register_variables: &mut Vec<Variable>,
+) {
+    let children = match svd_cache.get_children(parent_key) {
+        Ok(children) => children,
+        Err(_) => return,
+    };
+    for child_variable in children {
+        match child_variable.variable_node_type {
+            VariableNodeType::SvdRegister => {
+                if matches!(&child_variable.name, VariableName::Named(name) if is_match(name)) {
+                    register_variables.push(child_variable);
+                }
+            }
+            VariableNodeType::SvdField => {}
+            _ => find_svd_registers(
+                svd_cache,
+                Some(child_variable.variable_key),
+                is_match,
+                register_variables,
+            ),
+        }
+    }
+}
@@ -1,7 +1,13 @@
+/// Stop watchdogs and timers while the core is halted, for targets where the SVD file describes the debug freeze registers.
+pub(crate) mod debug_freeze;
+/// Decode the reset cause register, for targets where the SVD file describes a supported register.
+pub(crate) mod reset_cause;
 /// Notes about SVD:
 /// - Peripherals are 'grouped', but many only belong to a single group.
 /// - We only have to build the structure once down to 'fields' level.
 /// - Once an SVD file has been parsed, it's structure is loaded as a hierarchical set of variables.
+/// - The registers and fields of a peripheral are only added to the structure when the peripheral is first expanded, or searched by name.
 /// - Fields need to be read every stacktrace, because they will change value.
-// TODO: Implement 'lazy load' of registers, to only read target registers for peripherals that are expanded in the VSCode variable view.
 pub(crate) mod svd_variables;
+/// Write the values of SVD peripheral registers and fields, with the access that the SVD file allows.
+pub(crate) mod svd_writes;
@@ -0,0 +1,188 @@
+use super::svd_variables::{SvdCache, SvdFieldBits};
+use crate::DebuggerError;
+use anyhow::anyhow;
+use probe_rs::{
+    debug::{Variable, VariableCache, VariableName, VariableNodeType},
+    Core, MemoryInterface,
+};
+
+/// How the reset cause flags of a specific target are cleared.
+#[derive(Clone, Copy, Debug)]
+enum ClearMethod {
+    /// Write `1` to the bit position of every flag that is set.
+    WriteOneToClear,
+    /// Set the named field (e.g. `RMVF` on STM32) to clear all the flags.
+    SetField(&'static str),
+    /// The flags cannot be cleared through the debug interface.
+    ReadOnly,
+}
+
+/// The SVD peripheral and register names of a reset cause register, and how to clear it.
+struct KnownResetCauseRegister {
+    peripheral: &'static str,
+    register: &'static str,
+    clear_method: ClearMethod,
+}
+
+/// The reset cause registers that are currently supported. The first one that is described in the SVD file will be used.
+/// - STM32 families latch the reset flags in `RCC.CSR` (`RCC.RSR` on STM32H7), and clear them when `RMVF` is set.
+/// - nRF52 uses `POWER.RESETREAS`, and nRF53/nRF91 use `RESET.RESETREAS`. Both are write-one-to-clear.
+/// - Microchip SAM D/L/C (`RSTC.RCAUSE`) and RP2040 (`VREG_AND_CHIP_RESET.CHIP_RESET`) can only be read.
+const KNOWN_RESET_CAUSE_REGISTERS: [KnownResetCauseRegister; 6] = [
+    KnownResetCauseRegister {
+        peripheral: "RCC",
+        register: "CSR",
+        clear_method: ClearMethod::SetField("RMVF"),
+    },
+    KnownResetCauseRegister {
+        peripheral: "RCC",
+        register: "RSR",
+        clear_method: ClearMethod::SetField("RMVF"),
+    },
+    KnownResetCauseRegister {
+        peripheral: "POWER",
+        register: "RESETREAS",
+        clear_method: ClearMethod::WriteOneToClear,
+    },
+    KnownResetCauseRegister {
+        peripheral: "RESET",
+        register: "RESETREAS",
+        clear_method: ClearMethod::WriteOneToClear,
+    },
+    KnownResetCauseRegister {
+        peripheral: "RSTC",
+        register: "RCAUSE",
+        clear_method: ClearMethod::ReadOnly,
+    },
+    KnownResetCauseRegister {
+        peripheral: "VREG_AND_CHIP_RESET",
+        register: "CHIP_RESET",
+        clear_method: ClearMethod::ReadOnly,
+    },
+];
+
+/// The decoded contents of the reset cause register.
+#[derive(Debug)]
+pub(crate) struct ResetCause {
+    /// The name of the register, as `PERIPHERAL.REGISTER`.
+    pub(crate) register_name: String,
+    pub(crate) address: u64,
+    /// The raw value of the register, before it was cleared.
+    pub(crate) value: u32,
+    /// The names of the fields that are set. Fields that are wider than a single bit include their value, e.g. `FIELD=2`.
+    pub(crate) flags: Vec<String>,
+    /// `true` if the flags were cleared after they were read.
+    pub(crate) cleared: bool,
+}
+
+impl SvdCache {
+    /// Read, and decode, the reset cause register. If `clear` is `true`, the flags will be cleared after they were read.
+    /// Returns `Ok(None)` if the SVD file does not describe any of the supported reset cause registers.
+    pub(crate) fn reset_cause(
+        &mut self,
+        core: &mut Core,
+        clear: bool,
+    ) -> Result<Option<ResetCause>, DebuggerError> {
+        self.expand_peripherals_matching(core, &|name| {
+            KNOWN_RESET_CAUSE_REGISTERS
+                .iter()
+                .any(|known_register| name.ends_with(&format!(".{}", known_register.peripheral)))
+        })?;
+        let (known_register, register_variable) = if let Some(found) = KNOWN_RESET_CAUSE_REGISTERS
+            .iter()
+            .find_map(|known_register| {
+                find_svd_register(
+                    &self.svd_variable_cache,
+                    None,
+                    &format!(".{}.{}", known_register.peripheral, known_register.register),
+                )
+                .map(|register_variable| (known_register, register_variable))
+            }) {
+            found
+        } else {
+            return Ok(None);
+        };
+
+        let address = register_variable
+            .memory_location
+            .memory_address()
+            .map_err(|error| anyhow!("Invalid reset cause register address: {error}"))?;
+        let value = core.read_word_32(address)?;
+
+        let mut flags = vec![];
+        let mut clear_value = 0_u32;
+        for field_variable in self
+            .svd_variable_cache
+            .get_children(Some(register_variable.variable_key))?
+        {
+            let field_name = if let VariableName::Named(name) = &field_variable.name {
+                name.rsplit('.').next().unwrap_or(name).to_string()
+            } else {
+                continue;
+            };
+            let field_bits = SvdFieldBits::of(&field_variable);
+            if let ClearMethod::SetField(clear_field) = known_register.clear_method {
+                if field_name == clear_field {
+                    clear_value |= field_bits.mask();
+                    continue;
+                }
+            }
+            let field_value = field_bits.extract(value);
+            if field_value != 0 {
+                if field_bits.width == 1 {
+                    flags.push(field_name);
+                } else {
+                    flags.push(format!("{field_name}={field_value}"));
+                }
+            }
+        }
+
+        let cleared = if clear {
+            match known_register.clear_method {
+                ClearMethod::WriteOneToClear => {
+                    core.write_word_32(address, value)?;
+                    true
+                }
+                ClearMethod::SetField(_) if clear_value != 0 => {
+                    core.write_word_32(address, value | clear_value)?;
+                    true
+                }
+                ClearMethod::SetField(_) | ClearMethod::ReadOnly => false,
+            }
+        } else {
+            false
+        };
+
+        Ok(Some(ResetCause {
+            register_name: format!("{}.{}", known_register.peripheral, known_register.register),
+            address,
+            value,
+            flags,
+            cleared,
+        }))
+    }
+}
+
+/// Recursively search the SVD variables below `parent_key` for a register with a name that ends in `name_suffix`.
+fn find_svd_register(
+    svd_cache: &VariableCache,
+    parent_key: Option<i64>,
+    name_suffix: &str,
+) -> Option<Variable> {
+    svd_cache
+        .get_children(parent_key)
+        .ok()?
+        .into_iter()
+        .find_map(|child_variable| match child_variable.variable_node_type {
+            VariableNodeType::SvdRegister => {
+                if matches!(&child_variable.name, VariableName::Named(name) if name.ends_with(name_suffix))
+                {
+                    Some(child_variable)
+                } else {
+                    None
+                }
+            }
+            VariableNodeType::SvdField => None,
+            _ => find_svd_register(svd_cache, Some(child_variable.variable_key), name_suffix),
+        })
+}
@@ -8,10 +8,10 @@ use probe_rs::{
     },
     Core,
 };
-use std::{fmt::Debug, fs::File, io::Read, path::Path};
+use std::{collections::HashMap, fmt::Debug, fs::File, io::Read, path::Path, time::Instant};
 use svd_parser::{
     self as svd,
-    svd::{Access, Device},
+    svd::{Access, Device, Peripheral, Usage},
     Config,
 };
 
@@ -21,7 +21,90 @@ pub(crate) struct SvdCache {
     /// The SVD contents and structure will be stored as variables, down to the Field level.
     /// Unlike other VariableCache instances, it will only be built once per DebugSession.
     /// After that, only the SVD fields values change values, and the data for these will be re-read everytime they are queried by the debugger.
+    /// The registers and fields of a peripheral are only added when they are first needed, e.g. when the peripheral is expanded in the variables view.
     pub(crate) svd_variable_cache: VariableCache,
+    /// The device that was parsed from the SVD file, to add the registers of peripherals when they are first needed.
+    peripheral_device: Device,
+    /// The peripherals of which the registers have not been added to the `svd_variable_cache` yet.
+    /// They are keyed by the `variable_key` of the peripheral, with the index of the peripheral in `peripheral_device.peripherals`.
+    pub(crate) unexpanded_peripherals: HashMap<i64, usize>,
+    /// The `variable_key` of every peripheral, keyed by its dotted name, e.g. `GPIO.GPIOA`, to find the peripheral of a register or field name.
+    peripheral_keys_by_name: HashMap<String, i64>,
+    /// The attributes from the SVD file that are needed to write registers and fields, keyed by their `variable_key`.
+    pub(crate) svd_write_attributes: HashMap<i64, SvdWriteAttributes>,
+    /// The names of the `enumeratedValues` of fields, keyed by their `variable_key`. Fields without enumerated values are not included.
+    pub(crate) field_enumerated_values: HashMap<i64, FieldEnumeratedValues>,
+}
+
+/// The `enumeratedValues` that can be read from a field, to show the name of the current value of the field.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FieldEnumeratedValues {
+    /// The numeric value, and the name, of each enumerated value.
+    pub(crate) values: Vec<(u64, String)>,
+    /// The name of the enumerated value that is marked with `isDefault`, which applies to all the values that are not listed.
+    pub(crate) default_name: Option<String>,
+}
+
+impl FieldEnumeratedValues {
+    /// The name of the enumerated value for `field_value`, if there is one.
+    pub(crate) fn name(&self, field_value: u64) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(value, _)| *value == field_value)
+            .map(|(_, name)| name.as_str())
+            .or(self.default_name.as_deref())
+    }
+}
+
+/// The bits of an SVD field in its register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SvdFieldBits {
+    /// The bit position of the least significant bit of the field.
+    pub(crate) offset: u32,
+    /// The number of bits of the field.
+    pub(crate) width: u32,
+}
+
+impl SvdFieldBits {
+    /// The bits of the SVD field `field_variable`.
+    pub(crate) fn of(field_variable: &Variable) -> Self {
+        // For SVD fields, the range_lower_bound and range_upper_bound are the bit range LSB and MSB.
+        SvdFieldBits {
+            offset: field_variable.range_lower_bound as u32,
+            width: (field_variable.range_upper_bound - field_variable.range_lower_bound) as u32,
+        }
+    }
+
+    /// The mask of the field bits in the register value.
+    pub(crate) fn mask(&self) -> u32 {
+        if self.width >= 32 {
+            u32::MAX
+        } else {
+            ((1_u32 << self.width) - 1) << self.offset
+        }
+    }
+
+    /// The value of the field in `register_value`.
+    pub(crate) fn extract(&self, register_value: u32) -> u32 {
+        (register_value & self.mask()) >> self.offset
+    }
+
+    /// Replace the bits of the field in `register_value` with `field_value`. Bits of `field_value` that do not fit in the field are ignored.
+    pub(crate) fn insert(&self, register_value: u32, field_value: u32) -> u32 {
+        (register_value & !self.mask()) | ((field_value << self.offset) & self.mask())
+    }
+}
+
+/// The SVD attributes of a register or field, that determine if, and how, a new value can be written to it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SvdWriteAttributes {
+    /// The access that is allowed, or `None` if the SVD file does not restrict it. Fields inherit the access of their register.
+    pub(crate) access: Option<Access>,
+    /// The size of the register, in bits.
+    pub(crate) register_size: u32,
+    /// Whether the register can be read without side effects, which is required to modify a single field.
+    /// This is only set for registers, and the value of the parent register applies to fields.
+    pub(crate) register_readable: bool,
 }
 
 impl SvdCache {
@@ -40,10 +123,20 @@ impl SvdCache {
                     Some(dap_request_id),
                 )?;
                 let _ = svd_opened_file.read_to_string(svd_xml);
+                let start = Instant::now();
+                // The `enumeratedValues` are only used to show the names of field values, so an SVD file where they are malformed
+                // is parsed again without them, instead of failing to load the peripherals.
                 let svd_cache = match svd::parse_with_config(
                     svd_xml,
-                    &Config::default().expand(true).ignore_enums(true),
-                ) {
+                    &Config::default().expand(true).ignore_enums(false),
+                )
+                .or_else(|error| {
+                    tracing::warn!("Failed to parse the CMSIS-SVD file {:?} with its enumerated values, so it is parsed again without them, and the names of field values are not shown: {:?}", svd_file, error);
+                    svd::parse_with_config(
+                        svd_xml,
+                        &Config::default().expand(true).ignore_enums(true),
+                    )
+                }) {
                     Ok(peripheral_device) => {
                         debug_adapter
                             .update_progress(
@@ -53,13 +146,36 @@ impl SvdCache {
                             )
                             .ok();
 
+                        let mut unexpanded_peripherals = HashMap::new();
+                        let svd_variable_cache = variable_cache_from_svd(
+                            &peripheral_device,
+                            core,
+                            debug_adapter,
+                            progress_id,
+                            &mut unexpanded_peripherals,
+                        )?;
+                        tracing::info!(
+                            "Loaded {} peripherals from the SVD file in {:?}. Their registers are loaded when they are first expanded.",
+                            unexpanded_peripherals.len(),
+                            start.elapsed()
+                        );
+                        let peripheral_keys_by_name = unexpanded_peripherals
+                            .keys()
+                            .filter_map(|peripheral_key| {
+                                svd_variable_cache.get_variable_by_key(*peripheral_key).map(
+                                    |peripheral_variable| {
+                                        (peripheral_variable.name.to_string(), *peripheral_key)
+                                    },
+                                )
+                            })
+                            .collect();
                         Ok(SvdCache {
-                            svd_variable_cache: variable_cache_from_svd(
-                                peripheral_device,
-                                core,
-                                debug_adapter,
-                                progress_id,
-                            )?,
+                            svd_variable_cache,
+                            peripheral_device,
+                            unexpanded_peripherals,
+                            peripheral_keys_by_name,
+                            svd_write_attributes: HashMap::new(),
+                            field_enumerated_values: HashMap::new(),
                         })
                     }
                     Err(error) => Err(DebuggerError::Other(anyhow::anyhow!(
@@ -74,14 +190,97 @@ impl SvdCache {
             Err(error) => Err(DebuggerError::Other(anyhow::anyhow!("{}", error))),
         }
     }
+
+    /// Add the registers and fields of the peripheral with `peripheral_key` to the `svd_variable_cache`, if that has not been done yet.
+    pub(crate) fn expand_peripheral(
+        &mut self,
+        core: &mut Core,
+        peripheral_key: i64,
+    ) -> Result<(), DebuggerError> {
+        let peripheral_index = match self.unexpanded_peripherals.remove(&peripheral_key) {
+            Some(peripheral_index) => peripheral_index,
+            None => return Ok(()),
+        };
+        let peripheral_variable = self
+            .svd_variable_cache
+            .get_variable_by_key(peripheral_key)
+            .ok_or_else(|| {
+                DebuggerError::Other(anyhow::anyhow!(
+                    "Failed to find the SVD peripheral with key {peripheral_key}"
+                ))
+            })?;
+        cache_peripheral_registers(
+            &self.peripheral_device.peripherals[peripheral_index],
+            &peripheral_variable,
+            &mut self.svd_variable_cache,
+            core,
+            &mut self.svd_write_attributes,
+            &mut self.field_enumerated_values,
+        )
+    }
+
+    /// Add the registers and fields of all the peripherals with a name that matches `is_match` to the `svd_variable_cache`,
+    /// e.g. before the registers of a peripheral are searched by name. Peripheral names include their group, e.g. `GPIO.GPIOA`.
+    pub(crate) fn expand_peripherals_matching(
+        &mut self,
+        core: &mut Core,
+        is_match: &dyn Fn(&str) -> bool,
+    ) -> Result<(), DebuggerError> {
+        let peripheral_keys = self
+            .unexpanded_peripherals
+            .keys()
+            .copied()
+            .filter(|peripheral_key| {
+                self.svd_variable_cache
+                    .get_variable_by_key(*peripheral_key)
+                    .map_or(false, |peripheral_variable| {
+                        is_match(&peripheral_variable.name.to_string())
+                    })
+            })
+            .collect::<Vec<i64>>();
+        for peripheral_key in peripheral_keys {
+            self.expand_peripheral(core, peripheral_key)?;
+        }
+        Ok(())
+    }
+
+    /// Add the registers and fields of the peripheral with the dotted `name`, or of the peripheral that contains the register or field with that name,
+    /// to the `svd_variable_cache`, so that it can be found by name, e.g. `GPIO.GPIOA.ODR`. A numeric `name` is the `variable_key` of a peripheral.
+    /// Names that do not belong to a peripheral, and peripherals that were already expanded, are ignored.
+    pub(crate) fn expand_peripheral_for_name(
+        &mut self,
+        core: &mut Core,
+        name: &str,
+    ) -> Result<(), DebuggerError> {
+        if let Ok(peripheral_key) = name.parse::<i64>() {
+            return self.expand_peripheral(core, peripheral_key);
+        }
+        match dotted_name_prefixes(name)
+            .find_map(|prefix| self.peripheral_keys_by_name.get(prefix).copied())
+        {
+            Some(peripheral_key) => self.expand_peripheral(core, peripheral_key),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The prefixes of the dotted `name` that end at a `.`, from the shortest to the longest, followed by the `name` itself,
+/// e.g. `GPIO`, `GPIO.GPIOA` and `GPIO.GPIOA.ODR` for `GPIO.GPIOA.ODR`.
+fn dotted_name_prefixes(name: &str) -> impl Iterator<Item = &str> {
+    name.match_indices('.')
+        .map(move |(index, _)| &name[..index])
+        .chain(std::iter::once(name))
 }
 
 /// Create a [`probe_rs::debug::VariableCache`] from a Device that was parsed from a CMSIS-SVD file.
+/// Only the peripheral groups and peripherals are added, and the peripherals are added to `unexpanded_peripherals`,
+/// with their index in `peripheral_device.peripherals`, so that their registers can be added when they are first needed.
 pub(crate) fn variable_cache_from_svd<P: ProtocolAdapter>(
-    peripheral_device: Device,
+    peripheral_device: &Device,
     core: &mut Core,
     debug_adapter: &mut DebugAdapter<P>,
     progress_id: i64,
+    unexpanded_peripherals: &mut HashMap<i64, usize>,
 ) -> Result<probe_rs::debug::VariableCache, DebuggerError> {
     let mut svd_cache = probe_rs::debug::VariableCache::new();
     let mut device_root_variable = Variable::new(None, None);
@@ -92,7 +291,7 @@ pub(crate) fn variable_cache_from_svd<P: ProtocolAdapter>(
     let mut peripheral_group_variable = Variable::new(None, None);
     peripheral_group_variable.name = VariableName::Named(peripheral_device.name.clone());
     let mut peripheral_parent_key = device_root_variable.variable_key;
-    for peripheral in &peripheral_device.peripherals {
+    for (peripheral_index, peripheral) in peripheral_device.peripherals.iter().enumerate() {
         if let (Some(peripheral_group_name), VariableName::Named(variable_group_name)) =
             (&peripheral.group_name, &peripheral_group_variable.name)
         {
@@ -158,95 +357,200 @@ pub(crate) fn variable_cache_from_svd<P: ProtocolAdapter>(
         ));
         peripheral_variable =
             svd_cache.cache_variable(Some(peripheral_parent_key), peripheral_variable, core)?;
-        for register in peripheral.all_registers() {
-            let mut register_variable = Variable::new(None, None);
-            register_variable.name = VariableName::Named(format!(
+        unexpanded_peripherals.insert(peripheral_variable.variable_key, peripheral_index);
+    }
+
+    Ok(svd_cache)
+}
+
+/// Add the registers and fields of `peripheral` to the `svd_cache`, as children of `peripheral_variable`.
+/// The attributes that are needed to write the registers and fields are added to `svd_write_attributes`,
+/// and the `enumeratedValues` of fields are added to `field_enumerated_values`.
+fn cache_peripheral_registers(
+    peripheral: &Peripheral,
+    peripheral_variable: &Variable,
+    svd_cache: &mut VariableCache,
+    core: &mut Core,
+    svd_write_attributes: &mut HashMap<i64, SvdWriteAttributes>,
+    field_enumerated_values: &mut HashMap<i64, FieldEnumeratedValues>,
+) -> Result<(), DebuggerError> {
+    for register in peripheral.all_registers() {
+        let mut register_variable = Variable::new(None, None);
+        register_variable.name = VariableName::Named(format!(
+            "{}.{}",
+            &peripheral_variable.name,
+            register.name.clone()
+        ));
+        register_variable.type_name = VariableType::Other(
+            register
+                .description
+                .clone()
+                .unwrap_or_else(|| "Peripheral Register".to_string()),
+        );
+        register_variable.variable_node_type = VariableNodeType::SvdRegister;
+        register_variable.memory_location =
+            VariableLocation::Address(peripheral.base_address + register.address_offset as u64);
+        let mut register_has_restricted_read = false;
+        if register.read_action.is_some()
+            || (if let Some(register_access) = register.properties.access {
+                register_access == Access::ReadWriteOnce || register_access == Access::WriteOnly
+            } else {
+                false
+            })
+        {
+            register_variable.set_value(probe_rs::debug::VariableValue::Error(
+                "Register access doesn't allow reading, or will have side effects.".to_string(),
+            ));
+            register_has_restricted_read = true;
+        }
+        register_variable = svd_cache.cache_variable(
+            Some(peripheral_variable.variable_key),
+            register_variable,
+            core,
+        )?;
+        let register_size = register.properties.size.unwrap_or(32);
+        for field in register.fields() {
+            let mut field_variable = Variable::new(None, None);
+            field_variable.name = VariableName::Named(format!(
                 "{}.{}",
-                &peripheral_variable.name,
-                register.name.clone()
+                &register_variable.name,
+                field.name.clone()
             ));
-            register_variable.type_name = VariableType::Other(
-                register
+            field_variable.type_name = VariableType::Other(
+                field
                     .description
                     .clone()
-                    .unwrap_or_else(|| "Peripheral Register".to_string()),
+                    .unwrap_or_else(|| "Register Field".to_string()),
             );
-            register_variable.variable_node_type = VariableNodeType::SvdRegister;
-            register_variable.memory_location =
-                VariableLocation::Address(peripheral.base_address + register.address_offset as u64);
-            let mut register_has_restricted_read = false;
-            if register.read_action.is_some()
-                || (if let Some(register_access) = register.properties.access {
-                    register_access == Access::ReadWriteOnce || register_access == Access::WriteOnly
+            field_variable.variable_node_type = VariableNodeType::SvdField;
+            field_variable.memory_location = register_variable.memory_location.clone();
+            // For SVD fields, we overload the range_lower_bound and range_upper_bound as the bit range LSB and MSB.
+            field_variable.range_lower_bound = field.bit_offset() as i64;
+            field_variable.range_upper_bound = (field.bit_offset() + field.bit_width()) as i64;
+            if register_has_restricted_read {
+                register_variable.set_value(probe_rs::debug::VariableValue::Error(
+                    "Register access doesn't allow reading, or will have side effects.".to_string(),
+                ));
+            } else if field.read_action.is_some()
+                || (if let Some(field_access) = field.access {
+                    field_access == Access::ReadWriteOnce || field_access == Access::WriteOnly
                 } else {
                     false
                 })
             {
+                field_variable.set_value(probe_rs::debug::VariableValue::Error(
+                    "Field access doesn't allow reading, or will have side effects.".to_string(),
+                ));
+                // If we can't read any of the bits, then don't read the register either.
                 register_variable.set_value(probe_rs::debug::VariableValue::Error(
-                    "Register access doesn't allow reading, or will have side effects.".to_string(),
+                    "Some fields' access doesn't allow reading, or will have side effects."
+                        .to_string(),
                 ));
                 register_has_restricted_read = true;
+                register_variable = svd_cache.cache_variable(
+                    Some(peripheral_variable.variable_key),
+                    register_variable,
+                    core,
+                )?;
             }
-            register_variable = svd_cache.cache_variable(
-                Some(peripheral_variable.variable_key),
-                register_variable,
+            let field_variable = svd_cache.cache_variable(
+                Some(register_variable.variable_key),
+                field_variable,
                 core,
             )?;
-            for field in register.fields() {
-                let mut field_variable = Variable::new(None, None);
-                field_variable.name = VariableName::Named(format!(
-                    "{}.{}",
-                    &register_variable.name,
-                    field.name.clone()
-                ));
-                field_variable.type_name = VariableType::Other(
-                    field
-                        .description
-                        .clone()
-                        .unwrap_or_else(|| "Register Field".to_string()),
-                );
-                field_variable.variable_node_type = VariableNodeType::SvdField;
-                field_variable.memory_location = register_variable.memory_location.clone();
-                // For SVD fields, we overload the range_lower_bound and range_upper_bound as the bit range LSB and MSB.
-                field_variable.range_lower_bound = field.bit_offset() as i64;
-                field_variable.range_upper_bound = (field.bit_offset() + field.bit_width()) as i64;
-                if register_has_restricted_read {
-                    register_variable.set_value(probe_rs::debug::VariableValue::Error(
-                        "Register access doesn't allow reading, or will have side effects."
-                            .to_string(),
-                    ));
-                } else if field.read_action.is_some()
-                    || (if let Some(field_access) = field.access {
-                        field_access == Access::ReadWriteOnce || field_access == Access::WriteOnly
-                    } else {
-                        false
-                    })
-                {
-                    field_variable.set_value(probe_rs::debug::VariableValue::Error(
-                        "Field access doesn't allow reading, or will have side effects."
-                            .to_string(),
-                    ));
-                    // If we can't read any of the bits, then don't read the register either.
-                    register_variable.set_value(probe_rs::debug::VariableValue::Error(
-                        "Some fields' access doesn't allow reading, or will have side effects."
-                            .to_string(),
-                    ));
-                    register_has_restricted_read = true;
-                    register_variable = svd_cache.cache_variable(
-                        Some(peripheral_variable.variable_key),
-                        register_variable,
-                        core,
-                    )?;
+            svd_write_attributes.insert(
+                field_variable.variable_key,
+                SvdWriteAttributes {
+                    access: field.access.or(register.properties.access),
+                    register_size,
+                    register_readable: false,
+                },
+            );
+            // Only the enumerated values that describe the values that are read are used to show the value of the field.
+            let mut enumerated_values = FieldEnumeratedValues::default();
+            for enumerated_value in field
+                .enumerated_values
+                .iter()
+                .filter(|enumerated_values| enumerated_values.usage != Some(Usage::Write))
+                .flat_map(|enumerated_values| &enumerated_values.values)
+            {
+                match enumerated_value.value {
+                    Some(value) => enumerated_values
+                        .values
+                        .push((value, enumerated_value.name.clone())),
+                    None if enumerated_value.is_default == Some(true) => {
+                        enumerated_values.default_name = Some(enumerated_value.name.clone())
+                    }
+                    None => {}
                 }
-                // TODO: Extend the Variable definition, so that we can resolve the EnumeratedValues for fields.
-                svd_cache.cache_variable(
-                    Some(register_variable.variable_key),
-                    field_variable,
-                    core,
-                )?;
+            }
+            if !enumerated_values.values.is_empty() || enumerated_values.default_name.is_some() {
+                field_enumerated_values.insert(field_variable.variable_key, enumerated_values);
             }
         }
+        svd_write_attributes.insert(
+            register_variable.variable_key,
+            SvdWriteAttributes {
+                access: register.properties.access,
+                register_size,
+                register_readable: !register_has_restricted_read,
+            },
+        );
     }
+    Ok(())
+}
 
-    Ok(svd_cache)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field_bits(offset: i64, width: i64) -> SvdFieldBits {
+        let mut field_variable = Variable::new(None, None);
+        field_variable.variable_node_type = VariableNodeType::SvdField;
+        field_variable.range_lower_bound = offset;
+        field_variable.range_upper_bound = offset + width;
+        SvdFieldBits::of(&field_variable)
+    }
+
+    #[test]
+    fn dotted_name_prefixes_end_at_dots() {
+        assert_eq!(
+            dotted_name_prefixes("GPIO.GPIOA.ODR").collect::<Vec<_>>(),
+            vec!["GPIO", "GPIO.GPIOA", "GPIO.GPIOA.ODR"]
+        );
+        assert_eq!(
+            dotted_name_prefixes("1234").collect::<Vec<_>>(),
+            vec!["1234"]
+        );
+    }
+
+    #[test]
+    fn field_bits_of_a_field_variable() {
+        let bits = field_bits(4, 3);
+        assert_eq!(
+            bits,
+            SvdFieldBits {
+                offset: 4,
+                width: 3
+            }
+        );
+        assert_eq!(bits.mask(), 0b0111_0000);
+        assert_eq!(bits.extract(0xffff_ff5f), 0b101);
+        assert_eq!(bits.insert(0xffff_ffff, 0b010), 0xffff_ffaf);
+        // Bits that do not fit in the field are ignored.
+        assert_eq!(bits.insert(0, 0b1010), 0b0010_0000);
+    }
+
+    #[test]
+    fn field_bits_of_full_width_and_top_bit_fields() {
+        let full_width = field_bits(0, 32);
+        assert_eq!(full_width.mask(), u32::MAX);
+        assert_eq!(full_width.extract(0x1234_5678), 0x1234_5678);
+        assert_eq!(full_width.insert(0x1234_5678, 0x8765_4321), 0x8765_4321);
+
+        let top_bit = field_bits(31, 1);
+        assert_eq!(top_bit.mask(), 0x8000_0000);
+        assert_eq!(top_bit.extract(0x8000_0000), 1);
+        assert_eq!(top_bit.insert(0, 1), 0x8000_0000);
+    }
 }
@@ -0,0 +1,200 @@
+use super::svd_variables::{SvdCache, SvdFieldBits};
+use crate::DebuggerError;
+use anyhow::anyhow;
+use parse_int::parse;
+use probe_rs::{
+    debug::{Variable, VariableName, VariableNodeType, VariableValue},
+    Core, MemoryInterface,
+};
+use svd_parser::svd::Access;
+
+impl SvdCache {
+    /// Write `new_value` to the SVD register or field `name`, of the peripheral or register with `parent_key`, e.g. from a `setVariable` request.
+    /// The `name` is the name that is shown in the variables view, e.g. `ODR5`, or the dotted name, e.g. `GPIO.GPIOA.ODR.ODR5`.
+    /// Returns the value to show for the register or field, in the same format as it is shown in the variables view.
+    pub(crate) fn write_child_by_name(
+        &mut self,
+        core: &mut Core,
+        parent_key: i64,
+        name: &str,
+        new_value: &str,
+    ) -> Result<String, DebuggerError> {
+        self.expand_peripheral(core, parent_key)?;
+        let parent_name = self
+            .svd_variable_cache
+            .get_variable_by_key(parent_key)
+            .map(|parent_variable| parent_variable.name.to_string())
+            .ok_or_else(|| {
+                DebuggerError::Other(anyhow!(
+                    "Failed to find the SVD peripheral or register with key {parent_key}"
+                ))
+            })?;
+        let full_name = svd_child_name(&parent_name, name);
+        let variable = self
+            .svd_variable_cache
+            .get_variable_by_name_and_parent(&VariableName::Named(full_name), Some(parent_key))
+            .ok_or_else(|| {
+                DebuggerError::Other(anyhow!(
+                    "{name} is not a register or field of {parent_name} in the SVD file"
+                ))
+            })?;
+        self.write_variable(core, &variable, new_value)
+    }
+
+    /// Write `new_value` to the SVD register or field `variable`.
+    /// - Registers are written with the size from the SVD file (8, 16 or 32 bits).
+    /// - Fields are written by reading the register, replacing the bits of the field, and writing the register back.
+    ///   This requires that the register can be read without side effects.
+    ///
+    /// Values can be written in decimal, or with a `0x`, `0o` or `0b` prefix, and have to fit in the register or field.
+    /// Registers and fields that are read-only in the SVD file cannot be written.
+    pub(crate) fn write_variable(
+        &self,
+        core: &mut Core,
+        variable: &Variable,
+        new_value: &str,
+    ) -> Result<String, DebuggerError> {
+        let name = &variable.name;
+        let attributes = self
+            .svd_write_attributes
+            .get(&variable.variable_key)
+            .copied()
+            .ok_or_else(|| {
+                DebuggerError::Other(anyhow!(
+                    "{name} is not a peripheral register or field, and cannot be set"
+                ))
+            })?;
+        if attributes.access == Some(Access::ReadOnly) {
+            return Err(DebuggerError::Other(anyhow!(
+                "{name} is read-only, and cannot be set"
+            )));
+        }
+        let new_value = new_value.trim();
+        let value = parse::<u32>(new_value).map_err(|error| {
+            DebuggerError::Other(anyhow!("Invalid value {new_value:?} for {name}: {error}"))
+        })?;
+        let address = variable.memory_location.memory_address().map_err(|error| {
+            DebuggerError::Other(anyhow!("Invalid address for {name}: {error}"))
+        })?;
+
+        let register_value = match variable.variable_node_type {
+            VariableNodeType::SvdRegister => {
+                check_width(name, value, attributes.register_size)?;
+                value
+            }
+            VariableNodeType::SvdField => {
+                let field_bits = SvdFieldBits::of(variable);
+                check_width(name, value, field_bits.width)?;
+                let register_readable = variable
+                    .parent_key
+                    .and_then(|parent_key| self.svd_write_attributes.get(&parent_key))
+                    .map_or(false, |register_attributes| {
+                        register_attributes.register_readable
+                    });
+                if !register_readable {
+                    return Err(DebuggerError::Other(anyhow!(
+                        "{name} cannot be set, because its register cannot be read without side effects. Please set the value of the whole register instead."
+                    )));
+                }
+                let current_value = read_register(core, address, attributes.register_size)?;
+                field_bits.insert(current_value, value)
+            }
+            _ => {
+                return Err(DebuggerError::Other(anyhow!(
+                    "{name} is not a peripheral register or field, and cannot be set"
+                )))
+            }
+        };
+        write_register(core, address, attributes.register_size, register_value)?;
+
+        let mut updated_variable = variable.clone();
+        updated_variable.set_value(VariableValue::Valid(register_value.to_string()));
+        Ok(updated_variable.get_value(&self.svd_variable_cache))
+    }
+}
+
+/// The dotted name of the SVD register or field `name`, of the peripheral or register `parent_name`.
+/// The `name` can be the last part of the dotted name, as it is shown in the variables view, or the dotted name itself.
+fn svd_child_name(parent_name: &str, name: &str) -> String {
+    match name.strip_prefix(parent_name) {
+        Some(child_name) if child_name.starts_with('.') => name.to_string(),
+        _ => format!("{parent_name}.{name}"),
+    }
+}
+
+/// Check that `value` fits in `bit_width` bits.
+fn check_width(name: &VariableName, value: u32, bit_width: u32) -> Result<(), DebuggerError> {
+    if bit_width < 32 && value >> bit_width != 0 {
+        return Err(DebuggerError::Other(anyhow!(
+            "The value {value:#x} does not fit in the {bit_width} bits of {name}"
+        )));
+    }
+    Ok(())
+}
+
+/// Read a register of `register_size` bits. There is no 16 bit word access, so 16 bit registers are read as two bytes, in little endian order.
+fn read_register(core: &mut Core, address: u64, register_size: u32) -> Result<u32, DebuggerError> {
+    Ok(match register_size {
+        8 => core.read_word_8(address)? as u32,
+        16 => {
+            let mut bytes = [0_u8; 2];
+            core.read_8(address, &mut bytes)?;
+            u16::from_le_bytes(bytes) as u32
+        }
+        32 => core.read_word_32(address)?,
+        _ => return Err(unsupported_size(address, register_size)),
+    })
+}
+
+/// Write a register of `register_size` bits, in the same way as [`read_register`].
+fn write_register(
+    core: &mut Core,
+    address: u64,
+    register_size: u32,
+    value: u32,
+) -> Result<(), DebuggerError> {
+    match register_size {
+        8 => core.write_word_8(address, value as u8)?,
+        16 => core.write_8(address, &(value as u16).to_le_bytes())?,
+        32 => core.write_word_32(address, value)?,
+        _ => return Err(unsupported_size(address, register_size)),
+    }
+    Ok(())
+}
+
+fn unsupported_size(address: u64, register_size: u32) -> DebuggerError {
+    DebuggerError::Other(anyhow!(
+        "Registers of {register_size} bits, like the register at {address:#010x}, cannot be set"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svd_child_name_accepts_short_and_dotted_names() {
+        assert_eq!(
+            svd_child_name("GPIO.GPIOA.ODR", "ODR5"),
+            "GPIO.GPIOA.ODR.ODR5"
+        );
+        assert_eq!(
+            svd_child_name("GPIO.GPIOA.ODR", "GPIO.GPIOA.ODR.ODR5"),
+            "GPIO.GPIOA.ODR.ODR5"
+        );
+        // A child name that only starts with the name of the parent is not a dotted name.
+        assert_eq!(
+            svd_child_name("GPIO.GPIOA", "GPIO.GPIOAB"),
+            "GPIO.GPIOA.GPIO.GPIOAB"
+        );
+    }
+
+    #[test]
+    fn check_width_rejects_values_that_do_not_fit() {
+        let name = VariableName::Named("GPIO.GPIOA.ODR.ODR5".to_string());
+        assert!(check_width(&name, 1, 1).is_ok());
+        assert!(check_width(&name, 2, 1).is_err());
+        assert!(check_width(&name, u32::MAX, 32).is_ok());
+        assert!(check_width(&name, 0x100, 8).is_err());
+    }
+}
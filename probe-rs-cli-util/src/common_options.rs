@@ -83,6 +83,13 @@ pub struct FlashOptions {
         help = "Enable this flag to restore all bytes erased in the sector erase but not overwritten by any page."
     )]
     pub restore_unwritten: bool,
+    #[clap(
+        name = "verify-only",
+        long = "verify-only",
+        help = "Compare the contents of the target with the image, without erasing or programming anything.\n\
+        Fails if any of the memory occupied by the image differs."
+    )]
+    pub verify_only: bool,
     #[clap(
         name = "filename",
         long = "flash-layout",
@@ -460,6 +467,8 @@ pub enum OperationError {
         target_spec: Option<String>,
         path: PathBuf,
     },
+    #[error("The contents of the target do not match '{path}'. The first difference is at address {address:#010x}.")]
+    VerifyMismatch { path: PathBuf, address: u64 },
     #[error("Failed to parse the chip description '{path}'.")]
     FailedChipDescriptionParsing {
         #[source]
@@ -168,3 +168,57 @@ pub fn run_flash_download(
 
     Ok(())
 }
+
+/// Compares the memory occupied by the data in the loader with the data, without erasing or programming anything.
+/// The result of every contiguous data chunk is printed, and an error is returned for the first chunk that does not match.
+pub fn run_flash_verify(
+    session: &mut Session,
+    path: &Path,
+    opt: &FlashOptions,
+    loader: FlashLoader,
+) -> Result<(), OperationError> {
+    // Start timer.
+    let instant = Instant::now();
+
+    let results = loader
+        .verify(session)
+        .map_err(|error| OperationError::FlashingFailed {
+            source: error,
+            target: Box::new(session.target().clone()),
+            target_spec: opt.probe_options.chip.clone(),
+            path: path.to_path_buf(),
+        })?;
+
+    for result in &results {
+        let range = format!(
+            "{:#010x}..{:#010x} ({} bytes)",
+            result.address,
+            result.address + result.size,
+            result.size
+        );
+        match result.first_mismatch {
+            Some(address) => logging::println(format!(
+                "    {} {range}, first difference at {address:#010x}",
+                "Mismatch".red().bold(),
+            )),
+            None => logging::println(format!("       {} {range}", "Match".green().bold())),
+        }
+    }
+
+    if let Some(address) = results.iter().find_map(|result| result.first_mismatch) {
+        return Err(OperationError::VerifyMismatch {
+            path: path.to_path_buf(),
+            address,
+        });
+    }
+
+    // Stop timer.
+    let elapsed = instant.elapsed();
+    logging::println(format!(
+        "    {} in {}s",
+        "Verified".green().bold(),
+        elapsed.as_millis() as f32 / 1000.0,
+    ));
+
+    Ok(())
+}
@@ -3,6 +3,8 @@ use anyhow::{anyhow, Result};
 use defmt_decoder::DecodeError;
 use num_traits::Zero;
 use probe_rs::config::MemoryRegion;
+use probe_rs::debug::remap_source_path;
+pub use probe_rs::debug::SourcePathRemap;
 pub use probe_rs::rtt::ChannelMode;
 use probe_rs::rtt::{DownChannel, Rtt, ScanRegion, UpChannel};
 use probe_rs::Core;
@@ -10,11 +12,13 @@ use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::File;
 use std::{
+    cell::Cell,
     fmt,
     fmt::Write,
     fs,
-    io::{Read, Seek},
+    io::{BufReader, BufWriter, Read, Seek, Write as _},
     str::FromStr,
+    sync::{Arc, Mutex},
 };
 use time::{OffsetDateTime, UtcOffset};
 
@@ -82,6 +86,39 @@ impl Default for DataFormat {
         DataFormat::String
     }
 }
+impl DataFormat {
+    /// Returns `true` if data in this format can be encoded by the host, and sent to the target on a down channel.
+    /// Defmt frames can only be encoded by the firmware.
+    pub fn is_sendable(&self) -> bool {
+        !matches!(self, DataFormat::Defmt)
+    }
+
+    /// Encode `data` to send it to the target on a down channel.
+    /// - `String` sends the UTF-8 bytes of `data`, unchanged.
+    /// - `BinaryLE` expects hexadecimal bytes, separated by whitespace or commas, with an optional `0x` prefix, e.g. `0x01 0xA0 ff`.
+    pub fn encode(&self, data: &str) -> Result<Vec<u8>> {
+        match self {
+            DataFormat::String => Ok(data.as_bytes().to_vec()),
+            DataFormat::BinaryLE => data
+                .split(|character: char| character.is_whitespace() || character == ',')
+                .filter(|byte| !byte.is_empty())
+                .map(|byte| {
+                    let hex_digits = byte
+                        .strip_prefix("0x")
+                        .or_else(|| byte.strip_prefix("0X"))
+                        .unwrap_or(byte);
+                    u8::from_str_radix(hex_digits, 16).map_err(|error| {
+                        anyhow!("`{byte}` is not a valid hexadecimal byte: {error}")
+                    })
+                })
+                .collect(),
+            DataFormat::Defmt => Err(anyhow!(
+                "Data in the {:?} format cannot be sent to the target",
+                self
+            )),
+        }
+    }
+}
 
 /// The initial configuration for RTT (Real Time Transfer). This configuration is complimented with the additional information specified for each of the channels in `RttChannel`.
 #[derive(clap::Parser, Debug, Clone, Deserialize, Default)]
@@ -93,6 +130,174 @@ pub struct RttConfig {
     #[structopt(skip)]
     #[serde(default = "default_channel_formats", rename = "rttChannelFormats")]
     pub channels: Vec<RttChannelConfig>,
+    /// Replace the prefix of source paths, e.g. a path on the build machine, with a local path. The first matching entry is used.
+    /// This applies to defmt source locations, and the debugger also applies it to the source paths of the debug information.
+    #[structopt(skip)]
+    #[serde(default, rename = "sourcePathRemaps", alias = "rttSourcePathRemaps")]
+    pub source_path_remaps: Vec<SourcePathRemap>,
+    /// Show defmt source locations relative to this directory (e.g. the workspace root), so that editors can resolve them.
+    /// Locations outside of this directory are shown unchanged.
+    #[structopt(skip)]
+    #[serde(default, rename = "rttSourceRoot")]
+    pub source_root: Option<PathBuf>,
+    /// Record the raw data of every up channel to this file, so that it can be decoded again later, without the target.
+    /// See [`replay_rtt_recording`].
+    #[structopt(skip)]
+    #[serde(default, rename = "rttRecordingPath")]
+    pub recording_path: Option<PathBuf>,
+    /// How to locate the RTT control block on the target. The default uses the address of the `_SEGGER_RTT` symbol in the ELF file.
+    #[structopt(skip)]
+    #[serde(default, rename = "rttScanMode")]
+    pub scan_mode: RttScanMode,
+    /// Attach to these control blocks, instead of the one located with the `scan_mode`, e.g. for a bootloader and an application that each have one.
+    /// Their channels are merged, and numbered in the order of the control blocks. The `channels` configuration applies to the channels of every control block,
+    /// by their number in that control block, but the `recording_path` and the channel `log_file`s only apply to the first control block.
+    #[structopt(skip)]
+    #[serde(default, rename = "rttControlBlocks")]
+    pub control_blocks: Vec<RttControlBlockConfig>,
+    /// The number of times to retry attaching to RTT, when it fails, e.g. because the firmware has not initialized the control block yet after a reset.
+    #[structopt(skip)]
+    #[serde(default, rename = "rttRetries")]
+    pub retries: u32,
+    /// The time, in milliseconds, over which the `retries` are spread. The delay before each retry is `timeout_ms / retries`.
+    #[structopt(skip)]
+    #[serde(default, rename = "rttTimeoutMs")]
+    pub timeout_ms: u64,
+}
+
+/// How to locate the RTT control block on the target.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RttScanMode {
+    /// Use the address of the `_SEGGER_RTT` symbol in the ELF file.
+    #[default]
+    Exact,
+    /// Scan all the RAM regions of the target for the control block, e.g. for stripped binaries without the `_SEGGER_RTT` symbol.
+    /// This can be slow on targets with a lot of RAM.
+    Ram,
+    /// Scan `length` bytes of target memory, starting at `address`, for the control block.
+    Range { address: u32, length: u32 },
+    /// Use the control block at `address`, e.g. when the firmware is relocated, so that the `_SEGGER_RTT` symbol in the ELF file does not reflect where the control block is.
+    Address { address: u32 },
+}
+/// An RTT control block to attach to, when the target has more than one. See [`RttConfig::control_blocks`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RttControlBlockConfig {
+    /// The prefix for the names of the channels of this control block, e.g. `bootloader`.
+    pub label: String,
+    /// The address of the control block, or the start of the memory to scan for it, if `length` is set.
+    pub address: u32,
+    /// Scan `length` bytes of target memory, starting at `address`, for the control block.
+    #[serde(default)]
+    pub length: Option<u32>,
+}
+
+impl RttControlBlockConfig {
+    /// The region of target memory to search for the control block.
+    pub fn scan_region(&self) -> ScanRegion {
+        match self.length {
+            Some(length) => ScanRegion::Range(self.address..self.address.saturating_add(length)),
+            None => ScanRegion::Exact(self.address),
+        }
+    }
+}
+
+impl RttScanMode {
+    /// The region of target memory to search for the control block, where `rtt_symbol_address` is the address of the `_SEGGER_RTT` symbol, if it is known.
+    pub fn scan_region(&self, rtt_symbol_address: Option<u64>) -> Result<ScanRegion> {
+        match self {
+            RttScanMode::Exact => rtt_symbol_address
+                .ok_or_else(|| {
+                    anyhow!("No RTT control block found in ELF file. Use the `ram` or `range` scan mode to search the target memory for it.")
+                })
+                .and_then(|rtt_symbol_address| {
+                    u32::try_from(rtt_symbol_address).map_err(|_| {
+                        anyhow!("The `_SEGGER_RTT` symbol address {rtt_symbol_address:#x} is not a 32-bit address")
+                    })
+                })
+                .map(ScanRegion::Exact),
+            RttScanMode::Ram => Ok(ScanRegion::Ram),
+            RttScanMode::Range { address, length } => {
+                Ok(ScanRegion::Range(*address..address.saturating_add(*length)))
+            }
+            RttScanMode::Address { address } => {
+                if let Some(rtt_symbol_address) = rtt_symbol_address {
+                    log::trace!("Using the RTT control block address {address:#010x}, instead of the `_SEGGER_RTT` symbol address {rtt_symbol_address:#010x}");
+                }
+                Ok(ScanRegion::Exact(*address))
+            }
+        }
+    }
+}
+
+impl RttConfig {
+    /// The control blocks to attach to, with the label for the names of their channels, in the order of the `control_blocks`.
+    /// Without `control_blocks`, this is the single control block located with an explicit `scan_mode`, or else at the `device_control_block_address`
+    /// (from the device configuration of the debugger), if there is one, or else at the address of the `_SEGGER_RTT` symbol, `rtt_symbol_address`.
+    /// Every control block address must be in a RAM region of the `memory_map`.
+    pub fn control_block_scan_regions(
+        &self,
+        device_control_block_address: Option<u64>,
+        rtt_symbol_address: Option<u64>,
+        memory_map: &[MemoryRegion],
+    ) -> Result<Vec<(Option<String>, ScanRegion)>> {
+        let scan_regions = if !self.control_blocks.is_empty() {
+            if let Some(address) = device_control_block_address {
+                log::warn!("The RTT control block address {address:#010x} of the device configuration is not used, because the `rttControlBlocks` are configured.");
+            }
+            self.control_blocks
+                .iter()
+                .map(|control_block| {
+                    (
+                        Some(control_block.label.clone()),
+                        control_block.scan_region(),
+                    )
+                })
+                .collect()
+        } else {
+            let scan_region = match (self.scan_mode, device_control_block_address) {
+                (RttScanMode::Exact, Some(address)) => RttScanMode::Address {
+                    address: u32::try_from(address).map_err(|_| {
+                        anyhow!(
+                            "The RTT control block address {address:#x} is not a 32-bit address"
+                        )
+                    })?,
+                }
+                .scan_region(rtt_symbol_address)?,
+                (scan_mode, _) => scan_mode.scan_region(rtt_symbol_address)?,
+            };
+            vec![(None, scan_region)]
+        };
+        for (_, scan_region) in &scan_regions {
+            let address = match scan_region {
+                ScanRegion::Ram => continue,
+                ScanRegion::Range(range) => range.start,
+                ScanRegion::Exact(address) => *address,
+            };
+            if !memory_map.iter().any(|memory_region| {
+                matches!(memory_region, MemoryRegion::Ram(ram_region) if ram_region.range.contains(&u64::from(address)))
+            }) {
+                return Err(anyhow!(
+                    "The RTT control block address {address:#010x} is not in a RAM region of the target"
+                ));
+            }
+        }
+        Ok(scan_regions)
+    }
+
+    /// Apply the `source_path_remaps` and `source_root` to the source location `path`.
+    /// Paths that do not match any of them are returned unchanged.
+    pub fn map_source_path(&self, path: &Path) -> PathBuf {
+        let remapped_path = remap_source_path(&self.source_path_remaps, path);
+        match &self.source_root {
+            Some(source_root) => remapped_path
+                .strip_prefix(source_root)
+                .map(Path::to_path_buf)
+                .unwrap_or(remapped_path),
+            None => remapped_path,
+        }
+    }
 }
 
 /// The User specified configuration for each active RTT Channel. The configuration is passed via a DAP Client configuration (`launch.json`). If no configuration is specified, the defaults will be `Dataformat::String` and `show_timestamps=false`.
@@ -101,16 +306,175 @@ pub struct RttConfig {
 pub struct RttChannelConfig {
     pub channel_number: Option<usize>,
     pub channel_name: Option<String>,
+    /// The format of the data that is received from the target on this channel. This overrides the detected format,
+    /// which is `Defmt` for a channel named `defmt`, and `String` for all other channels.
     #[serde(default)]
-    pub data_format: DataFormat,
+    pub data_format: Option<DataFormat>,
     #[structopt(skip)]
     #[serde(default)]
-    // Control the inclusion of timestamps for DataFormat::String.
+    // Control the inclusion of timestamps for DataFormat::String. Each line is prefixed with the host time, e.g. `[12:34:56.789]`.
     pub show_timestamps: bool,
     #[structopt(skip)]
     #[serde(default = "default_include_location")]
     // Control the inclusion of source location information for DataFormat::Defmt.
     pub show_location: bool,
+    /// The format of the data that is sent to the target on the down channel with the same `channel_number`, if it differs from `data_format`,
+    /// e.g. a `BinaryLE` command channel that is paired with a `Defmt` log channel. The format has to be sendable, see [`DataFormat::is_sendable`].
+    #[structopt(skip)]
+    #[serde(default)]
+    pub down_data_format: Option<DataFormat>,
+    /// What the target does when the up channel buffer is full. Without it, a `Defmt` channel uses `BlockIfFull`, and other channels keep the mode that the target uses.
+    /// A non-blocking mode for a `Defmt` channel avoids stalling the target when the host cannot keep up, but frames are lost, and cannot be decoded.
+    #[structopt(skip)]
+    #[serde(default)]
+    pub channel_mode: Option<ChannelMode>,
+    /// Only show the `Defmt` frames that pass this filter, e.g. `warn,my_app::radio=trace`. See [`DefmtFilter`].
+    #[structopt(skip)]
+    #[serde(default)]
+    pub defmt_filter: Option<DefmtFilter>,
+    /// How `Defmt` frames are shown: as formatted text, or as one JSON object per line, for log processing tools.
+    #[structopt(skip)]
+    #[serde(default)]
+    pub defmt_format: DefmtFormat,
+    /// Write the decoded data of the up channel to this file, in addition to showing it. An existing file is appended to, and channels with the same `log_file` share the file.
+    #[structopt(skip)]
+    #[serde(default)]
+    pub log_file: Option<PathBuf>,
+    /// When the `log_file` grows beyond this number of bytes, it is renamed with a `.1` suffix (replacing the previous one), and a new file is started.
+    /// Without it, the file grows without limit.
+    #[structopt(skip)]
+    #[serde(default)]
+    pub log_file_max_size: Option<u64>,
+}
+
+/// How decoded `Defmt` frames are formatted. See [`RttChannelConfig::defmt_format`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DefmtFormat {
+    /// The human readable format, with the source location on a separate line, if `show_location` is set.
+    #[default]
+    Text,
+    /// One JSON object per frame and line, e.g.
+    /// `{"level":"INFO","timestamp":"0.000123","target":"my_app::radio","message":"...","location":{"file":"src/radio.rs","line":42}}`.
+    /// Fields that are not known for a frame are `null`.
+    Json,
+}
+/// A filter for defmt frames, by log level and module path, in the format of `RUST_LOG`, e.g. `warn,my_app::radio=trace`.
+/// - A directive without a module path (e.g. `warn`) sets the level for all modules. Without it, every level is shown.
+/// - A directive `module=level` sets the level for the module and its sub-modules. The longest matching module path is used.
+///
+/// The levels are `trace`, `debug`, `info`, `warn`, `error`, and `off`. Frames without a level (e.g. `defmt::println!`) are always shown.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub struct DefmtFilter {
+    /// The minimum level of modules that do not match any of the `module_levels`.
+    default_level: DefmtLevelFilter,
+    /// The minimum level of each module path.
+    module_levels: Vec<(String, DefmtLevelFilter)>,
+}
+
+/// The minimum level of the defmt frames that pass a [`DefmtFilter`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+enum DefmtLevelFilter {
+    #[default]
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Off,
+}
+
+impl FromStr for DefmtLevelFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_ascii_lowercase()[..] {
+            "trace" => Ok(Self::Trace),
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "warn" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            "off" => Ok(Self::Off),
+            _ => Err(anyhow!("`{s}` is not a valid defmt log level")),
+        }
+    }
+}
+
+impl DefmtLevelFilter {
+    /// Returns `true` if a frame with `level` passes this filter.
+    fn allows(&self, level: defmt_decoder::Level) -> bool {
+        let level = match level {
+            defmt_decoder::Level::Trace => Self::Trace,
+            defmt_decoder::Level::Debug => Self::Debug,
+            defmt_decoder::Level::Info => Self::Info,
+            defmt_decoder::Level::Warn => Self::Warn,
+            defmt_decoder::Level::Error => Self::Error,
+        };
+        level >= *self
+    }
+}
+
+impl FromStr for DefmtFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = DefmtFilter::default();
+        for directive in s
+            .split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+        {
+            match directive.split_once('=') {
+                Some((module_path, level)) => {
+                    let module_path = module_path.trim();
+                    if module_path.is_empty() {
+                        return Err(anyhow!(
+                            "The defmt filter directive `{directive}` has no module path"
+                        ));
+                    }
+                    filter
+                        .module_levels
+                        .push((module_path.to_string(), level.trim().parse()?))
+                }
+                // Unlike `RUST_LOG`, a directive without a level is not taken as a module path, so that a misspelled level is reported.
+                None => filter.default_level = directive.parse()?,
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl TryFrom<String> for DefmtFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl DefmtFilter {
+    /// Returns `true` if a frame with `level`, from the module `module_path`, passes the filter.
+    pub fn allows(&self, level: Option<defmt_decoder::Level>, module_path: Option<&str>) -> bool {
+        let level = match level {
+            Some(level) => level,
+            None => return true,
+        };
+        let level_filter = module_path
+            .and_then(|module_path| {
+                self.module_levels
+                    .iter()
+                    .filter(|(filter_path, _)| {
+                        module_path
+                            .strip_prefix(filter_path.as_str())
+                            .map_or(false, |rest| rest.is_empty() || rest.starts_with("::"))
+                    })
+                    .max_by_key(|(filter_path, _)| filter_path.len())
+                    .map(|(_, level_filter)| *level_filter)
+            })
+            .unwrap_or(self.default_level);
+        level_filter.allows(level)
+    }
 }
 
 /// This is the primary interface through which RTT channel data is read and written. Every actual RTT channel has a configuration and buffer that is used for this purpose.
@@ -119,12 +483,28 @@ pub struct RttActiveChannel {
     pub up_channel: Option<UpChannel>,
     pub down_channel: Option<DownChannel>,
     pub channel_name: String,
+    /// The format of the data that is received from the target on the `up_channel`.
     pub data_format: DataFormat,
-    /// Data that will be written to the down_channel (host to target)
-    _input_data: String,
+    /// The format of the data that is sent to the target on the `down_channel`.
+    pub down_data_format: DataFormat,
+    /// The mode to set for the `up_channel`, or `None` to keep the mode that the target uses. See [`RttChannelConfig::channel_mode`].
+    pub channel_mode: Option<ChannelMode>,
     rtt_buffer: RttBuffer,
     show_timestamps: bool,
     show_location: bool,
+    /// See [`RttChannelConfig::defmt_format`].
+    defmt_format: DefmtFormat,
+    /// `true` if the last `String` data that was decoded ended with a newline, so the next data starts a new line, that needs a timestamp.
+    at_line_start: Cell<bool>,
+    /// Only `Defmt` frames that pass this filter are shown. See [`RttChannelConfig::defmt_filter`].
+    defmt_filter: Option<DefmtFilter>,
+    /// Records the raw data of the up channel, if [`RttConfig::recording_path`] is set.
+    recorder: Option<Arc<Mutex<RttRecorder>>>,
+    /// Receives the decoded data of the up channel, if [`RttChannelConfig::log_file`] is set.
+    log_file: Option<Arc<Mutex<RttLogFile>>>,
+    /// Added to the channel numbers of the control block, so that they are unique when the channels of several control blocks are merged.
+    /// See [`RttActiveTarget::merge`].
+    channel_number_offset: usize,
 
     /// UTC offset used for creating timestamps
     ///
@@ -160,17 +540,16 @@ impl RttActiveChannel {
                     .as_ref()
                     .map(|down| down.name() == Some("defmt"))
             })
-            .unwrap_or(false); // If no explicit config is requested, assign a default
-        let (data_format, show_location) = if defmt_enabled {
-            let show_location = if let Some(channel_config) = channel_config {
-                channel_config.show_location
-            } else {
-                true
-            };
-            (DataFormat::Defmt, show_location)
+            // Without a channel (e.g. when a recording is replayed), use the configured name.
+            .unwrap_or_else(|| full_config.channel_name.as_deref() == Some("defmt"));
+        // A format from the channel configuration takes precedence over the detected format.
+        let data_format = full_config.data_format.unwrap_or(if defmt_enabled {
+            DataFormat::Defmt
         } else {
-            (full_config.data_format, false)
-        };
+            DataFormat::String
+        });
+        let show_location = data_format == DataFormat::Defmt
+            && channel_config.map_or(true, |channel_config| channel_config.show_location);
         let name = up_channel
             .as_ref()
             .and_then(|up| up.name().map(Into::into))
@@ -187,22 +566,49 @@ impl RttActiveChannel {
                     full_config.channel_number.unwrap_or(0)
                 )
             });
+        // Defmt cannot be encoded by the host, so a down channel that is paired with a defmt up channel defaults to `String`.
+        let down_data_format =
+            full_config
+                .down_data_format
+                .unwrap_or(if data_format.is_sendable() {
+                    data_format
+                } else {
+                    DataFormat::String
+                });
+        // For defmt, the channel blocks when full by default, because a partial frame cannot be decoded.
+        let channel_mode = full_config
+            .channel_mode
+            .or((data_format == DataFormat::Defmt).then_some(ChannelMode::BlockIfFull));
         Self {
             up_channel,
             down_channel,
             channel_name: name,
             data_format,
-            _input_data: String::new(),
+            down_data_format,
+            channel_mode,
             rtt_buffer: RttBuffer::new(buffer_size),
             show_timestamps: full_config.show_timestamps,
             show_location,
+            at_line_start: Cell::new(true),
+            defmt_format: full_config.defmt_format,
+            defmt_filter: full_config.defmt_filter,
+            recorder: None,
+            log_file: None,
+            channel_number_offset: 0,
             timestamp_offset,
         }
     }
 
     /// Returns the number of the `UpChannel`.
     pub fn number(&self) -> Option<usize> {
-        self.up_channel.as_ref().map(|uc| uc.number())
+        self.up_channel
+            .as_ref()
+            .map(|uc| uc.number() + self.channel_number_offset)
+    }
+
+    /// Replace the filter for `Defmt` frames, e.g. to show more detail while a problem is reproduced. `None` shows all frames.
+    pub fn set_defmt_filter(&mut self, defmt_filter: Option<DefmtFilter>) {
+        self.defmt_filter = defmt_filter;
     }
 
     /// Polls the RTT target for new data on the channel represented by `self`.
@@ -236,103 +642,260 @@ impl RttActiveChannel {
     /// Retrieves available data from the channel and if available, returns `Some(channel_number:String, formatted_data:String)`.
     /// If no data is available, or we encounter a recoverable error, it returns `None` value fore `formatted_data`.
     /// Non-recoverable errors are propagated to the caller.
+    /// If the channel is being recorded, the raw data is written to the recording before it is decoded.
     pub fn get_rtt_data(
         &mut self,
         core: &mut Core,
         defmt_state: Option<&(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
     ) -> Result<Option<(String, String)>, anyhow::Error> {
-        self
-            .poll_rtt(core)
-            .map(|bytes_read| {
-                Ok((
-                    self.number().unwrap_or(0).to_string(), // If the Channel doesn't have a number, then send the output to channel 0
-                    {
-                        let mut formatted_data = String::new();
-                        match self.data_format {
-                            DataFormat::String => {
-                                let incoming = String::from_utf8_lossy(&self.rtt_buffer.0[..bytes_read]).to_string();
-                                for (_i, line) in incoming.split_terminator('\n').enumerate() {
-                                    if self.show_timestamps {
-                                        write!(formatted_data, "{} :", OffsetDateTime::now_utc().to_offset(self.timestamp_offset))
-                                            .map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
+        let bytes_read = match self.poll_rtt(core) {
+            Some(bytes_read) => bytes_read,
+            None => return Ok(None),
+        };
+        // If the Channel doesn't have a number, then send the output to channel 0
+        let channel_number = self.number().unwrap_or(0);
+        let timestamp = OffsetDateTime::now_utc();
+        if let Some(recorder) = &self.recorder {
+            if let Err(error) = recorder
+                .lock()
+                .map_err(|_| anyhow!("The RTT recorder is poisoned"))
+                .and_then(|mut recorder| {
+                    recorder.record(timestamp, channel_number, &self.rtt_buffer.0[..bytes_read])
+                })
+            {
+                log::error!("Failed to record RTT data - {:?}", error);
+            }
+        }
+        let formatted_data =
+            self.decode_rtt_data(&self.rtt_buffer.0[..bytes_read], timestamp, defmt_state)?;
+        if let Some(log_file) = &self.log_file {
+            if let Err(error) = log_file
+                .lock()
+                .map_err(|_| anyhow!("The RTT log file is poisoned"))
+                .and_then(|mut log_file| log_file.write(&formatted_data))
+            {
+                log::error!("Failed to write RTT data to the log file - {:?}", error);
+            }
+        }
+        Ok(Some((channel_number.to_string(), formatted_data)))
+    }
+
+    /// Decode and format `data` that was received on this channel at `timestamp`, using the `data_format` of the channel.
+    /// This is used for live data, as well as for data that is replayed from a recording. See [`replay_rtt_recording`].
+    pub fn decode_rtt_data(
+        &self,
+        data: &[u8],
+        timestamp: OffsetDateTime,
+        defmt_state: Option<&(defmt_decoder::Table, Option<defmt_decoder::Locations>)>,
+    ) -> Result<String, anyhow::Error> {
+        let mut formatted_data = String::new();
+        match self.data_format {
+            DataFormat::String if self.show_timestamps => {
+                let incoming = String::from_utf8_lossy(data);
+                let timestamp = timestamp.to_offset(self.timestamp_offset);
+                // Partial lines are passed on unchanged, so that the timestamp is only added when the next line starts.
+                for line in incoming.split_inclusive('\n') {
+                    if self.at_line_start.get() {
+                        write!(
+                            formatted_data,
+                            "[{:02}:{:02}:{:02}.{:03}] ",
+                            timestamp.hour(),
+                            timestamp.minute(),
+                            timestamp.second(),
+                            timestamp.millisecond()
+                        )
+                        .map_or_else(
+                            |err| log::error!("Failed to format RTT data - {:?}", err),
+                            |r| r,
+                        );
+                    }
+                    formatted_data.push_str(line);
+                    self.at_line_start.set(line.ends_with('\n'));
+                }
+            }
+            DataFormat::String => {
+                let incoming = String::from_utf8_lossy(data).to_string();
+                for (_i, line) in incoming.split_terminator('\n').enumerate() {
+                    writeln!(formatted_data, "{line}").map_or_else(
+                        |err| log::error!("Failed to format RTT data - {:?}", err),
+                        |r| r,
+                    );
+                }
+            }
+            DataFormat::BinaryLE => {
+                for element in data {
+                    // Width of 4 allows 0xFF to be printed.
+                    write!(formatted_data, "{element:#04x}").map_or_else(
+                        |err| log::error!("Failed to format RTT data - {:?}", err),
+                        |r| r,
+                    );
+                }
+            }
+            DataFormat::Defmt => {
+                match defmt_state {
+                    Some((table, locs)) => {
+                        let mut stream_decoder = table.new_stream_decoder();
+                        stream_decoder.received(data);
+                        loop {
+                            match stream_decoder.decode() {
+                                Ok(frame) => {
+                                    let loc =
+                                        locs.as_ref().and_then(|locs| locs.get(&frame.index()));
+                                    if let Some(defmt_filter) = &self.defmt_filter {
+                                        if !defmt_filter.allows(
+                                            frame.level(),
+                                            loc.map(|loc| loc.module.as_str()),
+                                        ) {
+                                            continue;
+                                        }
                                     }
-                                    writeln!(formatted_data, "{line}").map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                }
-                            }
-                            DataFormat::BinaryLE => {
-                                for element in &self.rtt_buffer.0[..bytes_read] {
-                                    // Width of 4 allows 0xFF to be printed.
-                                    write!(formatted_data, "{element:#04x}").map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                }
-                            }
-                            DataFormat::Defmt => {
-                                match defmt_state {
-                                    Some((table, locs)) => {
-                                        let mut stream_decoder = table.new_stream_decoder();
-                                        stream_decoder.received(&self.rtt_buffer.0[..bytes_read]);
-                                        loop {
-                                            match stream_decoder.decode() {
-                                                Ok(frame) => {
-                                                    let loc = locs.as_ref().and_then(|locs| locs.get(&frame.index()) );
-                                                    writeln!(formatted_data, "{}", frame.display(false)).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                                    if self.show_location {
-                                                        if let Some(loc) = loc {
-                                                            let relpath = if let Ok(relpath) =
-                                                                loc.file.strip_prefix(&std::env::current_dir().unwrap())
-                                                            {
-                                                                relpath
-                                                            } else {
-                                                                // not relative; use full path
-                                                                &loc.file
-                                                            };
-                                                            writeln!(formatted_data,
-                                                                "└─ {}:{}",
-                                                                relpath.display(),
-                                                                loc.line
-                                                            ).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                                        } else {
-                                                            writeln!(formatted_data, "└─ <invalid location: defmt frame-index: {}>", frame.index()).map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                                        }
-                                                    }
-                                                    continue;
+                                    if self.defmt_format == DefmtFormat::Json {
+                                        writeln!(
+                                            formatted_data,
+                                            "{}",
+                                            defmt_frame_json(&frame, loc)
+                                        )
+                                        .map_or_else(
+                                            |err| {
+                                                log::error!("Failed to format RTT data - {:?}", err)
+                                            },
+                                            |r| r,
+                                        );
+                                        continue;
+                                    }
+                                    writeln!(formatted_data, "{}", frame.display(false))
+                                        .map_or_else(
+                                            |err| {
+                                                log::error!("Failed to format RTT data - {:?}", err)
+                                            },
+                                            |r| r,
+                                        );
+                                    if self.show_location {
+                                        if let Some(loc) = loc {
+                                            let relpath = if let Ok(relpath) = loc
+                                                .file
+                                                .strip_prefix(&std::env::current_dir().unwrap())
+                                            {
+                                                relpath
+                                            } else {
+                                                // not relative; use full path
+                                                &loc.file
+                                            };
+                                            writeln!(
+                                                formatted_data,
+                                                "└─ {}:{}",
+                                                relpath.display(),
+                                                loc.line
+                                            )
+                                            .map_or_else(
+                                                |err| {
+                                                    log::error!(
+                                                        "Failed to format RTT data - {:?}",
+                                                        err
+                                                    )
                                                 },
-                                                Err(DecodeError::UnexpectedEof) => break,
-                                                Err(DecodeError::Malformed) => match table.encoding().can_recover() {
-                                                    // If recovery is impossible, break out of here and propagate the error.
-                                                    false => {
-                                                        return Err(anyhow!("Unrecoverable error while decoding Defmt data and some data may have been lost: {:?}", DecodeError::Malformed));
-                                                    },
-                                                    // If recovery is possible, skip the current frame and continue with new data.
-                                                    true => continue,
+                                                |r| r,
+                                            );
+                                        } else {
+                                            writeln!(
+                                                formatted_data,
+                                                "└─ <invalid location: defmt frame-index: {}>",
+                                                frame.index()
+                                            )
+                                            .map_or_else(
+                                                |err| {
+                                                    log::error!(
+                                                        "Failed to format RTT data - {:?}",
+                                                        err
+                                                    )
                                                 },
-
-                                            }
+                                                |r| r,
+                                            );
                                         }
                                     }
-                                    None => {
-                                        write!(formatted_data, "Running rtt in defmt mode but table or locations could not be loaded.")
-                                            .map_or_else(|err| log::error!("Failed to format RTT data - {:?}", err), |r|r);
-                                    }
+                                    continue;
                                 }
+                                Err(DecodeError::UnexpectedEof) => break,
+                                Err(DecodeError::Malformed) => match table.encoding().can_recover()
+                                {
+                                    // If recovery is impossible, break out of here and propagate the error.
+                                    false => {
+                                        return Err(anyhow!("Unrecoverable error while decoding Defmt data and some data may have been lost: {:?}", DecodeError::Malformed));
+                                    }
+                                    // If recovery is possible, skip the current frame and continue with new data.
+                                    true => continue,
+                                },
                             }
-                        };
-                        formatted_data
+                        }
                     }
-                ))
-            }).transpose()
+                    None => {
+                        write!(
+                            formatted_data,
+                            "Running rtt in defmt mode but table or locations could not be loaded."
+                        )
+                        .map_or_else(
+                            |err| log::error!("Failed to format RTT data - {:?}", err),
+                            |r| r,
+                        );
+                    }
+                }
+            }
+        };
+        Ok(formatted_data)
     }
 
-    pub fn _push_rtt(&mut self, core: &mut Core) {
-        if let Some(down_channel) = self.down_channel.as_mut() {
-            self._input_data += "\n";
-            down_channel
-                .write(core, self._input_data.as_bytes())
-                .unwrap();
-            self._input_data.clear();
-        }
+    /// Returns the number of the `DownChannel`.
+    pub fn down_number(&self) -> Option<usize> {
+        self.down_channel
+            .as_ref()
+            .map(|dc| dc.number() + self.channel_number_offset)
+    }
+
+    /// Write the (already encoded) `data` to the down channel. This does not wait for space in the target buffer.
+    /// Returns the number of bytes that were written, which can be less than the length of `data` if the target buffer is full.
+    pub fn write_rtt(&mut self, core: &mut Core, data: &[u8]) -> Result<usize, anyhow::Error> {
+        let down_channel = self
+            .down_channel
+            .as_mut()
+            .ok_or_else(|| anyhow!("RTT channel {} has no down channel", self.channel_name))?;
+        down_channel
+            .write(core, data)
+            .map_err(|error| anyhow!("Error writing to RTT down channel: {}", error))
     }
 }
 
+/// Format a decoded defmt `frame`, and its `location`, as a single line JSON object. See [`DefmtFormat::Json`].
+fn defmt_frame_json(
+    frame: &defmt_decoder::Frame,
+    location: Option<&defmt_decoder::Location>,
+) -> serde_json::Value {
+    let level = frame.level().map(|level| match level {
+        defmt_decoder::Level::Trace => "TRACE",
+        defmt_decoder::Level::Debug => "DEBUG",
+        defmt_decoder::Level::Info => "INFO",
+        defmt_decoder::Level::Warn => "WARN",
+        defmt_decoder::Level::Error => "ERROR",
+    });
+    let location_json = location.map(|location| {
+        // Like the text format, show the file relative to the current directory, if possible.
+        let file = std::env::current_dir()
+            .ok()
+            .and_then(|current_dir| location.file.strip_prefix(current_dir).ok())
+            .unwrap_or(&location.file);
+        serde_json::json!({
+            "file": file.display().to_string(),
+            "line": location.line,
+        })
+    });
+    serde_json::json!({
+        "level": level,
+        "timestamp": frame.display_timestamp().map(|timestamp| timestamp.to_string()),
+        "target": location.map(|location| location.module.as_str()),
+        "message": frame.display_message().to_string(),
+        "location": location_json,
+    })
+}
+
 /// Once an active connection with the Target RTT control block has been established, we configure each of the active channels, and hold essential state information for successfull communication.
 #[derive(Debug)]
 pub struct RttActiveTarget {
@@ -348,7 +911,19 @@ impl RttActiveTarget {
         rtt_config: &RttConfig,
         timestamp_offset: UtcOffset,
     ) -> Result<Self> {
-        let mut active_channels = Vec::new();
+        if let Some(channel_config) = rtt_config.channels.iter().find(|channel_config| {
+            channel_config
+                .down_data_format
+                .map_or(false, |down_data_format| !down_data_format.is_sendable())
+        }) {
+            return Err(anyhow!(
+                "The down channel format {:?} of RTT channel {:?} cannot be sent to the target",
+                channel_config.down_data_format.unwrap_or_default(),
+                channel_config.channel_number
+            ));
+        }
+
+        let mut active_channels: Vec<RttActiveChannel> = Vec::new();
         // For each channel configured in the RTT Control Block (`Rtt`), check if there are additional user configuration in a `RttChannelConfig`. If not, apply defaults.
         let up_channels = rtt.up_channels().drain();
         let down_channels = rtt.down_channels().drain();
@@ -369,6 +944,13 @@ impl RttActiveTarget {
 
         for channel in down_channels {
             let number = channel.number();
+            // A down channel is paired with the up channel of the same number, so that they share a configuration, but each direction uses its own format.
+            if let Some(paired_channel) = active_channels.iter_mut().find(|active_channel| {
+                active_channel.number() == Some(number) && active_channel.down_channel.is_none()
+            }) {
+                paired_channel.down_channel = Some(channel);
+                continue;
+            }
             let channel_config = rtt_config
                 .channels
                 .clone()
@@ -393,43 +975,79 @@ impl RttActiveTarget {
             .iter()
             .any(|elem| elem.data_format == DataFormat::Defmt);
         let defmt_state = if defmt_enabled {
-            let elf = fs::read(elf_file).map_err(|err| {
-                anyhow!(
-                    "Error reading program binary while initalizing RTT: {}",
-                    err
-                )
-            })?;
-            if let Some(table) = defmt_decoder::Table::parse(&elf)? {
-                let locs = {
-                    let locs = table.get_locations(&elf)?;
-
-                    if !table.is_empty() && locs.is_empty() {
-                        log::warn!("Insufficient DWARF info; compile your program with `debug = 2` to enable location info.");
-                        None
-                    } else if table.indices().all(|idx| locs.contains_key(&(idx as u64))) {
-                        Some(locs)
-                    } else {
-                        log::warn!(
-                            "Location info is incomplete; it will be omitted from the output."
-                        );
-                        None
-                    }
-                };
-                Some((table, locs))
-            } else {
-                log::warn!("No `Table` definition in DWARF info; compile your program with `debug = 2` to enable location info.");
-                None
-            }
+            load_defmt_state(elf_file, rtt_config)?
         } else {
             None
         };
 
+        let recorder = match &rtt_config.recording_path {
+            Some(recording_path) => Some(Arc::new(Mutex::new(RttRecorder::create(
+                recording_path,
+                &active_channels,
+            )?))),
+            None => None,
+        };
+        for active_channel in active_channels.iter_mut() {
+            active_channel.recorder = recorder.clone();
+        }
+
+        let mut log_files: HashMap<PathBuf, Arc<Mutex<RttLogFile>>> = HashMap::new();
+        for active_channel in active_channels.iter_mut() {
+            let channel_config = match rtt_config.channels.iter().find(|channel_config| {
+                channel_config.channel_number.is_some()
+                    && channel_config.channel_number == active_channel.number()
+            }) {
+                Some(channel_config) => channel_config,
+                None => continue,
+            };
+            if let Some(log_file_path) = &channel_config.log_file {
+                let log_file = match log_files.get(log_file_path) {
+                    Some(log_file) => log_file.clone(),
+                    None => {
+                        let log_file = Arc::new(Mutex::new(RttLogFile::create(
+                            log_file_path,
+                            channel_config.log_file_max_size,
+                        )?));
+                        log_files.insert(log_file_path.clone(), log_file.clone());
+                        log_file
+                    }
+                };
+                active_channel.log_file = Some(log_file);
+            }
+        }
+
         Ok(Self {
             active_channels,
             defmt_state,
         })
     }
 
+    /// Prefix the names of all the channels with `label`, e.g. `bootloader: defmt`, to tell apart the channels of different control blocks.
+    pub fn label_channels(&mut self, label: &str) {
+        for active_channel in self.active_channels.iter_mut() {
+            active_channel.channel_name = format!("{label}: {}", active_channel.channel_name);
+        }
+    }
+
+    /// Add the channels of `other`, which is attached to another control block, and number them after the channels of `self`,
+    /// so that the channel numbers stay unambiguous.
+    pub fn merge(&mut self, other: RttActiveTarget) {
+        let channel_number_offset = self
+            .active_channels
+            .iter()
+            .flat_map(|active_channel| [active_channel.number(), active_channel.down_number()])
+            .flatten()
+            .max()
+            .map_or(0, |channel_number| channel_number + 1);
+        for mut active_channel in other.active_channels {
+            active_channel.channel_number_offset += channel_number_offset;
+            self.active_channels.push(active_channel);
+        }
+        if self.defmt_state.is_none() {
+            self.defmt_state = other.defmt_state;
+        }
+    }
+
     pub fn get_rtt_symbol<T: Read + Seek>(file: &mut T) -> Option<u64> {
         let mut buffer = Vec::new();
         if file.read_to_end(&mut buffer).is_ok() {
@@ -482,9 +1100,415 @@ impl RttActiveTarget {
         Ok(data)
     }
 
-    // pub fn push_rtt(&mut self) {
-    //     self.tabs[self.current_tab].push_rtt();
-    // }
+    /// Write the (already encoded) `data` to the down channel `channel_number`.
+    /// Returns the number of bytes that were written, which can be less than the length of `data` if the target buffer is full.
+    pub fn write_rtt(
+        &mut self,
+        core: &mut Core,
+        channel_number: usize,
+        data: &[u8],
+    ) -> Result<usize, anyhow::Error> {
+        self.active_channels
+            .iter_mut()
+            .find(|active_channel| active_channel.down_number() == Some(channel_number))
+            .ok_or_else(|| anyhow!("There is no RTT down channel with number {channel_number}"))?
+            .write_rtt(core, data)
+    }
+
+    /// Encode `data` using the format of the down channel `channel_number`.
+    pub fn encode_rtt(&self, channel_number: usize, data: &str) -> Result<Vec<u8>, anyhow::Error> {
+        self.active_channels
+            .iter()
+            .find(|active_channel| active_channel.down_number() == Some(channel_number))
+            .ok_or_else(|| anyhow!("There is no RTT down channel with number {channel_number}"))?
+            .down_data_format
+            .encode(data)
+    }
+}
+
+/// Load the defmt table and locations from `elf_file`, and apply the source path mapping of `rtt_config` to the locations.
+fn load_defmt_state(
+    elf_file: &Path,
+    rtt_config: &RttConfig,
+) -> Result<Option<(defmt_decoder::Table, Option<defmt_decoder::Locations>)>> {
+    let elf = fs::read(elf_file).map_err(|err| {
+        anyhow!(
+            "Error reading program binary while initalizing RTT: {}",
+            err
+        )
+    })?;
+    Ok(if let Some(table) = defmt_decoder::Table::parse(&elf)? {
+        let locs = {
+            let mut locs = table.get_locations(&elf)?;
+            for location in locs.values_mut() {
+                location.file = rtt_config.map_source_path(&location.file);
+            }
+
+            if !table.is_empty() && locs.is_empty() {
+                log::warn!("Insufficient DWARF info; compile your program with `debug = 2` to enable location info.");
+                None
+            } else if table.indices().all(|idx| locs.contains_key(&(idx as u64))) {
+                Some(locs)
+            } else {
+                log::warn!("Location info is incomplete; it will be omitted from the output.");
+                None
+            }
+        };
+        Some((table, locs))
+    } else {
+        log::warn!("No `Table` definition in DWARF info; compile your program with `debug = 2` to enable location info.");
+        None
+    })
+}
+
+/// The first bytes of an RTT recording, including the version of the recording format.
+const RTT_RECORDING_MAGIC: &[u8; 8] = b"PRSRTT02";
+/// The record that describes a recorded channel.
+const RTT_RECORD_CHANNEL: u8 = 0;
+/// The record that contains the data that was read from a channel.
+const RTT_RECORD_DATA: u8 = 1;
+
+/// Records the raw data of the up channels of an [`RttActiveTarget`] to a file, so that it can be decoded again later, without the target.
+/// See [`replay_rtt_recording`].
+///
+/// The file starts with [`RTT_RECORDING_MAGIC`], followed by records with little-endian fields:
+/// - A channel record for every channel, with the channel number (`u32`), the length of the name (`u32`), the name, the data format (`u8`),
+///   and the options that change how the data is shown: `show_timestamps` (`u8`), `show_location` (`u8`), and the `defmt_format` (`u8`).
+/// - A data record for every read, with the UTC timestamp in microseconds since the UNIX epoch (`i64`),
+///   the channel number (`u32`), the length of the data (`u32`), and the raw data.
+#[derive(Debug)]
+pub struct RttRecorder {
+    writer: BufWriter<File>,
+}
+
+impl RttRecorder {
+    /// Create the recording at `path`, and describe the up channels of `channels`.
+    pub fn create(path: &Path, channels: &[RttActiveChannel]) -> Result<Self> {
+        let recorded_channels = channels
+            .iter()
+            .filter_map(|channel| {
+                Some(RecordedChannel {
+                    number: channel.number()?,
+                    name: channel.channel_name.clone(),
+                    data_format: channel.data_format,
+                    show_timestamps: channel.show_timestamps,
+                    show_location: channel.show_location,
+                    defmt_format: channel.defmt_format,
+                })
+            })
+            .collect::<Vec<_>>();
+        Self::create_with_channels(path, &recorded_channels)
+    }
+
+    fn create_with_channels(path: &Path, channels: &[RecordedChannel]) -> Result<Self> {
+        let file = File::create(path)
+            .map_err(|error| anyhow!("Failed to create the RTT recording {:?}: {}", path, error))?;
+        let mut recorder = Self {
+            writer: BufWriter::new(file),
+        };
+        recorder.writer.write_all(RTT_RECORDING_MAGIC)?;
+        for channel in channels {
+            recorder.writer.write_all(&[RTT_RECORD_CHANNEL])?;
+            recorder
+                .writer
+                .write_all(&(channel.number as u32).to_le_bytes())?;
+            recorder
+                .writer
+                .write_all(&(channel.name.len() as u32).to_le_bytes())?;
+            recorder.writer.write_all(channel.name.as_bytes())?;
+            recorder.writer.write_all(&[
+                recorded_data_format(channel.data_format),
+                channel.show_timestamps as u8,
+                channel.show_location as u8,
+                recorded_defmt_format(channel.defmt_format),
+            ])?;
+        }
+        recorder.writer.flush()?;
+        Ok(recorder)
+    }
+
+    /// Append the raw `data` that was read from `channel_number` at `timestamp`.
+    pub fn record(
+        &mut self,
+        timestamp: OffsetDateTime,
+        channel_number: usize,
+        data: &[u8],
+    ) -> Result<()> {
+        let timestamp_micros = (timestamp.unix_timestamp_nanos() / 1000) as i64;
+        self.writer.write_all(&[RTT_RECORD_DATA])?;
+        self.writer.write_all(&timestamp_micros.to_le_bytes())?;
+        self.writer
+            .write_all(&(channel_number as u32).to_le_bytes())?;
+        self.writer.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.writer.write_all(data)?;
+        // Flush every record, so that the recording is usable even if the session ends unexpectedly.
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The description of a channel in an RTT recording, with the configuration that was used to show its data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecordedChannel {
+    number: usize,
+    name: String,
+    data_format: DataFormat,
+    show_timestamps: bool,
+    show_location: bool,
+    defmt_format: DefmtFormat,
+}
+
+/// A file that receives the decoded data of one or more RTT channels. See [`RttChannelConfig::log_file`].
+/// The file is flushed after every write, so that the last lines are kept if the session ends unexpectedly, and it is closed when it is dropped.
+#[derive(Debug)]
+pub struct RttLogFile {
+    path: PathBuf,
+    /// This is only `None` while the file is rotated, or if it could not be opened again after that.
+    writer: Option<BufWriter<File>>,
+    /// The size of the current file, or the number of bytes that were written since a rotation failed.
+    size: u64,
+    max_size: Option<u64>,
+}
+
+impl RttLogFile {
+    /// Create the log file at `path`, or append to it if it exists, so that the output of earlier sessions is kept.
+    /// The file is rotated when it grows beyond `max_size` bytes.
+    pub fn create(path: &Path, max_size: Option<u64>) -> Result<Self> {
+        let writer = Self::open_writer(path)?;
+        let size = writer
+            .get_ref()
+            .metadata()
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: Some(writer),
+            size,
+            max_size,
+        })
+    }
+
+    fn open_writer(path: &Path) -> Result<BufWriter<File>> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|error| anyhow!("Failed to open the RTT log file {:?}: {}", path, error))?;
+        Ok(BufWriter::new(file))
+    }
+
+    /// Append `data` to the log file, and rotate it first, if `data` would make it grow beyond the `max_size`.
+    pub fn write(&mut self, data: &str) -> Result<()> {
+        if let Some(max_size) = self.max_size {
+            if self.size > 0 && self.size + data.len() as u64 > max_size {
+                if let Err(error) = self.rotate() {
+                    log::warn!("{:?}", error);
+                }
+            }
+        }
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| anyhow!("The RTT log file {:?} is not open", self.path))?;
+        writer.write_all(data.as_bytes())?;
+        writer.flush()?;
+        self.size += data.len() as u64;
+        Ok(())
+    }
+
+    /// Rename the current file with a `.1` suffix, replacing the previous one, and start a new file.
+    /// If the file can not be renamed, writing continues in the current file, and the rotation is tried again after another `max_size` bytes.
+    fn rotate(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush()?;
+        }
+        // The file has to be closed before it is renamed, for Windows.
+        self.writer = None;
+        let mut rotated_path = self.path.clone().into_os_string();
+        rotated_path.push(".1");
+        let renamed = fs::rename(&self.path, &rotated_path);
+        self.writer = Some(Self::open_writer(&self.path)?);
+        self.size = 0;
+        renamed.map_err(|error| {
+            anyhow!(
+                "Failed to rotate the RTT log file {:?}: {}",
+                self.path,
+                error
+            )
+        })
+    }
+}
+
+/// The value that represents `data_format` in an RTT recording.
+fn recorded_data_format(data_format: DataFormat) -> u8 {
+    match data_format {
+        DataFormat::String => 0,
+        DataFormat::BinaryLE => 1,
+        DataFormat::Defmt => 2,
+    }
+}
+
+/// The value that represents `defmt_format` in an RTT recording.
+fn recorded_defmt_format(defmt_format: DefmtFormat) -> u8 {
+    match defmt_format {
+        DefmtFormat::Text => 0,
+        DefmtFormat::Json => 1,
+    }
+}
+
+/// Read a little-endian field of `N` bytes from an RTT recording.
+fn read_recorded_bytes<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0_u8; N];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(|error| anyhow!("The RTT recording is truncated: {}", error))?;
+    Ok(bytes)
+}
+
+/// A single read from an RTT channel, replayed from a recording, and decoded like live data.
+#[derive(Debug, Clone)]
+pub struct RttReplayEntry {
+    /// The time at which the data was read from the target.
+    pub timestamp: OffsetDateTime,
+    pub channel_number: usize,
+    pub channel_name: String,
+    /// The decoded and formatted data.
+    pub data: String,
+}
+
+/// Decode the RTT recording at `recording_path`, that was created by setting [`RttConfig::recording_path`].
+///
+/// Every channel is decoded with the format and options it was recorded with, unless `rtt_config` contains a configuration for the channel number,
+/// so that the decoding can be changed without the target. The `elf_file` is required to decode defmt channels.
+pub fn replay_rtt_recording(
+    recording_path: &Path,
+    elf_file: Option<&Path>,
+    rtt_config: &RttConfig,
+    timestamp_offset: UtcOffset,
+) -> Result<Vec<RttReplayEntry>> {
+    let file = File::open(recording_path).map_err(|error| {
+        anyhow!(
+            "Failed to open the RTT recording {:?}: {}",
+            recording_path,
+            error
+        )
+    })?;
+    let mut reader = BufReader::new(file);
+    if &read_recorded_bytes::<8>(&mut reader)? != RTT_RECORDING_MAGIC {
+        return Err(anyhow!(
+            "{:?} is not an RTT recording, or was recorded with an incompatible version",
+            recording_path
+        ));
+    }
+
+    let mut channels: HashMap<usize, RttActiveChannel> = HashMap::new();
+    let mut defmt_state = None;
+    let mut entries = vec![];
+    loop {
+        let mut record_type = [0_u8; 1];
+        match reader.read_exact(&mut record_type) {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error.into()),
+        }
+        match record_type[0] {
+            RTT_RECORD_CHANNEL => {
+                let number = u32::from_le_bytes(read_recorded_bytes(&mut reader)?) as usize;
+                let name_length = u32::from_le_bytes(read_recorded_bytes(&mut reader)?) as usize;
+                let mut name = vec![0_u8; name_length];
+                reader
+                    .read_exact(&mut name)
+                    .map_err(|error| anyhow!("The RTT recording is truncated: {}", error))?;
+                let [recorded_format, show_timestamps, show_location, recorded_defmt_format] =
+                    read_recorded_bytes::<4>(&mut reader)?;
+                let data_format = match recorded_format {
+                    0 => DataFormat::String,
+                    1 => DataFormat::BinaryLE,
+                    2 => DataFormat::Defmt,
+                    other => {
+                        return Err(anyhow!(
+                            "Invalid data format {} for channel {} in the RTT recording",
+                            other,
+                            number
+                        ))
+                    }
+                };
+                let defmt_format = match recorded_defmt_format {
+                    0 => DefmtFormat::Text,
+                    1 => DefmtFormat::Json,
+                    other => {
+                        return Err(anyhow!(
+                            "Invalid defmt format {} for channel {} in the RTT recording",
+                            other,
+                            number
+                        ))
+                    }
+                };
+                let channel_config = rtt_config
+                    .channels
+                    .iter()
+                    .find(|channel_config| channel_config.channel_number == Some(number))
+                    .cloned()
+                    .unwrap_or(RttChannelConfig {
+                        channel_number: Some(number),
+                        show_timestamps: show_timestamps != 0,
+                        show_location: show_location != 0,
+                        defmt_format,
+                        ..Default::default()
+                    });
+                let channel = RttActiveChannel::new(
+                    None,
+                    None,
+                    Some(RttChannelConfig {
+                        channel_name: Some(String::from_utf8_lossy(&name).to_string()),
+                        data_format: channel_config.data_format.or(Some(data_format)),
+                        ..channel_config
+                    }),
+                    timestamp_offset,
+                );
+                if channel.data_format == DataFormat::Defmt && defmt_state.is_none() {
+                    let elf_file = elf_file.ok_or_else(|| {
+                        anyhow!(
+                            "The program binary is required to decode the defmt channel {}",
+                            number
+                        )
+                    })?;
+                    defmt_state = load_defmt_state(elf_file, rtt_config)?;
+                }
+                channels.insert(number, channel);
+            }
+            RTT_RECORD_DATA => {
+                let timestamp_micros = i64::from_le_bytes(read_recorded_bytes(&mut reader)?);
+                let channel_number = u32::from_le_bytes(read_recorded_bytes(&mut reader)?) as usize;
+                let data_length = u32::from_le_bytes(read_recorded_bytes(&mut reader)?) as usize;
+                let mut data = vec![0_u8; data_length];
+                reader
+                    .read_exact(&mut data)
+                    .map_err(|error| anyhow!("The RTT recording is truncated: {}", error))?;
+                let channel = channels.get(&channel_number).ok_or_else(|| {
+                    anyhow!(
+                        "The RTT recording contains data for the undescribed channel {}",
+                        channel_number
+                    )
+                })?;
+                let timestamp =
+                    OffsetDateTime::from_unix_timestamp_nanos(timestamp_micros as i128 * 1000)?;
+                entries.push(RttReplayEntry {
+                    timestamp,
+                    channel_number,
+                    channel_name: channel.channel_name.clone(),
+                    data: channel.decode_rtt_data(&data, timestamp, defmt_state.as_ref())?,
+                });
+            }
+            other => {
+                return Err(anyhow!(
+                    "Invalid record type {} in the RTT recording",
+                    other
+                ))
+            }
+        }
+    }
+    Ok(entries)
 }
 
 struct RttBuffer(Vec<u8>);
@@ -504,3 +1528,315 @@ impl fmt::Debug for RttBuffer {
         self.0.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_file_is_appended_to_and_rotated_by_size() {
+        let path =
+            std::env::temp_dir().join(format!("probe-rs-rtt-log-{}.txt", std::process::id()));
+        let mut rotated_path = path.clone().into_os_string();
+        rotated_path.push(".1");
+        fs::write(&path, "earlier\n").unwrap();
+
+        let mut log_file = RttLogFile::create(&path, Some(16)).unwrap();
+        log_file.write("first\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "earlier\nfirst\n");
+        // This would grow the file beyond 16 bytes, so the file is rotated first.
+        log_file.write("second\n").unwrap();
+        drop(log_file);
+
+        assert_eq!(
+            fs::read_to_string(&rotated_path).unwrap(),
+            "earlier\nfirst\n"
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&rotated_path).unwrap();
+    }
+
+    fn memory_map() -> Vec<MemoryRegion> {
+        vec![MemoryRegion::Ram(probe_rs::config::RamRegion {
+            name: Some("RAM".to_string()),
+            range: 0x2000_0000..0x2001_0000,
+            is_boot_memory: false,
+            cores: vec!["main".to_string()],
+        })]
+    }
+
+    fn control_block(label: &str, address: u32, length: Option<u32>) -> RttControlBlockConfig {
+        RttControlBlockConfig {
+            label: label.to_string(),
+            address,
+            length,
+        }
+    }
+
+    #[test]
+    fn control_block_scan_regions_use_the_symbol_by_default() {
+        let rtt_config = RttConfig::default();
+        assert_eq!(
+            rtt_config
+                .control_block_scan_regions(None, Some(0x2000_0100), &memory_map())
+                .unwrap(),
+            vec![(None, ScanRegion::Exact(0x2000_0100))]
+        );
+        assert!(rtt_config
+            .control_block_scan_regions(None, None, &memory_map())
+            .is_err());
+    }
+
+    #[test]
+    fn control_block_scan_regions_prefer_the_device_address_over_the_symbol() {
+        let rtt_config = RttConfig::default();
+        assert_eq!(
+            rtt_config
+                .control_block_scan_regions(Some(0x2000_0400), Some(0x2000_0100), &memory_map())
+                .unwrap(),
+            vec![(None, ScanRegion::Exact(0x2000_0400))]
+        );
+    }
+
+    #[test]
+    fn control_block_scan_regions_use_every_control_block_in_order() {
+        let rtt_config = RttConfig {
+            control_blocks: vec![
+                control_block("bootloader", 0x2000_0000, None),
+                control_block("application", 0x2000_8000, Some(0x100)),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            rtt_config
+                .control_block_scan_regions(Some(0x2000_0400), Some(0x2000_0100), &memory_map())
+                .unwrap(),
+            vec![
+                (
+                    Some("bootloader".to_string()),
+                    ScanRegion::Exact(0x2000_0000)
+                ),
+                (
+                    Some("application".to_string()),
+                    ScanRegion::Range(0x2000_8000..0x2000_8100)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn control_block_scan_regions_prefer_the_address_scan_mode() {
+        let rtt_config = RttConfig {
+            scan_mode: RttScanMode::Address {
+                address: 0x2000_0800,
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            rtt_config
+                .control_block_scan_regions(Some(0x2000_0400), Some(0x2000_0100), &memory_map())
+                .unwrap(),
+            vec![(None, ScanRegion::Exact(0x2000_0800))]
+        );
+    }
+
+    #[test]
+    fn control_block_scan_regions_reject_addresses_outside_ram() {
+        let rtt_config = RttConfig {
+            control_blocks: vec![
+                control_block("bootloader", 0x2000_0000, None),
+                control_block("application", 0x0800_0000, Some(0x100)),
+            ],
+            ..Default::default()
+        };
+        assert!(rtt_config
+            .control_block_scan_regions(None, None, &memory_map())
+            .is_err());
+        assert!(RttConfig::default()
+            .control_block_scan_regions(Some(0x0800_0000), None, &memory_map())
+            .is_err());
+    }
+
+    #[test]
+    fn control_block_scan_regions_reject_addresses_wider_than_32_bits() {
+        assert!(RttConfig::default()
+            .control_block_scan_regions(Some(0x1_2000_0000), None, &memory_map())
+            .is_err());
+        assert!(RttConfig::default()
+            .control_block_scan_regions(None, Some(0x1_2000_0000), &memory_map())
+            .is_err());
+    }
+
+    #[test]
+    fn defmt_filter_parses_default_and_module_levels() {
+        let filter: DefmtFilter = " warn, my_app::radio = TRACE ,my_app=off,".parse().unwrap();
+        assert_eq!(
+            filter,
+            DefmtFilter {
+                default_level: DefmtLevelFilter::Warn,
+                module_levels: vec![
+                    ("my_app::radio".to_string(), DefmtLevelFilter::Trace),
+                    ("my_app".to_string(), DefmtLevelFilter::Off),
+                ],
+            }
+        );
+        assert_eq!("".parse::<DefmtFilter>().unwrap(), DefmtFilter::default());
+    }
+
+    #[test]
+    fn defmt_filter_rejects_unknown_levels() {
+        assert!("wran".parse::<DefmtFilter>().is_err());
+        assert!("my_app=verbose".parse::<DefmtFilter>().is_err());
+        assert!("=info".parse::<DefmtFilter>().is_err());
+    }
+
+    #[test]
+    fn defmt_filter_uses_the_longest_matching_module_path() {
+        let filter: DefmtFilter = "info,my_app=warn,my_app::radio=trace".parse().unwrap();
+        let trace = Some(defmt_decoder::Level::Trace);
+        assert!(filter.allows(trace, Some("my_app::radio")));
+        assert!(filter.allows(trace, Some("my_app::radio::tx")));
+        assert!(!filter.allows(trace, Some("my_app::radios")));
+        assert!(!filter.allows(Some(defmt_decoder::Level::Info), Some("my_app")));
+        assert!(filter.allows(Some(defmt_decoder::Level::Info), Some("other")));
+        assert!(!filter.allows(Some(defmt_decoder::Level::Debug), None));
+        // Frames without a level are always shown.
+        assert!("off"
+            .parse::<DefmtFilter>()
+            .unwrap()
+            .allows(None, Some("my_app")));
+    }
+
+    #[test]
+    fn map_source_path_remaps_and_strips_the_source_root() {
+        let rtt_config = RttConfig {
+            source_path_remaps: vec![SourcePathRemap {
+                from: PathBuf::from("/ci/build"),
+                to: PathBuf::from("/home/user/app"),
+            }],
+            source_root: Some(PathBuf::from("/home/user/app")),
+            ..Default::default()
+        };
+        assert_eq!(
+            rtt_config.map_source_path(Path::new("/ci/build/src/main.rs")),
+            PathBuf::from("src/main.rs")
+        );
+        assert_eq!(
+            rtt_config.map_source_path(Path::new("/rustc/library/core/src/panic.rs")),
+            PathBuf::from("/rustc/library/core/src/panic.rs")
+        );
+    }
+
+    /// Record `records` of `(channel number, data)` for a `String` channel 0 with timestamps, and a `BinaryLE` channel 1.
+    fn write_recording(path: &Path, records: &[(usize, &[u8])]) -> OffsetDateTime {
+        let channels = [
+            RecordedChannel {
+                number: 0,
+                name: "Terminal".to_string(),
+                data_format: DataFormat::String,
+                show_timestamps: true,
+                show_location: false,
+                defmt_format: DefmtFormat::Text,
+            },
+            RecordedChannel {
+                number: 1,
+                name: "Binary".to_string(),
+                data_format: DataFormat::BinaryLE,
+                show_timestamps: false,
+                show_location: false,
+                defmt_format: DefmtFormat::Text,
+            },
+        ];
+        // 2023-11-14 22:13:20.250 UTC
+        let timestamp =
+            OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_250_000_000).unwrap();
+        let mut recorder = RttRecorder::create_with_channels(path, &channels).unwrap();
+        for (channel_number, data) in records {
+            recorder.record(timestamp, *channel_number, data).unwrap();
+        }
+        timestamp
+    }
+
+    #[test]
+    fn recording_is_replayed_with_the_recorded_channel_configuration() {
+        let path =
+            std::env::temp_dir().join(format!("probe-rs-rtt-recording-{}.bin", std::process::id()));
+        let timestamp = write_recording(
+            &path,
+            &[(0, b"hello\nwor"), (1, &[0x01, 0xFF]), (0, b"ld\n")],
+        );
+
+        let entries =
+            replay_rtt_recording(&path, None, &RttConfig::default(), UtcOffset::UTC).unwrap();
+        fs::remove_file(&path).unwrap();
+        let replayed = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.timestamp,
+                    entry.channel_number,
+                    entry.channel_name.as_str(),
+                    entry.data.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            replayed,
+            vec![
+                (
+                    timestamp,
+                    0,
+                    "Terminal",
+                    "[22:13:20.250] hello\n[22:13:20.250] wor"
+                ),
+                (timestamp, 1, "Binary", "0x010xff"),
+                (timestamp, 0, "Terminal", "ld\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn channel_configuration_overrides_the_recorded_configuration() {
+        let path = std::env::temp_dir().join(format!(
+            "probe-rs-rtt-recording-override-{}.bin",
+            std::process::id()
+        ));
+        write_recording(&path, &[(0, b"hello\n"), (1, b"AB")]);
+        let rtt_config = RttConfig {
+            channels: vec![
+                RttChannelConfig {
+                    channel_number: Some(0),
+                    ..Default::default()
+                },
+                RttChannelConfig {
+                    channel_number: Some(1),
+                    data_format: Some(DataFormat::String),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let entries = replay_rtt_recording(&path, None, &rtt_config, UtcOffset::UTC).unwrap();
+        fs::remove_file(&path).unwrap();
+        let data = entries
+            .iter()
+            .map(|entry| entry.data.as_str())
+            .collect::<Vec<_>>();
+        // Channel 0 keeps its recorded format, but not its timestamps, and channel 1 is decoded as text.
+        assert_eq!(data, vec!["hello\n", "AB\n"]);
+    }
+
+    #[test]
+    fn replay_rejects_files_that_are_not_recordings() {
+        let path = std::env::temp_dir().join(format!(
+            "probe-rs-rtt-recording-invalid-{}.bin",
+            std::process::id()
+        ));
+        fs::write(&path, b"PRSRTT01").unwrap();
+        let result = replay_rtt_recording(&path, None, &RttConfig::default(), UtcOffset::UTC);
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -315,13 +315,13 @@ static ARM32_COMMON_REGS: RegisterFile = RegisterFile {
     fp_registers: None,
 };
 
-static AARCH32_COMMON_REGS: RegisterFile = RegisterFile {
+pub(crate) static AARCH32_COMMON_REGS: RegisterFile = RegisterFile {
     psr: Some(&register::CPSR),
 
     ..ARM32_COMMON_REGS
 };
 
-static AARCH32_FP_16_REGS: RegisterFile = RegisterFile {
+pub(crate) static AARCH32_FP_16_REGS: RegisterFile = RegisterFile {
     fp_status: Some(&register::AARCH32_FPSCR),
     fp_registers: Some(&[
         RegisterDescription {
@@ -441,7 +441,7 @@ static AARCH32_FP_16_REGS: RegisterFile = RegisterFile {
     ..AARCH32_COMMON_REGS
 };
 
-static AARCH32_FP_32_REGS: RegisterFile = RegisterFile {
+pub(crate) static AARCH32_FP_32_REGS: RegisterFile = RegisterFile {
     fp_status: Some(&register::AARCH32_FPSCR),
     fp_registers: Some(&[
         RegisterDescription {
@@ -673,7 +673,7 @@ static AARCH32_FP_32_REGS: RegisterFile = RegisterFile {
     ..AARCH32_COMMON_REGS
 };
 
-static CORTEX_M_COMMON_REGS: RegisterFile = RegisterFile {
+pub(crate) static CORTEX_M_COMMON_REGS: RegisterFile = RegisterFile {
     msp: Some(&register::MSP),
     psp: Some(&register::PSP),
     other: &[register::EXTRA],
@@ -682,7 +682,7 @@ static CORTEX_M_COMMON_REGS: RegisterFile = RegisterFile {
     ..ARM32_COMMON_REGS
 };
 
-static CORTEX_M_WITH_FP_REGS: RegisterFile = RegisterFile {
+pub(crate) static CORTEX_M_WITH_FP_REGS: RegisterFile = RegisterFile {
     fp_status: Some(&register::FPSCR),
     fp_registers: Some(&[
         RegisterDescription {
@@ -18,13 +18,15 @@ use register::RISCV_REGISTERS;
 use std::time::{Duration, Instant};
 
 #[macro_use]
-mod register;
+pub(crate) mod register;
 pub(crate) mod assembly;
 mod dtm;
 
 pub mod communication_interface;
 pub mod sequences;
 
+pub use register::{CsrDescription, MACHINE_CSRS};
+
 /// A interface to operate RISC-V cores.
 pub struct Riscv32<'probe> {
     interface: &'probe mut RiscvCommunicationInterface,
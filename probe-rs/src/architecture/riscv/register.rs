@@ -106,7 +106,7 @@ pub static S1: RegisterDescription = RegisterDescription {
     size_in_bits: 32,
 };
 
-pub(super) static RISCV_REGISTERS: RegisterFile = RegisterFile {
+pub(crate) static RISCV_REGISTERS: RegisterFile = RegisterFile {
     platform_registers: &[
         RegisterDescription {
             name: "x0",
@@ -426,3 +426,86 @@ pub(super) static RISCV_REGISTERS: RegisterFile = RegisterFile {
     fp_registers: None,
     fp_status: None,
 };
+
+/// A named RISC-V control and status register (CSR).
+///
+/// CSRs can be read and written with [`crate::Core::read_core_reg`] and [`crate::Core::write_core_reg`],
+/// using the CSR address as the [`RegisterId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsrDescription {
+    /// The name of the CSR, as it is used in the RISC-V privileged specification, e.g. `mcause`.
+    pub name: &'static str,
+    /// The 12-bit CSR address.
+    pub address: u16,
+}
+
+impl CsrDescription {
+    /// The [`RegisterId`] that is used to access the CSR.
+    pub fn id(&self) -> RegisterId {
+        RegisterId(self.address)
+    }
+}
+
+/// The machine mode CSRs that are most useful when debugging traps and interrupts.
+pub static MACHINE_CSRS: &[CsrDescription] = &[
+    CsrDescription {
+        name: "mstatus",
+        address: 0x300,
+    },
+    CsrDescription {
+        name: "misa",
+        address: 0x301,
+    },
+    CsrDescription {
+        name: "mie",
+        address: 0x304,
+    },
+    CsrDescription {
+        name: "mtvec",
+        address: 0x305,
+    },
+    CsrDescription {
+        name: "mscratch",
+        address: 0x340,
+    },
+    CsrDescription {
+        name: "mepc",
+        address: 0x341,
+    },
+    CsrDescription {
+        name: "mcause",
+        address: 0x342,
+    },
+    CsrDescription {
+        name: "mtval",
+        address: 0x343,
+    },
+    CsrDescription {
+        name: "mip",
+        address: 0x344,
+    },
+    CsrDescription {
+        name: "mvendorid",
+        address: 0xF11,
+    },
+    CsrDescription {
+        name: "marchid",
+        address: 0xF12,
+    },
+    CsrDescription {
+        name: "mimpid",
+        address: 0xF13,
+    },
+    CsrDescription {
+        name: "mhartid",
+        address: 0xF14,
+    },
+    CsrDescription {
+        name: "dcsr",
+        address: 0x7B0,
+    },
+    CsrDescription {
+        name: "dpc",
+        address: 0x7B1,
+    },
+];
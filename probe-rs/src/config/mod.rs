@@ -27,9 +27,9 @@ mod registry;
 mod target;
 
 pub use probe_rs_target::{
-    Chip, ChipFamily, Core, CoreType, FlashProperties, GenericRegion, InstructionSet, MemoryRange,
-    MemoryRegion, NvmRegion, PageInfo, RamRegion, RawFlashAlgorithm, SectorDescription, SectorInfo,
-    TargetDescriptionSource,
+    ArmCoreAccessOptions, Chip, ChipFamily, Core, CoreAccessOptions, CoreType, FlashProperties,
+    GenericRegion, InstructionSet, MemoryRange, MemoryRegion, NvmRegion, PageInfo, RamRegion,
+    RawFlashAlgorithm, SectorDescription, SectorInfo, TargetDescriptionSource,
 };
 
 pub use registry::{
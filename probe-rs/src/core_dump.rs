@@ -0,0 +1,568 @@
+use crate::architecture::arm::core::armv8a_core_regs::AARCH64_REGISTER_FILE;
+use crate::architecture::arm::core::{
+    AARCH32_COMMON_REGS, AARCH32_FP_16_REGS, AARCH32_FP_32_REGS, CORTEX_M_COMMON_REGS,
+    CORTEX_M_WITH_FP_REGS,
+};
+use crate::architecture::riscv::register::RISCV_REGISTERS;
+use crate::config::{ArmCoreAccessOptions, CoreAccessOptions, RiscvCoreAccessOptions};
+use crate::core::{Architecture, CoreInformation, RegisterFile};
+use crate::debug::stack_frame::StackFrame;
+use crate::{
+    Core, CoreInterface, CoreState, CoreStatus, CoreType, Error, HaltReason, InstructionSet,
+    MemoryInterface, RegisterId, RegisterValue,
+};
+use anyhow::anyhow;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+/// A snapshot of the registers and memory of a halted core, which can be stored in a file, and inspected later
+/// without a probe, through a read-only [`Core`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreDump {
+    core_type: CoreType,
+    instruction_set: InstructionSet,
+    fpu_support: bool,
+    register_file: DumpedRegisterFile,
+    registers: BTreeMap<RegisterId, RegisterValue>,
+    memory: Vec<DumpedMemory>,
+    /// The stack frames of the core when the dump was captured. Core dumps without them can still be loaded.
+    #[serde(default)]
+    stack_frames: Vec<DumpedStackFrame>,
+}
+
+/// A stack frame of the core when a [`CoreDump`] was captured, as it was shown by the debugger.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DumpedStackFrame {
+    /// The name of the function of the stack frame.
+    pub function_name: String,
+    /// The program counter of the stack frame.
+    pub pc: u64,
+    /// The source file and line of the program counter, if they are known.
+    pub source_location: Option<String>,
+}
+
+impl From<&StackFrame> for DumpedStackFrame {
+    fn from(stack_frame: &StackFrame) -> Self {
+        DumpedStackFrame {
+            function_name: stack_frame.function_name.clone(),
+            pc: stack_frame.pc.try_into().unwrap_or_default(),
+            source_location: stack_frame.source_location.as_ref().map(|source_location| {
+                let path = match (&source_location.directory, &source_location.file) {
+                    (Some(directory), Some(file)) => directory.join(file).display().to_string(),
+                    (None, Some(file)) => file.clone(),
+                    _ => "<unknown file>".to_string(),
+                };
+                match source_location.line {
+                    Some(line) => format!("{path}:{line}"),
+                    None => path,
+                }
+            }),
+        }
+    }
+}
+
+/// A contiguous range of memory in a [`CoreDump`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DumpedMemory {
+    start: u64,
+    #[serde(with = "base64_data")]
+    data: Vec<u8>,
+}
+
+impl DumpedMemory {
+    fn contains(&self, address: u64, length: usize) -> bool {
+        address >= self.start
+            && address
+                .checked_add(length as u64)
+                .map_or(false, |end| end <= self.start + self.data.len() as u64)
+    }
+}
+
+/// The static [`RegisterFile`] that the dumped core used, so that the replayed core exposes the same registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum DumpedRegisterFile {
+    CortexM,
+    CortexMWithFp,
+    Aarch32,
+    Aarch32Fp16,
+    Aarch32Fp32,
+    Aarch64,
+    Riscv,
+}
+
+impl DumpedRegisterFile {
+    const ALL: [DumpedRegisterFile; 7] = [
+        DumpedRegisterFile::CortexM,
+        DumpedRegisterFile::CortexMWithFp,
+        DumpedRegisterFile::Aarch32,
+        DumpedRegisterFile::Aarch32Fp16,
+        DumpedRegisterFile::Aarch32Fp32,
+        DumpedRegisterFile::Aarch64,
+        DumpedRegisterFile::Riscv,
+    ];
+
+    fn register_file(self) -> &'static RegisterFile {
+        match self {
+            DumpedRegisterFile::CortexM => &CORTEX_M_COMMON_REGS,
+            DumpedRegisterFile::CortexMWithFp => &CORTEX_M_WITH_FP_REGS,
+            DumpedRegisterFile::Aarch32 => &AARCH32_COMMON_REGS,
+            DumpedRegisterFile::Aarch32Fp16 => &AARCH32_FP_16_REGS,
+            DumpedRegisterFile::Aarch32Fp32 => &AARCH32_FP_32_REGS,
+            DumpedRegisterFile::Aarch64 => &AARCH64_REGISTER_FILE,
+            DumpedRegisterFile::Riscv => &RISCV_REGISTERS,
+        }
+    }
+
+    fn from_register_file(register_file: &'static RegisterFile) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|candidate| std::ptr::eq(candidate.register_file(), register_file))
+    }
+}
+
+impl CoreDump {
+    /// Capture the registers of `core`, and the contents of the memory `ranges`.
+    ///
+    /// The core has to be halted. Registers and memory ranges that cannot be read are left out of the dump.
+    pub fn capture(core: &mut Core, ranges: &[Range<u64>]) -> Result<CoreDump, Error> {
+        if !core.core_halted()? {
+            return Err(Error::Other(anyhow!(
+                "The core has to be halted to capture a core dump"
+            )));
+        }
+        let register_file = core.registers();
+        let dumped_register_file = DumpedRegisterFile::from_register_file(register_file)
+            .ok_or_else(|| {
+                Error::Other(anyhow!(
+                    "Core dumps are not supported for the registers of a {:?} core",
+                    core.core_type()
+                ))
+            })?;
+
+        let mut registers = BTreeMap::new();
+        let descriptions = register_file
+            .platform_registers
+            .iter()
+            .chain([
+                register_file.program_counter,
+                register_file.stack_pointer,
+                register_file.return_address,
+                register_file.frame_pointer,
+            ])
+            .chain(register_file.argument_registers)
+            .chain(register_file.result_registers)
+            .chain(register_file.msp)
+            .chain(register_file.psp)
+            .chain(register_file.psr)
+            .chain(register_file.fp_status)
+            .chain(register_file.fp_registers.unwrap_or_default())
+            .chain(register_file.other);
+        for description in descriptions {
+            if registers.contains_key(&description.id) {
+                continue;
+            }
+            match core.read_core_reg::<RegisterValue>(description.id) {
+                Ok(value) => {
+                    registers.insert(description.id, value);
+                }
+                Err(error) => tracing::warn!(
+                    "Register {} is not part of the core dump: {:?}",
+                    description.name,
+                    error
+                ),
+            }
+        }
+
+        let mut memory = vec![];
+        for range in ranges {
+            let mut data = vec![0_u8; (range.end - range.start) as usize];
+            // A single inaccessible region, e.g. external memory that is not initialized, should not prevent the rest of the dump.
+            match core.read(range.start, &mut data) {
+                Ok(()) => memory.push(DumpedMemory {
+                    start: range.start,
+                    data,
+                }),
+                Err(error) => tracing::warn!(
+                    "Memory {:#010x}..{:#010x} is not part of the core dump: {:?}",
+                    range.start,
+                    range.end,
+                    error
+                ),
+            }
+        }
+
+        Ok(CoreDump {
+            core_type: core.core_type(),
+            instruction_set: core.instruction_set()?,
+            fpu_support: core.fpu_support()?,
+            register_file: dumped_register_file,
+            registers,
+            memory,
+            stack_frames: vec![],
+        })
+    }
+
+    /// Add the stack frames of the core, e.g. as they were unwound by the debugger, to the dump.
+    pub fn set_stack_frames(&mut self, stack_frames: &[StackFrame]) {
+        self.stack_frames = stack_frames.iter().map(DumpedStackFrame::from).collect();
+    }
+
+    /// The stack frames of the core when the dump was captured, innermost first.
+    pub fn stack_frames(&self) -> &[DumpedStackFrame] {
+        &self.stack_frames
+    }
+
+    /// The memory ranges that are part of the dump.
+    pub fn memory_ranges(&self) -> Vec<Range<u64>> {
+        self.memory
+            .iter()
+            .map(|memory| memory.start..memory.start + memory.data.len() as u64)
+            .collect()
+    }
+
+    /// Store the core dump in the file at `path`.
+    pub fn store(&self, path: &Path) -> Result<(), Error> {
+        let file = std::fs::File::create(path).map_err(|error| {
+            Error::Other(anyhow!(
+                "Failed to create the core dump file {}: {error}",
+                path.display()
+            ))
+        })?;
+        serde_yaml::to_writer(std::io::BufWriter::new(file), self).map_err(|error| {
+            Error::Other(anyhow!(
+                "Failed to write the core dump file {}: {error}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Load a core dump that was stored with [`CoreDump::store`].
+    pub fn load(path: &Path) -> Result<CoreDump, Error> {
+        let file = std::fs::File::open(path).map_err(|error| {
+            Error::Other(anyhow!(
+                "Failed to open the core dump file {}: {error}",
+                path.display()
+            ))
+        })?;
+        serde_yaml::from_reader(std::io::BufReader::new(file)).map_err(|error| {
+            Error::Other(anyhow!(
+                "Failed to read the core dump file {}: {error}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Create the [`CoreState`] for the [`Core`] that replays this dump.
+    pub fn create_state(&self) -> CoreState {
+        let core_access_options = match self.core_type.architecture() {
+            Architecture::Arm => CoreAccessOptions::Arm(ArmCoreAccessOptions::default()),
+            Architecture::Riscv => CoreAccessOptions::Riscv(RiscvCoreAccessOptions {}),
+        };
+        CoreState::new(0, core_access_options)
+    }
+
+    /// A read-only [`Core`], that returns the dumped registers and memory.
+    ///
+    /// The core is always reported as halted, and any operation that would change the state of the core returns an error.
+    pub fn core<'a>(&'a self, state: &'a mut CoreState) -> Core<'a> {
+        Core::new(DumpedCore { dump: self }, state)
+    }
+
+    fn read_memory(&self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        let memory = self
+            .memory
+            .iter()
+            .find(|memory| memory.contains(address, data.len()))
+            .ok_or_else(|| {
+                Error::Other(anyhow!(
+                    "The memory at {address:#010x} ({} bytes) is not part of the core dump",
+                    data.len()
+                ))
+            })?;
+        let offset = (address - memory.start) as usize;
+        data.copy_from_slice(&memory.data[offset..offset + data.len()]);
+        Ok(())
+    }
+}
+
+/// Store the dumped memory as base64, which is a lot more compact than a YAML list of bytes.
+mod base64_data {
+    use super::BASE64;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64.encode(data))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        BASE64.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The [`CoreInterface`] of a [`CoreDump`].
+struct DumpedCore<'a> {
+    dump: &'a CoreDump,
+}
+
+impl DumpedCore<'_> {
+    fn read_only_error(&self) -> Error {
+        Error::Other(anyhow!(
+            "This operation is not supported, because the core is replayed from a core dump"
+        ))
+    }
+}
+
+impl MemoryInterface for DumpedCore<'_> {
+    fn supports_native_64bit_access(&mut self) -> bool {
+        true
+    }
+
+    fn read_word_64(&mut self, address: u64) -> Result<u64, Error> {
+        let mut bytes = [0_u8; 8];
+        self.dump.read_memory(address, &mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        let mut bytes = [0_u8; 4];
+        self.dump.read_memory(address, &mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        let mut bytes = [0_u8; 1];
+        self.dump.read_memory(address, &mut bytes)?;
+        Ok(bytes[0])
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), Error> {
+        for (index, word) in data.iter_mut().enumerate() {
+            *word = self.read_word_64(address + index as u64 * 8)?;
+        }
+        Ok(())
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        for (index, word) in data.iter_mut().enumerate() {
+            *word = self.read_word_32(address + index as u64 * 4)?;
+        }
+        Ok(())
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.dump.read_memory(address, data)
+    }
+
+    fn write_word_64(&mut self, _address: u64, _data: u64) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn write_word_32(&mut self, _address: u64, _data: u32) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn write_word_8(&mut self, _address: u64, _data: u8) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn write_64(&mut self, _address: u64, _data: &[u64]) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn write_32(&mut self, _address: u64, _data: &[u32]) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn write_8(&mut self, _address: u64, _data: &[u8]) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn supports_8bit_transfers(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl CoreInterface for DumpedCore<'_> {
+    fn wait_for_core_halted(&mut self, _timeout: Duration) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        Ok(CoreStatus::Halted(HaltReason::Request))
+    }
+
+    fn halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        let pc = self.read_core_reg(self.registers().program_counter().id)?;
+        Ok(CoreInformation { pc: pc.try_into()? })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        Err(self.read_only_error())
+    }
+
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        Err(self.read_only_error())
+    }
+
+    fn read_core_reg(&mut self, address: RegisterId) -> Result<RegisterValue, Error> {
+        self.dump.registers.get(&address).copied().ok_or_else(|| {
+            Error::Other(anyhow!(
+                "The register {address:?} is not part of the core dump"
+            ))
+        })
+    }
+
+    fn write_core_reg(&mut self, _address: RegisterId, _value: RegisterValue) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u64>>, Error> {
+        Ok(vec![])
+    }
+
+    fn enable_breakpoints(&mut self, _state: bool) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn set_hw_breakpoint(&mut self, _unit_index: usize, _addr: u64) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn clear_hw_breakpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(self.read_only_error())
+    }
+
+    fn registers(&self) -> &'static RegisterFile {
+        self.dump.register_file.register_file()
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        false
+    }
+
+    fn architecture(&self) -> Architecture {
+        self.dump.core_type.architecture()
+    }
+
+    fn core_type(&self) -> CoreType {
+        self.dump.core_type
+    }
+
+    fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
+        Ok(self.dump.instruction_set)
+    }
+
+    fn fpu_support(&mut self) -> Result<bool, Error> {
+        Ok(self.dump.fpu_support)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn core_dump() -> CoreDump {
+        CoreDump {
+            core_type: CoreType::Armv7em,
+            instruction_set: InstructionSet::Thumb2,
+            fpu_support: false,
+            register_file: DumpedRegisterFile::CortexM,
+            registers: BTreeMap::from([
+                (RegisterId(13), RegisterValue::U32(0x2000_0ff0)),
+                (RegisterId(15), RegisterValue::U32(0x0800_0100)),
+            ]),
+            memory: vec![DumpedMemory {
+                start: 0x2000_0000,
+                data: (0..16).collect(),
+            }],
+            stack_frames: vec![DumpedStackFrame {
+                function_name: "main".to_string(),
+                pc: 0x0800_0100,
+                source_location: Some("src/main.rs:12".to_string()),
+            }],
+        }
+    }
+
+    #[test]
+    fn dumped_memory_contains_only_ranges_inside_the_data() {
+        let memory = DumpedMemory {
+            start: 0x2000_0000,
+            data: vec![0; 16],
+        };
+        assert!(memory.contains(0x2000_0000, 16));
+        assert!(memory.contains(0x2000_000c, 4));
+        assert!(memory.contains(0x2000_0010, 0));
+        assert!(!memory.contains(0x2000_000d, 4));
+        assert!(!memory.contains(0x1fff_ffff, 1));
+        assert!(!memory.contains(u64::MAX, 2));
+    }
+
+    #[test]
+    fn read_memory_copies_the_dumped_data_up_to_the_end_of_a_range() {
+        let core_dump = core_dump();
+        let mut data = [0_u8; 4];
+        core_dump.read_memory(0x2000_000c, &mut data).unwrap();
+        assert_eq!(data, [12, 13, 14, 15]);
+        assert!(core_dump.read_memory(0x2000_000d, &mut data).is_err());
+        assert!(core_dump.read_memory(0x1fff_fffe, &mut data).is_err());
+    }
+
+    #[test]
+    fn replayed_core_reads_the_dumped_registers_and_memory() {
+        let core_dump = core_dump();
+        let mut state = core_dump.create_state();
+        let mut core = core_dump.core(&mut state);
+        assert_eq!(
+            core.read_core_reg::<u32>(RegisterId(15)).unwrap(),
+            0x0800_0100
+        );
+        assert_eq!(core.read_word_32(0x2000_0004).unwrap(), 0x0706_0504);
+        assert!(core.write_word_32(0x2000_0004, 0).is_err());
+        assert!(core.run().is_err());
+    }
+
+    #[test]
+    fn store_and_load_round_trip() {
+        let core_dump = core_dump();
+        let path =
+            std::env::temp_dir().join(format!("probe-rs-core-dump-{}.yaml", std::process::id()));
+        core_dump.store(&path).unwrap();
+        let loaded = CoreDump::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.core_type, core_dump.core_type);
+        assert_eq!(loaded.register_file, core_dump.register_file);
+        assert_eq!(loaded.registers, core_dump.registers);
+        assert_eq!(loaded.memory_ranges(), vec![0x2000_0000..0x2000_0010]);
+        assert_eq!(loaded.memory[0].data, core_dump.memory[0].data);
+        assert_eq!(loaded.stack_frames(), core_dump.stack_frames());
+    }
+}
@@ -1,6 +1,7 @@
 use super::{
-    function_die::FunctionDie, get_sequential_key, unit_info::UnitInfo, unit_info::UnitIter,
-    variable::*, DebugError, DebugRegisters, SourceLocation, StackFrame, VariableCache,
+    function_die::FunctionDie, get_sequential_key, remap_source_path, unit_info::UnitInfo,
+    unit_info::UnitIter, variable::*, DebugError, DebugRegisters, SourceLocation, SourcePathRemap,
+    StackFrame, VariableCache,
 };
 use crate::{
     core::Core,
@@ -8,13 +9,14 @@ use crate::{
     MemoryInterface, RegisterValue,
 };
 use ::gimli::{FileEntry, LineProgramHeader, UnwindContext};
-use gimli::{BaseAddresses, ColumnType, DebugFrame, UnwindSection};
+use gimli::{BaseAddresses, ColumnType, DebugFrame, DebugInfoOffset, Reader, UnwindSection};
 use object::read::{Object, ObjectSection};
 use probe_rs_target::InstructionSet;
 use registers::RegisterGroup;
 use std::{
     borrow,
     cmp::Ordering,
+    collections::HashMap,
     convert::TryInto,
     num::NonZeroU64,
     ops::ControlFlow,
@@ -45,34 +47,44 @@ pub struct VerifiedBreakpoint {
 pub struct DebugInfo {
     pub(crate) dwarf: gimli::Dwarf<DwarfReader>,
     pub(crate) frame_section: gimli::DebugFrame<DwarfReader>,
-    pub(crate) locations_section: gimli::LocationLists<DwarfReader>,
-    pub(crate) address_section: gimli::DebugAddr<DwarfReader>,
     pub(crate) debug_line_section: gimli::DebugLine<DwarfReader>,
+    /// The split DWARF (`.dwo` or `.dwp`) sections for each skeleton unit, indexed by the offset of the skeleton unit in [`DebugInfo::dwarf`].
+    pub(crate) split_dwarf: HashMap<DebugInfoOffset, gimli::Dwarf<DwarfReader>>,
+    /// The split DWARF files that are referenced by skeleton units, but could not be found.
+    missing_split_dwarf_files: Vec<PathBuf>,
+    /// The maximum number of elements of a `Vec`, `VecDeque` or `HashMap` that are read from the target, when the collection is expanded.
+    collection_element_limit: usize,
+    /// The prefixes of the source paths that are replaced. See [`DebugInfo::set_source_path_remaps`].
+    source_path_remaps: Vec<SourcePathRemap>,
 }
 
+/// The default for [`DebugInfo::set_collection_element_limit`].
+const DEFAULT_COLLECTION_ELEMENT_LIMIT: usize = 100;
+
 impl DebugInfo {
     /// Read debug info directly from a ELF file.
+    ///
+    /// If the ELF file uses split DWARF, the debug info is read from a `<path>.dwp` package next to the ELF file,
+    /// or from the `.dwo` files that are referenced by the skeleton units.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<DebugInfo, DebugError> {
-        let data = std::fs::read(path)?;
+        let data = std::fs::read(path.as_ref())?;
 
-        DebugInfo::from_raw(&data)
+        DebugInfo::load(&data, Some(path.as_ref()))
     }
 
     /// Parse debug information directly from a buffer containing an ELF file.
+    ///
+    /// Split DWARF can only be loaded from `.dwo` files, where the path can be resolved without the path of the ELF file.
     pub fn from_raw(data: &[u8]) -> Result<Self, DebugError> {
+        DebugInfo::load(data, None)
+    }
+
+    /// Parse the debug information in `data`, and the split DWARF that it references. See [`DebugInfo::from_file`].
+    fn load(data: &[u8], binary_path: Option<&Path>) -> Result<Self, DebugError> {
         let object = object::File::parse(data)?;
 
-        // Load a section and return as `Cow<[u8]>`.
         let load_section = |id: gimli::SectionId| -> Result<DwarfReader, gimli::Error> {
-            let data = object
-                .section_by_name(id.name())
-                .and_then(|section| section.uncompressed_data().ok())
-                .unwrap_or_else(|| borrow::Cow::Borrowed(&[][..]));
-
-            Ok(gimli::read::EndianRcSlice::new(
-                Rc::from(&*data),
-                gimli::LittleEndian,
-            ))
+            load_object_section(&object, id.name())
         };
 
         // Load all of the sections.
@@ -80,21 +92,202 @@ impl DebugInfo {
 
         use gimli::Section;
         let frame_section = gimli::DebugFrame::load(load_section)?;
-        let address_section = gimli::DebugAddr::load(load_section)?;
-        let debug_loc = gimli::DebugLoc::load(load_section)?;
-        let debug_loc_lists = gimli::DebugLocLists::load(load_section)?;
-        let locations_section = gimli::LocationLists::new(debug_loc, debug_loc_lists);
         let debug_line_section = gimli::DebugLine::load(load_section)?;
 
-        Ok(DebugInfo {
+        let mut debug_info = DebugInfo {
             dwarf: dwarf_cow,
             frame_section,
-            locations_section,
-            address_section,
             debug_line_section,
+            split_dwarf: HashMap::new(),
+            missing_split_dwarf_files: vec![],
+            collection_element_limit: DEFAULT_COLLECTION_ELEMENT_LIMIT,
+            source_path_remaps: Vec::new(),
+        };
+        debug_info.load_split_dwarf(binary_path)?;
+        Ok(debug_info)
+    }
+
+    /// The split DWARF (`.dwo`) files that are referenced by the debug info, but could not be found.
+    /// Breakpoints and variables are not available for the compilation units in these files.
+    pub fn missing_split_dwarf_files(&self) -> &[PathBuf] {
+        &self.missing_split_dwarf_files
+    }
+
+    /// Set the maximum number of elements of a `Vec`, `VecDeque` or `HashMap` that are shown as the children of the collection. Defaults to 100.
+    pub fn set_collection_element_limit(&mut self, collection_element_limit: usize) {
+        self.collection_element_limit = collection_element_limit;
+    }
+
+    /// Replace the `from` prefix of the source paths in the debug information with `to`, e.g. to map the paths of a binary that was built
+    /// on another machine to the local checkout. The remaps are tried in order, and only the first one with a matching prefix is applied.
+    /// This applies to the source locations, and to the source paths that are matched when breakpoints are set.
+    pub fn set_source_path_remaps(&mut self, source_path_remaps: Vec<SourcePathRemap>) {
+        self.source_path_remaps = source_path_remaps;
+    }
+
+    /// Find, and load, the split DWARF for every skeleton unit.
+    /// A `<binary_path>.dwp` package is used if it exists, otherwise the `.dwo` file that is named in
+    /// the `DW_AT_dwo_name` (or `DW_AT_GNU_dwo_name`) attribute of the skeleton unit.
+    fn load_split_dwarf(&mut self, binary_path: Option<&Path>) -> Result<(), DebugError> {
+        let dwarf_package_data = binary_path
+            .map(|binary_path| {
+                let mut dwarf_package_path = binary_path.as_os_str().to_owned();
+                dwarf_package_path.push(".dwp");
+                PathBuf::from(dwarf_package_path)
+            })
+            .filter(|dwarf_package_path| dwarf_package_path.is_file())
+            .map(std::fs::read)
+            .transpose()?;
+        let dwarf_package = dwarf_package_data
+            .as_deref()
+            .map(|dwarf_package_data| -> Result<_, DebugError> {
+                let object = object::File::parse(dwarf_package_data)?;
+                let empty_section =
+                    gimli::read::EndianRcSlice::new(Rc::from(&[][..]), gimli::LittleEndian);
+                Ok(gimli::DwarfPackage::load(
+                    |id: gimli::SectionId| load_dwo_section(&object, id),
+                    empty_section,
+                )?)
+            })
+            .transpose()?;
+
+        let mut units = self.dwarf.units();
+        while let Some(header) = units.next()? {
+            let skeleton_offset = match header.offset().as_debug_info_offset() {
+                Some(skeleton_offset) => skeleton_offset,
+                None => continue,
+            };
+            let skeleton_unit = self.dwarf.unit(header)?;
+            let dwo_id = match skeleton_unit.dwo_id {
+                Some(dwo_id) => dwo_id,
+                // This is not a skeleton unit, and the debug info is in the main binary.
+                None => continue,
+            };
+
+            if let Some(split_dwarf) = dwarf_package
+                .as_ref()
+                .map(|dwarf_package| dwarf_package.find_cu(dwo_id, &self.dwarf))
+                .transpose()?
+                .flatten()
+            {
+                self.split_dwarf.insert(skeleton_offset, split_dwarf);
+                continue;
+            }
+
+            let dwo_path = match self.dwo_path(&skeleton_unit, binary_path)? {
+                Some(dwo_path) => dwo_path,
+                None => continue,
+            };
+            if !dwo_path.is_file() {
+                tracing::warn!(
+                    "The split DWARF file {} does not exist. Debug information for this compilation unit will not be available.",
+                    dwo_path.display()
+                );
+                self.missing_split_dwarf_files.push(dwo_path);
+                continue;
+            }
+            let dwo_data = std::fs::read(&dwo_path)?;
+            let dwo_object = object::File::parse(&*dwo_data)?;
+            let mut split_dwarf =
+                gimli::Dwarf::load(|id: gimli::SectionId| load_dwo_section(&dwo_object, id))?;
+            split_dwarf.make_dwo(&self.dwarf);
+            // The line program of the skeleton unit is used for split units, and it can reference the `.debug_line_str` section of the main binary.
+            split_dwarf.debug_line_str = self.dwarf.debug_line_str.clone();
+            self.split_dwarf.insert(skeleton_offset, split_dwarf);
+        }
+        Ok(())
+    }
+
+    /// Get the path of the `.dwo` file for a skeleton unit.
+    /// Relative paths are resolved against the compilation directory, or if that does not exist, against the directory of the binary.
+    fn dwo_path(
+        &self,
+        skeleton_unit: &gimli::Unit<DwarfReader>,
+        binary_path: Option<&Path>,
+    ) -> Result<Option<PathBuf>, DebugError> {
+        let mut entries = skeleton_unit.entries();
+        let root_entry = match entries.next_dfs()? {
+            Some((_, root_entry)) => root_entry,
+            None => return Ok(None),
+        };
+        let dwo_name_attribute = match root_entry.attr_value(gimli::DW_AT_dwo_name)? {
+            Some(dwo_name_attribute) => dwo_name_attribute,
+            None => match root_entry.attr_value(gimli::DW_AT_GNU_dwo_name)? {
+                Some(dwo_name_attribute) => dwo_name_attribute,
+                None => return Ok(None),
+            },
+        };
+        let dwo_name = self
+            .dwarf
+            .attr_string(skeleton_unit, dwo_name_attribute)?
+            .to_string_lossy()?
+            .into_owned();
+        let dwo_path = PathBuf::from(dwo_name);
+        if dwo_path.is_absolute() {
+            return Ok(Some(dwo_path));
+        }
+        let comp_dir_path = skeleton_unit
+            .comp_dir
+            .as_ref()
+            .and_then(|comp_dir| comp_dir.to_string_lossy().ok())
+            .map(|comp_dir| PathBuf::from(comp_dir.into_owned()).join(&dwo_path));
+        let binary_dir_path = binary_path
+            .and_then(Path::parent)
+            .map(|binary_dir| binary_dir.join(&dwo_path));
+        Ok(match (comp_dir_path, binary_dir_path) {
+            (Some(comp_dir_path), Some(binary_dir_path)) if !comp_dir_path.is_file() => {
+                Some(binary_dir_path)
+            }
+            (Some(comp_dir_path), _) => Some(comp_dir_path),
+            (None, binary_dir_path) => binary_dir_path.or(Some(dwo_path)),
         })
     }
 
+    /// Get the [`UnitInfo`] for a unit in [`DebugInfo::dwarf`]. For skeleton units, the [`UnitInfo`] refers to the split unit.
+    pub(crate) fn unit_info(
+        &self,
+        header: gimli::UnitHeader<DwarfReader>,
+    ) -> Result<UnitInfo, DebugError> {
+        let debug_info_offset = header.offset().as_debug_info_offset();
+        let unit = self.dwarf.unit(header)?;
+        if let Some(split_dwarf) =
+            debug_info_offset.and_then(|offset| self.split_dwarf.get(&offset))
+        {
+            let mut split_units = split_dwarf.units();
+            while let Some(split_header) = split_units.next()? {
+                let mut split_unit = split_dwarf.unit(split_header)?;
+                if split_unit.dwo_id != unit.dwo_id {
+                    continue;
+                }
+                split_unit.copy_relocated_attributes(&unit);
+                // Split units use the line program of the skeleton unit.
+                if split_unit.line_program.is_none() {
+                    split_unit.line_program = unit.line_program.clone();
+                }
+                return Ok(UnitInfo {
+                    debug_info: self,
+                    dwarf: split_dwarf,
+                    unit: split_unit,
+                    debug_info_offset,
+                });
+            }
+        }
+        Ok(UnitInfo {
+            debug_info: self,
+            dwarf: &self.dwarf,
+            unit,
+            debug_info_offset,
+        })
+    }
+
+    /// Get the [`UnitInfo`] for the unit at `offset` in [`DebugInfo::dwarf`], as returned by [`UnitInfo::debug_info_offset`].
+    pub(crate) fn unit_info_from_offset(
+        &self,
+        offset: DebugInfoOffset,
+    ) -> Result<UnitInfo, DebugError> {
+        self.unit_info(self.dwarf.debug_info.header_from_offset(offset)?)
+    }
+
     /// Get the name of the function at the given address.
     ///
     /// If no function is found, `None` will be returend.
@@ -269,11 +462,8 @@ impl DebugInfo {
 
     pub(crate) fn get_next_unit_info(&self, units: &mut UnitIter) -> Option<UnitInfo> {
         while let Ok(Some(header)) = units.next() {
-            if let Ok(unit) = self.dwarf.unit(header) {
-                return Some(UnitInfo {
-                    debug_info: self,
-                    unit,
-                });
+            if let Ok(unit_info) = self.unit_info(header) {
+                return Some(unit_info);
             };
         }
         None
@@ -295,7 +485,7 @@ impl DebugInfo {
         if let Ok(mut header_tree) = unit_info.unit.header.entries_tree(abbrevs, None) {
             let unit_node = header_tree.root()?;
             let mut static_root_variable = Variable::new(
-                unit_info.unit.header.offset().as_debug_info_offset(),
+                unit_info.debug_info_offset,
                 Some(unit_node.entry().offset()),
             );
             static_root_variable.variable_node_type = VariableNodeType::DirectLookup;
@@ -305,6 +495,89 @@ impl DebugInfo {
         Ok(static_variable_cache)
     }
 
+    /// Creates the unpopulated cache for the global variables of the program, i.e. the variables with a static address in any compile unit,
+    /// so that they can be inspected irrespective of the current stack frame.
+    /// The `[VariableName::GlobalScopeRoot]` has a [`VariableNodeType::DirectLookup`] child for every compile unit that declares such variables,
+    /// which, like the `[VariableName::StaticScope]`, is only resolved when a user requests it from the debug client.
+    pub fn create_global_scope_cache(
+        &self,
+        core: &mut Core<'_>,
+    ) -> Result<VariableCache, DebugError> {
+        let mut global_variable_cache = VariableCache::new();
+        let mut global_root_variable = Variable::new(None, None);
+        global_root_variable.variable_node_type = VariableNodeType::DoNotRecurse;
+        global_root_variable.name = VariableName::GlobalScopeRoot;
+        let global_root_variable =
+            global_variable_cache.cache_variable(None, global_root_variable, core)?;
+
+        let mut units = self.get_units();
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            if !unit_info.has_static_variables() {
+                continue;
+            }
+            let abbrevs = &unit_info.unit.abbreviations;
+            if let Ok(mut header_tree) = unit_info.unit.header.entries_tree(abbrevs, None) {
+                let unit_node = header_tree.root()?;
+                let mut unit_variable = Variable::new(
+                    unit_info.debug_info_offset,
+                    Some(unit_node.entry().offset()),
+                );
+                unit_variable.variable_node_type = VariableNodeType::DirectLookup;
+                unit_variable.name = VariableName::Namespace(
+                    unit_info
+                        .unit
+                        .name
+                        .as_ref()
+                        .and_then(|unit_name| unit_name.to_string_lossy().ok())
+                        .map_or_else(
+                            || "<unknown compile unit>".to_string(),
+                            |unit_name| unit_name.into_owned(),
+                        ),
+                );
+                global_variable_cache.cache_variable(
+                    Some(global_root_variable.variable_key),
+                    unit_variable,
+                    core,
+                )?;
+            }
+        }
+        Ok(global_variable_cache)
+    }
+
+    /// The offset of the member `member_name` from the start of the structure type named `struct_name`, as defined in the first compile unit that has it.
+    /// This allows reading the fields of data structures, like the task control blocks of an RTOS, without resolving them as variables.
+    pub fn struct_member_offset(&self, struct_name: &str, member_name: &str) -> Option<u64> {
+        let mut units = self.get_units();
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            if let Some(member_offset) = unit_info.struct_member_offset(struct_name, member_name) {
+                return Some(member_offset);
+            }
+        }
+        None
+    }
+
+    /// The size, in bytes, of the member `member_name` of the structure type named `struct_name`, as defined in the first compile unit that has it.
+    pub fn struct_member_byte_size(&self, struct_name: &str, member_name: &str) -> Option<u64> {
+        let mut units = self.get_units();
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            if let Some(byte_size) = unit_info.struct_member_byte_size(struct_name, member_name) {
+                return Some(byte_size);
+            }
+        }
+        None
+    }
+
+    /// The size, in bytes, of the structure type named `struct_name`, as defined in the first compile unit that has it.
+    pub fn struct_byte_size(&self, struct_name: &str) -> Option<u64> {
+        let mut units = self.get_units();
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            if let Some(byte_size) = unit_info.struct_byte_size(struct_name) {
+                return Some(byte_size);
+            }
+        }
+        None
+    }
+
     /// Creates the unpopulated cache for `function` variables
     pub(crate) fn create_function_scope_cache(
         &self,
@@ -322,7 +595,7 @@ impl DebugInfo {
         let function_node = tree.root()?;
 
         let mut function_root_variable = Variable::new(
-            unit_info.unit.header.offset().as_debug_info_offset(),
+            unit_info.debug_info_offset,
             Some(function_node.entry().offset()),
         );
         function_root_variable.variable_node_type = VariableNodeType::DirectLookup;
@@ -350,12 +623,7 @@ impl DebugInfo {
                 // Only attempt this part if we have not yet resolved the referenced children.
                 if !cache.has_children(parent_variable)? {
                     if let Some(header_offset) = parent_variable.unit_header_offset {
-                        let unit_header =
-                            self.dwarf.debug_info.header_from_offset(header_offset)?;
-                        let unit_info = UnitInfo {
-                            debug_info: self,
-                            unit: gimli::Unit::new(&self.dwarf, unit_header)?,
-                        };
+                        let unit_info = self.unit_info_from_offset(header_offset)?;
                         // Reference to a type, or an node.entry() to another type or a type modifier which will point to another type.
                         let mut type_tree = unit_info
                             .unit
@@ -365,7 +633,7 @@ impl DebugInfo {
                         let mut referenced_variable = cache.cache_variable(
                             Some(parent_variable.variable_key),
                             Variable::new(
-                                unit_info.unit.header.offset().as_debug_info_offset(),
+                                unit_info.debug_info_offset,
                                 Some(referenced_node.entry().offset()),
                             ),
                             core,
@@ -405,12 +673,19 @@ impl DebugInfo {
                 // Only attempt this if the children are not already resolved.
                 if !cache.has_children(parent_variable)? {
                     if let Some(header_offset) = parent_variable.unit_header_offset {
-                        let unit_header =
-                            self.dwarf.debug_info.header_from_offset(header_offset)?;
-                        let unit_info = UnitInfo {
-                            debug_info: self,
-                            unit: gimli::Unit::new(&self.dwarf, unit_header)?,
-                        };
+                        let unit_info = self.unit_info_from_offset(header_offset)?;
+                        // Collections show their elements, rather than their internal members.
+                        if unit_info.cache_collection_elements(
+                            type_offset,
+                            parent_variable,
+                            core,
+                            cache,
+                            stack_frame_registers,
+                            frame_base,
+                            self.collection_element_limit,
+                        )? {
+                            return Ok(());
+                        }
                         // Find the parent node
                         let mut type_tree = unit_info
                             .unit
@@ -446,12 +721,7 @@ impl DebugInfo {
                 // Only attempt this if the children are not already resolved.
                 if !cache.has_children(parent_variable)? {
                     if let Some(header_offset) = parent_variable.unit_header_offset {
-                        let unit_header =
-                            self.dwarf.debug_info.header_from_offset(header_offset)?;
-                        let unit_info = UnitInfo {
-                            debug_info: self,
-                            unit: gimli::Unit::new(&self.dwarf, unit_header)?,
-                        };
+                        let unit_info = self.unit_info_from_offset(header_offset)?;
                         // Find the parent node
                         let mut type_tree = unit_info.unit.header.entries_tree(
                             &unit_info.unit.abbreviations,
@@ -935,113 +1205,61 @@ impl DebugInfo {
                                 if cur_line.get() == line {
                                     // The first match of the file and row will be used to build the SourceStatements, and then:
                                     // 1. If there is an exact column match, we will use the low_pc of the statement at that column and line.
-                                    // 2. If there is no exact column match, we use the first available statement in the line.
+                                    // 2. If there is no exact column match, we use the nearest statement after the requested column, e.g. the second statement of `if x { a(); b(); }`.
+                                    // 3. If there is no column, or no statement after it, we use the first available statement in the line.
                                     let source_statements =
                                         SourceStatements::new(self, &unit_header, row.address())?
                                             .statements;
+                                    let line_statements = source_statements
+                                        .iter()
+                                        .filter(|statement| statement.line == Some(cur_line));
+                                    let halt_statement = column
+                                        .and_then(NonZeroU64::new)
+                                        .map(ColumnType::Column)
+                                        .and_then(|requested_column| {
+                                            line_statements
+                                                .clone()
+                                                .filter(|statement| {
+                                                    statement.column >= requested_column
+                                                })
+                                                .min_by_key(|statement| statement.column)
+                                        })
+                                        .or_else(|| line_statements.clone().next());
                                     if let Some((halt_address, Some(halt_location))) =
-                                        source_statements
-                                            .iter()
-                                            .find(|statement| {
-                                                statement.line == Some(cur_line)
-                                                    && column
-                                                        .and_then(NonZeroU64::new)
-                                                        .map(ColumnType::Column)
-                                                        .map_or(false, |col| {
-                                                            col == statement.column
+                                        halt_statement.map(|source_statement| {
+                                            (
+                                                source_statement.low_pc(),
+                                                line_program
+                                                    .header()
+                                                    .file(source_statement.file_index)
+                                                    .and_then(|file_entry| {
+                                                        self.find_file_and_directory(
+                                                            &unit_header.unit,
+                                                            line_program.header(),
+                                                            file_entry,
+                                                        )
+                                                        .map(|(file, directory)| SourceLocation {
+                                                            line: source_statement
+                                                                .line
+                                                                .map(std::num::NonZeroU64::get),
+                                                            column: Some(
+                                                                source_statement.column.into(),
+                                                            ),
+                                                            file,
+                                                            directory,
+                                                            low_pc: Some(
+                                                                source_statement.low_pc() as u32
+                                                            ),
+                                                            high_pc: Some(
+                                                                source_statement
+                                                                    .instruction_range
+                                                                    .end
+                                                                    as u32,
+                                                            ),
                                                         })
-                                            })
-                                            .map(|source_statement| {
-                                                (
-                                                    source_statement.low_pc(),
-                                                    line_program
-                                                        .header()
-                                                        .file(source_statement.file_index)
-                                                        .and_then(|file_entry| {
-                                                            self.find_file_and_directory(
-                                                                &unit_header.unit,
-                                                                line_program.header(),
-                                                                file_entry,
-                                                            )
-                                                            .map(|(file, directory)| {
-                                                                SourceLocation {
-                                                                    line: source_statement
-                                                                        .line
-                                                                        .map(
-                                                                        std::num::NonZeroU64::get,
-                                                                    ),
-                                                                    column: Some(
-                                                                        source_statement
-                                                                            .column
-                                                                            .into(),
-                                                                    ),
-                                                                    file,
-                                                                    directory,
-                                                                    low_pc: Some(
-                                                                        source_statement.low_pc()
-                                                                            as u32,
-                                                                    ),
-                                                                    high_pc: Some(
-                                                                        source_statement
-                                                                            .instruction_range
-                                                                            .end
-                                                                            as u32,
-                                                                    ),
-                                                                }
-                                                            })
-                                                        }),
-                                                )
-                                            })
-                                    {
-                                        return Ok(VerifiedBreakpoint {
-                                            address: halt_address,
-                                            source_location: halt_location,
-                                        });
-                                    } else if let Some((halt_address, Some(halt_location))) =
-                                        source_statements
-                                            .iter()
-                                            .find(|statement| statement.line == Some(cur_line))
-                                            .map(|source_statement| {
-                                                (
-                                                    source_statement.low_pc(),
-                                                    line_program
-                                                        .header()
-                                                        .file(source_statement.file_index)
-                                                        .and_then(|file_entry| {
-                                                            self.find_file_and_directory(
-                                                                &unit_header.unit,
-                                                                line_program.header(),
-                                                                file_entry,
-                                                            )
-                                                            .map(|(file, directory)| {
-                                                                SourceLocation {
-                                                                    line: source_statement
-                                                                        .line
-                                                                        .map(
-                                                                        std::num::NonZeroU64::get,
-                                                                    ),
-                                                                    column: Some(
-                                                                        source_statement
-                                                                            .column
-                                                                            .into(),
-                                                                    ),
-                                                                    file,
-                                                                    directory,
-                                                                    low_pc: Some(
-                                                                        source_statement.low_pc()
-                                                                            as u32,
-                                                                    ),
-                                                                    high_pc: Some(
-                                                                        source_statement
-                                                                            .instruction_range
-                                                                            .end
-                                                                            as u32,
-                                                                    ),
-                                                                }
-                                                            })
-                                                        }),
-                                                )
-                                            })
+                                                    }),
+                                            )
+                                        })
                                     {
                                         return Ok(VerifiedBreakpoint {
                                             address: halt_address,
@@ -1055,12 +1273,65 @@ impl DebugInfo {
                 }
             }
         }
-        Err(DebugError::Other(anyhow::anyhow!(
-            "No valid breakpoint information found for file: {:?}, line: {:?}, column: {:?}",
-            path,
+        Err(DebugError::NoBreakpointLocation {
+            path: path.to_path_buf(),
             line,
-            column
-        )))
+            column,
+        })
+    }
+
+    /// Find all the valid breakpoint locations in a source file, between `start_line` and `end_line` (inclusive).
+    /// A valid location is the line and column of a statement boundary (`is_stmt`) in the line program,
+    /// which is also where [`DebugInfo::get_breakpoint_location`] will place a breakpoint.
+    /// The locations are returned as `(line, column)`, where the column is `None` if the statement starts at the beginning of the line.
+    /// They are sorted by line and column, and lines that were optimized away are not included.
+    pub fn get_breakpoint_locations(
+        &self,
+        path: &Path,
+        start_line: u64,
+        end_line: u64,
+    ) -> Result<Vec<(u64, Option<u64>)>, DebugError> {
+        let mut breakpoint_locations = Vec::new();
+        let mut unit_iter = self.dwarf.units();
+
+        while let Some(unit_header) = self.get_next_unit_info(&mut unit_iter) {
+            let unit = &unit_header.unit;
+
+            if let Some(ref line_program) = unit.line_program {
+                let header = line_program.header();
+
+                if !header.file_names().iter().any(|file_name| {
+                    self.get_path(unit, header, file_name).as_deref() == Some(path)
+                }) {
+                    continue;
+                }
+
+                let mut rows = line_program.clone().rows();
+                while let Some((header, row)) = rows.next_row()? {
+                    if !row.is_stmt() || row.end_sequence() {
+                        continue;
+                    }
+                    let line = match row.line() {
+                        Some(line) if (start_line..=end_line).contains(&line.get()) => line.get(),
+                        _ => continue,
+                    };
+                    let row_path = row
+                        .file(header)
+                        .and_then(|file_entry| self.get_path(unit, header, file_entry));
+                    if row_path.as_deref() == Some(path) {
+                        let column = match row.column() {
+                            ColumnType::LeftEdge => None,
+                            ColumnType::Column(column) => Some(column.get()),
+                        };
+                        breakpoint_locations.push((line, column));
+                    }
+                }
+            }
+        }
+
+        breakpoint_locations.sort_unstable();
+        breakpoint_locations.dedup();
+        Ok(breakpoint_locations)
     }
 
     /// Get the absolute path for an entry in a line program header
@@ -1099,7 +1370,7 @@ impl DebugInfo {
             }
         }
 
-        Some(combined_path)
+        Some(remap_source_path(&self.source_path_remaps, &combined_path))
     }
 
     pub(crate) fn find_file_and_directory(
@@ -1120,6 +1391,28 @@ impl DebugInfo {
     }
 }
 
+/// Load a section from an object file. Sections that do not exist are loaded as empty sections.
+fn load_object_section(object: &object::File, name: &str) -> Result<DwarfReader, gimli::Error> {
+    let data = object
+        .section_by_name(name)
+        .and_then(|section| section.uncompressed_data().ok())
+        .unwrap_or_else(|| borrow::Cow::Borrowed(&[][..]));
+
+    Ok(gimli::read::EndianRcSlice::new(
+        Rc::from(&*data),
+        gimli::LittleEndian,
+    ))
+}
+
+/// Load the split DWARF variant (e.g. `.debug_info.dwo`) of a section from a `.dwo` or `.dwp` file.
+fn load_dwo_section(
+    object: &object::File,
+    id: gimli::SectionId,
+) -> Result<DwarfReader, gimli::Error> {
+    // Sections without a split DWARF variant are loaded as empty sections.
+    load_object_section(object, id.dwo_name().unwrap_or_default())
+}
+
 /// Get a handle to the [`gimli::UnwindTableRow`] for this call frame, so that we can reference it to unwind register values.
 fn get_unwind_info<'a>(
     unwind_context: &'a mut Box<UnwindContext<DwarfReader>>,
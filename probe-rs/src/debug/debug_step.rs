@@ -483,7 +483,7 @@ fn get_compile_unit_info(
 ) -> Result<super::unit_info::UnitInfo, DebugError> {
     let mut units = debug_info.get_units();
     while let Some(header) = debug_info.get_next_unit_info(&mut units) {
-        match debug_info.dwarf.unit_ranges(&header.unit) {
+        match header.dwarf.unit_ranges(&header.unit) {
             Ok(mut ranges) => {
                 while let Ok(Some(range)) = ranges.next() {
                     if (range.begin <= program_counter) && (range.end > program_counter) {
@@ -36,7 +36,7 @@ use gimli::DebuggingInformationEntry;
 
 use std::{
     io,
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::Utf8Error,
     sync::atomic::{AtomicI64, Ordering},
     vec,
@@ -83,6 +83,17 @@ pub enum DebugError {
         /// A message that can be displayed to the user to help them understand the reason for the incomplete results.
         message: String,
     },
+    /// The debug information has no valid breakpoint location for the requested source location,
+    /// e.g. because the source file is not part of the binary, or the line was optimized away.
+    #[error("No valid breakpoint information found for file: {path:?}, line: {line:?}, column: {column:?}")]
+    NoBreakpointLocation {
+        /// The path of the requested source file.
+        path: PathBuf,
+        /// The requested line.
+        line: u64,
+        /// The requested column, if any.
+        column: Option<u64>,
+    },
     /// Some other error occurred.
     #[error(transparent)]
     Other(#[from] anyhow::Error),
@@ -150,6 +161,29 @@ impl SourceLocation {
     }
 }
 
+/// Replace the `from` prefix of a source path with `to`, e.g. to map a directory of the machine that built the binary to the local checkout.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcePathRemap {
+    /// The prefix of the source paths in the binary.
+    pub from: PathBuf,
+    /// The local path that replaces the prefix.
+    pub to: PathBuf,
+}
+
+/// Apply the first of the `source_path_remaps` whose `from` prefix matches (whole path components of) `path`.
+/// Paths that do not match any of them are returned unchanged.
+pub fn remap_source_path(source_path_remaps: &[SourcePathRemap], path: &Path) -> PathBuf {
+    source_path_remaps
+        .iter()
+        .find_map(|remap| {
+            path.strip_prefix(&remap.from)
+                .ok()
+                .map(|relative_path| remap.to.join(relative_path))
+        })
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
 /// If file information is available, it returns `Some(directory:PathBuf, file_name:String)`, otherwise `None`.
 fn extract_file(
     debug_info: &DebugInfo,
@@ -217,12 +251,14 @@ fn extract_line(attribute_value: gimli::AttributeValue<GimliReader>) -> Option<u
 }
 
 fn extract_name(
-    debug_info: &DebugInfo,
+    unit_info: &unit_info::UnitInfo,
     attribute_value: gimli::AttributeValue<GimliReader>,
 ) -> String {
     match attribute_value {
-        gimli::AttributeValue::DebugStrRef(name_ref) => {
-            if let Ok(name_raw) = debug_info.dwarf.string(name_ref) {
+        // Split units use indexed strings (`DW_FORM_strx`).
+        name_ref @ (gimli::AttributeValue::DebugStrRef(_)
+        | gimli::AttributeValue::DebugStrOffsetsIndex(_)) => {
+            if let Ok(name_raw) = unit_info.dwarf.attr_string(&unit_info.unit, name_ref) {
                 String::from_utf8_lossy(&name_raw).to_string()
             } else {
                 "Invalid DW_AT_name value".to_string()
@@ -354,3 +390,45 @@ pub(crate) fn _print_all_attributes(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remaps() -> Vec<SourcePathRemap> {
+        vec![
+            SourcePathRemap {
+                from: PathBuf::from("/ci/build/app/src"),
+                to: PathBuf::from("/home/user/app/src"),
+            },
+            SourcePathRemap {
+                from: PathBuf::from("/ci/build"),
+                to: PathBuf::from("/home/user"),
+            },
+        ]
+    }
+
+    #[test]
+    fn remap_source_path_uses_the_first_matching_remap() {
+        assert_eq!(
+            remap_source_path(&remaps(), Path::new("/ci/build/app/src/main.rs")),
+            PathBuf::from("/home/user/app/src/main.rs")
+        );
+        assert_eq!(
+            remap_source_path(&remaps(), Path::new("/ci/build/lib/src/lib.rs")),
+            PathBuf::from("/home/user/lib/src/lib.rs")
+        );
+    }
+
+    #[test]
+    fn remap_source_path_only_matches_whole_path_components() {
+        assert_eq!(
+            remap_source_path(&remaps(), Path::new("/ci/builder/main.rs")),
+            PathBuf::from("/ci/builder/main.rs")
+        );
+        assert_eq!(
+            remap_source_path(&[], Path::new("/ci/build/main.rs")),
+            PathBuf::from("/ci/build/main.rs")
+        );
+    }
+}
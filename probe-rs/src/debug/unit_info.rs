@@ -18,7 +18,12 @@ pub(crate) enum ExpressionResult {
 
 pub(crate) struct UnitInfo<'debuginfo> {
     pub(crate) debug_info: &'debuginfo DebugInfo,
+    /// The DWARF sections that contain `unit`. For split units, these are the sections of the `.dwo` or `.dwp` file.
+    pub(crate) dwarf: &'debuginfo gimli::Dwarf<DwarfReader>,
     pub(crate) unit: gimli::Unit<GimliReader, usize>,
+    /// The offset of the unit in the `.debug_info` section of the main binary. For split units, this is the offset of the skeleton unit.
+    /// Use [`DebugInfo::unit_info_from_offset`] to get the [`UnitInfo`] for this offset.
+    pub(crate) debug_info_offset: Option<gimli::DebugInfoOffset>,
 }
 
 impl<'debuginfo> UnitInfo<'debuginfo> {
@@ -38,7 +43,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
         let mut entries_cursor = self.unit.entries();
         while let Ok(Some((_depth, current))) = entries_cursor.next_dfs() {
             if current.tag() == gimli::DW_TAG_subprogram {
-                let mut ranges = self.debug_info.dwarf.die_ranges(&self.unit, current)?;
+                let mut ranges = self.dwarf.die_ranges(&self.unit, current)?;
 
                 while let Ok(Some(ranges)) = ranges.next() {
                     if ranges.begin <= address && address < ranges.end {
@@ -108,6 +113,125 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
         Ok(vec![])
     }
 
+    /// The offset of the definition (not a declaration) of the structure type named `struct_name`, if this unit has it.
+    fn find_struct_type(&self, struct_name: &str) -> Option<UnitOffset> {
+        let mut entries_cursor = self.unit.entries();
+        while let Ok(Some((_depth, current))) = entries_cursor.next_dfs() {
+            if current.tag() == gimli::DW_TAG_structure_type
+                && !matches!(current.attr(gimli::DW_AT_declaration), Ok(Some(_)))
+                && current
+                    .attr(gimli::DW_AT_name)
+                    .ok()
+                    .flatten()
+                    .map(|name_attr| extract_name(self, name_attr.value()))
+                    .as_deref()
+                    == Some(struct_name)
+            {
+                return Some(current.offset());
+            }
+        }
+        None
+    }
+
+    /// The entry of the member `member_name` of the structure type named `struct_name`, if this unit defines it.
+    fn find_struct_member(&self, struct_name: &str, member_name: &str) -> Option<UnitOffset> {
+        let mut struct_tree = self
+            .unit
+            .header
+            .entries_tree(
+                &self.unit.abbreviations,
+                Some(self.find_struct_type(struct_name)?),
+            )
+            .ok()?;
+        let struct_node = struct_tree.root().ok()?;
+        let mut member_nodes = struct_node.children();
+        while let Ok(Some(member_node)) = member_nodes.next() {
+            let member = member_node.entry();
+            if member.tag() == gimli::DW_TAG_member
+                && member
+                    .attr(gimli::DW_AT_name)
+                    .ok()
+                    .flatten()
+                    .map(|name_attr| extract_name(self, name_attr.value()))
+                    .as_deref()
+                    == Some(member_name)
+            {
+                return Some(member.offset());
+            }
+        }
+        None
+    }
+
+    /// The offset of the member `member_name` of the structure type named `struct_name`, if this unit defines it.
+    pub(crate) fn struct_member_offset(&self, struct_name: &str, member_name: &str) -> Option<u64> {
+        self.unit
+            .entry(self.find_struct_member(struct_name, member_name)?)
+            .ok()?
+            .attr_value(gimli::DW_AT_data_member_location)
+            .ok()
+            .flatten()
+            .and_then(|location| location.udata_value())
+    }
+
+    /// The size, in bytes, of the member `member_name` of the structure type named `struct_name`, if this unit defines it.
+    /// Typedefs and type qualifiers, e.g. `volatile UBaseType_t`, are followed to the type that has a size.
+    pub(crate) fn struct_member_byte_size(
+        &self,
+        struct_name: &str,
+        member_name: &str,
+    ) -> Option<u64> {
+        let mut entry = self
+            .unit
+            .entry(self.find_struct_member(struct_name, member_name)?)
+            .ok()?;
+        // The number of type references is limited, to guard against a cycle in corrupt debug information.
+        for _ in 0..16 {
+            if let Some(byte_size) = extract_byte_size(&entry) {
+                return Some(byte_size);
+            }
+            match entry.attr_value(gimli::DW_AT_type).ok().flatten()? {
+                gimli::AttributeValue::UnitRef(type_offset) => {
+                    entry = self.unit.entry(type_offset).ok()?
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// The size, in bytes, of the structure type named `struct_name`, if this unit defines it.
+    pub(crate) fn struct_byte_size(&self, struct_name: &str) -> Option<u64> {
+        let struct_entry = self.unit.entry(self.find_struct_type(struct_name)?).ok()?;
+        extract_byte_size(&struct_entry)
+    }
+
+    /// Returns `true` if the unit declares variables with a location outside of its functions, i.e. variables with a static address, like `static CONFIG`.
+    pub(crate) fn has_static_variables(&self) -> bool {
+        let mut entries_cursor = self.unit.entries();
+        let mut current_depth = 0;
+        let mut function_depth = None;
+        while let Ok(Some((depth, current))) = entries_cursor.next_dfs() {
+            current_depth += depth;
+            if function_depth.map_or(false, |function_depth| current_depth <= function_depth) {
+                function_depth = None;
+            }
+            if function_depth.is_some() {
+                // Variables inside functions are local variables, or function scoped statics that are only visible in the function.
+                continue;
+            }
+            match current.tag() {
+                gimli::DW_TAG_subprogram => function_depth = Some(current_depth),
+                gimli::DW_TAG_variable
+                    if matches!(current.attr(gimli::DW_AT_location), Ok(Some(_))) =>
+                {
+                    return true
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
     /// Check if the function located at the given offset contains inlined functions at the
     /// given address.
     pub(crate) fn find_inlined_functions(
@@ -132,7 +256,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                 }
 
                 if current.tag() == gimli::DW_TAG_inlined_subroutine {
-                    let mut ranges = self.debug_info.dwarf.die_ranges(&self.unit, current)?;
+                    let mut ranges = self.dwarf.die_ranges(&self.unit, current)?;
 
                     while let Ok(Some(ranges)) = ranges.next() {
                         if ranges.begin <= address && address < ranges.end {
@@ -243,7 +367,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
             .transpose()?
             .flatten()
         {
-            child_variable.name = VariableName::Named(extract_name(self.debug_info, name));
+            child_variable.name = VariableName::Named(extract_name(self, name));
         }
 
         if let Some(attributes_entry) = attributes_entry {
@@ -411,7 +535,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                             let mut discriminant_variable = cache.cache_variable(
                                 Some(parent_variable.variable_key),
                                 Variable::new(
-                                    self.unit.header.offset().as_debug_info_offset(),
+                                    self.debug_info_offset,
                                     Some(discriminant_node.entry().offset()),
                                 ),
                                 core,
@@ -428,10 +552,15 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                             if !discriminant_variable.is_valid() {
                                 parent_variable.role = VariantRole::VariantPart(u64::MAX);
                             } else {
+                                let discriminant_value = discriminant_variable.get_value(cache);
                                 parent_variable.role = VariantRole::VariantPart(
-                                    discriminant_variable
-                                        .get_value(cache)
-                                        .parse()
+                                    discriminant_value
+                                        .parse::<u64>()
+                                        .or_else(|_| {
+                                            discriminant_value
+                                                .parse::<i64>()
+                                                .map(|value| value as u64)
+                                        })
                                         .unwrap_or(u64::MAX),
                                 );
                             }
@@ -543,12 +672,12 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                     gimli::DW_TAG_namespace => {
                         // Use these parents to extract `statics`.
                         let mut namespace_variable = Variable::new(
-                            self.unit.header.offset().as_debug_info_offset(),
+                            self.debug_info_offset,
                             Some(child_node.entry().offset()),
                         );
 
                         namespace_variable.name = if let Ok(Some(attr)) = child_node.entry().attr(gimli::DW_AT_name) {
-                            VariableName::Namespace(extract_name(self.debug_info, attr.value()))
+                            VariableName::Namespace(extract_name(self, attr.value()))
                         } else { VariableName::AnonymousNamespace };
                         namespace_variable.type_name = VariableType::Namespace;
                         namespace_variable.memory_location = VariableLocation::Unavailable;
@@ -560,20 +689,20 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                                 gimli::DW_TAG_variable => {
                                     // We only want the TOP level variables of the namespace (statics).
                                     let static_child_variable = cache.cache_variable(Some(namespace_variable.variable_key), Variable::new(
-                                        self.unit.header.offset().as_debug_info_offset(),
+                                        self.debug_info_offset,
                                         Some(namespace_child_node.entry().offset()),), core)?;
                                     self.process_tree_node_attributes(&mut namespace_child_node, &mut namespace_variable, static_child_variable, core, stack_frame_registers, frame_base, cache)?;
                                 }
                                 gimli::DW_TAG_namespace => {
                                     // Recurse for additional namespace variables.
                                     let mut namespace_child_variable = Variable::new(
-                                        self.unit.header.offset().as_debug_info_offset(),
+                                        self.debug_info_offset,
                                         Some(namespace_child_node.entry().offset()),);
                                     namespace_child_variable.name = if let Ok(Some(attr)) = namespace_child_node.entry().attr(gimli::DW_AT_name) {
 
                                         match &namespace_variable.name {
                                             VariableName::Namespace(name) => {
-                                            VariableName::Namespace(format!("{}::{}", name, extract_name(self.debug_info, attr.value())))
+                                            VariableName::Namespace(format!("{}::{}", name, extract_name(self, attr.value())))
                                             }
                                             other => return Err(DebugError::UnwindIncompleteResults {message: format!("Unable to construct namespace variable, unexpected parent name: {other:?}")})
                                         }
@@ -602,7 +731,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                     gimli::DW_TAG_enumerator         // Possible values for enumerators, used by extract_type() when processing DW_TAG_enumeration_type.
                     => {
                         let mut child_variable = cache.cache_variable(Some(parent_variable.variable_key), Variable::new(
-                        self.unit.header.offset().as_debug_info_offset(),
+                        self.debug_info_offset,
                         Some(child_node.entry().offset()),
                     ), core)?;
                         child_variable = self.process_tree_node_attributes(&mut child_node, &mut parent_variable, child_variable, core, stack_frame_registers, frame_base, cache,)?;
@@ -627,7 +756,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                         // TODO: Handle Level 3 nodes that belong to a DW_AT_discr_list, instead of having a discreet DW_AT_discr_value 
                         let mut child_variable = cache.cache_variable(
                             Some(parent_variable.variable_key),
-                            Variable::new(self.unit.header.offset().as_debug_info_offset(),Some(child_node.entry().offset())),
+                            Variable::new(self.debug_info_offset,Some(child_node.entry().offset())),
                             core
                         )?;
                         // To determine the discriminant, we use the following rules:
@@ -639,6 +768,12 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                         child_variable = self.process_tree_node_attributes(&mut child_node, &mut parent_variable, child_variable, core, stack_frame_registers, frame_base, cache, )?;
                         // At this point we have everything we need (It has updated the parent's `role`) from the child_variable, so elimnate it before we continue ...
                         cache.remove_cache_entry(child_variable.variable_key)?;
+                        // Niche optimized enums (e.g. `Option<&T>`) only have a DW_AT_discr_value for the variants without data, and any other discriminant value belongs to the default variant.
+                        if let VariantRole::VariantPart(discriminant) = parent_variable.role {
+                            if !self.has_variant_for_discriminant(child_node.entry().offset(), discriminant)? {
+                                parent_variable.role = VariantRole::VariantPart(u64::MAX);
+                            }
+                        }
                         parent_variable = self.process_tree(child_node, parent_variable, core, stack_frame_registers, frame_base, cache)?;
                     }
                     gimli::DW_TAG_variant // variant is a child of a structure, and one of them should have a discriminant value to match the DW_TAG_variant_part 
@@ -647,15 +782,15 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                         if !cache.has_children(&parent_variable)? {
                             let mut child_variable = cache.cache_variable(
                                 Some(parent_variable.variable_key),
-                                Variable::new(self.unit.header.offset().as_debug_info_offset(), Some(child_node.entry().offset())),
+                                Variable::new(self.debug_info_offset, Some(child_node.entry().offset())),
                                 core
                             )?;
                             self.extract_variant_discriminant(&child_node, &mut child_variable)?;
                             child_variable = self.process_tree_node_attributes(&mut child_node, &mut parent_variable, child_variable, core, stack_frame_registers, frame_base, cache)?;
                             if child_variable.is_valid() {
                                 if let VariantRole::Variant(discriminant) = child_variable.role {
-                                    // Only process the discriminant variants or when we eventually   encounter the default 
-                                    if parent_variable.role == VariantRole::VariantPart(discriminant) || discriminant == u64::MAX {
+                                    // Only process the variant that matches the discriminant, which is the default variant if no other variant matches.
+                                    if parent_variable.role == VariantRole::VariantPart(discriminant) {
                                         self.process_memory_location(child_node.entry(), &parent_variable, &mut child_variable, Some(core), stack_frame_registers, frame_base)?;
                                         // Recursively process each relevant child node.
                                         child_variable = self.process_tree(child_node, child_variable, core, stack_frame_registers, frame_base, cache)?;
@@ -676,7 +811,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                         // This tag is a child node fore parent types such as (array, vector, etc.).
                         // Recursively process each node, but pass the parent_variable so that new children are caught despite missing these tags.
                         let mut range_variable = cache.cache_variable(Some(parent_variable.variable_key),Variable::new(
-                        self.unit.header.offset().as_debug_info_offset(),
+                        self.debug_info_offset,
                         Some(child_node.entry().offset()),
                     ), core)?;
                         range_variable = self.process_tree_node_attributes(&mut child_node, &mut parent_variable, range_variable, core, stack_frame_registers, frame_base, cache)?;
@@ -721,10 +856,9 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                                 = child_node.entry().attr(gimli::DW_AT_ranges) {
                                     match ranges.value() {
                                         gimli::AttributeValue::RangeListsRef(raw_range_lists_offset) => {
-                                            let range_lists_offset = self.debug_info.dwarf.ranges_offset_from_raw(&self.unit, raw_range_lists_offset);
+                                            let range_lists_offset = self.dwarf.ranges_offset_from_raw(&self.unit, raw_range_lists_offset);
 
                                             if let Ok(mut ranges) = self
-                                                .debug_info
                                                 .dwarf
                                                 .ranges(&self.unit, range_lists_offset) {
                                                     while let Ok(Some(ranges)) = ranges.next() {
@@ -794,11 +928,10 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                 Ok(optional_discr_value_attr) => {
                     match optional_discr_value_attr {
                         Some(discr_attr) => {
-                            match discr_attr.value() {
-                                gimli::AttributeValue::Data1(const_value) => {
-                                    VariantRole::Variant(const_value as u64)
-                                }
-                                other_attribute_value => {
+                            match discriminant_value(discr_attr.value()) {
+                                Some(const_value) => VariantRole::Variant(const_value),
+                                None => {
+                                    let other_attribute_value = discr_attr.value();
                                     variable.set_value(VariableValue::Error(format!("Unimplemented: Attribute Value for DW_AT_discr_value: {:.100}", format!("{other_attribute_value:?}"))));
                                     VariantRole::Variant(u64::MAX)
                                 }
@@ -839,7 +972,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
     ) -> Result<Variable, DebugError> {
         let type_name = match node.entry().attr(gimli::DW_AT_name) {
             Ok(optional_name_attr) => {
-                optional_name_attr.map(|name_attr| extract_name(self.debug_info, name_attr.value()))
+                optional_name_attr.map(|name_attr| extract_name(self, name_attr.value()))
             }
             Err(error) => {
                 let message = format!("Error: evaluating type name: {error:?} ");
@@ -853,6 +986,11 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                 gimli::DW_TAG_base_type => {
                     child_variable.type_name =
                         VariableType::Base(type_name.unwrap_or_else(|| "<unnamed>".to_string()));
+                    if let Ok(Some(gimli::AttributeValue::Encoding(encoding))) =
+                        node.entry().attr_value(gimli::DW_AT_encoding)
+                    {
+                        child_variable.base_type_encoding = Some(encoding);
+                    }
                     self.process_memory_location(
                         node.entry(),
                         parent_variable,
@@ -982,6 +1120,26 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                                 )?;
                                 child_variable.variable_node_type = temp_node_type;
                             }
+                            // Strings, and byte slices, are shown as their text, rather than as a pointer and length.
+                            if let VariableLocation::Address(address) =
+                                child_variable.memory_location
+                            {
+                                if let Some(string_layout) =
+                                    self.string_layout(node.entry().offset())?
+                                {
+                                    let string_value = self
+                                        .read_string_value(core, address, &string_layout)
+                                        .map_or_else(
+                                            |error| {
+                                                VariableValue::Error(format!(
+                                                    "Failed to read string value: {error:?}"
+                                                ))
+                                            },
+                                            VariableValue::Valid,
+                                        );
+                                    child_variable.set_value(string_value);
+                                }
+                            }
                         }
                     } else {
                         // If something is already broken, then do nothing ...
@@ -1012,6 +1170,16 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                     if parent_variable.is_valid() && child_variable.is_valid() {
                         let enumerator_values =
                             cache.get_children(Some(child_variable.variable_key))?;
+                        // The enumerators are kept, so that a new value can be validated when the variable is set.
+                        // Discriminants that do not fit in a byte can not be read, see below, and are not included.
+                        child_variable.enumerators = enumerator_values
+                            .iter()
+                            .filter_map(|enumerator_variable| {
+                                let discriminant =
+                                    enumerator_variable.get_value(cache).parse::<u8>().ok()?;
+                                Some((enumerator_variable.name.to_string(), discriminant))
+                            })
+                            .collect();
 
                         if let VariableLocation::Address(address) = child_variable.memory_location {
                             // NOTE: hard-coding value of variable.byte_size to 1 ... replace with code if necessary.
@@ -1073,10 +1241,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                                             let mut subrange_variable = cache.cache_variable(
                                                 Some(child_variable.variable_key),
                                                 Variable::new(
-                                                    self.unit
-                                                        .header
-                                                        .offset()
-                                                        .as_debug_info_offset(),
+                                                    self.debug_info_offset,
                                                     Some(node.entry().offset()),
                                                 ),
                                                 core,
@@ -1212,12 +1377,9 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                                             .attr(gimli::DW_AT_name)
                                         {
                                             Ok(optional_name_attr) => match optional_name_attr {
-                                                Some(name_attr) => {
-                                                    VariableType::Other(extract_name(
-                                                        self.debug_info,
-                                                        name_attr.value(),
-                                                    ))
-                                                }
+                                                Some(name_attr) => VariableType::Other(
+                                                    extract_name(self, name_attr.value()),
+                                                ),
                                                 None => VariableType::Unknown,
                                             },
                                             Err(error) => VariableType::Other(format!(
@@ -1294,10 +1456,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
         if let Ok(array_member_type_node) = array_member_type_tree.root() {
             let mut array_member_variable = cache.cache_variable(
                 Some(child_variable.variable_key),
-                Variable::new(
-                    self.unit.header.offset().as_debug_info_offset(),
-                    Some(unit_ref),
-                ),
+                Variable::new(self.debug_info_offset, Some(unit_ref)),
                 core,
             )?;
             array_member_variable.member_index = Some(array_member_index);
@@ -1401,10 +1560,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                     ExpressionResult::Location(location_from_expression) => {
                         match &location_from_expression {
                             VariableLocation::Unavailable => {
-                                child_variable.set_value(VariableValue::Error(
-                                    "<value optimized away by compiler, out of scope, or dropped>"
-                                        .to_string(),
-                                ));
+                                child_variable.set_value(VariableValue::OptimizedOut);
                             }
                             VariableLocation::Error(error_message)
                             | VariableLocation::Unsupported(error_message) => {
@@ -1486,13 +1642,7 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                         }
                     },
                     gimli::AttributeValue::LocationListsRef(location_list_offset) => {
-                        match self.debug_info.locations_section.locations(
-                            location_list_offset,
-                            self.unit.header.encoding(),
-                            self.unit.low_pc,
-                            &self.debug_info.address_section,
-                            self.unit.addr_base,
-                        ) {
+                        match self.dwarf.locations(&self.unit, location_list_offset) {
                             Ok(mut locations) => {
                                 if let Some(program_counter) = stack_frame_registers
                                     .get_program_counter()
@@ -1618,7 +1768,8 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                 }
                 Location::Address { address } => {
                     if address.is_zero() {
-                        Ok(ExpressionResult::Location(VariableLocation::Error("The value of this variable may have been optimized out of the debug info, by the compiler.".to_string())))
+                        // The compiler optimized out the variable, and left a placeholder address in the debug info.
+                        Ok(ExpressionResult::Location(VariableLocation::Unavailable))
                     } else if !supports_native_64bit_access {
                         if *address < u32::MAX as u64 {
                             Ok(ExpressionResult::Location(VariableLocation::Address(
@@ -1696,9 +1847,8 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
                             )),
                         }
                     } else {
-                        Ok(ExpressionResult::Location(VariableLocation::Error(
-                            format!("Error: Cannot resolve register: {register:?}"),
-                        )))
+                        // The register was not restored while unwinding this frame, e.g. because it was reused after the variable went out of scope.
+                        Ok(ExpressionResult::Location(VariableLocation::Unavailable))
                     }
                 }
                 l => Ok(ExpressionResult::Location(VariableLocation::Error(
@@ -1922,4 +2072,633 @@ impl<'debuginfo> UnitInfo<'debuginfo> {
         }
         Ok(false)
     }
+
+    /// Returns `true` if one of the DW_TAG_variant children of the DW_TAG_variant_part at `variant_part_ref` has a DW_AT_discr_value that matches `discriminant`.
+    fn has_variant_for_discriminant(
+        &self,
+        variant_part_ref: UnitOffset,
+        discriminant: u64,
+    ) -> Result<bool, DebugError> {
+        let mut entries_tree = self
+            .unit
+            .header
+            .entries_tree(&self.unit.abbreviations, Some(variant_part_ref))?;
+        let mut child_nodes = entries_tree.root()?.children();
+        while let Some(child_node) = child_nodes.next()? {
+            if child_node.entry().tag() == gimli::DW_TAG_variant {
+                if let Some(discr_attr) = child_node.entry().attr(gimli::DW_AT_discr_value)? {
+                    if discriminant_value(discr_attr.value()) == Some(discriminant) {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Determine the [`StringLayout`] of `&str`, `&[u8]` and `String` types, from the DWARF layout of their members.
+    /// - `&str` and `&[u8]` have a `data_ptr` and a `length` member.
+    /// - `String` wraps a `Vec<u8>`, which has the data pointer nested in its `buf`, and a `len` member.
+    ///
+    /// Returns `None` for all other types.
+    fn string_layout(&self, type_ref: UnitOffset) -> Result<Option<StringLayout>, DebugError> {
+        let mut entries_tree = self
+            .unit
+            .header
+            .entries_tree(&self.unit.abbreviations, Some(type_ref))?;
+        let type_node = entries_tree.root()?;
+        let type_name = type_node
+            .entry()
+            .attr(gimli::DW_AT_name)?
+            .map(|name_attr| extract_name(self, name_attr.value()));
+        if !matches!(type_name.as_deref(), Some("&str" | "&[u8]" | "String")) {
+            return Ok(None);
+        }
+        let mut leaf_members = vec![];
+        self.find_leaf_members(type_node, 0, &mut vec![], &mut leaf_members)?;
+        let data_pointer = leaf_members
+            .iter()
+            .find(|leaf_member| leaf_member.is_pointer)
+            .cloned();
+        let length = leaf_members
+            .iter()
+            .find(|leaf_member| leaf_member.is_named("length") || leaf_member.is_named("len"))
+            .cloned();
+        Ok(data_pointer
+            .zip(length)
+            .map(|(data_pointer, length)| StringLayout {
+                data_pointer,
+                length,
+            }))
+    }
+
+    /// Recursively collect the pointer and base type members of a structure, with their offsets from `base_offset`.
+    /// The members of nested structures are included, with the names of the enclosing members in their [`LeafMember::path`].
+    fn find_leaf_members(
+        &self,
+        type_node: gimli::EntriesTreeNode<GimliReader>,
+        base_offset: u64,
+        path: &mut Vec<String>,
+        leaf_members: &mut Vec<LeafMember>,
+    ) -> Result<(), DebugError> {
+        // Guard against runaway recursion in malformed debug information.
+        if path.len() > 8 {
+            return Ok(());
+        }
+        let mut child_nodes = type_node.children();
+        while let Some(child_node) = child_nodes.next()? {
+            let member = child_node.entry();
+            if member.tag() != gimli::DW_TAG_member {
+                continue;
+            }
+            let member_offset = base_offset
+                + member
+                    .attr_value(gimli::DW_AT_data_member_location)?
+                    .and_then(|location| location.udata_value())
+                    .unwrap_or(0);
+            let member_type_ref = match member.attr_value(gimli::DW_AT_type)? {
+                Some(gimli::AttributeValue::UnitRef(unit_ref)) => unit_ref,
+                _ => continue,
+            };
+            let member_name = member
+                .attr(gimli::DW_AT_name)?
+                .map(|name_attr| extract_name(self, name_attr.value()))
+                .unwrap_or_default();
+            let mut member_type_tree = self
+                .unit
+                .header
+                .entries_tree(&self.unit.abbreviations, Some(member_type_ref))?;
+            let member_type_node = member_type_tree.root()?;
+            path.push(member_name);
+            match member_type_node.entry().tag() {
+                gimli::DW_TAG_pointer_type => leaf_members.push(LeafMember {
+                    path: path.clone(),
+                    offset: member_offset,
+                    byte_size: self.unit.header.address_size() as u64,
+                    is_pointer: true,
+                }),
+                gimli::DW_TAG_base_type => leaf_members.push(LeafMember {
+                    path: path.clone(),
+                    offset: member_offset,
+                    byte_size: extract_byte_size(member_type_node.entry())
+                        .unwrap_or_else(|| self.unit.header.address_size() as u64),
+                    is_pointer: false,
+                }),
+                gimli::DW_TAG_structure_type => {
+                    self.find_leaf_members(member_type_node, member_offset, path, leaf_members)?;
+                }
+                _ => {}
+            }
+            path.pop();
+        }
+        Ok(())
+    }
+
+    /// Read the text of the string at `address` from target memory, using the [`StringLayout`] of its type.
+    fn read_string_value(
+        &self,
+        core: &mut Core<'_>,
+        address: u64,
+        string_layout: &StringLayout,
+    ) -> Result<String, DebugError> {
+        let data_address = string_layout.data_pointer.read(core, address)?;
+        let string_length = string_layout.length.read(core, address)? as usize;
+        read_string(core, data_address, string_length)
+    }
+
+    /// If `parent_variable` is a `Vec`, `VecDeque` or `HashMap`, then read the first `element_limit` elements from the heap, and cache them as its children, named `__0`, `__1`, ...
+    /// If there are more elements, then a final `…more` child reports how many are not shown.
+    ///
+    /// Returns `false` if the variable is not a (supported) collection, and its members should be cached as for any other structure.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn cache_collection_elements(
+        &self,
+        type_ref: UnitOffset,
+        parent_variable: &Variable,
+        core: &mut Core<'_>,
+        cache: &mut VariableCache,
+        stack_frame_registers: &DebugRegisters,
+        frame_base: Option<u64>,
+        element_limit: usize,
+    ) -> Result<bool, DebugError> {
+        let address = match parent_variable.memory_location {
+            VariableLocation::Address(address) => address,
+            _ => return Ok(false),
+        };
+        let mut entries_tree = self
+            .unit
+            .header
+            .entries_tree(&self.unit.abbreviations, Some(type_ref))?;
+        let type_node = entries_tree.root()?;
+        let type_name = match type_node.entry().attr(gimli::DW_AT_name)? {
+            Some(name_attr) => extract_name(self, name_attr.value()),
+            None => return Ok(false),
+        };
+        // The name is checked first, because finding the namespace of the type walks the compile unit.
+        let collection_kind =
+            match CollectionKind::from_type_name(&type_name).filter(|collection_kind| {
+                self.type_namespace(type_ref)
+                    .map_or(false, |namespace| collection_kind.is_defined_in(&namespace))
+            }) {
+                Some(collection_kind) => collection_kind,
+                None => return Ok(false),
+            };
+        let element_type_ref = match collection_kind {
+            CollectionKind::Vec | CollectionKind::VecDeque => {
+                self.template_type_parameter(type_ref, "T")?
+            }
+            // The `(K, V)` tuples are stored in a `hashbrown::raw::RawTable<(K, V)>`.
+            CollectionKind::HashMap => match self.find_member_type(type_ref, "RawTable<")? {
+                Some(raw_table_ref) => self.template_type_parameter(raw_table_ref, "T")?,
+                None => None,
+            },
+        };
+        let (element_type_ref, element_byte_size) =
+            match element_type_ref.and_then(|element_type_ref| {
+                let mut element_type_tree = self
+                    .unit
+                    .header
+                    .entries_tree(&self.unit.abbreviations, Some(element_type_ref))
+                    .ok()?;
+                let element_byte_size = extract_byte_size(element_type_tree.root().ok()?.entry())?;
+                Some((element_type_ref, element_byte_size))
+            }) {
+                Some(element_type) => element_type,
+                None => return Ok(false),
+            };
+
+        let mut leaf_members = vec![];
+        self.find_leaf_members(type_node, 0, &mut vec![], &mut leaf_members)?;
+        let find_leaf_member = |predicate: &dyn Fn(&LeafMember) -> bool| {
+            leaf_members
+                .iter()
+                .find(|leaf_member| predicate(*leaf_member))
+        };
+
+        let (element_count, element_addresses) = match collection_kind {
+            CollectionKind::Vec | CollectionKind::VecDeque => {
+                let (data_pointer, length) = match (
+                    find_leaf_member(&|leaf_member| leaf_member.is_pointer),
+                    find_leaf_member(&|leaf_member| leaf_member.is_named("len")),
+                ) {
+                    (Some(data_pointer), Some(length)) => (data_pointer, length),
+                    // E.g. older versions of `VecDeque`, which had a `tail` instead of a `len`.
+                    _ => return Ok(false),
+                };
+                let element_count = length.read(core, address)? as usize;
+                let shown_count = element_count.min(element_limit);
+                // An empty collection may have a null, or dangling, data pointer, which must not be read.
+                let data_address = if shown_count.is_zero() {
+                    0
+                } else {
+                    data_pointer.read(core, address)?
+                };
+                let element_indexes: Vec<u64> = match collection_kind {
+                    CollectionKind::VecDeque if !shown_count.is_zero() => {
+                        // A `VecDeque` is a ring buffer, that starts at `head`, and wraps around at the capacity.
+                        let head =
+                            match find_leaf_member(&|leaf_member| leaf_member.is_named("head")) {
+                                Some(head) => head.read(core, address)?,
+                                None => 0,
+                            };
+                        let capacity = match find_leaf_member(&|leaf_member| {
+                            !leaf_member.is_pointer && leaf_member.is_within("cap")
+                        }) {
+                            Some(capacity) => capacity.read(core, address)?.max(1),
+                            None => u64::MAX,
+                        };
+                        (0..shown_count as u64)
+                            .map(|index| (head + index) % capacity)
+                            .collect()
+                    }
+                    _ => (0..shown_count as u64).collect(),
+                };
+                (
+                    element_count,
+                    element_indexes
+                        .into_iter()
+                        .map(|index| {
+                            data_address.wrapping_add(index.wrapping_mul(element_byte_size))
+                        })
+                        .collect::<Vec<u64>>(),
+                )
+            }
+            CollectionKind::HashMap => {
+                let (control_pointer, bucket_mask, items) = match (
+                    find_leaf_member(&|leaf_member| {
+                        leaf_member.is_pointer && leaf_member.is_within("ctrl")
+                    }),
+                    find_leaf_member(&|leaf_member| leaf_member.is_named("bucket_mask")),
+                    find_leaf_member(&|leaf_member| leaf_member.is_named("items")),
+                ) {
+                    (Some(control_pointer), Some(bucket_mask), Some(items)) => {
+                        (control_pointer, bucket_mask, items)
+                    }
+                    _ => return Ok(false),
+                };
+                let element_count = items.read(core, address)? as usize;
+                let mut element_addresses = vec![];
+                if !element_count.is_zero() && !element_limit.is_zero() {
+                    let control_address = control_pointer.read(core, address)?;
+                    let bucket_count =
+                        (bucket_mask.read(core, address)? + 1).min(MAX_HASH_MAP_BUCKETS) as usize;
+                    let mut control_bytes = vec![0_u8; bucket_count];
+                    core.read(control_address, &mut control_bytes)?;
+                    element_addresses = occupied_bucket_addresses(
+                        control_address,
+                        &control_bytes,
+                        element_byte_size,
+                    )
+                    .take(element_count.min(element_limit))
+                    .collect();
+                }
+                (element_count, element_addresses)
+            }
+        };
+
+        for (element_index, element_address) in element_addresses.iter().enumerate() {
+            self.cache_collection_element(
+                element_type_ref,
+                parent_variable,
+                element_index,
+                *element_address,
+                core,
+                cache,
+                stack_frame_registers,
+                frame_base,
+            )?;
+        }
+        if element_count > element_addresses.len() {
+            let mut more_variable = cache.cache_variable(
+                Some(parent_variable.variable_key),
+                Variable::new(self.debug_info_offset, None),
+                core,
+            )?;
+            more_variable.name = VariableName::Named("…more".to_string());
+            more_variable.variable_node_type = VariableNodeType::DoNotRecurse;
+            more_variable.set_value(VariableValue::Valid(format!(
+                "{} more elements",
+                element_count - element_addresses.len()
+            )));
+            cache.cache_variable(Some(parent_variable.variable_key), more_variable, core)?;
+        }
+        Ok(true)
+    }
+
+    /// Cache the element of a collection at `element_address`, as a child of `parent_variable`.
+    #[allow(clippy::too_many_arguments)]
+    fn cache_collection_element(
+        &self,
+        element_type_ref: UnitOffset,
+        parent_variable: &Variable,
+        element_index: usize,
+        element_address: u64,
+        core: &mut Core<'_>,
+        cache: &mut VariableCache,
+        stack_frame_registers: &DebugRegisters,
+        frame_base: Option<u64>,
+    ) -> Result<(), DebugError> {
+        let mut element_type_tree = self
+            .unit
+            .header
+            .entries_tree(&self.unit.abbreviations, Some(element_type_ref))?;
+        let element_type_node = element_type_tree.root()?;
+        let mut element_variable = cache.cache_variable(
+            Some(parent_variable.variable_key),
+            Variable::new(self.debug_info_offset, Some(element_type_ref)),
+            core,
+        )?;
+        element_variable.name = VariableName::Named(format!("__{element_index}"));
+        element_variable.source_location = parent_variable.source_location.clone();
+        element_variable.memory_location = VariableLocation::Address(element_address);
+        element_variable = self.extract_type(
+            element_type_node,
+            parent_variable,
+            element_variable,
+            core,
+            stack_frame_registers,
+            frame_base,
+            cache,
+        )?;
+        cache.cache_variable(Some(parent_variable.variable_key), element_variable, core)?;
+        Ok(())
+    }
+
+    /// The names of the namespaces that enclose the type at `type_ref`, e.g. `["alloc", "vec"]` for `alloc::vec::Vec<T>`,
+    /// or `None` if the type is not in this unit.
+    fn type_namespace(&self, type_ref: UnitOffset) -> Option<Vec<String>> {
+        let mut entries_cursor = self.unit.entries();
+        let mut depth = 0_isize;
+        let mut namespaces: Vec<(isize, String)> = vec![];
+        while let Ok(Some((depth_delta, entry))) = entries_cursor.next_dfs() {
+            depth += depth_delta;
+            while namespaces
+                .last()
+                .map_or(false, |(namespace_depth, _)| *namespace_depth >= depth)
+            {
+                namespaces.pop();
+            }
+            if entry.offset() == type_ref {
+                return Some(namespaces.into_iter().map(|(_, name)| name).collect());
+            }
+            if entry.tag() == gimli::DW_TAG_namespace {
+                let name = entry
+                    .attr(gimli::DW_AT_name)
+                    .ok()
+                    .flatten()
+                    .map(|name_attr| extract_name(self, name_attr.value()))
+                    .unwrap_or_default();
+                namespaces.push((depth, name));
+            }
+        }
+        None
+    }
+
+    /// The type of the DW_TAG_template_type_parameter named `parameter_name`, of the type at `type_ref`.
+    fn template_type_parameter(
+        &self,
+        type_ref: UnitOffset,
+        parameter_name: &str,
+    ) -> Result<Option<UnitOffset>, DebugError> {
+        let mut entries_tree = self
+            .unit
+            .header
+            .entries_tree(&self.unit.abbreviations, Some(type_ref))?;
+        let mut child_nodes = entries_tree.root()?.children();
+        while let Some(child_node) = child_nodes.next()? {
+            let entry = child_node.entry();
+            if entry.tag() == gimli::DW_TAG_template_type_parameter
+                && entry
+                    .attr(gimli::DW_AT_name)?
+                    .map(|name_attr| extract_name(self, name_attr.value()))
+                    .as_deref()
+                    == Some(parameter_name)
+            {
+                if let Some(gimli::AttributeValue::UnitRef(unit_ref)) =
+                    entry.attr_value(gimli::DW_AT_type)?
+                {
+                    return Ok(Some(unit_ref));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Recursively search the members of the structure at `type_ref`, for a member with a structure type whose name starts with `type_name_prefix`.
+    fn find_member_type(
+        &self,
+        type_ref: UnitOffset,
+        type_name_prefix: &str,
+    ) -> Result<Option<UnitOffset>, DebugError> {
+        let mut entries_tree = self
+            .unit
+            .header
+            .entries_tree(&self.unit.abbreviations, Some(type_ref))?;
+        let mut child_nodes = entries_tree.root()?.children();
+        while let Some(child_node) = child_nodes.next()? {
+            let entry = child_node.entry();
+            if entry.tag() != gimli::DW_TAG_member {
+                continue;
+            }
+            let member_type_ref = match entry.attr_value(gimli::DW_AT_type)? {
+                Some(gimli::AttributeValue::UnitRef(unit_ref)) => unit_ref,
+                _ => continue,
+            };
+            let mut member_type_tree = self
+                .unit
+                .header
+                .entries_tree(&self.unit.abbreviations, Some(member_type_ref))?;
+            let member_type_node = member_type_tree.root()?;
+            if member_type_node.entry().tag() != gimli::DW_TAG_structure_type {
+                continue;
+            }
+            let member_type_name = member_type_node
+                .entry()
+                .attr(gimli::DW_AT_name)?
+                .map(|name_attr| extract_name(self, name_attr.value()))
+                .unwrap_or_default();
+            if member_type_name.starts_with(type_name_prefix) {
+                return Ok(Some(member_type_ref));
+            }
+            if let Some(nested_type_ref) =
+                self.find_member_type(member_type_ref, type_name_prefix)?
+            {
+                return Ok(Some(nested_type_ref));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The maximum number of `HashMap` buckets that are scanned for elements, to guard against corrupt, or not yet initialized, tables.
+const MAX_HASH_MAP_BUCKETS: u64 = 4096;
+
+/// A member of a structure, with a pointer or base type, that is found by [`UnitInfo::find_leaf_members`].
+#[derive(Clone, Debug)]
+struct LeafMember {
+    /// The names of the members, from the outer structure to this member, e.g. `["vec", "buf", "ptr", "pointer", "pointer"]`.
+    path: Vec<String>,
+    /// The offset from the start of the outer structure.
+    offset: u64,
+    byte_size: u64,
+    is_pointer: bool,
+}
+
+impl LeafMember {
+    /// `true` if the name of this member is `name`.
+    fn is_named(&self, name: &str) -> bool {
+        self.path
+            .last()
+            .map_or(false, |member_name| member_name == name)
+    }
+
+    /// `true` if this member is, or is nested in, a member named `name`.
+    fn is_within(&self, name: &str) -> bool {
+        self.path.iter().any(|member_name| member_name == name)
+    }
+
+    /// Read the unsigned, little-endian, value of this member, from the structure at `address` in target memory.
+    fn read(&self, core: &mut Core<'_>, address: u64) -> Result<u64, DebugError> {
+        if !(1..=8).contains(&self.byte_size) {
+            return Err(DebugError::Other(anyhow::anyhow!(
+                "Unsupported size of {} bytes for `{}`",
+                self.byte_size,
+                self.path.join(".")
+            )));
+        }
+        let mut value_bytes = [0_u8; 8];
+        core.read_8(
+            address + self.offset,
+            &mut value_bytes[..self.byte_size as usize],
+        )?;
+        Ok(u64::from_le_bytes(value_bytes))
+    }
+}
+
+/// The location of the members that describe the text of a string, relative to the address of the string variable.
+struct StringLayout {
+    /// The pointer to the first byte of the text.
+    data_pointer: LeafMember,
+    /// The length, in bytes, of the text.
+    length: LeafMember,
+}
+
+/// The heap allocated collections of the Rust standard library, for which the elements are shown as children, instead of the internal members.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CollectionKind {
+    Vec,
+    VecDeque,
+    HashMap,
+}
+
+impl CollectionKind {
+    fn from_type_name(type_name: &str) -> Option<CollectionKind> {
+        if type_name.starts_with("Vec<") {
+            Some(CollectionKind::Vec)
+        } else if type_name.starts_with("VecDeque<") {
+            Some(CollectionKind::VecDeque)
+        } else if type_name.starts_with("HashMap<") {
+            Some(CollectionKind::HashMap)
+        } else {
+            None
+        }
+    }
+
+    /// The namespace of the standard library where the collection is defined, so that types with the same name in other crates are not decoded as collections.
+    fn namespace(&self) -> &'static [&'static str] {
+        match self {
+            CollectionKind::Vec => &["alloc", "vec"],
+            CollectionKind::VecDeque => &["alloc", "collections", "vec_deque"],
+            CollectionKind::HashMap => &["std", "collections", "hash", "map"],
+        }
+    }
+
+    /// `true` if `namespace` is the namespace of the standard library where this collection is defined.
+    fn is_defined_in(&self, namespace: &[String]) -> bool {
+        namespace
+            .iter()
+            .map(String::as_str)
+            .eq(self.namespace().iter().copied())
+    }
+}
+
+/// The addresses of the occupied buckets of a hashbrown `RawTable`, with the control bytes at `control_address`.
+/// The buckets are stored in reverse order before the control bytes, and a control byte with the top bit clear marks a bucket that is in use.
+fn occupied_bucket_addresses(
+    control_address: u64,
+    control_bytes: &[u8],
+    element_byte_size: u64,
+) -> impl Iterator<Item = u64> + '_ {
+    control_bytes
+        .iter()
+        .enumerate()
+        .filter(|(_, control_byte)| **control_byte & 0x80 == 0)
+        .map(move |(bucket_index, _)| {
+            control_address.wrapping_sub((bucket_index as u64 + 1).wrapping_mul(element_byte_size))
+        })
+}
+
+/// The value of a DW_AT_discr_value attribute, which the compiler encodes in a constant form that matches the size of the discriminant.
+fn discriminant_value(attribute_value: gimli::AttributeValue<GimliReader>) -> Option<u64> {
+    match attribute_value {
+        gimli::AttributeValue::Data1(value) => Some(value as u64),
+        gimli::AttributeValue::Data2(value) => Some(value as u64),
+        gimli::AttributeValue::Data4(value) => Some(value as u64),
+        gimli::AttributeValue::Data8(value) => Some(value),
+        gimli::AttributeValue::Udata(value) => Some(value),
+        gimli::AttributeValue::Sdata(value) => Some(value as u64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn occupied_buckets_are_found_from_the_control_bytes() {
+        // EMPTY (0xFF) and DELETED (0x80) buckets have the top bit set, and occupied buckets store 7 bits of the hash.
+        let control_bytes = [0xFF, 0x12, 0x80, 0x7F, 0xFF, 0x00];
+        let addresses: Vec<u64> =
+            occupied_bucket_addresses(0x2000_0100, &control_bytes, 8).collect();
+        assert_eq!(
+            addresses,
+            vec![
+                0x2000_0100 - 2 * 8,
+                0x2000_0100 - 4 * 8,
+                0x2000_0100 - 6 * 8
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_table_has_no_occupied_buckets() {
+        assert_eq!(
+            occupied_bucket_addresses(0x2000_0100, &[0xFF; 4], 8).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn collections_are_recognized_in_their_standard_library_namespace() {
+        let namespace =
+            |path: &[&str]| path.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        let vec_kind = CollectionKind::from_type_name("Vec<u8, alloc::alloc::Global>");
+        assert_eq!(vec_kind, Some(CollectionKind::Vec));
+        assert!(CollectionKind::Vec.is_defined_in(&namespace(&["alloc", "vec"])));
+        assert!(!CollectionKind::Vec.is_defined_in(&namespace(&["my_crate", "vec"])));
+        assert!(!CollectionKind::Vec.is_defined_in(&namespace(&[])));
+        assert!(CollectionKind::VecDeque.is_defined_in(&namespace(&[
+            "alloc",
+            "collections",
+            "vec_deque"
+        ])));
+        assert!(CollectionKind::HashMap.is_defined_in(&namespace(&[
+            "std",
+            "collections",
+            "hash",
+            "map"
+        ])));
+        assert_eq!(CollectionKind::from_type_name("Vector<u8>"), None);
+    }
 }
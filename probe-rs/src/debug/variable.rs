@@ -1,6 +1,6 @@
 use super::*;
 use anyhow::anyhow;
-use gimli::{DebugInfoOffset, UnitOffset};
+use gimli::{DebugInfoOffset, DwAte, UnitOffset};
 use num_traits::Zero;
 use std::str::FromStr;
 
@@ -33,6 +33,11 @@ pub enum VariableValue {
     /// - The contained warning message will be displayed to the user.
     /// - The debugger will not attempt to resolve additional fields or children of this variable.
     Error(String),
+    /// The variable has no location at the current program counter, e.g. because the compiler optimized it out,
+    /// or because it was kept in a register that has been reused since.
+    /// - The variable will be visible to the user, with `<optimized out>` as its value.
+    /// - The debugger will not attempt to resolve additional fields or children of this variable.
+    OptimizedOut,
     /// The value has not been set. This could be because ...
     /// - It is too early in the process to have discovered its value, or ...
     /// - The variable cannot have a stored value, e.g. a `struct`. In this case, please use `Variable::get_value` to infer a human readable value from the value of the struct's fields.
@@ -50,6 +55,7 @@ impl std::fmt::Display for VariableValue {
         match self {
             VariableValue::Valid(value) => value.fmt(f),
             VariableValue::Error(error) => write!(f, "< {error} >",),
+            VariableValue::OptimizedOut => write!(f, "<optimized out>"),
             VariableValue::Empty => write!(
                 f,
                 "Value not set. Please use Variable::get_value() to infer a human readable variable value"
@@ -61,7 +67,7 @@ impl std::fmt::Display for VariableValue {
 impl VariableValue {
     /// A VariableValue is valid if it doesn't contain an Info or a Warning.
     pub fn is_valid(&self) -> bool {
-        !matches!(self, VariableValue::Error(_))
+        !matches!(self, VariableValue::Error(_) | VariableValue::OptimizedOut)
     }
     /// No value or error is present
     pub fn is_empty(&self) -> bool {
@@ -74,6 +80,8 @@ impl VariableValue {
 pub enum VariableName {
     /// Top-level variable for static variables, child of a stack frame variable, and holds all the static scoped variables which are directly visible to the compile unit of the frame.
     StaticScopeRoot,
+    /// Top-level variable for global variables, independent of any stack frame, and holds the static scoped variables of all the compile units.
+    GlobalScopeRoot,
     /// Top-level variable for registers, child of a stack frame variable.
     RegistersRoot,
     /// Top-level variable for local scoped variables, child of a stack frame variable.
@@ -102,6 +110,7 @@ impl std::fmt::Display for VariableName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             VariableName::StaticScopeRoot => write!(f, "Static Variable"),
+            VariableName::GlobalScopeRoot => write!(f, "Global Variable"),
             VariableName::RegistersRoot => write!(f, "Platform Register"),
             VariableName::LocalScopeRoot => write!(f, "Function Variable"),
             VariableName::PeripheralScopeRoot => write!(f, "Peripheral Variable"),
@@ -333,6 +342,8 @@ pub struct Variable {
     pub memory_location: VariableLocation,
     /// The size of this variable in bytes.
     pub byte_size: Option<u64>,
+    /// The DWARF encoding of the value, if this variable has a base type, e.g. `DW_ATE_signed` or `DW_ATE_float`.
+    pub base_type_encoding: Option<DwAte>,
     /// If  this is a subrange (array, vector, etc.), is the ordinal position of this variable in that range
     pub member_index: Option<i64>,
     /// If this is a subrange (array, vector, etc.), we need to temporarily store the lower bound.
@@ -341,6 +352,8 @@ pub struct Variable {
     pub range_upper_bound: i64,
     /// The role of this variable.
     pub role: VariantRole,
+    /// If this is an enum, the name and the discriminant of each of its enumerators.
+    pub enumerators: Vec<(String, u8)>,
 }
 
 impl Variable {
@@ -356,12 +369,27 @@ impl Variable {
         }
     }
 
+    /// `true` if this variable has a signed or unsigned integer base type.
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self.base_type_encoding,
+            Some(gimli::DW_ATE_signed | gimli::DW_ATE_unsigned)
+        )
+    }
+
     /// Implementing set_value(), because the library passes errors into the value of the variable.
     /// This ensures debug front ends can see the errors, but doesn't fail because of a single variable not being able to decode correctly.
     pub fn set_value(&mut self, new_value: VariableValue) {
         // Allow some block when logic requires it.
         #[allow(clippy::if_same_then_else)]
-        if new_value.is_valid() {
+        if self.value == VariableValue::OptimizedOut
+            && matches!(
+                new_value,
+                VariableValue::Empty | VariableValue::OptimizedOut
+            )
+        {
+            // Clearing the value of a complex type does not make an optimized out variable available.
+        } else if new_value.is_valid() {
             // Simply overwrite existing value with a new valid one.
             self.value = new_value;
         } else if self.value.is_valid() {
@@ -452,6 +480,27 @@ impl Variable {
         Ok(updated_value)
     }
 
+    /// For [`VariableNodeType::SvdField`] variables, the value of the field bits, extracted from the stored value of the register.
+    /// Returns `None` for other variables, or if the register value is not valid.
+    pub fn svd_field_value(&self) -> Option<u32> {
+        if self.variable_node_type != VariableNodeType::SvdField {
+            return None;
+        }
+        match &self.value {
+            VariableValue::Valid(register_value) => {
+                let register_u32_value = register_value.parse::<u32>().ok()?;
+                let bit_width = (self.range_upper_bound - self.range_lower_bound) as u32;
+                let field_value = register_u32_value >> self.range_lower_bound;
+                if bit_width >= 32 {
+                    Some(field_value)
+                } else {
+                    Some(field_value & ((1 << bit_width) - 1))
+                }
+            }
+            _ => None,
+        }
+    }
+
     /// Implementing get_value(), because Variable.value has to be private (a requirement of updating the value without overriding earlier values ... see set_value()).
     pub fn get_value(&self, variable_cache: &variable_cache::VariableCache) -> String {
         // Allow for chained `if let` without complaining
@@ -522,8 +571,11 @@ impl Variable {
                             // If a user sees this error, then there is a logic problem in the stack unwind
                             "Error: This is a bug! Attempted to evaluate a Variable with no type or no memory location".to_string()
                         }
-                    } else if self.type_name == VariableType::Struct("None".to_string()) {
-                        "None".to_string()
+                    } else if self.type_name == VariableType::Struct("None".to_string())
+                        || self.is_active_variant(variable_cache)
+                    {
+                        // Variants without data, like `None`, are shown by their name.
+                        self.name.to_string()
                     } else if matches!(&self.type_name, VariableType::Array{item_type_name: _,  count} if *count == 0)
                     {
                         self.formatted_variable_value(variable_cache, 0_usize, false)
@@ -548,7 +600,7 @@ impl Variable {
         core: &mut Core<'_>,
         variable_cache: &variable_cache::VariableCache,
     ) {
-        if let VariableValue::Error(_) = self.value {
+        if let VariableValue::Error(_) | VariableValue::OptimizedOut = self.value {
             // Nothing more to do ...
             return;
         } else if self.variable_node_type == VariableNodeType::SvdRegister
@@ -779,24 +831,36 @@ impl Variable {
                         }
                         format!("{}{}{:\t<indentation$}]", compound_value, line_feed, "")
                     }
-                    VariableType::Struct(name) if name == "Ok" || name == "Err" => {
-                        // Handle special structure types like the variant values of `Option<>` and `Result<>`
-                        compound_value = format!(
-                            "{}{:\t<indentation$}{}: {} = {}(",
-                            line_feed, "", self.name, self.type_name, compound_value
-                        );
-                        for child in children {
-                            compound_value = format!(
-                                "{}{}",
-                                compound_value,
-                                child.formatted_variable_value(
-                                    variable_cache,
-                                    indentation + 1,
-                                    false
-                                )
-                            );
+                    VariableType::Struct(_)
+                        if matches!(self.role, VariantRole::VariantPart(_))
+                            && children.len() == 1 =>
+                    {
+                        // Enums, like `Option<>` and `Result<>`, are shown as their active variant, e.g. `Some(42)`
+                        let variant_value =
+                            children[0].formatted_variant_value(variable_cache, indentation);
+                        if show_name {
+                            format!(
+                                "{}{:\t<indentation$}{}: {} = {}",
+                                line_feed, "", self.name, self.type_name, variant_value
+                            )
+                        } else {
+                            format!("{}{:\t<indentation$}{}", line_feed, "", variant_value)
                         }
-                        format!("{}{}{:\t<indentation$})", compound_value, line_feed, "")
+                    }
+                    VariableType::Struct(name)
+                        if name == "Ok"
+                            || name == "Err"
+                            || self.is_active_variant(variable_cache) =>
+                    {
+                        // Handle the variant values of `Option<>`, `Result<>`, and other enums
+                        format!(
+                            "{}{:\t<indentation$}{}: {} = {}",
+                            line_feed,
+                            "",
+                            self.name,
+                            self.type_name,
+                            self.formatted_variant_value(variable_cache, indentation)
+                        )
                     }
                     _ => {
                         // Generic handling of other structured types.
@@ -896,6 +960,100 @@ impl Variable {
             }
         }
     }
+
+    /// `true` if this variable is the active variant of an enum, i.e. the only child of a variable with a `VariantPart` role.
+    fn is_active_variant(&self, variable_cache: &variable_cache::VariableCache) -> bool {
+        self.parent_key
+            .and_then(|parent_key| variable_cache.get_variable_by_key(parent_key))
+            .map_or(false, |parent_variable| {
+                matches!(parent_variable.role, VariantRole::VariantPart(_))
+            })
+    }
+
+    /// Format the active variant of an enum the way Rust would, e.g. `None`, `Some(42)`, or `Move { x: 1, y: 2 }`.
+    fn formatted_variant_value(
+        &self,
+        variable_cache: &variable_cache::VariableCache,
+        indentation: usize,
+    ) -> String {
+        let fields = variable_cache
+            .get_children(Some(self.variable_key))
+            .unwrap_or_default();
+        if fields.is_empty() {
+            return self.name.to_string();
+        }
+        // Tuple variants have fields named `__0`, `__1`, ...
+        let is_tuple = matches!(&fields[0].name, VariableName::Named(field_name) if field_name.starts_with("__"));
+        let field_values = fields
+            .iter()
+            .map(|field| field.formatted_variable_value(variable_cache, indentation + 1, !is_tuple))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let line_feed = if indentation.is_zero() { "" } else { "\n" };
+        if is_tuple {
+            format!(
+                "{}({}{}{:\t<indentation$})",
+                self.name, field_values, line_feed, ""
+            )
+        } else {
+            format!(
+                "{} {{{}{}{:\t<indentation$}}}",
+                self.name, field_values, line_feed, ""
+            )
+        }
+    }
+}
+
+/// The maximum number of bytes that are read from target memory for the value of a string.
+/// This guards against corrupt, or not yet initialized, lengths that would otherwise read large amounts of memory.
+pub(crate) const STRING_LENGTH_LIMIT: usize = 200;
+
+/// Decode the UTF-8 `bytes` of a string, replacing invalid sequences with `U+FFFD`.
+/// If the string was truncated to [`STRING_LENGTH_LIMIT`], then an incomplete trailing character is dropped, and `…` is appended.
+pub(crate) fn decode_string(bytes: &[u8], is_truncated: bool) -> String {
+    if !is_truncated {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+    let complete_bytes = match std::str::from_utf8(bytes) {
+        // `error_len()` is `None` when the bytes end in the middle of a character.
+        Err(error) if error.error_len().is_none() => &bytes[..error.valid_up_to()],
+        _ => bytes,
+    };
+    format!("{}…", String::from_utf8_lossy(complete_bytes))
+}
+
+/// Read the text of a string with `string_length` bytes at `data_address` from target memory.
+/// At most [`STRING_LENGTH_LIMIT`] bytes are read, and the text is decoded with [`decode_string`].
+pub(crate) fn read_string(
+    core: &mut Core<'_>,
+    data_address: u64,
+    string_length: usize,
+) -> Result<String, DebugError> {
+    if string_length.is_zero() {
+        // A string with length 0 doesn't need to be read from memory.
+        return Ok(String::new());
+    }
+    if data_address.is_zero() {
+        return Err(DebugError::Other(anyhow!(
+            "The string data pointer is null"
+        )));
+    }
+    // Limit string length to work around buggy information, otherwise the debugger
+    // can hang due to buggy debug information.
+    //
+    // TODO: If implemented, the variable should not be fetched automatically,
+    // but only when requested by the user. This workaround can then be removed.
+    let is_truncated = string_length > STRING_LENGTH_LIMIT;
+    if is_truncated {
+        tracing::warn!(
+            "Very long string ({} bytes), truncating to {} bytes.",
+            string_length,
+            STRING_LENGTH_LIMIT
+        );
+    }
+    let mut string_bytes = vec![0_u8; string_length.min(STRING_LENGTH_LIMIT)];
+    core.read(data_address, &mut string_bytes)?;
+    Ok(decode_string(&string_bytes, is_truncated))
 }
 
 /// Traits and Impl's to read from, and write to, memory value based on Variable::typ and Variable::location.
@@ -993,7 +1151,7 @@ impl Value for String {
         let mut str_value: String = "".to_owned();
         if let Ok(children) = variable_cache.get_children(Some(variable.variable_key)) {
             if !children.is_empty() {
-                let mut string_length = match children.iter().find(|child_variable| {
+                let string_length = match children.iter().find(|child_variable| {
                     child_variable.name == VariableName::Named("length".to_string())
                 }) {
                     Some(string_length) => {
@@ -1026,26 +1184,7 @@ impl Value for String {
                 if string_location.is_zero() {
                     str_value = "Error: Failed to determine &str memory location".to_string();
                 } else {
-                    // Limit string length to work around buggy information, otherwise the debugger
-                    // can hang due to buggy debug information.
-                    //
-                    // TODO: If implemented, the variable should not be fetched automatically,
-                    // but only when requested by the user. This workaround can then be removed.
-                    if string_length > 200 {
-                        tracing::warn!(
-                            "Very long string ({} bytes), truncating to 200 bytes.",
-                            string_length
-                        );
-                        string_length = 200;
-                    }
-
-                    if string_length.is_zero() {
-                        // A string with length 0 doesn't need to be read from memory.
-                    } else {
-                        let mut buff = vec![0u8; string_length];
-                        core.read(string_location, &mut buff)?;
-                        str_value = core::str::from_utf8(&buff)?.to_owned();
-                    }
+                    str_value = read_string(core, string_location, string_length)?;
                 }
             } else {
                 str_value = "Error: Failed to evaluate &str value".to_string();
@@ -1470,3 +1609,42 @@ impl Value for f64 {
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_empty_string() {
+        assert_eq!(decode_string(&[], false), "");
+        assert_eq!(decode_string(&[], true), "…");
+    }
+
+    #[test]
+    fn decode_complete_string() {
+        assert_eq!(decode_string("grüße".as_bytes(), false), "grüße");
+    }
+
+    #[test]
+    fn decode_invalid_utf8_replaces_the_invalid_bytes() {
+        assert_eq!(decode_string(b"ab\xFFcd", false), "ab\u{FFFD}cd");
+        assert_eq!(decode_string(b"ab\xFFcd", true), "ab\u{FFFD}cd…");
+    }
+
+    #[test]
+    fn decode_truncated_string_drops_the_incomplete_character() {
+        // `ü` is encoded as `C3 BC`, and the truncation happened after its first byte.
+        let bytes = "grü".as_bytes();
+        assert_eq!(decode_string(&bytes[..bytes.len() - 1], true), "gr…");
+        assert_eq!(decode_string(bytes, true), "grü…");
+    }
+
+    #[test]
+    fn decode_string_that_was_not_truncated_keeps_an_incomplete_character_visible() {
+        let bytes = "grü".as_bytes();
+        assert_eq!(
+            decode_string(&bytes[..bytes.len() - 1], false),
+            "gr\u{FFFD}"
+        );
+    }
+}
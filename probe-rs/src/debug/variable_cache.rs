@@ -234,6 +234,7 @@ impl VariableCache {
             .cloned()
             .collect();
         for child in children {
+            self.remove_cache_entry_children(child.variable_key)?;
             if self.variable_hash_map.remove(&child.variable_key).is_none() {
                 return Err(anyhow!("Failed to remove a `VariableCache` entry with key: {}. Please report this as a bug.", child.variable_key).into());
             };
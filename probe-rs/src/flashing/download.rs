@@ -115,6 +115,9 @@ pub struct DownloadOptions {
     pub verify: bool,
     /// Disable double buffering when loading flash.
     pub disable_double_buffering: bool,
+    /// Load the flash algorithm, its stack and data buffers into the RAM region that contains this address.
+    /// If this is `None`, the first RAM region that is accessible from the flashing core is used.
+    pub flash_algorithm_ram_address: Option<u64>,
 }
 
 impl DownloadOptions {
@@ -50,7 +50,7 @@ pub fn erase_all(session: &mut Session, progress: Option<FlashProgress>) -> Resu
         let algo = algo.unwrap().clone();
 
         let core_index = session.target().core_index_by_name(&core_name).unwrap();
-        let mut flasher = Flasher::new(session, core_index, &algo, progress.clone())?;
+        let mut flasher = Flasher::new(session, core_index, &algo, progress.clone(), None)?;
 
         if flasher.is_chip_erase_supported() {
             tracing::debug!("     -- chip erase supported, doing it.");
@@ -136,7 +136,7 @@ pub fn erase_sectors(
         let algo = algo.unwrap().clone();
 
         let core_index = session.target().core_index_by_name(&core_name).unwrap();
-        let mut flasher = Flasher::new(session, core_index, &algo, progress.clone())?;
+        let mut flasher = Flasher::new(session, core_index, &algo, progress.clone(), None)?;
 
         let sectors = flasher
             .flash_algorithm()
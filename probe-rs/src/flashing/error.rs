@@ -122,6 +122,24 @@ pub enum FlashError {
         /// The name of the chip.
         name: String,
     },
+    /// No RAM region, accessible from the core that runs the flash algorithm, contains the requested address.
+    #[error("No RAM region accessible from core {core_name} contains the requested flash algorithm address {address:#010x}.")]
+    NoRamRegionAtAddress {
+        /// The requested address.
+        address: u64,
+        /// The name of the core that runs the flash algorithm.
+        core_name: String,
+    },
+    /// The requested RAM region is too small to hold the flash algorithm, its stack, and a data buffer.
+    #[error("The RAM region {start:#010x}..{end:#010x} is too small for the flash algorithm and its buffers, which require {required_size} bytes.")]
+    RamRegionTooSmall {
+        /// The start of the RAM region.
+        start: u64,
+        /// The end of the RAM region.
+        end: u64,
+        /// The number of bytes required, starting at the flash algorithm load address.
+        required_size: u64,
+    },
     /// The given flash algorithm did not have a leng multiple of 4 bytes.
     ///
     /// This means that the flash algorithm that was loaded is broken.
@@ -151,4 +169,10 @@ pub enum FlashError {
     /// The register value supplied for this flash algorithm is out of the supported range.
     #[error("The register value {0:08X?} is out of the supported range.")]
     RegisterValueNotSupported(u64),
+    /// No memory region that a core can access contains the data at this address, so it can not be read back for verification.
+    #[error("No memory region that a core can access contains the address {address:#010x}.")]
+    NoCoreAccessAtAddress {
+        /// The start address of the data that should be verified.
+        address: u64,
+    },
 }
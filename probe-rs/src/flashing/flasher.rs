@@ -62,30 +62,56 @@ impl<'session> Flasher<'session> {
         core_index: usize,
         raw_flash_algorithm: &RawFlashAlgorithm,
         progress: Option<FlashProgress>,
+        ram_address: Option<u64>,
     ) -> Result<Self, FlashError> {
         let target = session.target();
 
         // Find a RAM region from which we can run the algo.
         let mm = &target.memory_map;
         let core_name = &target.cores[core_index].name;
-        let ram = mm
+        let mut ram_regions = mm
             .iter()
             .filter_map(|mm| match mm {
                 MemoryRegion::Ram(ram) => Some(ram),
                 _ => None,
             })
-            .find(|ram| {
-                // The RAM must be accessible from the core we're going to run the algo on.
-                ram.cores.contains(core_name)
-            })
-            .ok_or(FlashError::NoRamDefined {
+            // The RAM must be accessible from the core we're going to run the algo on.
+            .filter(|ram| ram.cores.contains(core_name));
+        let ram = if let Some(ram_address) = ram_address {
+            ram_regions
+                .find(|ram| ram.range.contains(&ram_address))
+                .ok_or_else(|| FlashError::NoRamRegionAtAddress {
+                    address: ram_address,
+                    core_name: core_name.clone(),
+                })?
+        } else {
+            ram_regions.next().ok_or(FlashError::NoRamDefined {
                 name: session.target().name.clone(),
-            })?;
+            })?
+        };
 
         tracing::info!("Chosen RAM to run the algo: {:x?}", ram);
 
         let flash_algorithm = FlashAlgorithm::assemble_from_raw(raw_flash_algorithm, ram, target)?;
 
+        // When the user explicitly chose the RAM region, make sure nothing outside of it gets overwritten.
+        if ram_address.is_some() {
+            let required_end = flash_algorithm
+                .page_buffers
+                .iter()
+                .max()
+                .map_or(flash_algorithm.begin_stack, |last_buffer| {
+                    last_buffer + flash_algorithm.flash_properties.page_size as u64
+                });
+            if required_end > ram.range.end {
+                return Err(FlashError::RamRegionTooSmall {
+                    start: ram.range.start,
+                    end: ram.range.end,
+                    required_size: required_end - flash_algorithm.load_address,
+                });
+            }
+        }
+
         let mut this = Self {
             session,
             core_index,
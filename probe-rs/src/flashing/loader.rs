@@ -14,6 +14,18 @@ use crate::memory::MemoryInterface;
 use crate::session::Session;
 use crate::Target;
 
+/// The result of comparing one contiguous chunk of data with the contents of the target memory.
+/// See [`FlashLoader::verify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyResult {
+    /// The start address of the data chunk.
+    pub address: u64,
+    /// The size of the data chunk, in bytes.
+    pub size: u64,
+    /// The address of the first byte that does not match the data, or `None` if the memory matches.
+    pub first_mismatch: Option<u64>,
+}
+
 /// `FlashLoader` is a struct which manages the flashing of any chunks of data onto any sections of flash.
 ///
 /// Use [add_data()](FlashLoader::add_data) to add a chunk of data.
@@ -301,7 +313,13 @@ impl FlashLoader {
                 .iter()
                 .position(|c| c.name == core_name)
                 .unwrap();
-            let mut flasher = Flasher::new(session, core, &algo, options.progress.clone())?;
+            let mut flasher = Flasher::new(
+                session,
+                core,
+                &algo,
+                options.progress.clone(),
+                options.flash_algorithm_ram_address,
+            )?;
 
             let mut do_chip_erase = options.do_chip_erase;
 
@@ -387,41 +405,64 @@ impl FlashLoader {
 
         if options.verify {
             tracing::debug!("Verifying!");
-            for (&address, data) in &self.builder.data {
-                tracing::debug!(
-                    "    data: {:08x}-{:08x} ({} bytes)",
-                    address,
-                    address + data.len() as u64,
-                    data.len()
-                );
-
-                let associated_region = session
-                    .target()
-                    .get_memory_region_by_address(address)
-                    .unwrap();
-                let core_name = match associated_region {
-                    MemoryRegion::Ram(r) => &r.cores,
-                    MemoryRegion::Generic(r) => &r.cores,
-                    MemoryRegion::Nvm(r) => &r.cores,
-                }
-                .first()
-                .unwrap();
-                let core_index = session.target().core_index_by_name(core_name).unwrap();
-                let mut core = session.core(core_index).map_err(FlashError::Core)?;
-
-                let mut written_data = vec![0; data.len()];
-                core.read(address, &mut written_data)
-                    .map_err(FlashError::Core)?;
-
-                if data != &written_data {
-                    return Err(FlashError::Verify);
-                }
+            if self
+                .verify(session)?
+                .iter()
+                .any(|result| result.first_mismatch.is_some())
+            {
+                return Err(FlashError::Verify);
             }
         }
 
         Ok(())
     }
 
+    /// Reads back the memory that is occupied by the stored data chunks, and compares it to the data,
+    /// without erasing or programming anything.
+    ///
+    /// Returns one [`VerifyResult`] per contiguous data chunk, in address order.
+    pub fn verify(&self, session: &mut Session) -> Result<Vec<VerifyResult>, FlashError> {
+        let mut results = Vec::with_capacity(self.builder.data.len());
+        for (&address, data) in &self.builder.data {
+            tracing::debug!(
+                "    data: {:08x}-{:08x} ({} bytes)",
+                address,
+                address + data.len() as u64,
+                data.len()
+            );
+
+            let core_index = session
+                .target()
+                .get_memory_region_by_address(address)
+                .and_then(|associated_region| {
+                    match associated_region {
+                        MemoryRegion::Ram(r) => &r.cores,
+                        MemoryRegion::Generic(r) => &r.cores,
+                        MemoryRegion::Nvm(r) => &r.cores,
+                    }
+                    .first()
+                })
+                .and_then(|core_name| session.target().core_index_by_name(core_name))
+                .ok_or(FlashError::NoCoreAccessAtAddress { address })?;
+            let mut core = session.core(core_index).map_err(FlashError::Core)?;
+
+            let mut written_data = vec![0; data.len()];
+            core.read(address, &mut written_data)
+                .map_err(FlashError::Core)?;
+
+            results.push(VerifyResult {
+                address,
+                size: data.len() as u64,
+                first_mismatch: data
+                    .iter()
+                    .zip(written_data.iter())
+                    .position(|(expected, actual)| expected != actual)
+                    .map(|offset| address + offset as u64),
+            });
+        }
+        Ok(results)
+    }
+
     /// Try to find a flash algorithm for the given NvmRegion.
     /// Errors when:
     /// - there's no algo for the region.
@@ -73,6 +73,8 @@ pub mod config;
 
 #[warn(missing_docs)]
 mod core;
+#[warn(missing_docs)]
+mod core_dump;
 pub mod debug;
 mod error;
 #[warn(missing_docs)]
@@ -93,6 +95,7 @@ pub use crate::core::{
     CoreStatus, HaltReason, MemoryMappedRegister, RegisterDescription, RegisterFile, RegisterId,
     RegisterValue, SpecificCoreState,
 };
+pub use crate::core_dump::{CoreDump, DumpedStackFrame};
 pub use crate::error::Error;
 pub use crate::memory::MemoryInterface;
 pub use crate::probe::{
@@ -161,6 +161,49 @@ pub enum DebugProbeError {
     Timeout,
 }
 
+impl DebugProbeError {
+    /// Returns `true` if the error was caused by a lost connection to the probe, e.g. because the probe was unplugged.
+    /// This includes the USB errors of every probe type, also when they are wrapped in [`DebugProbeError::ProbeSpecific`].
+    pub fn is_probe_disconnected(&self) -> bool {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(self);
+        while let Some(error) = source {
+            if is_disconnected_transport_error(error) {
+                return true;
+            }
+            source = error.source();
+        }
+        false
+    }
+}
+
+/// Check if `error` is a USB, or HID, transport error of a probe that is no longer connected.
+fn is_disconnected_transport_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    if let Some(probe_error) = error.downcast_ref::<DebugProbeError>() {
+        matches!(probe_error, DebugProbeError::Usb(_))
+    } else if let Some(rusb_error) = error.downcast_ref::<rusb::Error>() {
+        is_disconnected_rusb_error(rusb_error)
+    } else if let Some(send_error) = error.downcast_ref::<cmsisdap::commands::SendError>() {
+        // The `rusb::Error` of `SendError::UsbError` is not its `source`, so it has to be checked here.
+        match send_error {
+            cmsisdap::commands::SendError::UsbError(rusb_error) => {
+                is_disconnected_rusb_error(rusb_error)
+            }
+            // A generic HID error, e.g. a timeout, is only a disconnect if the HID device was no longer enumerated.
+            cmsisdap::commands::SendError::HidDeviceDisconnected(_) => true,
+            _ => false,
+        }
+    } else if let Some(jaylink_error) = error.downcast_ref::<jaylink::Error>() {
+        matches!(jaylink_error.kind(), jaylink::ErrorKind::Usb)
+    } else {
+        false
+    }
+}
+
+/// Only `NoDevice` means that the probe is gone. Other errors, e.g. `Io`, can also be caused by a transient transfer failure.
+fn is_disconnected_rusb_error(error: &rusb::Error) -> bool {
+    matches!(error, rusb::Error::NoDevice)
+}
+
 /// An error during probe creation accured.
 /// This is almost always a sign of a bad USB setup.
 /// Check UDEV rules if you are on Linux and try installing Zadig
@@ -913,3 +956,62 @@ pub enum AttachMethod {
     /// This is required on targets that can remap SWD pins or disable the SWD interface in sleep.
     UnderReset,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::cmsisdap::commands::{CmsisDapError, CommandId, SendError};
+
+    fn cmsis_dap_send_error(source: SendError) -> DebugProbeError {
+        DebugProbeError::ProbeSpecific(Box::new(CmsisDapError::Send {
+            command_id: CommandId::Info,
+            source,
+        }))
+    }
+
+    #[test]
+    fn usb_errors_of_a_disconnected_probe_are_detected() {
+        assert!(DebugProbeError::Usb(None).is_probe_disconnected());
+        assert!(
+            cmsis_dap_send_error(SendError::UsbError(rusb::Error::NoDevice))
+                .is_probe_disconnected()
+        );
+        assert!(
+            DebugProbeError::ProbeSpecific(Box::new(stlink::StlinkError::Usb(
+                rusb::Error::NoDevice
+            )))
+            .is_probe_disconnected()
+        );
+        assert!(cmsis_dap_send_error(SendError::HidDeviceDisconnected(
+            hidapi::HidError::HidApiError {
+                message: "The device is not connected.".to_string()
+            }
+        ))
+        .is_probe_disconnected());
+    }
+
+    #[test]
+    fn other_probe_errors_are_not_a_disconnect() {
+        assert!(
+            !cmsis_dap_send_error(SendError::UsbError(rusb::Error::Access)).is_probe_disconnected()
+        );
+        assert!(!cmsis_dap_send_error(SendError::UsbError(rusb::Error::Io)).is_probe_disconnected());
+        assert!(!cmsis_dap_send_error(SendError::NotEnoughData).is_probe_disconnected());
+        assert!(!DebugProbeError::Timeout.is_probe_disconnected());
+        assert!(
+            !DebugProbeError::ProbeSpecific(Box::new(stlink::StlinkError::UnalignedAddress))
+                .is_probe_disconnected()
+        );
+    }
+
+    #[test]
+    fn generic_hid_errors_are_not_a_disconnect() {
+        // `hidapi` reports a failed read, e.g. a timeout, with the same error as a missing device.
+        assert!(
+            !cmsis_dap_send_error(SendError::HidApi(hidapi::HidError::HidApiError {
+                message: "hid_read_timeout: timeout".to_string()
+            }))
+            .is_probe_disconnected()
+        );
+    }
+}
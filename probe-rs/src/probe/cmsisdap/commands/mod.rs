@@ -6,6 +6,7 @@ pub mod transfer;
 
 use crate::probe::cmsisdap::commands::general::info::PacketSizeCommand;
 use crate::DebugProbeError;
+use std::ffi::{CStr, CString};
 use std::str::Utf8Error;
 use std::time::Duration;
 
@@ -36,6 +37,9 @@ pub enum CmsisDapError {
 pub enum SendError {
     #[error("Error in the USB HID access")]
     HidApi(#[from] hidapi::HidError),
+    /// The HID access failed, and the HID device was no longer found when the devices were enumerated again.
+    #[error("The USB HID device is no longer connected")]
+    HidDeviceDisconnected(#[source] hidapi::HidError),
     #[error("Error in the USB access")]
     UsbError(rusb::Error),
     #[error("Not enough data in response from probe")]
@@ -67,6 +71,22 @@ impl From<rusb::Error> for SendError {
     }
 }
 
+/// Convert a failed access of the HID device at `path` into a [`SendError`].
+/// `hidapi` reports every failure, e.g. a timeout, with the same generic error, so the HID devices are enumerated again,
+/// to tell a disconnected probe apart from a transient failure.
+fn hid_send_error(path: &CStr, error: hidapi::HidError) -> SendError {
+    let device_connected = hidapi::HidApi::new().map_or(true, |hid_api| {
+        hid_api
+            .device_list()
+            .any(|device_info| device_info.path() == path)
+    });
+    if device_connected {
+        SendError::HidApi(error)
+    } else {
+        SendError::HidDeviceDisconnected(error)
+    }
+}
+
 impl From<CmsisDapError> for DebugProbeError {
     fn from(error: CmsisDapError) -> Self {
         DebugProbeError::ProbeSpecific(Box::new(error))
@@ -75,10 +95,11 @@ impl From<CmsisDapError> for DebugProbeError {
 
 pub enum CmsisDapDevice {
     /// CMSIS-DAP v1 over HID.
-    /// Stores a HID device handle and maximum HID report size.
+    /// Stores a HID device handle, maximum HID report size, and the platform specific path of the HID device.
     V1 {
         handle: hidapi::HidDevice,
         report_size: usize,
+        path: CString,
     },
 
     /// CMSIS-DAP v2 over WinUSB/Bulk.
@@ -97,7 +118,10 @@ impl CmsisDapDevice {
     /// Read from the probe into `buf`, returning the number of bytes read on success.
     fn read(&self, buf: &mut [u8]) -> Result<usize, SendError> {
         match self {
-            CmsisDapDevice::V1 { handle, .. } => match handle.read_timeout(buf, 1000)? {
+            CmsisDapDevice::V1 { handle, path, .. } => match handle
+                .read_timeout(buf, 1000)
+                .map_err(|error| hid_send_error(path, error))?
+            {
                 // Timeout is not indicated by error, but by returning 0 read bytes
                 0 => Err(SendError::Timeout),
                 n => Ok(n),
@@ -112,7 +136,9 @@ impl CmsisDapDevice {
     /// Write `buf` to the probe, returning the number of bytes written on success.
     fn write(&self, buf: &[u8]) -> Result<usize, SendError> {
         match self {
-            CmsisDapDevice::V1 { handle, .. } => Ok(handle.write(buf)?),
+            CmsisDapDevice::V1 { handle, path, .. } => handle
+                .write(buf)
+                .map_err(|error| hid_send_error(path, error)),
             CmsisDapDevice::V2 { handle, out_ep, .. } => {
                 let timeout = Duration::from_millis(100);
                 // Skip first byte as it's set to 0 for HID transfers
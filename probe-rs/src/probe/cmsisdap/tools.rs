@@ -372,6 +372,8 @@ pub fn open_device_from_selector(
             // common size for CMSIS-DAPv1 HID devices. We'll request the
             // actual size to use from the probe later.
             report_size: 64,
+            // Used to check if the probe is still connected, when a HID access fails.
+            path: device_info.path().to_owned(),
         }),
         _ => {
             // Return NotFound if this VID:PID was not a valid CMSIS-DAP probe,
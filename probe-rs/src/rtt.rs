@@ -306,7 +306,7 @@ impl Rtt {
 }
 
 /// Used to specify which memory regions to scan for the RTT control block.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ScanRegion {
     /// Scans all RAM regions known to probe-rs. This is the default and should always work, however
     /// if your device has a lot of RAM, scanning all of it is slow.
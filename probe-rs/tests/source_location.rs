@@ -105,3 +105,93 @@ fn find_non_existing_unit_by_path() {
         .get_breakpoint_location(unit_path, 14, None)
         .is_err());
 }
+
+#[test]
+fn breakpoint_locations_in_line_range() {
+    let debug_info = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+    let path = Path::new("/Users/jacknoppe/dev/probe-rs-debugger-test/src/main.rs");
+
+    // Line 255 has no statements, so it is not a valid breakpoint location.
+    assert_eq!(
+        debug_info
+            .get_breakpoint_locations(path, 240, 256)
+            .expect("Failed to find breakpoint locations."),
+        vec![
+            (240, Some(28)),
+            (241, Some(28)),
+            (242, Some(20)),
+            (243, Some(24)),
+            (244, Some(26)),
+            (245, Some(24)),
+            (246, Some(23)),
+            (247, Some(17)),
+            (248, Some(21)),
+            (249, Some(25)),
+            (250, Some(5)),
+            (251, Some(32)),
+            (252, Some(27)),
+            (253, Some(34)),
+            (254, Some(17)),
+            (256, Some(40)),
+        ]
+    );
+}
+
+#[test]
+fn breakpoint_locations_include_every_statement_of_a_line() {
+    let debug_info = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+    let path = Path::new("/Users/jacknoppe/dev/probe-rs-debugger-test/src/main.rs");
+
+    assert_eq!(
+        debug_info
+            .get_breakpoint_locations(path, 212, 212)
+            .expect("Failed to find breakpoint locations."),
+        vec![(212, Some(24)), (212, Some(34))]
+    );
+}
+
+#[test]
+fn no_breakpoint_locations_outside_of_the_debug_info() {
+    let debug_info = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+    let path = Path::new("/Users/jacknoppe/dev/probe-rs-debugger-test/src/main.rs");
+    let unit_path =
+        Path::new("/Users/jacknoppe/dev/probe-rs-debugger-test/src/non-existent-path.rs");
+
+    assert!(debug_info
+        .get_breakpoint_locations(unit_path, 1, 1000)
+        .unwrap()
+        .is_empty());
+    assert!(debug_info
+        .get_breakpoint_locations(path, 256, 240)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn breakpoint_location_column_selects_the_nearest_statement() {
+    // Line 212 has a statement at column 24, and another one at column 34.
+    let test_data = [
+        (0x80004EA, None, 24),
+        (0x80004EA, Some(24), 24),
+        // The nearest statement at or after the requested column.
+        (0x8000510, Some(25), 34),
+        (0x8000510, Some(34), 34),
+        // Without a statement at or after the requested column, the first statement of the line is used.
+        (0x80004EA, Some(40), 24),
+    ];
+
+    let di = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+
+    let path = Path::new("/Users/jacknoppe/dev/probe-rs-debugger-test/src/main.rs");
+
+    for (addr, col, halt_col) in test_data.iter() {
+        let breakpoint = di
+            .get_breakpoint_location(path, 212, *col)
+            .expect("Failed to find valid breakpoint locations.");
+        assert_eq!(
+            (*addr, Some(ColumnType::Column(*halt_col))),
+            (breakpoint.address, breakpoint.source_location.column),
+            "Breakpoint does not match for col={col:?}",
+        );
+    }
+}